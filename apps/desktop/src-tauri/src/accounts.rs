@@ -13,8 +13,13 @@
 //! The database layer only stores encrypted strings.
 
 use crate::auth::SessionManager;
-use crate::crypto::{decrypt_field, encrypt_field, CryptoError};
-use crate::db::{Account as DbAccount, AccountSearch, CreateAccount, Database, Pagination, UpdateAccount, DbError};
+use crate::crypto::{decrypt_field, encrypt_field, CryptoError, SecretKey};
+use crate::db::{
+    Account as DbAccount, AccountSearch, BatchItemOutcome, BatchMode, BatchOutcome, CreateAccount, Database, DbError,
+    Pagination, UpdateAccount,
+};
+use crate::history::{HistoryError, MutationKind, Target, Tracker};
+use crate::search_index::{self, MatchMode, SearchIndexError};
 use std::collections::HashMap;
 
 // ============================================================================
@@ -79,7 +84,12 @@ pub struct UpdateAccountPayload {
 #[derive(Debug, Clone, serde::Deserialize, Default)]
 pub struct AccountSearchParams {
     pub query: Option<String>,
+    #[serde(default)]
+    pub match_mode: MatchMode,
     pub group_id: Option<String>,
+    /// Also match accounts in subgroups of `group_id`
+    #[serde(default)]
+    pub include_subgroups: bool,
     pub tag_id: Option<String>,
     pub year: Option<String>,
     pub offset: Option<i64>,
@@ -90,6 +100,8 @@ pub struct AccountSearchParams {
 #[derive(Debug, Clone, serde::Deserialize)]
 pub struct BatchDeleteRequest {
     pub ids: Vec<String>,
+    #[serde(default)]
+    pub mode: BatchMode,
 }
 
 /// Batch update request
@@ -97,6 +109,43 @@ pub struct BatchDeleteRequest {
 pub struct BatchUpdateRequest {
     pub ids: Vec<String>,
     pub updates: UpdateAccountPayload,
+    #[serde(default)]
+    pub mode: BatchMode,
+}
+
+/// Status of one account in a batch
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchItemStatus {
+    /// Applied and committed
+    Ok,
+    /// No account with this ID
+    NotFound,
+    /// Rejected (bad ID, bad field values or constraint violation)
+    ValidationError,
+    /// Valid, but not applied because another item failed in
+    /// all-or-nothing mode
+    RolledBack,
+}
+
+/// Per-account batch result
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct BatchItemResult {
+    pub id: String,
+    pub status: BatchItemStatus,
+    pub message: Option<String>,
+}
+
+/// Batch operation result
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct BatchResult {
+    pub mode: BatchMode,
+    /// Whether any changes were committed
+    pub committed: bool,
+    pub succeeded: usize,
+    pub failed: usize,
+    /// Results in request order
+    pub results: Vec<BatchItemResult>,
 }
 
 // ============================================================================
@@ -118,6 +167,14 @@ pub enum AccountError {
     #[error("Crypto error: {0}")]
     Crypto(#[from] CryptoError),
 
+    /// Search index error
+    #[error("Search index error: {0}")]
+    SearchIndex(#[from] SearchIndexError),
+
+    /// Undo history error
+    #[error("History error: {0}")]
+    History(#[from] HistoryError),
+
     /// Invalid input
     #[error("Invalid input: {0}")]
     InvalidInput(String),
@@ -135,7 +192,7 @@ pub type AccountResult<T> = std::result::Result<T, AccountError>;
 // ============================================================================
 
 /// Convert database account to frontend account with decryption
-fn db_to_frontend_account(db_account: DbAccount, key: &[u8; 32]) -> AccountResult<Account> {
+pub(crate) fn db_to_frontend_account(db_account: DbAccount, key: &SecretKey) -> AccountResult<Account> {
     // Decrypt sensitive fields
     let email = decrypt_field(&db_account.email, key)?;
     let password = decrypt_field(&db_account.password, key)?;
@@ -183,7 +240,7 @@ fn db_to_frontend_account(db_account: DbAccount, key: &[u8; 32]) -> AccountResul
 }
 
 /// Convert frontend creation payload to database creation payload with encryption
-fn frontend_to_db_create(payload: CreateAccountPayload, key: &[u8; 32]) -> AccountResult<CreateAccount> {
+pub(crate) fn frontend_to_db_create(payload: CreateAccountPayload, key: &SecretKey) -> AccountResult<CreateAccount> {
     // Validate required fields
     if payload.email.is_empty() {
         return Err(AccountError::InvalidInput("Email is required".to_string()));
@@ -239,7 +296,7 @@ fn frontend_to_db_create(payload: CreateAccountPayload, key: &[u8; 32]) -> Accou
 }
 
 /// Convert frontend update payload to database update payload with encryption
-fn frontend_to_db_update(payload: UpdateAccountPayload, key: &[u8; 32]) -> AccountResult<UpdateAccount> {
+fn frontend_to_db_update(payload: UpdateAccountPayload, key: &SecretKey) -> AccountResult<UpdateAccount> {
     // Parse account ID
     let id = payload.id.parse::<i64>()
         .map_err(|_| AccountError::InvalidInput("Invalid account ID".to_string()))?;
@@ -294,6 +351,239 @@ fn frontend_to_db_update(payload: UpdateAccountPayload, key: &[u8; 32]) -> Accou
     })
 }
 
+/// Blind search index tokens for the indexed fields being written
+///
+/// Mirrors the encryption rules above: a `None` or empty value on update
+/// means "unchanged" and leaves the field's tokens alone. The result is
+/// stored in the same transaction as the account row.
+pub(crate) fn written_field_tokens(
+    key: &SecretKey,
+    email: Option<&str>,
+    recovery_email: Option<&str>,
+    notes: Option<&str>,
+) -> Vec<(&'static str, Vec<String>)> {
+    [
+        ("email", email),
+        ("recovery_email", recovery_email),
+        ("notes", notes),
+    ]
+    .into_iter()
+    .filter_map(|(field, value)| {
+        value
+            .filter(|v| !v.is_empty())
+            .map(|v| (field, search_index::field_tokens(key, v)))
+    })
+    .collect()
+}
+
+/// Delete several accounts in one transaction
+pub fn batch_delete_accounts(
+    db: &Database,
+    key: &SecretKey,
+    request: BatchDeleteRequest,
+) -> AccountResult<BatchResult> {
+    let parsed: Vec<Result<i64, String>> = request.ids
+        .iter()
+        .map(|id| id.parse::<i64>().map_err(|_| "Invalid account ID".to_string()))
+        .collect();
+
+    let tracker = Tracker::capture(db, batch_targets(&parsed, |id| *id))?;
+
+    let results = run_batch(request.ids, request.mode, parsed, |ids| {
+        db.batch_delete_accounts(ids, request.mode)
+    })?;
+
+    if results.committed {
+        tracker.record(db, key, MutationKind::BatchDeleteAccounts)?;
+    }
+
+    Ok(results)
+}
+
+/// Apply the same update to several accounts in one transaction
+pub fn batch_update_accounts(
+    db: &Database,
+    key: &SecretKey,
+    request: BatchUpdateRequest,
+) -> AccountResult<BatchResult> {
+    let parsed: Vec<Result<UpdateAccount, String>> = request.ids
+        .iter()
+        .map(|id| {
+            let mut payload = request.updates.clone();
+            payload.id = id.clone();
+            frontend_to_db_update(payload, key).map_err(|e| match e {
+                AccountError::InvalidInput(msg) => msg,
+                other => other.to_string(),
+            })
+        })
+        .collect();
+
+    let tracker = Tracker::capture(db, batch_targets(&parsed, |update| update.id))?;
+
+    // Keep the search index in step with email/notes changes
+    let tokens = written_field_tokens(
+        key,
+        request.updates.email.as_deref(),
+        request.updates.recovery_email.as_deref(),
+        request.updates.notes.as_deref(),
+    );
+
+    let results = run_batch(request.ids.clone(), request.mode, parsed, |updates| {
+        db.batch_update_accounts(updates, &tokens, request.mode)
+    })?;
+
+    if results.committed {
+        tracker.record(db, key, MutationKind::BatchUpdateAccounts)?;
+    }
+
+    Ok(results)
+}
+
+/// History targets for the valid items of a batch
+fn batch_targets<T>(parsed: &[Result<T, String>], id: impl Fn(&T) -> i64) -> Vec<Target> {
+    parsed
+        .iter()
+        .filter_map(|item| item.as_ref().ok())
+        .map(|item| Target::Account(id(item)))
+        .collect()
+}
+
+/// Validate batch items, run the valid ones and merge both into one report
+///
+/// In all-or-nothing mode a validation failure means nothing is sent to the
+/// database at all. Items are accounts: `ids` are the requested account IDs.
+pub(crate) fn run_batch<T>(
+    ids: Vec<String>,
+    mode: BatchMode,
+    parsed: Vec<Result<T, String>>,
+    execute: impl FnOnce(&[T]) -> Result<BatchOutcome, DbError>,
+) -> Result<BatchResult, DbError> {
+    let has_invalid = parsed.iter().any(|p| p.is_err());
+
+    let mut valid = Vec::new();
+    let mut errors = Vec::with_capacity(parsed.len());
+    for item in parsed {
+        match item {
+            Ok(value) => {
+                valid.push(value);
+                errors.push(None);
+            }
+            Err(msg) => errors.push(Some(msg)),
+        }
+    }
+
+    let (committed, mut outcomes) = if has_invalid && mode == BatchMode::AllOrNothing {
+        (false, Vec::new().into_iter())
+    } else {
+        let outcome = execute(&valid)?;
+        (outcome.committed, outcome.items.into_iter())
+    };
+
+    let results: Vec<BatchItemResult> = ids
+        .into_iter()
+        .zip(errors)
+        .map(|(id, error)| {
+            let (status, message) = match error {
+                Some(msg) => (BatchItemStatus::ValidationError, Some(msg)),
+                None => match outcomes.next() {
+                    Some(BatchItemOutcome::Applied) if committed => (BatchItemStatus::Ok, None),
+                    Some(BatchItemOutcome::NotFound) => {
+                        (BatchItemStatus::NotFound, Some(format!("Account {} not found", id)))
+                    }
+                    Some(BatchItemOutcome::Invalid(msg)) => (BatchItemStatus::ValidationError, Some(msg)),
+                    // Applied but rolled back, or never run
+                    _ => (BatchItemStatus::RolledBack, None),
+                },
+            };
+            BatchItemResult { id, status, message }
+        })
+        .collect();
+
+    let succeeded = results.iter().filter(|r| r.status == BatchItemStatus::Ok).count();
+
+    Ok(BatchResult {
+        mode,
+        committed,
+        succeeded,
+        failed: results.len() - succeeded,
+        results,
+    })
+}
+
+/// Search accounts, decrypting the results
+///
+/// With a text query, `offset` and `limit` count verified matches: rows
+/// that only matched through a blind index false positive are dropped and
+/// the page is refilled from the following rows, so a page is only short
+/// when there are no more matches.
+pub fn search_accounts(db: &Database, key: &SecretKey, params: AccountSearchParams) -> AccountResult<Vec<Account>> {
+    // Turn the text query into blind index tokens
+    let query = params.query.filter(|q| !q.trim().is_empty());
+    let query_tokens = query
+        .as_deref()
+        .map(|q| search_index::query_tokens(key, q, params.match_mode))
+        .unwrap_or_default();
+
+    let offset = params.offset.unwrap_or(0).max(0);
+    let limit = params.limit.unwrap_or(50);
+    let mut search = AccountSearch {
+        query_tokens,
+        group_id: params.group_id.and_then(|id| id.parse::<i64>().ok()),
+        include_subgroups: params.include_subgroups,
+        tag_id: params.tag_id.and_then(|id| id.parse::<i64>().ok()),
+        year: params.year.and_then(|y| y.parse::<i32>().ok()),
+        pagination: Pagination { offset, limit },
+    };
+
+    let Some(query) = query else {
+        return db.search_accounts(search)?
+            .into_iter()
+            .map(|acc| db_to_frontend_account(acc, key))
+            .collect();
+    };
+    if limit <= 0 {
+        return Ok(Vec::new());
+    }
+
+    // Matches before `offset` cannot be told apart from false positives
+    // without decrypting them, so scan from the first row
+    let mut page = Vec::new();
+    let mut skipped = 0;
+    let mut row_offset = 0;
+    loop {
+        search.pagination = Pagination { offset: row_offset, limit };
+        let rows = db.search_accounts(search.clone())?;
+        let fetched = rows.len() as i64;
+
+        for row in rows {
+            let account = db_to_frontend_account(row, key)?;
+            if !account_matches_query(&account, &query, params.match_mode) {
+                continue;
+            }
+            if skipped < offset {
+                skipped += 1;
+                continue;
+            }
+            page.push(account);
+            if page.len() as i64 == limit {
+                return Ok(page);
+            }
+        }
+
+        if fetched < limit {
+            return Ok(page);
+        }
+        row_offset += limit;
+    }
+}
+
+/// Check whether a decrypted account matches a text query
+fn account_matches_query(account: &Account, query: &str, mode: MatchMode) -> bool {
+    search_index::matches_query(&account.email, query, mode)
+        || account.recovery_email.as_deref().is_some_and(|v| search_index::matches_query(v, query, mode))
+        || account.notes.as_deref().is_some_and(|v| search_index::matches_query(v, query, mode))
+}
+
 // ============================================================================
 // Tauri Commands
 // ============================================================================
@@ -365,12 +655,25 @@ pub fn create_account_command(
     let key = session_manager.get_session_key()
         .map_err(|e| e.to_string())?;
 
+    // Keep the plaintext of indexed fields for the search index
+    let (email, recovery_email, notes) = (
+        account.email.clone(),
+        account.recovery_email.clone(),
+        account.notes.clone(),
+    );
+
     // Convert and encrypt
     let create_account = frontend_to_db_create(account, &key)
         .map_err(|e| e.to_string())?;
 
+    let tokens = written_field_tokens(&key, Some(&email), recovery_email.as_deref(), notes.as_deref());
+
     // Create in database
-    let id = db.create_account(create_account)
+    let id = db.create_account_with_tokens(create_account, &tokens)
+        .map_err(|e| e.to_string())?;
+
+    Tracker::created(Target::Account(id))
+        .record(&db, &key, MutationKind::CreateAccount)
         .map_err(|e| e.to_string())?;
 
     Ok(id.to_string())
@@ -389,12 +692,28 @@ pub fn update_account_command(
     let key = session_manager.get_session_key()
         .map_err(|e| e.to_string())?;
 
+    // Keep the plaintext of indexed fields for the search index
+    let (email, recovery_email, notes) = (
+        account.email.clone(),
+        account.recovery_email.clone(),
+        account.notes.clone(),
+    );
+
     // Convert and encrypt
     let update_account = frontend_to_db_update(account, &key)
         .map_err(|e| e.to_string())?;
+    let account_id = update_account.id;
+
+    let tracker = Tracker::capture(&db, vec![Target::Account(account_id)])
+        .map_err(|e| e.to_string())?;
+
+    let tokens = written_field_tokens(&key, email.as_deref(), recovery_email.as_deref(), notes.as_deref());
 
     // Update in database
-    db.update_account(update_account)
+    db.update_account_with_tokens(update_account, &tokens)
+        .map_err(|e| e.to_string())?;
+
+    tracker.record(&db, &key, MutationKind::UpdateAccount)
         .map_err(|e| e.to_string())?;
 
     Ok(())
@@ -404,27 +723,40 @@ pub fn update_account_command(
 #[tauri::command]
 pub fn delete_account_command(
     db: tauri::State<Database>,
+    session_manager: tauri::State<SessionManager>,
     id: String,
 ) -> Result<(), String> {
+    // Get session key
+    let key = session_manager.get_session_key()
+        .map_err(|e| e.to_string())?;
+
     // Parse ID
     let account_id = id.parse::<i64>()
         .map_err(|_| "Invalid account ID".to_string())?;
 
+    let tracker = Tracker::capture(&db, vec![Target::Account(account_id)])
+        .map_err(|e| e.to_string())?;
+
     // Delete from database
     db.delete_account(account_id)
         .map_err(|e| e.to_string())?;
 
+    tracker.record(&db, &key, MutationKind::DeleteAccount)
+        .map_err(|e| e.to_string())?;
+
     Ok(())
 }
 
 /// Search accounts with filters
 ///
 /// Supports searching by:
-/// - Text query (searches email, recovery_email, notes)
-/// - Group ID filter
+/// - Text query (searches email, recovery_email, notes through the blind
+///   index; `match_mode` selects contains, prefix or exact matching)
+/// - Group ID filter (with `include_subgroups`, accounts in nested groups too)
 /// - Tag ID filter
 /// - Year filter
-/// - Pagination (offset/limit)
+/// - Pagination (offset/limit; with a query they count verified matches,
+///   so only the last page is shorter than `limit`)
 #[tauri::command]
 pub fn search_accounts_command(
     db: tauri::State<Database>,
@@ -435,27 +767,8 @@ pub fn search_accounts_command(
     let key = session_manager.get_session_key()
         .map_err(|e| e.to_string())?;
 
-    // Build search parameters
-    let search = AccountSearch {
-        query: params.query,
-        group_id: params.group_id.and_then(|id| id.parse::<i64>().ok()),
-        tag_id: params.tag_id.and_then(|id| id.parse::<i64>().ok()),
-        year: params.year.and_then(|y| y.parse::<i32>().ok()),
-        pagination: Pagination {
-            offset: params.offset.unwrap_or(0),
-            limit: params.limit.unwrap_or(50),
-        },
-    };
-
-    // Search database
-    let db_accounts = db.search_accounts(search)
-        .map_err(|e| e.to_string())?;
-
-    // Convert and decrypt
-    db_accounts
-        .into_iter()
-        .map(|acc| db_to_frontend_account(acc, &key).map_err(|e| e.to_string()))
-        .collect()
+    search_accounts(&db, &key, params)
+        .map_err(|e| e.to_string())
 }
 
 /// Get total count of accounts
@@ -468,54 +781,38 @@ pub fn get_accounts_count_command(
 }
 
 /// Batch delete multiple accounts
+///
+/// Runs in one transaction and reports the outcome for every ID.
 #[tauri::command]
 pub fn batch_delete_accounts_command(
     db: tauri::State<Database>,
+    session_manager: tauri::State<SessionManager>,
     request: BatchDeleteRequest,
-) -> Result<usize, String> {
-    let mut deleted = 0;
-
-    for id_str in &request.ids {
-        if let Ok(id) = id_str.parse::<i64>() {
-            if db.delete_account(id).is_ok() {
-                deleted += 1;
-            }
-        }
-    }
+) -> Result<BatchResult, String> {
+    // Get session key
+    let key = session_manager.get_session_key()
+        .map_err(|e| e.to_string())?;
 
-    Ok(deleted)
+    batch_delete_accounts(&db, &key, request)
+        .map_err(|e| e.to_string())
 }
 
 /// Batch update multiple accounts
 ///
-/// Applies the same updates to all specified accounts.
+/// Applies the same updates to all specified accounts in one transaction and
+/// reports the outcome for every ID.
 #[tauri::command]
 pub fn batch_update_accounts_command(
     db: tauri::State<Database>,
     session_manager: tauri::State<SessionManager>,
     request: BatchUpdateRequest,
-) -> Result<usize, String> {
+) -> Result<BatchResult, String> {
     // Get session key
     let key = session_manager.get_session_key()
         .map_err(|e| e.to_string())?;
 
-    let mut updated = 0;
-
-    for id_str in &request.ids {
-        if let Ok(id) = id_str.parse::<i64>() {
-            // Create update payload for this specific ID
-            let mut update_payload = request.updates.clone();
-            update_payload.id = id_str.clone();
-
-            if let Ok(db_update) = frontend_to_db_update(update_payload, &key) {
-                if db.update_account(db_update).is_ok() {
-                    updated += 1;
-                }
-            }
-        }
-    }
-
-    Ok(updated)
+    batch_update_accounts(&db, &key, request)
+        .map_err(|e| e.to_string())
 }
 
 /// Get account statistics
@@ -565,8 +862,8 @@ mod tests {
         Database::init_in_memory().expect("Failed to create test database")
     }
 
-    fn get_test_key() -> [u8; 32] {
-        derive_key(b"test-password", b"test-salt-16-bytes!!")
+    fn get_test_key() -> SecretKey {
+        derive_key(b"test-password", b"test-salt-16byte")
     }
 
     #[test]
@@ -709,4 +1006,220 @@ mod tests {
         let result = frontend_to_db_create(payload, &key).unwrap();
         assert_eq!(result.field_order, Some("email,password,notes".to_string()));
     }
+
+    fn create_test_account(db: &Database, key: &SecretKey, email: &str) -> String {
+        let payload = CreateAccountPayload {
+            raw_import_id: None,
+            email: email.to_string(),
+            password: "secret123".to_string(),
+            recovery_email: None,
+            totp_secret: None,
+            year: None,
+            notes: None,
+            group_id: None,
+            field_order: None,
+        };
+
+        let create = frontend_to_db_create(payload, key).unwrap();
+        db.create_account(create).unwrap().to_string()
+    }
+
+    fn create_indexed_account(db: &Database, key: &SecretKey, email: &str) -> String {
+        let id = create_test_account(db, key, email);
+        search_index::index_account_fields(db, key, id.parse().unwrap(), &[("email", Some(email))]).unwrap();
+        id
+    }
+
+    fn statuses(result: &BatchResult) -> Vec<BatchItemStatus> {
+        result.results.iter().map(|r| r.status).collect()
+    }
+
+    #[test]
+    fn test_batch_delete_invalid_id_aborts_all_or_nothing() {
+        let db = get_test_db();
+        let key = get_test_key();
+        let id = create_test_account(&db, &key, "a@example.com");
+
+        let result = batch_delete_accounts(&db, &key, BatchDeleteRequest {
+            ids: vec![id, "abc".to_string()],
+            mode: BatchMode::AllOrNothing,
+        }).unwrap();
+
+        assert!(!result.committed);
+        assert_eq!(statuses(&result), vec![BatchItemStatus::RolledBack, BatchItemStatus::ValidationError]);
+        assert_eq!((result.succeeded, result.failed), (0, 2));
+        assert_eq!(db.get_accounts_count().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_batch_delete_best_effort_per_id_results() {
+        let db = get_test_db();
+        let key = get_test_key();
+        let id = create_test_account(&db, &key, "a@example.com");
+
+        let result = batch_delete_accounts(&db, &key, BatchDeleteRequest {
+            ids: vec!["abc".to_string(), id.clone(), "999".to_string()],
+            mode: BatchMode::BestEffort,
+        }).unwrap();
+
+        assert!(result.committed);
+        assert_eq!(
+            statuses(&result),
+            vec![BatchItemStatus::ValidationError, BatchItemStatus::Ok, BatchItemStatus::NotFound]
+        );
+        assert_eq!(result.results[1].id, id);
+        assert_eq!(db.get_accounts_count().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_batch_update_applies_and_reindexes() {
+        let db = get_test_db();
+        let key = get_test_key();
+        let first = create_test_account(&db, &key, "a@example.com");
+        let second = create_test_account(&db, &key, "b@example.com");
+
+        let mut updates = UpdateAccountPayload {
+            id: String::new(),
+            email: None,
+            password: None,
+            recovery_email: None,
+            totp_secret: None,
+            year: None,
+            notes: Some("shared note".to_string()),
+            group_id: None,
+            field_order: None,
+        };
+
+        let result = batch_update_accounts(&db, &key, BatchUpdateRequest {
+            ids: vec![first.clone(), second.clone()],
+            updates: updates.clone(),
+            mode: BatchMode::AllOrNothing,
+        }).unwrap();
+
+        assert!(result.committed);
+        assert_eq!(result.succeeded, 2);
+        let account = db.get_account(first.parse().unwrap()).unwrap();
+        assert_eq!(decrypt_field(account.notes.as_ref().unwrap(), &key).unwrap(), "shared note");
+
+        // Nothing to update is a validation error for every item
+        updates.notes = None;
+        let result = batch_update_accounts(&db, &key, BatchUpdateRequest {
+            ids: vec![first, second],
+            updates,
+            mode: BatchMode::BestEffort,
+        }).unwrap();
+
+        assert_eq!(result.succeeded, 0);
+        assert_eq!(statuses(&result), vec![BatchItemStatus::ValidationError; 2]);
+    }
+
+    #[test]
+    fn test_batch_update_tokens_follow_the_transaction() {
+        let db = get_test_db();
+        let key = get_test_key();
+        let id = create_indexed_account(&db, &key, "old@example.com");
+
+        let search = |query: &str| -> usize {
+            search_accounts(&db, &key, AccountSearchParams {
+                query: Some(query.to_string()),
+                ..Default::default()
+            })
+            .unwrap()
+            .len()
+        };
+        let request = |mode| BatchUpdateRequest {
+            ids: vec![id.clone(), "999".to_string()],
+            updates: UpdateAccountPayload {
+                id: String::new(),
+                email: Some("new@example.com".to_string()),
+                password: None,
+                recovery_email: None,
+                totp_secret: None,
+                year: None,
+                notes: None,
+                group_id: None,
+                field_order: None,
+            },
+            mode,
+        };
+
+        // A rolled back batch leaves the old tokens in place
+        let result = batch_update_accounts(&db, &key, request(BatchMode::AllOrNothing)).unwrap();
+        assert!(!result.committed);
+        assert_eq!((search("old@"), search("new@")), (1, 0));
+
+        let result = batch_update_accounts(&db, &key, request(BatchMode::BestEffort)).unwrap();
+        assert!(result.committed);
+        assert_eq!((search("old@"), search("new@")), (0, 1));
+    }
+
+    #[test]
+    fn test_search_pages_skip_false_positives() {
+        let db = get_test_db();
+        let key = get_test_key();
+        // "abc.bcd" carries both trigrams of "abcd" without containing it
+        for email in [
+            "abc.bcd1@example.com",
+            "abcd1@example.com",
+            "abc.bcd2@example.com",
+            "abc.bcd3@example.com",
+            "abcd2@example.com",
+            "abc.bcd4@example.com",
+            "abcd3@example.com",
+        ] {
+            create_indexed_account(&db, &key, email);
+        }
+
+        let page = |offset: i64| -> Vec<String> {
+            search_accounts(&db, &key, AccountSearchParams {
+                query: Some("abcd".to_string()),
+                offset: Some(offset),
+                limit: Some(2),
+                ..Default::default()
+            })
+            .unwrap()
+            .into_iter()
+            .map(|a| a.email)
+            .collect()
+        };
+
+        let first = page(0);
+        let second = page(2);
+        assert_eq!(first.len(), 2);
+        assert_eq!(second.len(), 1);
+        let mut found: Vec<String> = first.into_iter().chain(second).collect();
+        found.sort();
+        assert_eq!(found, vec!["abcd1@example.com", "abcd2@example.com", "abcd3@example.com"]);
+        assert!(page(3).is_empty());
+    }
+
+    #[test]
+    fn test_search_pages_with_equal_timestamps() {
+        let db = get_test_db();
+        let key = get_test_key();
+        for i in 0..6 {
+            create_indexed_account(&db, &key, &format!("abc.bcd{}@example.com", i));
+            create_indexed_account(&db, &key, &format!("abcd{}@example.com", i));
+        }
+        // A bulk import writes many rows within the same second
+        db.get_conn()
+            .unwrap()
+            .execute("UPDATE accounts SET created_at = '2024-01-01 00:00:00'", [])
+            .unwrap();
+
+        let mut found = Vec::new();
+        for offset in 0..8 {
+            let page = search_accounts(&db, &key, AccountSearchParams {
+                query: Some("abcd".to_string()),
+                offset: Some(offset),
+                limit: Some(1),
+                ..Default::default()
+            })
+            .unwrap();
+            found.extend(page.into_iter().map(|a| a.email));
+        }
+
+        let expected: Vec<String> = (0..6).rev().map(|i| format!("abcd{}@example.com", i)).collect();
+        assert_eq!(found, expected);
+    }
 }
@@ -0,0 +1,507 @@
+//! Audit log commands for GManager Desktop
+//!
+//! The log itself is written by the database layer; see [`crate::db::audit`]
+//! for how entries are encrypted and chained. This module exposes it to the
+//! frontend:
+//!
+//! - Paginated, filterable queries with the details decrypted
+//! - CSV and JSON export
+//! - A retention policy, applied at startup, that prunes old entries
+
+use crate::auth::SessionManager;
+use crate::crypto::{decrypt_field, is_encrypted, SecretKey};
+use crate::db::audit::AuditReport;
+use crate::db::{Database, DbError, LogSearch, OperationLog, Pagination};
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+// ============================================================================
+// Constants
+// ============================================================================
+
+/// Settings key for the stored retention policy
+const LOG_RETENTION_SETTING: &str = "log_retention_policy";
+
+/// Largest page returned by `get_operation_logs_command`
+pub const MAX_LOG_PAGE_SIZE: i64 = 500;
+
+/// Format of `created_at` in `operation_logs`
+const LOG_TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+// ============================================================================
+// Type Definitions
+// ============================================================================
+
+/// Operation log filters from the frontend
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LogQuery {
+    pub account_id: Option<String>,
+    /// Action name, e.g. `CREATE` (case-insensitive)
+    pub action: Option<String>,
+    /// Start of the range (RFC 3339 or `YYYY-MM-DD`), inclusive
+    pub from: Option<String>,
+    /// End of the range (RFC 3339 or `YYYY-MM-DD`); a plain date includes
+    /// the whole day
+    pub to: Option<String>,
+}
+
+/// Operation log entry with decrypted details
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogEntry {
+    pub id: String,
+    pub account_id: Option<String>,
+    pub action: String,
+    /// None when the entry has no details or they cannot be decrypted
+    pub details: Option<String>,
+    pub created_at: String,
+}
+
+/// A page of log entries
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogPage {
+    pub entries: Vec<LogEntry>,
+    /// Number of entries matching the filters
+    pub total: i64,
+    pub offset: i64,
+    pub limit: i64,
+}
+
+/// Export file format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogExportFormat {
+    Csv,
+    Json,
+}
+
+/// How long log entries are kept
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LogRetentionPolicy {
+    /// Entries older than this many days are removed at startup (None keeps
+    /// every entry)
+    pub max_age_days: Option<i64>,
+}
+
+// ============================================================================
+// Error Types
+// ============================================================================
+
+/// Audit log operation errors
+#[derive(Debug, Error)]
+pub enum AuditError {
+    /// Database error
+    #[error("Database error: {0}")]
+    Database(#[from] DbError),
+
+    /// Invalid filter or policy
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+
+    /// Export could not be written
+    #[error("Export failed: {0}")]
+    Export(String),
+}
+
+/// Result type for audit log operations
+pub type AuditResult<T> = std::result::Result<T, AuditError>;
+
+// ============================================================================
+// Queries
+// ============================================================================
+
+/// Get a page of log entries, newest first
+pub fn get_logs(
+    db: &Database,
+    key: &SecretKey,
+    query: &LogQuery,
+    offset: i64,
+    limit: i64,
+) -> AuditResult<LogPage> {
+    if !(1..=MAX_LOG_PAGE_SIZE).contains(&limit) || offset < 0 {
+        return Err(AuditError::InvalidInput(format!(
+            "Page size must be between 1 and {}",
+            MAX_LOG_PAGE_SIZE
+        )));
+    }
+
+    let search = parse_log_query(query)?;
+    let logs = db.search_operation_logs(&search, Some(&Pagination::new(offset, limit)))?;
+
+    Ok(LogPage {
+        entries: logs.into_iter().map(|log| open_entry(log, key)).collect(),
+        total: db.count_operation_logs(&search)?,
+        offset,
+        limit,
+    })
+}
+
+/// Export every log entry matching `query`
+///
+/// The export contains decrypted details (account emails among them) and
+/// is not chained; `verify_audit_log_command` only covers the database.
+pub fn export_logs(
+    db: &Database,
+    key: &SecretKey,
+    query: &LogQuery,
+    format: LogExportFormat,
+) -> AuditResult<String> {
+    let search = parse_log_query(query)?;
+    let entries: Vec<LogEntry> = db
+        .search_operation_logs(&search, None)?
+        .into_iter()
+        .map(|log| open_entry(log, key))
+        .collect();
+
+    match format {
+        LogExportFormat::Json => {
+            serde_json::to_string_pretty(&entries).map_err(|e| AuditError::Export(e.to_string()))
+        }
+        LogExportFormat::Csv => write_csv(&entries),
+    }
+}
+
+fn write_csv(entries: &[LogEntry]) -> AuditResult<String> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    let export_err = |e: csv::Error| AuditError::Export(e.to_string());
+
+    writer
+        .write_record(["id", "created_at", "action", "account_id", "details"])
+        .map_err(export_err)?;
+    for entry in entries {
+        writer
+            .write_record([
+                entry.id.as_str(),
+                entry.created_at.as_str(),
+                entry.action.as_str(),
+                entry.account_id.as_deref().unwrap_or(""),
+                &csv_cell(entry.details.as_deref().unwrap_or("")),
+            ])
+            .map_err(export_err)?;
+    }
+
+    let bytes = writer.into_inner().map_err(|e| AuditError::Export(e.to_string()))?;
+    String::from_utf8(bytes).map_err(|e| AuditError::Export(e.to_string()))
+}
+
+/// Keep spreadsheets from evaluating a cell as a formula
+fn csv_cell(value: &str) -> String {
+    if value.starts_with(['=', '+', '-', '@']) {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    }
+}
+
+/// Convert a stored entry for the frontend
+fn open_entry(log: OperationLog, key: &SecretKey) -> LogEntry {
+    // Entries sealed before the session started are still plaintext
+    let details = log.details.and_then(|details| {
+        if is_encrypted(&details) {
+            decrypt_field(&details, key).ok()
+        } else {
+            Some(details)
+        }
+    });
+
+    LogEntry {
+        id: log.id.to_string(),
+        account_id: log.account_id.map(|id| id.to_string()),
+        action: log.action,
+        details,
+        created_at: log.created_at,
+    }
+}
+
+/// Validate the frontend filters and convert them for the database
+fn parse_log_query(query: &LogQuery) -> AuditResult<LogSearch> {
+    let account_id = query
+        .account_id
+        .as_deref()
+        .map(|id| {
+            id.parse::<i64>()
+                .map_err(|_| AuditError::InvalidInput(format!("Invalid account ID: {}", id)))
+        })
+        .transpose()?;
+
+    let action = query
+        .action
+        .as_deref()
+        .map(str::trim)
+        .filter(|a| !a.is_empty())
+        .map(str::to_uppercase);
+
+    Ok(LogSearch {
+        account_id,
+        action,
+        from: query.from.as_deref().map(|d| parse_bound(d, false)).transpose()?,
+        to: query.to.as_deref().map(|d| parse_bound(d, true)).transpose()?,
+    })
+}
+
+/// Normalize a range bound to the `created_at` format (UTC)
+///
+/// A plain date as the upper bound means the end of that day.
+fn parse_bound(value: &str, upper: bool) -> AuditResult<String> {
+    let value = value.trim();
+
+    let timestamp = if let Ok(date_time) = DateTime::parse_from_rfc3339(value) {
+        date_time.with_timezone(&Utc).naive_utc()
+    } else if let Ok(date_time) = NaiveDateTime::parse_from_str(value, LOG_TIMESTAMP_FORMAT) {
+        date_time
+    } else if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        let day = if upper { date + Duration::days(1) } else { date };
+        day.and_hms_opt(0, 0, 0).unwrap_or_default()
+    } else {
+        return Err(AuditError::InvalidInput(format!("Invalid date: {}", value)));
+    };
+
+    Ok(timestamp.format(LOG_TIMESTAMP_FORMAT).to_string())
+}
+
+// ============================================================================
+// Retention
+// ============================================================================
+
+/// Load the stored retention policy (defaults to keeping every entry)
+pub fn load_retention_policy(db: &Database) -> AuditResult<LogRetentionPolicy> {
+    match db.get_setting(LOG_RETENTION_SETTING)? {
+        Some(json) => {
+            let policy: LogRetentionPolicy = serde_json::from_str(&json)
+                .map_err(|e| AuditError::InvalidInput(e.to_string()))?;
+            validate_retention_policy(&policy)?;
+            Ok(policy)
+        }
+        None => Ok(LogRetentionPolicy::default()),
+    }
+}
+
+/// Validate and store a retention policy
+pub fn save_retention_policy(db: &Database, policy: LogRetentionPolicy) -> AuditResult<()> {
+    validate_retention_policy(&policy)?;
+
+    let json = serde_json::to_string(&policy)
+        .map_err(|e| AuditError::InvalidInput(e.to_string()))?;
+    db.set_setting(LOG_RETENTION_SETTING, &json)?;
+
+    Ok(())
+}
+
+fn validate_retention_policy(policy: &LogRetentionPolicy) -> AuditResult<()> {
+    match policy.max_age_days {
+        Some(days) if days < 1 => Err(AuditError::InvalidInput(
+            "Log retention must be at least 1 day".to_string(),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Prune entries older than the stored policy allows
+///
+/// # Returns
+/// Number of entries removed
+pub fn apply_retention_policy(db: &Database) -> AuditResult<i64> {
+    match load_retention_policy(db)?.max_age_days {
+        Some(days) => Ok(db.clear_old_logs(days)?),
+        None => Ok(0),
+    }
+}
+
+// ============================================================================
+// Tauri Commands
+// ============================================================================
+
+/// Get a page of operation log entries, newest first
+///
+/// # Arguments
+/// * `query` - Filters by account, action and date range
+/// * `offset` - Number of entries to skip
+/// * `limit` - Page size (at most `MAX_LOG_PAGE_SIZE`)
+#[tauri::command]
+pub fn get_operation_logs_command(
+    db: tauri::State<Database>,
+    session_manager: tauri::State<SessionManager>,
+    query: Option<LogQuery>,
+    offset: i64,
+    limit: i64,
+) -> Result<LogPage, String> {
+    let key = session_manager.get_session_key()
+        .map_err(|e| e.to_string())?;
+
+    get_logs(&db, &key, &query.unwrap_or_default(), offset, limit)
+        .map_err(|e| e.to_string())
+}
+
+/// Export operation log entries as CSV or JSON
+///
+/// # Returns
+/// File contents for the frontend to save
+#[tauri::command]
+pub fn export_operation_logs_command(
+    db: tauri::State<Database>,
+    session_manager: tauri::State<SessionManager>,
+    query: Option<LogQuery>,
+    format: LogExportFormat,
+) -> Result<String, String> {
+    let key = session_manager.get_session_key()
+        .map_err(|e| e.to_string())?;
+
+    export_logs(&db, &key, &query.unwrap_or_default(), format)
+        .map_err(|e| e.to_string())
+}
+
+/// Remove operation log entries older than `days`
+///
+/// # Returns
+/// Number of entries removed
+#[tauri::command]
+pub fn clear_old_logs_command(
+    db: tauri::State<Database>,
+    session_manager: tauri::State<SessionManager>,
+    days: i64,
+) -> Result<i64, String> {
+    session_manager.get_session_key()
+        .map_err(|e| e.to_string())?;
+
+    if days < 1 {
+        return Err("Days must be at least 1".to_string());
+    }
+
+    db.clear_old_logs(days)
+        .map_err(|e| e.to_string())
+}
+
+/// Get the log retention policy
+#[tauri::command]
+pub fn get_log_retention_command(
+    db: tauri::State<Database>,
+) -> Result<LogRetentionPolicy, String> {
+    load_retention_policy(&db)
+        .map_err(|e| e.to_string())
+}
+
+/// Update the log retention policy and apply it right away
+///
+/// # Returns
+/// Number of entries removed
+#[tauri::command]
+pub fn set_log_retention_command(
+    db: tauri::State<Database>,
+    session_manager: tauri::State<SessionManager>,
+    policy: LogRetentionPolicy,
+) -> Result<i64, String> {
+    session_manager.get_session_key()
+        .map_err(|e| e.to_string())?;
+
+    save_retention_policy(&db, policy)
+        .and_then(|_| apply_retention_policy(&db))
+        .map_err(|e| e.to_string())
+}
+
+/// Verify that no audit log entry was altered or removed
+///
+/// # Returns
+/// Report listing every broken, altered or unsealed entry
+#[tauri::command]
+pub fn verify_audit_log_command(
+    db: tauri::State<Database>,
+    session_manager: tauri::State<SessionManager>,
+) -> Result<AuditReport, String> {
+    let key = session_manager.get_session_key()
+        .map_err(|e| e.to_string())?;
+
+    db.verify_audit_log(&key)
+        .map_err(|e| e.to_string())
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::derive_key;
+    use std::sync::Arc;
+
+    fn setup() -> (Database, Arc<SecretKey>) {
+        let db = Database::init_in_memory().expect("Failed to create test database");
+        let key = Arc::new(derive_key(b"test-password", b"test-salt-16byte"));
+        db.set_audit_key(&key).unwrap();
+        (db, key)
+    }
+
+    #[test]
+    fn test_get_logs_decrypts_and_filters() {
+        let (db, key) = setup();
+        db.log_operation(None, "CREATE", Some("Created account: a@example.com")).unwrap();
+        db.log_operation(None, "UPDATE", Some("Updated account")).unwrap();
+
+        let query = LogQuery {
+            action: Some("create".to_string()),
+            ..Default::default()
+        };
+        let page = get_logs(&db, &key, &query, 0, 50).unwrap();
+
+        assert_eq!(page.total, 1);
+        assert_eq!(page.entries[0].details.as_deref(), Some("Created account: a@example.com"));
+        assert!(get_logs(&db, &key, &query, 0, MAX_LOG_PAGE_SIZE + 1).is_err());
+    }
+
+    #[test]
+    fn test_parse_bound() {
+        assert_eq!(parse_bound("2024-03-01", false).unwrap(), "2024-03-01 00:00:00");
+        assert_eq!(parse_bound("2024-03-01", true).unwrap(), "2024-03-02 00:00:00");
+        assert_eq!(
+            parse_bound("2024-03-01T12:00:00+02:00", false).unwrap(),
+            "2024-03-01 10:00:00"
+        );
+        assert!(parse_bound("yesterday", false).is_err());
+    }
+
+    #[test]
+    fn test_export_formats() {
+        let (db, key) = setup();
+        db.log_operation(None, "CREATE", Some("=HYPERLINK(\"x\")")).unwrap();
+
+        let csv = export_logs(&db, &key, &LogQuery::default(), LogExportFormat::Csv).unwrap();
+        let mut lines = csv.lines();
+        assert_eq!(lines.next(), Some("id,created_at,action,account_id,details"));
+        assert!(lines.next().unwrap().contains("'=HYPERLINK"));
+
+        let json = export_logs(&db, &key, &LogQuery::default(), LogExportFormat::Json).unwrap();
+        let entries: Vec<LogEntry> = serde_json::from_str(&json).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].action, "CREATE");
+    }
+
+    #[test]
+    fn test_retention_policy() {
+        let (db, _key) = setup();
+        assert_eq!(load_retention_policy(&db).unwrap(), LogRetentionPolicy::default());
+        assert!(save_retention_policy(&db, LogRetentionPolicy { max_age_days: Some(0) }).is_err());
+
+        db.log_operation(None, "OLD", None).unwrap();
+        db.log_operation(None, "NEW", None).unwrap();
+        db.get_conn().unwrap()
+            .execute(
+                "UPDATE operation_logs SET created_at = datetime('now', '-100 days') WHERE action = 'OLD'",
+                [],
+            )
+            .unwrap();
+
+        // Keeping everything removes nothing
+        assert_eq!(apply_retention_policy(&db).unwrap(), 0);
+
+        save_retention_policy(&db, LogRetentionPolicy { max_age_days: Some(30) }).unwrap();
+        assert_eq!(apply_retention_policy(&db).unwrap(), 1);
+
+        let actions: Vec<String> = db
+            .search_operation_logs(&LogSearch::default(), None)
+            .unwrap()
+            .into_iter()
+            .map(|log| log.action)
+            .collect();
+        assert_eq!(actions, vec!["PRUNE_LOGS", "NEW"]);
+    }
+}
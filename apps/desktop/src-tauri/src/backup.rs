@@ -0,0 +1,623 @@
+//! Database backups for GManager Desktop
+//!
+//! Backups are full copies of the (encrypted) database file, written to a
+//! configurable directory as `gmanager-backup-YYYYMMDD-HHMMSS-mmm.db` (UTC).
+//! Each backup is written to a temporary file, verified with
+//! `PRAGMA integrity_check` and only then renamed into place.
+//!
+//! With scheduling enabled in the [`BackupPolicy`], a background task on
+//! the Tauri async runtime writes a backup whenever the newest one is older
+//! than the configured interval, then rotates old copies: the newest backup
+//! of each of the last `keep_daily` days and of each of the last
+//! `keep_weekly` ISO weeks is kept, everything else is deleted.
+//!
+//! Restoring first writes a backup of the current database, then replaces
+//! it atomically (see [`crate::db::backup`]) and locks the session, since
+//! the restored vault may have a different master password.
+
+use crate::auth::SessionManager;
+use crate::db::backup::verify_backup_file;
+use crate::db::{Database, DbError};
+use chrono::{DateTime, Datelike, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use thiserror::Error;
+
+// ============================================================================
+// Constants
+// ============================================================================
+
+/// Setting holding the JSON-encoded backup policy
+const BACKUP_POLICY_SETTING: &str = "backup_policy";
+
+/// Default backup directory, inside the app data directory
+const DEFAULT_BACKUP_DIR: &str = "backups";
+
+const BACKUP_FILE_PREFIX: &str = "gmanager-backup-";
+const BACKUP_FILE_EXTENSION: &str = ".db";
+
+/// Timestamp part of backup file names (UTC)
+const BACKUP_TIMESTAMP_FORMAT: &str = "%Y%m%d-%H%M%S-%3f";
+
+/// How often the scheduler checks whether a backup is due
+const SCHEDULER_CHECK_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+/// Longest allowed interval between scheduled backups (one week)
+const MAX_INTERVAL_HOURS: u32 = 7 * 24;
+
+// ============================================================================
+// Type Definitions
+// ============================================================================
+
+/// Backup location, schedule and rotation settings
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackupPolicy {
+    /// Write backups in the background
+    pub enabled: bool,
+    /// Backup directory (the app data `backups` directory when None)
+    pub directory: Option<String>,
+    /// Hours between scheduled backups
+    pub interval_hours: u32,
+    /// Number of days to keep the newest backup of
+    pub keep_daily: u32,
+    /// Number of weeks to keep the newest backup of
+    pub keep_weekly: u32,
+}
+
+impl Default for BackupPolicy {
+    fn default() -> Self {
+        BackupPolicy {
+            enabled: false,
+            directory: None,
+            interval_hours: 24,
+            keep_daily: 7,
+            keep_weekly: 4,
+        }
+    }
+}
+
+/// A backup file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BackupInfo {
+    pub file_name: String,
+    pub path: String,
+    /// RFC 3339 time the backup was taken
+    pub created_at: String,
+    pub size_bytes: u64,
+}
+
+/// Outcome of a restore
+#[derive(Debug, Clone, Serialize)]
+pub struct RestoreReport {
+    /// Backup that was restored
+    pub restored_from: String,
+    /// Backup of the database as it was before the restore
+    pub previous_state: BackupInfo,
+}
+
+// ============================================================================
+// Error Types
+// ============================================================================
+
+/// Backup operation errors
+#[derive(Debug, Error)]
+pub enum BackupError {
+    /// Database error
+    #[error("Database error: {0}")]
+    Database(#[from] DbError),
+
+    /// File system error
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// Invalid policy or path
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+}
+
+/// Result type for backup operations
+pub type BackupResult<T> = std::result::Result<T, BackupError>;
+
+// ============================================================================
+// Backup Operations
+// ============================================================================
+
+/// Write a verified backup into `directory`
+///
+/// # Errors
+/// - `Database` - The copy failed or did not pass the integrity check (no
+///   file is left behind)
+/// - `Io` - The directory cannot be created or written
+pub fn create_backup(db: &Database, directory: &Path) -> BackupResult<BackupInfo> {
+    std::fs::create_dir_all(directory)?;
+
+    let file_name = format!(
+        "{}{}{}",
+        BACKUP_FILE_PREFIX,
+        Utc::now().format(BACKUP_TIMESTAMP_FORMAT),
+        BACKUP_FILE_EXTENSION
+    );
+    let path = directory.join(&file_name);
+    let partial = directory.join(format!(".{}.partial", file_name));
+
+    // VACUUM INTO refuses to overwrite a file
+    if partial.exists() {
+        std::fs::remove_file(&partial)?;
+    }
+
+    let written = db.backup(&partial).and_then(|_| verify_backup_file(&partial));
+    if let Err(e) = written {
+        let _ = std::fs::remove_file(&partial);
+        return Err(e.into());
+    }
+    std::fs::rename(&partial, &path)?;
+
+    db.log_operation(None, "BACKUP", Some(&format!("Backup written to {}", file_name)))?;
+
+    backup_info(&path)?.ok_or_else(|| BackupError::InvalidInput(format!("Backup {} disappeared", file_name)))
+}
+
+/// Backups in `directory`, newest first
+///
+/// Files not named like backups are ignored; a missing directory has no
+/// backups.
+pub fn list_backups(directory: &Path) -> BackupResult<Vec<BackupInfo>> {
+    let entries = match std::fs::read_dir(directory) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut backups = Vec::new();
+    for entry in entries {
+        backups.extend(backup_info(&entry?.path())?);
+    }
+
+    // The fixed-width timestamps in the names sort chronologically
+    backups.sort_by(|a, b| b.file_name.cmp(&a.file_name));
+    Ok(backups)
+}
+
+/// Delete backups the policy no longer keeps
+///
+/// The newest backup is always kept. Returns the deleted file names.
+pub fn rotate_backups(directory: &Path, policy: &BackupPolicy) -> BackupResult<Vec<String>> {
+    let backups = list_backups(directory)?;
+
+    let mut keep: HashSet<&str> = HashSet::new();
+    let mut days = HashSet::new();
+    let mut weeks = HashSet::new();
+    for (index, backup) in backups.iter().enumerate() {
+        let Some(created_at) = parse_backup_time(&backup.file_name) else {
+            continue;
+        };
+        let week = created_at.iso_week();
+
+        // Backups are newest first, so the first one seen per period is kept
+        let new_day = days.len() < policy.keep_daily as usize && days.insert(created_at.date_naive());
+        let new_week =
+            weeks.len() < policy.keep_weekly as usize && weeks.insert((week.year(), week.week()));
+        if index == 0 || new_day || new_week {
+            keep.insert(&backup.file_name);
+        }
+    }
+
+    let mut deleted = Vec::new();
+    for backup in &backups {
+        if !keep.contains(backup.file_name.as_str()) {
+            std::fs::remove_file(&backup.path)?;
+            deleted.push(backup.file_name.clone());
+        }
+    }
+
+    Ok(deleted)
+}
+
+/// Replace the database with a backup
+///
+/// The current database is backed up into `directory` first, so a restore
+/// can be undone by restoring that backup.
+///
+/// # Errors
+/// Fails without changing the database if the backup does not verify.
+pub fn restore_backup(db: &Database, directory: &Path, backup_path: &Path) -> BackupResult<RestoreReport> {
+    if !backup_path.is_file() {
+        return Err(BackupError::InvalidInput(format!(
+            "Backup not found: {}",
+            backup_path.display()
+        )));
+    }
+    verify_backup_file(backup_path)?;
+
+    let previous_state = create_backup(db, directory)?;
+    db.restore_backup(backup_path)?;
+
+    let restored_from = backup_path.to_string_lossy().into_owned();
+    db.log_operation(
+        None,
+        "RESTORE_BACKUP",
+        Some(&format!("Restored from {}", restored_from)),
+    )?;
+
+    Ok(RestoreReport {
+        restored_from,
+        previous_state,
+    })
+}
+
+/// Write and rotate a backup if the policy has scheduling enabled and the
+/// newest backup is older than the interval
+///
+/// Returns the new backup, if one was written.
+pub fn run_scheduled_backup(db: &Database, default_directory: &Path) -> BackupResult<Option<BackupInfo>> {
+    let policy = load_backup_policy(db)?;
+    if !policy.enabled {
+        return Ok(None);
+    }
+
+    let directory = backup_directory(&policy, default_directory);
+    let newest = list_backups(&directory)?
+        .first()
+        .and_then(|b| parse_backup_time(&b.file_name));
+    let interval = chrono::Duration::hours(policy.interval_hours as i64);
+    if newest.is_some_and(|t| Utc::now() - t < interval) {
+        return Ok(None);
+    }
+
+    let backup = create_backup(db, &directory)?;
+    rotate_backups(&directory, &policy)?;
+    Ok(Some(backup))
+}
+
+/// Check for due backups in the background
+pub fn spawn_backup_scheduler(app: AppHandle, db: Database) {
+    let Ok(default_directory) = default_backup_directory(&app) else {
+        return;
+    };
+
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(SCHEDULER_CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            let db = db.clone();
+            let directory = default_directory.clone();
+            // VACUUM INTO blocks, keep it off the async workers
+            let _ = tokio::task::spawn_blocking(move || run_scheduled_backup(&db, &directory)).await;
+        }
+    });
+}
+
+// ============================================================================
+// Backup Policy
+// ============================================================================
+
+/// Read the stored backup policy (defaults when none is stored)
+pub fn load_backup_policy(db: &Database) -> BackupResult<BackupPolicy> {
+    match db.get_setting(BACKUP_POLICY_SETTING)? {
+        Some(json) => {
+            let policy: BackupPolicy = serde_json::from_str(&json)
+                .map_err(|e| BackupError::InvalidInput(e.to_string()))?;
+            validate_backup_policy(&policy)?;
+            Ok(policy)
+        }
+        None => Ok(BackupPolicy::default()),
+    }
+}
+
+/// Validate and store a backup policy
+pub fn save_backup_policy(db: &Database, policy: BackupPolicy) -> BackupResult<()> {
+    validate_backup_policy(&policy)?;
+
+    let json = serde_json::to_string(&policy)
+        .map_err(|e| BackupError::InvalidInput(e.to_string()))?;
+    db.set_setting(BACKUP_POLICY_SETTING, &json)?;
+
+    Ok(())
+}
+
+fn validate_backup_policy(policy: &BackupPolicy) -> BackupResult<()> {
+    if policy.interval_hours == 0 || policy.interval_hours > MAX_INTERVAL_HOURS {
+        return Err(BackupError::InvalidInput(format!(
+            "Backup interval must be between 1 and {} hours",
+            MAX_INTERVAL_HOURS
+        )));
+    }
+    if policy.keep_daily == 0 && policy.keep_weekly == 0 {
+        return Err(BackupError::InvalidInput(
+            "At least one daily or weekly backup must be kept".to_string(),
+        ));
+    }
+    if let Some(directory) = &policy.directory {
+        if !Path::new(directory).is_absolute() {
+            return Err(BackupError::InvalidInput(
+                "Backup directory must be an absolute path".to_string(),
+            ));
+        }
+    }
+    Ok(())
+}
+
+// ============================================================================
+// Helper Functions
+// ============================================================================
+
+/// Directory backups are written to under `policy`
+fn backup_directory(policy: &BackupPolicy, default_directory: &Path) -> PathBuf {
+    match &policy.directory {
+        Some(directory) => PathBuf::from(directory),
+        None => default_directory.to_path_buf(),
+    }
+}
+
+fn default_backup_directory(app: &AppHandle) -> BackupResult<PathBuf> {
+    let app_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| BackupError::InvalidInput(format!("Cannot find app data directory: {}", e)))?;
+    Ok(app_dir.join(DEFAULT_BACKUP_DIR))
+}
+
+/// Configured backup directory for a command
+fn configured_directory(app: &AppHandle, db: &Database) -> BackupResult<PathBuf> {
+    let policy = load_backup_policy(db)?;
+    Ok(backup_directory(&policy, &default_backup_directory(app)?))
+}
+
+/// Describe a backup file (None if `path` is not named like a backup)
+fn backup_info(path: &Path) -> BackupResult<Option<BackupInfo>> {
+    let Some(file_name) = path.file_name().map(|n| n.to_string_lossy().into_owned()) else {
+        return Ok(None);
+    };
+    let Some(created_at) = parse_backup_time(&file_name) else {
+        return Ok(None);
+    };
+    let metadata = std::fs::metadata(path)?;
+    if !metadata.is_file() {
+        return Ok(None);
+    }
+
+    Ok(Some(BackupInfo {
+        path: path.to_string_lossy().into_owned(),
+        file_name,
+        created_at: created_at.to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+        size_bytes: metadata.len(),
+    }))
+}
+
+/// Creation time encoded in a backup file name
+fn parse_backup_time(file_name: &str) -> Option<DateTime<Utc>> {
+    let timestamp = file_name
+        .strip_prefix(BACKUP_FILE_PREFIX)?
+        .strip_suffix(BACKUP_FILE_EXTENSION)?;
+    NaiveDateTime::parse_from_str(timestamp, BACKUP_TIMESTAMP_FORMAT)
+        .ok()
+        .map(|t| t.and_utc())
+}
+
+// ============================================================================
+// Tauri Commands
+// ============================================================================
+
+/// Write a backup now
+///
+/// # Returns
+/// The new backup
+#[tauri::command]
+pub fn create_backup_command(
+    app: AppHandle,
+    db: tauri::State<Database>,
+    session_manager: tauri::State<SessionManager>,
+) -> Result<BackupInfo, String> {
+    session_manager.get_session_key()
+        .map_err(|e| e.to_string())?;
+
+    let directory = configured_directory(&app, &db).map_err(|e| e.to_string())?;
+    let backup = create_backup(&db, &directory).map_err(|e| e.to_string())?;
+    let policy = load_backup_policy(&db).map_err(|e| e.to_string())?;
+    rotate_backups(&directory, &policy).map_err(|e| e.to_string())?;
+
+    Ok(backup)
+}
+
+/// List the backups in the configured directory, newest first
+#[tauri::command]
+pub fn list_backups_command(
+    app: AppHandle,
+    db: tauri::State<Database>,
+    session_manager: tauri::State<SessionManager>,
+) -> Result<Vec<BackupInfo>, String> {
+    session_manager.get_session_key()
+        .map_err(|e| e.to_string())?;
+
+    let directory = configured_directory(&app, &db).map_err(|e| e.to_string())?;
+    list_backups(&directory).map_err(|e| e.to_string())
+}
+
+/// Restore a backup file and lock the vault
+///
+/// # Arguments
+/// * `path` - Backup file to restore (usually from `list_backups_command`)
+///
+/// # Returns
+/// The restored file and a backup of the replaced database
+#[tauri::command]
+pub fn restore_backup_command(
+    app: AppHandle,
+    db: tauri::State<Database>,
+    session_manager: tauri::State<SessionManager>,
+    path: String,
+) -> Result<RestoreReport, String> {
+    session_manager.get_session_key()
+        .map_err(|e| e.to_string())?;
+
+    let directory = configured_directory(&app, &db).map_err(|e| e.to_string())?;
+    let report = restore_backup(&db, &directory, Path::new(&path)).map_err(|e| e.to_string())?;
+
+    // The restored vault must be unlocked with its own password
+    session_manager.clear_session()
+        .map_err(|e| e.to_string())?;
+
+    Ok(report)
+}
+
+/// Get the backup policy
+#[tauri::command]
+pub fn get_backup_policy_command(
+    db: tauri::State<Database>,
+) -> Result<BackupPolicy, String> {
+    load_backup_policy(&db)
+        .map_err(|e| e.to_string())
+}
+
+/// Set the backup policy (requires an unlocked vault)
+#[tauri::command]
+pub fn set_backup_policy_command(
+    db: tauri::State<Database>,
+    session_manager: tauri::State<SessionManager>,
+    policy: BackupPolicy,
+) -> Result<(), String> {
+    session_manager.get_session_key()
+        .map_err(|e| e.to_string())?;
+
+    save_backup_policy(&db, policy)
+        .map_err(|e| e.to_string())
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{CreateGroup, GroupMembers};
+
+    /// Temporary directory removed on drop
+    struct TestDir(PathBuf);
+
+    impl TestDir {
+        fn new(name: &str) -> Self {
+            let nanos = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_nanos();
+            let path = std::env::temp_dir()
+                .join(format!("gmanager-{}-{}-{}", name, std::process::id(), nanos));
+            std::fs::create_dir_all(&path).unwrap();
+            TestDir(path)
+        }
+    }
+
+    impl Drop for TestDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn touch_backup(dir: &Path, timestamp: &str) {
+        let name = format!("{}{}{}", BACKUP_FILE_PREFIX, timestamp, BACKUP_FILE_EXTENSION);
+        std::fs::write(dir.join(name), b"").unwrap();
+    }
+
+    fn names(dir: &Path) -> Vec<String> {
+        list_backups(dir).unwrap().into_iter().map(|b| b.file_name).collect()
+    }
+
+    #[test]
+    fn test_create_list_and_restore() {
+        let dir = TestDir::new("backups");
+        let db = Database::init_in_memory().unwrap();
+        db.create_group(CreateGroup {
+            name: "Work".to_string(),
+            color: "#000000".to_string(),
+            sort_order: 0,
+            parent_id: None,
+        })
+        .unwrap();
+
+        let backup = create_backup(&db, &dir.0).unwrap();
+        assert!(backup.size_bytes > 0);
+        assert!(parse_backup_time(&backup.file_name).is_some());
+        // Only the verified file is left, and unrelated files are ignored
+        std::fs::write(dir.0.join("notes.txt"), b"").unwrap();
+        assert_eq!(names(&dir.0), vec![backup.file_name.clone()]);
+
+        let group = db.get_groups().unwrap().into_iter().find(|g| g.name == "Work").unwrap();
+        db.delete_group_with_members(group.id, GroupMembers::MoveToDefault).unwrap();
+
+        let report = restore_backup(&db, &dir.0, Path::new(&backup.path)).unwrap();
+        assert!(db.get_groups().unwrap().iter().any(|g| g.name == "Work"));
+        assert_eq!(list_backups(&dir.0).unwrap()[0], report.previous_state);
+
+        let missing = dir.0.join("missing.db");
+        assert!(matches!(
+            restore_backup(&db, &dir.0, &missing),
+            Err(BackupError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn test_rotation_keeps_daily_and_weekly() {
+        let dir = TestDir::new("rotation");
+        // 2024-01-15 is a Monday (ISO week 2024-W03)
+        for timestamp in [
+            "20240115-180000-000",
+            "20240115-060000-000",
+            "20240114-060000-000", // W02
+            "20240113-060000-000", // W02
+            "20240110-060000-000", // W02
+            "20240103-060000-000", // W01
+            "20231227-060000-000", // 2023-W52
+        ] {
+            touch_backup(&dir.0, timestamp);
+        }
+
+        let policy = BackupPolicy {
+            keep_daily: 2,
+            keep_weekly: 3,
+            ..BackupPolicy::default()
+        };
+        let deleted = rotate_backups(&dir.0, &policy).unwrap();
+
+        // Days: the 15th and 14th; weeks: W03, W02 and W01
+        let kept: Vec<String> = names(&dir.0)
+            .into_iter()
+            .map(|n| n[BACKUP_FILE_PREFIX.len()..BACKUP_FILE_PREFIX.len() + 15].to_string())
+            .collect();
+        assert_eq!(kept, vec!["20240115-180000", "20240114-060000", "20240103-060000"]);
+        assert_eq!(deleted.len(), 4);
+    }
+
+    #[test]
+    fn test_scheduled_backup_respects_policy() {
+        let dir = TestDir::new("scheduled");
+        let db = Database::init_in_memory().unwrap();
+
+        // Disabled by default
+        assert_eq!(run_scheduled_backup(&db, &dir.0).unwrap(), None);
+
+        save_backup_policy(&db, BackupPolicy { enabled: true, ..BackupPolicy::default() }).unwrap();
+        assert!(run_scheduled_backup(&db, &dir.0).unwrap().is_some());
+        // Not due again until the interval has passed
+        assert_eq!(run_scheduled_backup(&db, &dir.0).unwrap(), None);
+        assert_eq!(list_backups(&dir.0).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_policy_validation() {
+        let db = Database::init_in_memory().unwrap();
+        let invalid = [
+            BackupPolicy { interval_hours: 0, ..BackupPolicy::default() },
+            BackupPolicy { interval_hours: MAX_INTERVAL_HOURS + 1, ..BackupPolicy::default() },
+            BackupPolicy { keep_daily: 0, keep_weekly: 0, ..BackupPolicy::default() },
+            BackupPolicy { directory: Some("relative/dir".to_string()), ..BackupPolicy::default() },
+        ];
+        for policy in invalid {
+            assert!(save_backup_policy(&db, policy).is_err());
+        }
+        assert_eq!(load_backup_policy(&db).unwrap(), BackupPolicy::default());
+    }
+}
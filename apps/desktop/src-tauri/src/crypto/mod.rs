@@ -1,20 +1,31 @@
 // Cryptography module for GManager Desktop
 //
 // This module provides AES-256-GCM encryption for sensitive account data.
-// Uses the ring crate for cryptographic operations and PBKDF2 for key derivation.
+// Uses the ring crate for cryptographic operations and Argon2id (or PBKDF2 for
+// older vaults) for key derivation.
 //
 // Security considerations:
 // - AES-256-GCM provides authenticated encryption (confidentiality + integrity)
-// - PBKDF2 with 100,000 iterations slows down brute-force attacks on master password
+// - Argon2id is memory-hard, which makes GPU/ASIC brute-forcing of the master
+//   password expensive; its parameters are stored per vault so they can grow
 // - Random nonce for each encryption prevents pattern analysis
 // - 128-bit nonce provides sufficient randomness for field-level encryption
 
 use ring::aead::{Aad, AES_256_GCM, LessSafeKey, Nonce, UnboundKey};
+use ring::hmac;
 use ring::pbkdf2;
 use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroize;
 
 use std::num::NonZeroU32;
 
+mod secret_key;
+
+pub use secret_key::SecretKey;
+#[cfg(test)]
+pub(crate) use secret_key::wipe_log;
+
 /// Macro for creating array references from slices
 /// Used for nonce conversion
 macro_rules! array_ref {
@@ -47,10 +58,45 @@ const NONCE_SIZE: usize = 12;
 /// This should be increased periodically as hardware improves
 const PBKDF2_ITERATIONS: u32 = 100_000;
 
+/// Argon2id memory cost for new vaults in KiB (64 MiB)
+const ARGON2_MEMORY_KIB: u32 = 64 * 1024;
+
+/// Argon2id number of passes for new vaults
+const ARGON2_TIME_COST: u32 = 3;
+
+/// Argon2id degree of parallelism for new vaults
+const ARGON2_LANES: u32 = 4;
+
+/// Upper bound on Argon2id memory (1 GiB) so a corrupted vault record
+/// cannot make unlock allocate unbounded memory
+const ARGON2_MAX_MEMORY_KIB: u32 = 1024 * 1024;
+
+/// Upper bound on Argon2id passes, so untrusted parameters (e.g. from an
+/// export file) cannot make key derivation run for hours
+const ARGON2_MAX_TIME_COST: u32 = 10 * ARGON2_TIME_COST;
+
+/// Upper bound on PBKDF2 iterations, for the same reason
+const PBKDF2_MAX_ITERATIONS: u32 = 10 * PBKDF2_ITERATIONS;
+
 /// Prefix for encrypted data to help identify encrypted fields
 /// This provides a sanity check when parsing encrypted strings
 const ENCRYPTED_PREFIX: &str = "enc1:";
 
+/// Prefix for a data key wrapped by a key-encryption key
+const WRAPPED_KEY_PREFIX: &str = "wrap1:";
+
+/// Additional authenticated data binding wrapped keys to their purpose
+const WRAPPED_KEY_AAD: &[u8] = b"gmanager-vault-data-key";
+
+/// Context string used to derive the blind index key from the session key
+const SEARCH_KEY_CONTEXT: &[u8] = b"gmanager-search-index-v1";
+
+/// Context string used to derive the audit log MAC key from the session key
+const AUDIT_KEY_CONTEXT: &[u8] = b"gmanager-audit-log-v1";
+
+/// Number of HMAC output bytes kept for each blind index token (128 bits)
+const BLIND_TOKEN_SIZE: usize = 16;
+
 // ============================================================================
 // Error Types
 // ============================================================================
@@ -92,6 +138,10 @@ pub enum CryptoError {
     /// Key derivation failed
     #[error("Key derivation failed")]
     KeyDerivationFailed,
+
+    /// KDF parameters are out of range
+    #[error("Invalid KDF parameters: {0}")]
+    InvalidKdfParams(String),
 }
 
 /// Result type for crypto operations
@@ -103,6 +153,9 @@ pub type Result<T> = std::result::Result<T, CryptoError>;
 
 /// Derives a 256-bit encryption key from a master password using PBKDF2
 ///
+/// Uses the fixed legacy iteration count. Vault keys are derived with
+/// `derive_key_with_params` using the parameters stored in the vault.
+///
 /// # Arguments
 /// * `password` - The master password as a byte slice
 /// * `salt` - A unique salt value (should be stored with the encrypted data)
@@ -117,7 +170,7 @@ pub type Result<T> = std::result::Result<T, CryptoError>;
 /// - Using the same salt with the same password always produces the same key
 ///
 /// # Example
-/// ```rust
+/// ```rust,ignore
 /// use ring::rand::{SecureRandom, SystemRandom};
 ///
 /// let rng = SystemRandom::new();
@@ -126,20 +179,62 @@ pub type Result<T> = std::result::Result<T, CryptoError>;
 ///
 /// let key = derive_key(b"my-master-password", &salt)?;
 /// ```
-pub fn derive_key(password: &[u8], salt: &[u8; 16]) -> [u8; KEY_SIZE] {
-    let mut key = [0u8; KEY_SIZE];
+pub fn derive_key(password: &[u8], salt: &[u8; 16]) -> SecretKey {
+    let mut key = SecretKey::zeroed();
+    derive_key_pbkdf2(
+        password,
+        salt,
+        NonZeroU32::new(PBKDF2_ITERATIONS).expect("non-zero iterations"),
+        key.as_mut_bytes(),
+    );
+    key
+}
 
-    // PBKDF2-HMAC-SHA256 is used for key derivation
-    // The iteration count should be increased as hardware improves
+/// Derives a 256-bit key with the algorithm and cost stored in `params`
+///
+/// # Errors
+/// - `InvalidKdfParams` - The parameters are out of range
+/// - `KeyDerivationFailed` - Argon2 failed (e.g. could not allocate memory)
+pub fn derive_key_with_params(
+    password: &[u8],
+    salt: &[u8; 16],
+    params: &KdfParams,
+) -> Result<SecretKey> {
+    params.validate()?;
+
+    let mut key = SecretKey::zeroed();
+    match *params {
+        KdfParams::Pbkdf2 { iterations } => {
+            let iterations = NonZeroU32::new(iterations).expect("validated non-zero iterations");
+            derive_key_pbkdf2(password, salt, iterations, key.as_mut_bytes());
+        }
+        KdfParams::Argon2id { memory_kib, time_cost, lanes } => {
+            let argon_params = argon2::Params::new(memory_kib, time_cost, lanes, Some(KEY_SIZE))
+                .map_err(|e| CryptoError::InvalidKdfParams(e.to_string()))?;
+            let argon = argon2::Argon2::new(
+                argon2::Algorithm::Argon2id,
+                argon2::Version::V0x13,
+                argon_params,
+            );
+
+            argon
+                .hash_password_into(password, salt, key.as_mut_bytes())
+                .map_err(|_| CryptoError::KeyDerivationFailed)?;
+        }
+    }
+
+    Ok(key)
+}
+
+/// PBKDF2-HMAC-SHA256 with the given iteration count
+fn derive_key_pbkdf2(password: &[u8], salt: &[u8; 16], iterations: NonZeroU32, out: &mut [u8; KEY_SIZE]) {
     pbkdf2::derive(
         pbkdf2::PBKDF2_HMAC_SHA256,
-        NonZeroU32::new(PBKDF2_ITERATIONS).expect("non-zero iterations"),
+        iterations,
         salt,
         password,
-        &mut key,
+        out,
     );
-
-    key
 }
 
 /// Generate a cryptographically random salt for key derivation
@@ -160,6 +255,286 @@ pub fn generate_salt() -> Result<[u8; 16]> {
     Ok(salt)
 }
 
+// ============================================================================
+// KDF Parameters
+// ============================================================================
+
+/// Key derivation algorithm and cost parameters of a vault
+///
+/// Stored as JSON in the vault record, e.g.
+/// `{"algorithm":"argon2id","memory_kib":65536,"time_cost":3,"lanes":4}`,
+/// so the cost can be raised without locking out existing vaults.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "algorithm", rename_all = "snake_case")]
+pub enum KdfParams {
+    /// PBKDF2-HMAC-SHA256 (vaults created before Argon2id support)
+    Pbkdf2 { iterations: u32 },
+    /// Argon2id (RFC 9106)
+    Argon2id {
+        /// Memory cost in KiB
+        memory_kib: u32,
+        /// Number of passes over the memory
+        time_cost: u32,
+        /// Degree of parallelism
+        lanes: u32,
+    },
+}
+
+impl Default for KdfParams {
+    /// Parameters used for new vaults and as the upgrade target on unlock
+    fn default() -> Self {
+        KdfParams::Argon2id {
+            memory_kib: ARGON2_MEMORY_KIB,
+            time_cost: ARGON2_TIME_COST,
+            lanes: ARGON2_LANES,
+        }
+    }
+}
+
+impl KdfParams {
+    /// Parameters of vaults created before the KDF was configurable
+    pub const fn legacy() -> Self {
+        KdfParams::Pbkdf2 { iterations: PBKDF2_ITERATIONS }
+    }
+
+    /// Check that the parameters are usable
+    pub fn validate(&self) -> Result<()> {
+        match *self {
+            KdfParams::Pbkdf2 { iterations } => {
+                if iterations == 0 {
+                    return Err(CryptoError::InvalidKdfParams(
+                        "PBKDF2 iterations must be non-zero".to_string(),
+                    ));
+                }
+                if iterations > PBKDF2_MAX_ITERATIONS {
+                    return Err(CryptoError::InvalidKdfParams(format!(
+                        "PBKDF2 iterations must not exceed {}",
+                        PBKDF2_MAX_ITERATIONS
+                    )));
+                }
+            }
+            KdfParams::Argon2id { memory_kib, time_cost, lanes } => {
+                if memory_kib > ARGON2_MAX_MEMORY_KIB {
+                    return Err(CryptoError::InvalidKdfParams(format!(
+                        "Argon2id memory must not exceed {} KiB",
+                        ARGON2_MAX_MEMORY_KIB
+                    )));
+                }
+                if time_cost > ARGON2_MAX_TIME_COST {
+                    return Err(CryptoError::InvalidKdfParams(format!(
+                        "Argon2id time cost must not exceed {}",
+                        ARGON2_MAX_TIME_COST
+                    )));
+                }
+                argon2::Params::new(memory_kib, time_cost, lanes, Some(KEY_SIZE))
+                    .map_err(|e| CryptoError::InvalidKdfParams(e.to_string()))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether these parameters are cheaper to brute-force than `other`
+    ///
+    /// PBKDF2 is always weaker than Argon2id. An Argon2id guess costs memory
+    /// times passes (the memory blocks filled), so a lower time cost can be
+    /// made up for with more memory and vice versa; lanes only change how
+    /// the work is spread over cores, not its total cost.
+    pub fn is_weaker_than(&self, other: &KdfParams) -> bool {
+        match (*self, *other) {
+            (KdfParams::Pbkdf2 { iterations: a }, KdfParams::Pbkdf2 { iterations: b }) => a < b,
+            (KdfParams::Pbkdf2 { .. }, KdfParams::Argon2id { .. }) => true,
+            (KdfParams::Argon2id { .. }, KdfParams::Pbkdf2 { .. }) => false,
+            (
+                KdfParams::Argon2id { memory_kib: m1, time_cost: t1, .. },
+                KdfParams::Argon2id { memory_kib: m2, time_cost: t2, .. },
+            ) => u64::from(m1) * u64::from(t1) < u64::from(m2) * u64::from(t2),
+        }
+    }
+
+    /// Argon2id parameters at least as strong as both these and the
+    /// defaults in every component
+    ///
+    /// Used as the upgrade target, so resealing a vault never lowers its
+    /// memory, passes or lanes. PBKDF2 parameters become the defaults.
+    pub fn strengthened(&self) -> KdfParams {
+        match (*self, KdfParams::default()) {
+            (
+                KdfParams::Argon2id { memory_kib, time_cost, lanes },
+                KdfParams::Argon2id { memory_kib: m, time_cost: t, lanes: l },
+            ) => KdfParams::Argon2id {
+                memory_kib: memory_kib.max(m),
+                time_cost: time_cost.max(t),
+                lanes: lanes.max(l),
+            },
+            (_, default) => default,
+        }
+    }
+}
+
+// ============================================================================
+// Key Wrapping (Envelope Encryption)
+// ============================================================================
+
+/// Generate a random 256-bit vault data key
+///
+/// The data key encrypts account fields. It is never stored in plaintext;
+/// it is wrapped by a key-encryption key (e.g. the password-derived key)
+/// with `wrap_key` before being persisted.
+pub fn generate_data_key() -> Result<SecretKey> {
+    let rng = SystemRandom::new();
+    let mut key = SecretKey::zeroed();
+
+    rng.fill(key.as_mut_bytes())
+        .map_err(|_| CryptoError::RandomError)?;
+
+    Ok(key)
+}
+
+/// Wraps a data key with a key-encryption key using AES-256-GCM
+///
+/// # Output Format
+/// ```text
+/// wrap1:<base64(nonce + encrypted_key + tag)>
+/// ```
+/// The wrapped key is bound to its purpose through the AAD, so a wrapped key
+/// cannot be passed off as an encrypted account field or vice versa.
+pub fn wrap_key(data_key: &[u8; KEY_SIZE], wrapping_key: &[u8; KEY_SIZE]) -> Result<String> {
+    let nonce = generate_nonce()?;
+
+    let unbound_key = UnboundKey::new(&AES_256_GCM, wrapping_key)
+        .expect("key is valid size");
+    let sealing_key = LessSafeKey::new(unbound_key);
+
+    let mut sealed = data_key.to_vec();
+    let result = sealing_key.seal_in_place_append_tag(
+        Nonce::assume_unique_for_key(nonce),
+        Aad::from(WRAPPED_KEY_AAD),
+        &mut sealed,
+    );
+    if result.is_err() {
+        // Still holds the plaintext key
+        sealed.zeroize();
+        return Err(CryptoError::KeyDerivationFailed);
+    }
+
+    let mut out = nonce.to_vec();
+    out.extend_from_slice(&sealed);
+
+    Ok(format!("{}{}", WRAPPED_KEY_PREFIX, BASE64.encode(&out)))
+}
+
+/// Unwraps a data key produced by `wrap_key`
+///
+/// # Errors
+/// - `InvalidFormat` - The wrapped key is malformed
+/// - `DecryptionFailed` - Wrong wrapping key or tampered data
+pub fn unwrap_key(wrapped: &str, wrapping_key: &[u8; KEY_SIZE]) -> Result<SecretKey> {
+    let encoded_part = wrapped
+        .strip_prefix(WRAPPED_KEY_PREFIX)
+        .ok_or_else(|| CryptoError::InvalidFormat("Missing wrapped key prefix".to_string()))?;
+    let data = BASE64.decode(encoded_part)?;
+
+    if data.len() != NONCE_SIZE + KEY_SIZE + 16 {
+        return Err(CryptoError::InvalidFormat(
+            "Wrapped key has invalid length".to_string()
+        ));
+    }
+
+    let nonce = Nonce::assume_unique_for_key(*array_ref!(data, 0, NONCE_SIZE));
+
+    let unbound_key = UnboundKey::new(&AES_256_GCM, wrapping_key)
+        .expect("key is valid size");
+    let opening_key = LessSafeKey::new(unbound_key);
+
+    let mut buffer = data[NONCE_SIZE..].to_vec();
+    let plaintext = opening_key
+        .open_in_place(nonce, Aad::from(WRAPPED_KEY_AAD), &mut buffer)
+        .map_err(|e| CryptoError::DecryptionFailed(e.to_string()))?;
+
+    let mut key = SecretKey::zeroed();
+    key.as_mut_bytes().copy_from_slice(plaintext);
+    buffer.zeroize();
+
+    Ok(key)
+}
+
+// ============================================================================
+// Blind Index
+// ============================================================================
+
+/// Derives the blind index key from the session key
+///
+/// A separate key keeps search tokens cryptographically independent from
+/// field ciphertext: HMAC-SHA256(session_key, context).
+pub fn derive_search_key(key: &[u8; KEY_SIZE]) -> SecretKey {
+    derive_subkey(key, SEARCH_KEY_CONTEXT)
+}
+
+/// Derives the audit log MAC key from the session key
+///
+/// HMAC-SHA256(session_key, context), like the search key.
+pub fn derive_audit_key(key: &[u8; KEY_SIZE]) -> SecretKey {
+    derive_subkey(key, AUDIT_KEY_CONTEXT)
+}
+
+/// HMAC-SHA256(key, context) as a new key
+fn derive_subkey(key: &[u8; KEY_SIZE], context: &[u8]) -> SecretKey {
+    let mac_key = hmac::Key::new(hmac::HMAC_SHA256, key);
+    let tag = hmac::sign(&mac_key, context);
+
+    let mut subkey = SecretKey::zeroed();
+    subkey.as_mut_bytes().copy_from_slice(tag.as_ref());
+    subkey
+}
+
+/// Computes the MAC of an audit log entry
+///
+/// # Arguments
+/// * `audit_key` - Key from `derive_audit_key`
+/// * `message` - Canonical encoding of the entry (including the previous
+///   entry's MAC, which chains the entries together)
+///
+/// # Returns
+/// Hex-encoded HMAC-SHA256
+pub fn audit_mac(audit_key: &[u8; KEY_SIZE], message: &[u8]) -> String {
+    let mac_key = hmac::Key::new(hmac::HMAC_SHA256, audit_key);
+    hex::encode(hmac::sign(&mac_key, message).as_ref())
+}
+
+/// Checks a MAC produced by `audit_mac` in constant time
+///
+/// # Returns
+/// False if `mac` is not valid hex or does not match `message`
+pub fn verify_audit_mac(audit_key: &[u8; KEY_SIZE], message: &[u8], mac: &str) -> bool {
+    let Ok(tag) = hex::decode(mac) else {
+        return false;
+    };
+    let mac_key = hmac::Key::new(hmac::HMAC_SHA256, audit_key);
+    hmac::verify(&mac_key, message, &tag).is_ok()
+}
+
+/// Computes a deterministic blind index token for a search term
+///
+/// # Arguments
+/// * `search_key` - Key from `derive_search_key`
+/// * `kind` - Token kind (e.g. exact, prefix, trigram), so equal strings of
+///   different kinds never collide
+/// * `term` - The normalized search term
+///
+/// # Returns
+/// Hex-encoded, truncated HMAC-SHA256 of `kind || 0x00 || term`
+pub fn blind_token(search_key: &[u8; KEY_SIZE], kind: &str, term: &str) -> String {
+    let mac_key = hmac::Key::new(hmac::HMAC_SHA256, search_key);
+
+    let mut ctx = hmac::Context::with_key(&mac_key);
+    ctx.update(kind.as_bytes());
+    ctx.update(&[0]);
+    ctx.update(term.as_bytes());
+    let tag = ctx.sign();
+
+    hex::encode(&tag.as_ref()[..BLIND_TOKEN_SIZE])
+}
+
 // ============================================================================
 // Single Field Encryption/Decryption
 // ============================================================================
@@ -181,7 +556,7 @@ pub fn generate_salt() -> Result<[u8; 16]> {
 ///
 /// # Output Format
 /// The encrypted string is base64-encoded with the structure:
-/// ```
+/// ```text
 /// enc1:<base64(nonce + ciphertext + tag)>
 /// ```
 /// - `enc1:` - Version prefix (allows future algorithm upgrades)
@@ -205,19 +580,16 @@ pub fn encrypt_field(plaintext: &str, key: &[u8; KEY_SIZE]) -> Result<String> {
         .expect("key is valid size");
     let sealing_key = LessSafeKey::new(unbound_key);
 
-    // Convert plaintext to bytes
-    let plaintext_bytes = plaintext.as_bytes();
-
-    // Create buffer: nonce + plaintext (tag will be appended by ring)
-    let mut out = nonce.to_vec();
-    out.extend_from_slice(plaintext_bytes);
-
-    // Encrypt in-place (tag is appended to out)
-    let nonce = Nonce::assume_unique_for_key(nonce);
+    // Encrypt the plaintext in-place (tag is appended by ring)
+    let mut sealed = plaintext.as_bytes().to_vec();
     sealing_key
-        .seal_in_place_append_tag(nonce, Aad::empty(), &mut out)
+        .seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::empty(), &mut sealed)
         .map_err(|e| CryptoError::DecryptionFailed(e.to_string()))?;
 
+    // Output buffer: nonce + ciphertext + tag
+    let mut out = nonce.to_vec();
+    out.extend_from_slice(&sealed);
+
     // Encode with version prefix and base64
     let encoded = format!("{}{}", ENCRYPTED_PREFIX, BASE64.encode(&out));
 
@@ -353,7 +725,7 @@ pub struct AccountFields {
 /// - Empty strings (`""`) are preserved but not encrypted (return `Some("")`)
 ///
 /// # Example
-/// ```rust
+/// ```rust,ignore
 /// let key = derive_key(b"master-password", &salt)?;
 /// let encrypted = encrypt_account_fields(&account, &key)?;
 /// ```
@@ -560,8 +932,8 @@ pub fn validate_key(key: &[u8]) -> Result<()> {
 mod tests {
     use super::*;
 
-    fn make_test_key() -> [u8; KEY_SIZE] {
-        derive_key(b"test-master-password", b"test-salt-16-bytes!!")
+    fn make_test_key() -> SecretKey {
+        derive_key(b"test-master-password", b"test-salt-16byte")
     }
 
     #[test]
@@ -569,7 +941,7 @@ mod tests {
         let salt = [1u8; 16];
         let key1 = derive_key(b"password", &salt);
         let key2 = derive_key(b"password", &salt);
-        assert_eq!(key1, key2);
+        assert_eq!(*key1, *key2);
     }
 
     #[test]
@@ -577,7 +949,7 @@ mod tests {
         let salt = [1u8; 16];
         let key1 = derive_key(b"password1", &salt);
         let key2 = derive_key(b"password2", &salt);
-        assert_ne!(key1, key2);
+        assert_ne!(*key1, *key2);
     }
 
     #[test]
@@ -587,7 +959,7 @@ mod tests {
         let salt2 = [2u8; 16];
         let key1 = derive_key(password, &salt1);
         let key2 = derive_key(password, &salt2);
-        assert_ne!(key1, key2);
+        assert_ne!(*key1, *key2);
     }
 
     #[test]
@@ -622,7 +994,7 @@ mod tests {
     #[test]
     fn test_decrypt_with_wrong_key_fails() {
         let key1 = make_test_key();
-        let key2 = derive_key(b"different-password", b"different-salt-16!!");
+        let key2 = derive_key(b"different-password", b"different-salt16");
         let plaintext = "secret@email.com";
 
         let encrypted = encrypt_field(plaintext, &key1).unwrap();
@@ -727,6 +1099,155 @@ mod tests {
         assert!(validate_key(&[0u8; 64]).is_err());
     }
 
+    #[test]
+    fn test_derive_key_with_legacy_params_matches_derive_key() {
+        let salt = b"test-salt-16byte";
+        let key = derive_key_with_params(b"password", salt, &KdfParams::legacy()).unwrap();
+        assert_eq!(*key, *derive_key(b"password", salt));
+    }
+
+    #[test]
+    fn test_derive_key_with_argon2id() {
+        let salt = b"test-salt-16byte";
+        let params = KdfParams::Argon2id { memory_kib: 1024, time_cost: 1, lanes: 1 };
+
+        let key1 = derive_key_with_params(b"password", salt, &params).unwrap();
+        let key2 = derive_key_with_params(b"password", salt, &params).unwrap();
+        assert_eq!(*key1, *key2);
+        assert_ne!(*key1, *derive_key(b"password", salt));
+
+        let stronger = KdfParams::Argon2id { memory_kib: 2048, time_cost: 1, lanes: 1 };
+        assert_ne!(*key1, *derive_key_with_params(b"password", salt, &stronger).unwrap());
+    }
+
+    #[test]
+    fn test_invalid_kdf_params_rejected() {
+        let salt = b"test-salt-16byte";
+        for params in [
+            KdfParams::Pbkdf2 { iterations: 0 },
+            KdfParams::Argon2id { memory_kib: 1024, time_cost: 0, lanes: 1 },
+            KdfParams::Argon2id { memory_kib: ARGON2_MAX_MEMORY_KIB + 1, time_cost: 1, lanes: 1 },
+            KdfParams::Argon2id { memory_kib: 1024, time_cost: ARGON2_MAX_TIME_COST + 1, lanes: 1 },
+            KdfParams::Pbkdf2 { iterations: PBKDF2_MAX_ITERATIONS + 1 },
+            KdfParams::Pbkdf2 { iterations: u32::MAX },
+        ] {
+            assert!(matches!(
+                derive_key_with_params(b"password", salt, &params),
+                Err(CryptoError::InvalidKdfParams(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_kdf_params_strength_ordering() {
+        let argon = KdfParams::default();
+        assert!(KdfParams::legacy().is_weaker_than(&argon));
+        assert!(!argon.is_weaker_than(&KdfParams::legacy()));
+        assert!(!argon.is_weaker_than(&argon));
+        assert!(KdfParams::Pbkdf2 { iterations: 1000 }.is_weaker_than(&KdfParams::legacy()));
+        assert!(KdfParams::Argon2id { memory_kib: 1024, time_cost: 10, lanes: 4 }.is_weaker_than(&argon));
+        assert!(!KdfParams::Argon2id { memory_kib: 128 * 1024, time_cost: 3, lanes: 1 }.is_weaker_than(&argon));
+        // More memory makes up for fewer passes
+        assert!(!KdfParams::Argon2id { memory_kib: 256 * 1024, time_cost: 1, lanes: 4 }.is_weaker_than(&argon));
+        assert!(KdfParams::Argon2id { memory_kib: 128 * 1024, time_cost: 1, lanes: 4 }.is_weaker_than(&argon));
+    }
+
+    #[test]
+    fn test_strengthened_never_lowers_a_parameter() {
+        assert_eq!(KdfParams::legacy().strengthened(), KdfParams::default());
+        assert_eq!(KdfParams::default().strengthened(), KdfParams::default());
+
+        let KdfParams::Argon2id { memory_kib: m, time_cost: t, lanes: l } = KdfParams::default() else {
+            unreachable!()
+        };
+        for (memory_kib, time_cost, lanes) in [(256 * 1024, 1, 1), (1024, 10, 8), (32 * 1024, 2, 4)] {
+            let current = KdfParams::Argon2id { memory_kib, time_cost, lanes };
+            assert_eq!(
+                current.strengthened(),
+                KdfParams::Argon2id {
+                    memory_kib: memory_kib.max(m),
+                    time_cost: time_cost.max(t),
+                    lanes: lanes.max(l),
+                }
+            );
+            assert!(!current.strengthened().is_weaker_than(&current));
+            assert!(!current.strengthened().is_weaker_than(&KdfParams::default()));
+        }
+    }
+
+    #[test]
+    fn test_kdf_params_json_format() {
+        let json = serde_json::to_string(&KdfParams::legacy()).unwrap();
+        assert_eq!(json, r#"{"algorithm":"pbkdf2","iterations":100000}"#);
+
+        let parsed: KdfParams = serde_json::from_str(
+            r#"{"algorithm":"argon2id","memory_kib":65536,"time_cost":3,"lanes":4}"#,
+        )
+        .unwrap();
+        assert_eq!(parsed, KdfParams::default());
+    }
+
+    #[test]
+    fn test_wrap_and_unwrap_data_key() {
+        let wrapping_key = make_test_key();
+        let data_key = generate_data_key().unwrap();
+
+        let wrapped = wrap_key(&data_key, &wrapping_key).unwrap();
+        assert!(wrapped.starts_with(WRAPPED_KEY_PREFIX));
+        assert_ne!(*generate_data_key().unwrap(), *data_key);
+
+        assert_eq!(*unwrap_key(&wrapped, &wrapping_key).unwrap(), *data_key);
+    }
+
+    #[test]
+    fn test_unwrap_with_wrong_key_fails() {
+        let data_key = generate_data_key().unwrap();
+        let wrapped = wrap_key(&data_key, &make_test_key()).unwrap();
+
+        let other_key = derive_key(b"different-password", b"different-salt16");
+        assert!(matches!(
+            unwrap_key(&wrapped, &other_key),
+            Err(CryptoError::DecryptionFailed(_))
+        ));
+        assert!(unwrap_key("wrap1:AAAA", &make_test_key()).is_err());
+        assert!(unwrap_key("enc1:AAAA", &make_test_key()).is_err());
+    }
+
+    #[test]
+    fn test_audit_key_is_independent() {
+        let key = make_test_key();
+        let audit_key = derive_audit_key(&key);
+
+        assert_ne!(*audit_key, *key);
+        assert_ne!(*audit_key, *derive_search_key(&key));
+
+        let mac = audit_mac(&audit_key, b"entry");
+        assert_eq!(mac, audit_mac(&audit_key, b"entry"));
+        assert_ne!(mac, audit_mac(&audit_key, b"entry2"));
+        assert_eq!(mac.len(), 64);
+
+        assert!(verify_audit_mac(&audit_key, b"entry", &mac));
+        assert!(!verify_audit_mac(&audit_key, b"entry2", &mac));
+        assert!(!verify_audit_mac(&audit_key, b"entry", &mac[..62]));
+        assert!(!verify_audit_mac(&audit_key, b"entry", "not hex"));
+    }
+
+    #[test]
+    fn test_blind_token_is_deterministic_and_keyed() {
+        let search_key = derive_search_key(&make_test_key());
+        assert_ne!(*search_key, *make_test_key());
+
+        let token = blind_token(&search_key, "eq", "user@example.com");
+        assert_eq!(token, blind_token(&search_key, "eq", "user@example.com"));
+        assert_eq!(token.len(), BLIND_TOKEN_SIZE * 2);
+
+        // Kind, term and key all change the token
+        assert_ne!(token, blind_token(&search_key, "tri", "user@example.com"));
+        assert_ne!(token, blind_token(&search_key, "eq", "other@example.com"));
+        let other_key = derive_search_key(&derive_key(b"other", b"different-salt16"));
+        assert_ne!(token, blind_token(&other_key, "eq", "user@example.com"));
+    }
+
     #[test]
     fn test_is_encrypted() {
         assert!(is_encrypted("enc1:SGVsbG8="));
@@ -0,0 +1,189 @@
+//! Secret key material for GManager Desktop
+//!
+//! `SecretKey` holds a 256-bit key in a single heap allocation that is:
+//! - never copied implicitly (not `Clone`/`Copy`)
+//! - never printed (no `Debug`/`Display`)
+//! - zeroed on drop
+//! - locked into RAM with mlock/VirtualLock where the OS allows it, so it is
+//!   not written to swap
+//!
+//! Keys are borrowed as `&[u8; 32]` through `Deref` when handed to ring;
+//! callers should pass `&SecretKey` around instead of copying the bytes out.
+
+use std::ops::Deref;
+use zeroize::Zeroize;
+
+use super::KEY_SIZE;
+
+/// A 256-bit key that wipes itself on drop
+pub struct SecretKey {
+    /// Boxed so the bytes never move (moving the struct only moves the pointer)
+    bytes: Box<[u8; KEY_SIZE]>,
+    /// Whether the allocation was successfully memory-locked
+    locked: bool,
+}
+
+impl SecretKey {
+    /// Allocate an all-zero key to be filled in place
+    pub(crate) fn zeroed() -> Self {
+        let bytes = Box::new([0u8; KEY_SIZE]);
+        let locked = memory_lock::lock(bytes.as_ptr(), KEY_SIZE);
+        SecretKey { bytes, locked }
+    }
+
+    /// Move key bytes into a `SecretKey`, zeroing the source buffer
+    pub fn from_bytes(source: &mut [u8; KEY_SIZE]) -> Self {
+        let mut key = Self::zeroed();
+        key.bytes.copy_from_slice(source);
+        source.zeroize();
+        key
+    }
+
+    /// Mutable access for filling the key in place (KDF output, RNG, unwrap)
+    pub(crate) fn as_mut_bytes(&mut self) -> &mut [u8; KEY_SIZE] {
+        &mut self.bytes
+    }
+
+    /// Whether the key memory is locked against swapping
+    pub fn is_memory_locked(&self) -> bool {
+        self.locked
+    }
+
+    /// Address of the key bytes (tests use it to observe wiping)
+    #[cfg(test)]
+    pub(crate) fn address(&self) -> usize {
+        self.bytes.as_ptr() as usize
+    }
+}
+
+impl Deref for SecretKey {
+    type Target = [u8; KEY_SIZE];
+
+    fn deref(&self) -> &Self::Target {
+        &self.bytes
+    }
+}
+
+impl Drop for SecretKey {
+    fn drop(&mut self) {
+        self.bytes.zeroize();
+
+        #[cfg(test)]
+        wipe_log::record(self.address(), self.bytes.iter().all(|b| *b == 0));
+
+        if self.locked {
+            memory_lock::unlock(self.bytes.as_ptr(), KEY_SIZE);
+        }
+    }
+}
+
+// ============================================================================
+// Memory Locking
+// ============================================================================
+
+/// Best-effort page locking; failures (e.g. RLIMIT_MEMLOCK) are not fatal
+#[cfg(unix)]
+mod memory_lock {
+    pub fn lock(ptr: *const u8, len: usize) -> bool {
+        // SAFETY: `ptr` points to a live allocation of at least `len` bytes
+        unsafe { libc::mlock(ptr as *const libc::c_void, len) == 0 }
+    }
+
+    pub fn unlock(ptr: *const u8, len: usize) {
+        // SAFETY: same allocation that was passed to `lock`
+        unsafe {
+            libc::munlock(ptr as *const libc::c_void, len);
+        }
+    }
+}
+
+#[cfg(windows)]
+mod memory_lock {
+    use windows_sys::Win32::System::Memory::{VirtualLock, VirtualUnlock};
+
+    pub fn lock(ptr: *const u8, len: usize) -> bool {
+        // SAFETY: `ptr` points to a live allocation of at least `len` bytes
+        unsafe { VirtualLock(ptr as *const core::ffi::c_void, len) != 0 }
+    }
+
+    pub fn unlock(ptr: *const u8, len: usize) {
+        // SAFETY: same allocation that was passed to `lock`
+        unsafe {
+            VirtualUnlock(ptr as *const core::ffi::c_void, len);
+        }
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+mod memory_lock {
+    pub fn lock(_ptr: *const u8, _len: usize) -> bool {
+        false
+    }
+
+    pub fn unlock(_ptr: *const u8, _len: usize) {}
+}
+
+// ============================================================================
+// Test Support
+// ============================================================================
+
+/// Records whether each dropped key was all zeros right before its memory
+/// was released, so tests can check wiping without reading freed memory
+#[cfg(test)]
+pub(crate) mod wipe_log {
+    use std::cell::RefCell;
+
+    thread_local! {
+        static DROPPED: RefCell<Vec<(usize, bool)>> = const { RefCell::new(Vec::new()) };
+    }
+
+    pub fn record(address: usize, zeroed: bool) {
+        DROPPED.with(|log| log.borrow_mut().push((address, zeroed)));
+    }
+
+    /// Whether the most recent key dropped at `address` had been zeroed
+    /// (`None` if no key at that address was dropped on this thread)
+    pub fn was_wiped(address: usize) -> Option<bool> {
+        DROPPED.with(|log| {
+            log.borrow()
+                .iter()
+                .rev()
+                .find(|(a, _)| *a == address)
+                .map(|(_, zeroed)| *zeroed)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_bytes_zeroes_source() {
+        let mut source = [42u8; KEY_SIZE];
+        let key = SecretKey::from_bytes(&mut source);
+
+        assert_eq!(source, [0u8; KEY_SIZE]);
+        assert_eq!(*key, [42u8; KEY_SIZE]);
+    }
+
+    #[test]
+    fn test_drop_wipes_key() {
+        let mut source = [42u8; KEY_SIZE];
+        let key = SecretKey::from_bytes(&mut source);
+        let address = key.address();
+
+        drop(key);
+        assert_eq!(wipe_log::was_wiped(address), Some(true));
+    }
+
+    #[test]
+    fn test_move_keeps_bytes_in_place() {
+        let mut source = [1u8; KEY_SIZE];
+        let key = SecretKey::from_bytes(&mut source);
+        let address = key.address();
+
+        let moved = Box::new(key);
+        assert_eq!(moved.address(), address);
+    }
+}
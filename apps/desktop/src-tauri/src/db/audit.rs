@@ -0,0 +1,630 @@
+//! Tamper-evident audit log
+//!
+//! Entries in `operation_logs` written while a session is open are *sealed*:
+//! `details` is encrypted with the session key and the entry is chained to
+//! the previous one with an HMAC (keyed by `derive_audit_key`) over
+//!
+//! ```text
+//! [id, prev_hash, sealed_account_id, action, details, created_at]
+//! ```
+//!
+//! (with a trailing `"late"` for entries sealed late, see below).
+//!
+//! `prev_hash` of the first entry is the chain anchor (all zeros until old
+//! entries are pruned). The MAC of the newest entry is kept in the
+//! `audit_log_head` setting, itself authenticated, so removing entries from
+//! the end of the log is detected as well.
+//!
+//! Entries written without a session key (and entries from before the chain
+//! existed) are stored unsealed and sealed at the next unlock. Anyone with
+//! write access to the file can insert such rows, so they are marked
+//! `sealed_late` under the MAC and verification reports them as
+//! unverified: the chain only proves they were not changed after that
+//! unlock. Pruning
+//! through [`Database::clear_old_logs`] moves the anchor, which is not
+//! authenticated: deleting the oldest entries and moving the anchor by hand
+//! looks the same as retention pruning.
+
+use super::{Database, DbError, DbResult};
+use crate::crypto::{audit_mac, derive_audit_key, encrypt_field, is_encrypted, verify_audit_mac, SecretKey};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use std::sync::{Arc, PoisonError};
+
+/// Setting holding the MAC of the newest sealed entry
+const HEAD_SETTING: &str = "audit_log_head";
+
+/// Setting holding the `prev_hash` expected for the oldest entry
+const ANCHOR_SETTING: &str = "audit_log_anchor";
+
+/// `prev_hash` of the very first entry
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+// ============================================================================
+// Types
+// ============================================================================
+
+/// Kind of problem found while verifying the audit log
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditIssueKind {
+    /// The entry does not link to the previous one: entries were deleted,
+    /// inserted or reordered before it
+    BrokenLink,
+    /// The entry's content no longer matches its MAC
+    Altered,
+    /// The entry was never sealed
+    Unsealed,
+    /// The newest entries are missing or the head record was tampered with
+    Truncated,
+    /// The entry was written while the vault was locked and sealed at a
+    /// later unlock, so its original content cannot be vouched for
+    Unverified,
+}
+
+impl AuditIssueKind {
+    /// True for issues that show the log was tampered with
+    fn is_tampering(self) -> bool {
+        self != AuditIssueKind::Unverified
+    }
+}
+
+/// A problem found while verifying the audit log
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AuditIssue {
+    /// Affected entry (None for problems with the log as a whole)
+    pub log_id: Option<i64>,
+    pub kind: AuditIssueKind,
+    pub message: String,
+}
+
+/// Result of verifying the audit log
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditReport {
+    /// True when no tampering was found (`Unverified` entries are listed
+    /// in `issues` but do not count)
+    pub valid: bool,
+    /// Number of entries checked
+    pub checked: usize,
+    pub issues: Vec<AuditIssue>,
+}
+
+/// A sealed entry as stored
+struct ChainRow {
+    id: i64,
+    account_id: Option<i64>,
+    sealed_account_id: Option<i64>,
+    action: String,
+    details: Option<String>,
+    created_at: String,
+    prev_hash: Option<String>,
+    entry_hash: Option<String>,
+    sealed_late: bool,
+}
+
+// ============================================================================
+// Writing
+// ============================================================================
+
+/// Append a log entry, sealing it when `key` is available
+pub(crate) fn append_entry(
+    conn: &Connection,
+    key: Option<&SecretKey>,
+    account_id: Option<i64>,
+    action: &str,
+    details: Option<&str>,
+) -> DbResult<()> {
+    let Some(key) = key else {
+        conn.execute(
+            "INSERT INTO operation_logs (account_id, action, details) VALUES (?1, ?2, ?3)",
+            params![account_id, action, details],
+        )?;
+        return Ok(());
+    };
+
+    let details = details.map(|d| encrypt_details(d, key)).transpose()?;
+    conn.execute(
+        "INSERT INTO operation_logs (account_id, sealed_account_id, action, details)
+         VALUES (?1, ?1, ?2, ?3)",
+        params![account_id, action, details],
+    )?;
+
+    seal_entry(conn, &derive_audit_key(key), conn.last_insert_rowid())
+}
+
+/// Encrypt and chain every unsealed entry, oldest first
+fn seal_pending(conn: &Connection, key: &SecretKey) -> DbResult<usize> {
+    let audit_key = derive_audit_key(key);
+    let pending: Vec<(i64, Option<String>)> = {
+        let mut stmt = conn.prepare(
+            "SELECT id, details FROM operation_logs WHERE entry_hash IS NULL ORDER BY id",
+        )?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect::<Result<_, _>>()?
+    };
+
+    for (id, details) in &pending {
+        let details = match details {
+            Some(d) if !is_encrypted(d) => Some(encrypt_details(d, key)?),
+            other => other.clone(),
+        };
+        conn.execute(
+            "UPDATE operation_logs SET details = ?1, sealed_account_id = account_id, sealed_late = 1
+             WHERE id = ?2",
+            params![details, id],
+        )?;
+        seal_entry(conn, &audit_key, *id)?;
+    }
+
+    Ok(pending.len())
+}
+
+/// Link entry `id` to the current head and make it the new head
+fn seal_entry(conn: &Connection, audit_key: &SecretKey, id: i64) -> DbResult<()> {
+    // Link to the stored head even if its MAC fails; verification reports it
+    let prev_hash = match head_hash(conn)? {
+        Some(head) => head,
+        None => chain_anchor(conn)?,
+    };
+
+    let mut row = read_row(conn, id)?;
+    row.prev_hash = Some(prev_hash.clone());
+    let entry_hash = entry_mac(audit_key, &row);
+
+    conn.execute(
+        "UPDATE operation_logs SET prev_hash = ?1, entry_hash = ?2 WHERE id = ?3",
+        params![prev_hash, entry_hash, id],
+    )?;
+    write_head(conn, audit_key, &entry_hash)
+}
+
+/// Point the anchor at the oldest remaining entry after pruning
+pub(crate) fn advance_anchor(conn: &Connection) -> DbResult<()> {
+    let oldest: Option<String> = conn
+        .query_row(
+            "SELECT prev_hash FROM operation_logs WHERE entry_hash IS NOT NULL ORDER BY id LIMIT 1",
+            [],
+            |row| row.get(0),
+        )
+        .optional()?;
+
+    // With every sealed entry gone the next one links to the current head
+    let anchor = match oldest {
+        Some(prev_hash) => Some(prev_hash),
+        None => head_hash(conn)?,
+    };
+
+    if let Some(anchor) = anchor {
+        put_setting(conn, ANCHOR_SETTING, &anchor)?;
+    }
+    Ok(())
+}
+
+// ============================================================================
+// Verification
+// ============================================================================
+
+/// Walk the chain and report every inconsistency
+fn verify(conn: &Connection, key: &SecretKey) -> DbResult<AuditReport> {
+    let audit_key = derive_audit_key(key);
+    let rows: Vec<ChainRow> = {
+        let mut stmt = conn.prepare(
+            "SELECT id, account_id, sealed_account_id, action, details, created_at, prev_hash, entry_hash,
+                    sealed_late
+             FROM operation_logs ORDER BY id",
+        )?;
+        let rows = stmt.query_map([], map_row)?;
+        rows.collect::<Result<_, _>>()?
+    };
+
+    let mut issues = Vec::new();
+    let mut expected_prev = chain_anchor(conn)?;
+
+    for row in &rows {
+        let issue = |kind, message: &str| AuditIssue {
+            log_id: Some(row.id),
+            kind,
+            message: message.to_string(),
+        };
+
+        let Some(entry_hash) = &row.entry_hash else {
+            issues.push(issue(AuditIssueKind::Unsealed, "Entry is not sealed"));
+            continue;
+        };
+
+        if row.prev_hash.as_deref() != Some(expected_prev.as_str()) {
+            issues.push(issue(
+                AuditIssueKind::BrokenLink,
+                "Entry does not follow the previous entry; entries were removed or reordered",
+            ));
+        }
+
+        if !verify_audit_mac(&audit_key, &entry_message(row), entry_hash) {
+            issues.push(issue(AuditIssueKind::Altered, "Entry content does not match its MAC"));
+        } else if !account_link_intact(conn, row)? {
+            issues.push(issue(AuditIssueKind::Altered, "Entry account was changed"));
+        } else if row.sealed_late {
+            issues.push(issue(
+                AuditIssueKind::Unverified,
+                "Entry was written while the vault was locked and sealed at a later unlock",
+            ));
+        }
+
+        expected_prev = entry_hash.clone();
+    }
+
+    let has_sealed = rows.iter().any(|r| r.entry_hash.is_some());
+    match read_head(conn, &audit_key)? {
+        Some(head) if head == expected_prev => {}
+        None if !has_sealed => {}
+        _ => issues.push(AuditIssue {
+            log_id: None,
+            kind: AuditIssueKind::Truncated,
+            message: "Newest entries are missing or the log head was modified".to_string(),
+        }),
+    }
+
+    Ok(AuditReport {
+        valid: !issues.iter().any(|i| i.kind.is_tampering()),
+        checked: rows.len(),
+        issues,
+    })
+}
+
+/// `account_id` may only differ from the sealed id by being nulled when the
+/// account was deleted
+fn account_link_intact(conn: &Connection, row: &ChainRow) -> DbResult<bool> {
+    match (row.account_id, row.sealed_account_id) {
+        (current, sealed) if current == sealed => Ok(true),
+        (None, Some(sealed)) => {
+            let exists: bool = conn.query_row(
+                "SELECT EXISTS(SELECT 1 FROM accounts WHERE id = ?1)",
+                params![sealed],
+                |row| row.get(0),
+            )?;
+            Ok(!exists)
+        }
+        _ => Ok(false),
+    }
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+fn encrypt_details(details: &str, key: &SecretKey) -> DbResult<String> {
+    encrypt_field(details, key).map_err(|e| DbError::Audit(e.to_string()))
+}
+
+/// Canonical encoding of an entry, covered by its MAC
+///
+/// The late marker is only appended when set, so entries sealed before the
+/// flag existed keep verifying.
+fn entry_message(row: &ChainRow) -> Vec<u8> {
+    let mut message = serde_json::json!([
+        row.id,
+        row.prev_hash,
+        row.sealed_account_id,
+        row.action,
+        row.details,
+        row.created_at,
+    ]);
+    if row.sealed_late {
+        if let Some(fields) = message.as_array_mut() {
+            fields.push("late".into());
+        }
+    }
+    message.to_string().into_bytes()
+}
+
+fn entry_mac(audit_key: &SecretKey, row: &ChainRow) -> String {
+    audit_mac(audit_key, &entry_message(row))
+}
+
+/// The head is stored as `<entry_hash>:<mac>` so it cannot be moved back
+/// to an older entry without the key
+fn head_message(entry_hash: &str) -> Vec<u8> {
+    format!("head:{}", entry_hash).into_bytes()
+}
+
+fn head_mac(audit_key: &SecretKey, entry_hash: &str) -> String {
+    audit_mac(audit_key, &head_message(entry_hash))
+}
+
+/// Current head, or None if there is none or its MAC does not verify
+fn read_head(conn: &Connection, audit_key: &SecretKey) -> DbResult<Option<String>> {
+    let head = get_setting(conn, HEAD_SETTING)?;
+    Ok(head.and_then(|value| {
+        let (entry_hash, mac) = value.split_once(':')?;
+        verify_audit_mac(audit_key, &head_message(entry_hash), mac).then(|| entry_hash.to_string())
+    }))
+}
+
+/// Current head without checking its MAC
+fn head_hash(conn: &Connection) -> DbResult<Option<String>> {
+    let head = get_setting(conn, HEAD_SETTING)?;
+    Ok(head.map(|value| value.split(':').next().unwrap_or_default().to_string()))
+}
+
+fn write_head(conn: &Connection, audit_key: &SecretKey, entry_hash: &str) -> DbResult<()> {
+    let value = format!("{}:{}", entry_hash, head_mac(audit_key, entry_hash));
+    put_setting(conn, HEAD_SETTING, &value)
+}
+
+fn chain_anchor(conn: &Connection) -> DbResult<String> {
+    Ok(get_setting(conn, ANCHOR_SETTING)?.unwrap_or_else(|| GENESIS_HASH.to_string()))
+}
+
+fn read_row(conn: &Connection, id: i64) -> DbResult<ChainRow> {
+    conn.query_row(
+        "SELECT id, account_id, sealed_account_id, action, details, created_at, prev_hash, entry_hash,
+                sealed_late
+         FROM operation_logs WHERE id = ?1",
+        params![id],
+        map_row,
+    )
+    .map_err(Into::into)
+}
+
+fn map_row(row: &rusqlite::Row) -> rusqlite::Result<ChainRow> {
+    Ok(ChainRow {
+        id: row.get(0)?,
+        account_id: row.get(1)?,
+        sealed_account_id: row.get(2)?,
+        action: row.get(3)?,
+        details: row.get(4)?,
+        created_at: row.get(5)?,
+        prev_hash: row.get(6)?,
+        entry_hash: row.get(7)?,
+        sealed_late: row.get(8)?,
+    })
+}
+
+fn get_setting(conn: &Connection, key: &str) -> DbResult<Option<String>> {
+    let value = conn
+        .query_row("SELECT value FROM settings WHERE key = ?1", params![key], |row| row.get(0))
+        .optional()?;
+    Ok(value)
+}
+
+fn put_setting(conn: &Connection, key: &str, value: &str) -> DbResult<()> {
+    conn.execute(
+        "INSERT INTO settings (key, value) VALUES (?1, ?2)
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value, updated_at = CURRENT_TIMESTAMP",
+        params![key, value],
+    )?;
+    Ok(())
+}
+
+// ============================================================================
+// Database Integration
+// ============================================================================
+
+impl Database {
+    /// Use `key` to seal new log entries and seal the pending ones
+    ///
+    /// Only a weak reference is kept, so entries are written unsealed again
+    /// once the session ends. The key is installed even if sealing the
+    /// pending entries fails.
+    ///
+    /// # Returns
+    /// Number of previously unsealed entries that were sealed
+    pub fn set_audit_key(&self, key: &Arc<SecretKey>) -> DbResult<usize> {
+        *self.audit_key.lock().unwrap_or_else(PoisonError::into_inner) = Arc::downgrade(key);
+
+        let mut conn = self.get_conn()?;
+        let tx = conn.transaction()?;
+        let sealed = seal_pending(&tx, key)?;
+        tx.commit()?;
+
+        Ok(sealed)
+    }
+
+    /// Session key for sealing log entries, if a session is open
+    pub(crate) fn audit_key(&self) -> Option<Arc<SecretKey>> {
+        self.audit_key.lock().unwrap_or_else(PoisonError::into_inner).upgrade()
+    }
+
+    /// Verify the audit log hash chain
+    ///
+    /// Detects altered entries, entries deleted from the middle or the end
+    /// of the log, and entries that were never sealed.
+    pub fn verify_audit_log(&self, key: &SecretKey) -> DbResult<AuditReport> {
+        let conn = self.get_conn()?;
+        verify(&conn, key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::{decrypt_field, derive_key};
+    use crate::db::CreateAccount;
+
+    fn setup() -> (Database, Arc<SecretKey>) {
+        let db = Database::init_in_memory().unwrap();
+        let key = Arc::new(derive_key(b"test-password", b"test-salt-16byte"));
+        db.set_audit_key(&key).unwrap();
+        (db, key)
+    }
+
+    fn log_entries(db: &Database, count: usize) {
+        for i in 0..count {
+            db.log_operation(None, "TEST", Some(&format!("entry {}", i))).unwrap();
+        }
+    }
+
+    fn kinds(report: &AuditReport) -> Vec<AuditIssueKind> {
+        report.issues.iter().map(|i| i.kind).collect()
+    }
+
+    fn create_account(db: &Database, email: &str) -> i64 {
+        db.create_account(CreateAccount {
+            raw_import_id: None,
+            email: email.to_string(),
+            password: "password123".to_string(),
+            recovery_email: None,
+            totp_secret: None,
+            year: None,
+            notes: None,
+            group_id: None,
+            field_order: None,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_details_are_encrypted() {
+        let (db, key) = setup();
+        create_account(&db, "secret@example.com");
+
+        let details: String = db
+            .get_conn().unwrap()
+            .query_row("SELECT details FROM operation_logs WHERE action = 'CREATE'", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+
+        assert!(!details.contains("secret@example.com"));
+        assert!(decrypt_field(&details, &key).unwrap().contains("secret@example.com"));
+    }
+
+    #[test]
+    fn test_intact_chain_verifies() {
+        let (db, key) = setup();
+        log_entries(&db, 3);
+
+        let report = db.verify_audit_log(&key).unwrap();
+        assert!(report.valid, "{:?}", report.issues);
+        assert_eq!(report.checked, 3);
+    }
+
+    #[test]
+    fn test_altered_entry_is_detected() {
+        let (db, key) = setup();
+        log_entries(&db, 3);
+
+        db.get_conn().unwrap()
+            .execute("UPDATE operation_logs SET action = 'FORGED' WHERE id = 2", [])
+            .unwrap();
+
+        let report = db.verify_audit_log(&key).unwrap();
+        assert_eq!(kinds(&report), vec![AuditIssueKind::Altered]);
+        assert_eq!(report.issues[0].log_id, Some(2));
+    }
+
+    #[test]
+    fn test_deleted_entry_is_detected() {
+        let (db, key) = setup();
+        log_entries(&db, 3);
+
+        db.get_conn().unwrap().execute("DELETE FROM operation_logs WHERE id = 2", []).unwrap();
+
+        let report = db.verify_audit_log(&key).unwrap();
+        assert_eq!(kinds(&report), vec![AuditIssueKind::BrokenLink]);
+        assert_eq!(report.issues[0].log_id, Some(3));
+    }
+
+    #[test]
+    fn test_truncated_tail_is_detected() {
+        let (db, key) = setup();
+        log_entries(&db, 3);
+
+        db.get_conn().unwrap().execute("DELETE FROM operation_logs WHERE id = 3", []).unwrap();
+
+        let report = db.verify_audit_log(&key).unwrap();
+        assert_eq!(kinds(&report), vec![AuditIssueKind::Truncated]);
+    }
+
+    #[test]
+    fn test_deleted_account_keeps_chain_valid() {
+        let (db, key) = setup();
+        let id = create_account(&db, "gone@example.com");
+        db.delete_account(id).unwrap();
+
+        let report = db.verify_audit_log(&key).unwrap();
+        assert!(report.valid, "{:?}", report.issues);
+    }
+
+    #[test]
+    fn test_pending_entries_are_sealed_on_unlock() {
+        let db = Database::init_in_memory().unwrap();
+        db.log_operation(None, "LEGACY", Some("written while locked")).unwrap();
+
+        let key = Arc::new(derive_key(b"test-password", b"test-salt-16byte"));
+        let before = db.verify_audit_log(&key).unwrap();
+        assert_eq!(kinds(&before), vec![AuditIssueKind::Unsealed]);
+
+        assert_eq!(db.set_audit_key(&key).unwrap(), 1);
+        log_entries(&db, 1);
+
+        // Sealing does not vouch for what was written while locked
+        let after = db.verify_audit_log(&key).unwrap();
+        assert!(after.valid, "{:?}", after.issues);
+        assert_eq!(kinds(&after), vec![AuditIssueKind::Unverified]);
+        assert_eq!(after.issues[0].log_id, Some(1));
+        let details = db.get_operation_logs(None, 10).unwrap();
+        assert!(details.iter().all(|l| is_encrypted(l.details.as_deref().unwrap())));
+    }
+
+    #[test]
+    fn test_late_flag_is_covered_by_the_mac() {
+        let (db, key) = setup();
+        db.log_operation(None, "OK", None).unwrap();
+        // A row forged into the file while locked is sealed at the next unlock
+        db.get_conn().unwrap()
+            .execute("INSERT INTO operation_logs (action, details) VALUES ('FORGED', 'x')", [])
+            .unwrap();
+        db.set_audit_key(&key).unwrap();
+        assert_eq!(kinds(&db.verify_audit_log(&key).unwrap()), vec![AuditIssueKind::Unverified]);
+
+        // Clearing the flag to pass it off as genuine breaks its MAC
+        db.get_conn().unwrap()
+            .execute("UPDATE operation_logs SET sealed_late = 0 WHERE action = 'FORGED'", [])
+            .unwrap();
+        let report = db.verify_audit_log(&key).unwrap();
+        assert!(!report.valid);
+        assert_eq!(kinds(&report), vec![AuditIssueKind::Altered]);
+    }
+
+    #[test]
+    fn test_entries_are_unsealed_after_session_ends() {
+        let (db, key) = setup();
+        let weak = Arc::downgrade(&key);
+        drop(key);
+        assert!(weak.upgrade().is_none());
+
+        log_entries(&db, 1);
+
+        let sealed: Option<String> = db
+            .get_conn().unwrap()
+            .query_row("SELECT entry_hash FROM operation_logs", [], |row| row.get(0))
+            .unwrap();
+        assert!(sealed.is_none());
+    }
+
+    #[test]
+    fn test_pruning_keeps_chain_valid() {
+        let (db, key) = setup();
+        log_entries(&db, 3);
+
+        db.get_conn().unwrap()
+            .execute(
+                "UPDATE operation_logs SET created_at = datetime('now', '-40 days') WHERE id <= 2",
+                [],
+            )
+            .unwrap();
+        assert_eq!(db.clear_old_logs(30).unwrap(), 2);
+
+        let report = db.verify_audit_log(&key).unwrap();
+        assert!(report.valid, "{:?}", report.issues);
+
+        // Pruning everything still lets new entries link up
+        db.get_conn().unwrap()
+            .execute("UPDATE operation_logs SET created_at = datetime('now', '-40 days')", [])
+            .unwrap();
+        db.clear_old_logs(30).unwrap();
+        log_entries(&db, 1);
+        assert!(db.verify_audit_log(&key).unwrap().valid);
+    }
+}
@@ -0,0 +1,215 @@
+//! Backup verification and restore
+//!
+//! Backups are written with `VACUUM INTO` ([`Database::backup`]). Before a
+//! backup is kept or restored it is opened read-only and checked with
+//! `PRAGMA integrity_check`.
+//!
+//! A restore first copies the backup into an in-memory staging database and
+//! migrates it to the current schema, then copies the staged database over
+//! the live one with SQLite's online backup API in a single step. The live
+//! database is therefore either left untouched or fully replaced, and every
+//! `Database` handle keeps working afterwards.
+
+use super::migrations::{self, LATEST_VERSION};
+use super::{Database, DbError, DbResult};
+use rusqlite::backup::{Backup, StepResult};
+use rusqlite::{Connection, OpenFlags};
+use std::path::Path;
+use std::sync::{PoisonError, Weak};
+
+/// Tables every vault database has, whatever its schema version
+///
+/// Tables added by later migrations (e.g. `settings` in v6) are created
+/// when the backup is migrated during restore.
+const REQUIRED_TABLES: [&str; 2] = ["accounts", "groups"];
+
+/// Check that `path` is an intact vault database this version can open
+///
+/// Returns the schema version of the file.
+///
+/// # Errors
+/// - `Integrity` - The file fails the integrity check or is not a vault
+/// - `SchemaTooNew` - The file was written by a newer app version
+pub fn verify_backup_file(path: &Path) -> DbResult<i64> {
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(|e| DbError::Integrity(format!("Cannot open {}: {}", path.display(), e)))?;
+
+    let problems = integrity_check(&conn)
+        .map_err(|e| DbError::Integrity(format!("{} is not a database: {}", path.display(), e)))?;
+    if !problems.is_empty() {
+        return Err(DbError::Integrity(problems.join("; ")));
+    }
+
+    for table in REQUIRED_TABLES {
+        let exists: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?1)",
+            [table],
+            |row| row.get(0),
+        )?;
+        if !exists {
+            return Err(DbError::Integrity(format!(
+                "{} is not a vault database (missing table {})",
+                path.display(),
+                table
+            )));
+        }
+    }
+
+    let version = migrations::schema_version(&conn)?;
+    if version > LATEST_VERSION {
+        return Err(DbError::SchemaTooNew {
+            found: version,
+            supported: LATEST_VERSION,
+        });
+    }
+
+    Ok(version)
+}
+
+/// Run `PRAGMA integrity_check` and return the problems it reports
+pub(crate) fn integrity_check(conn: &Connection) -> DbResult<Vec<String>> {
+    let mut stmt = conn.prepare("PRAGMA integrity_check")?;
+    let rows = stmt
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(rows.into_iter().filter(|r| r != "ok").collect())
+}
+
+/// Copy every page of `from` into `to` in one step
+fn copy_database(from: &Connection, to: &mut Connection) -> DbResult<()> {
+    let backup = Backup::new(from, to)?;
+    match backup.step(-1)? {
+        StepResult::Done => Ok(()),
+        other => Err(DbError::InvalidInput(format!("Database copy did not finish: {:?}", other))),
+    }
+}
+
+impl Database {
+    /// Replace the whole database with a verified backup
+    ///
+    /// The backup is migrated to the current schema before it replaces the
+    /// live data. The audit log key is dropped because the restored log
+    /// belongs to the restored vault; it is set again at the next unlock.
+    ///
+    /// # Errors
+    /// Fails without changing the live database if the backup does not
+    /// verify or cannot be migrated.
+    pub fn restore_backup(&self, path: &Path) -> DbResult<()> {
+        verify_backup_file(path)?;
+
+        let source = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+        let mut staged = Connection::open_in_memory()?;
+        copy_database(&source, &mut staged)?;
+        migrations::run_migrations(&mut staged)?;
+
+        let mut conn = self.get_conn()?;
+        copy_database(&staged, &mut conn)?;
+        if self.readers.is_some() {
+            // The copy brings the backup's journal mode along
+            super::pool::enable_wal(&conn)?;
+        }
+        *self.audit_key.lock().unwrap_or_else(PoisonError::into_inner) = Weak::new();
+
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::CreateGroup;
+    use std::path::PathBuf;
+
+    fn temp_path(name: &str) -> PathBuf {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        std::env::temp_dir().join(format!("gmanager-{}-{}-{}.db", name, std::process::id(), nanos))
+    }
+
+    fn create_group(db: &Database, name: &str) {
+        db.create_group(CreateGroup {
+            name: name.to_string(),
+            color: "#000000".to_string(),
+            sort_order: 0,
+            parent_id: None,
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn test_backup_verify_and_restore() {
+        let db = Database::init_in_memory().unwrap();
+        create_group(&db, "Before");
+
+        let path = temp_path("restore");
+        db.backup(&path).unwrap();
+        assert_eq!(verify_backup_file(&path).unwrap(), LATEST_VERSION);
+
+        create_group(&db, "After");
+        db.restore_backup(&path).unwrap();
+
+        let names: Vec<String> = db.get_groups().unwrap().into_iter().map(|g| g.name).collect();
+        assert!(names.contains(&"Before".to_string()));
+        assert!(!names.contains(&"After".to_string()));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_restore_backup_from_older_schema() {
+        let path = temp_path("unversioned");
+        Connection::open(&path)
+            .unwrap()
+            .execute_batch(include_str!("fixtures/unversioned.sql"))
+            .unwrap();
+        assert_eq!(verify_backup_file(&path).unwrap(), 0);
+
+        let db = Database::init_in_memory().unwrap();
+        db.restore_backup(&path).unwrap();
+
+        let names: Vec<String> = db.get_groups().unwrap().into_iter().map(|g| g.name).collect();
+        assert!(names.contains(&"Work".to_string()));
+        assert_eq!(db.get_accounts_count().unwrap(), 1);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_rejects_damaged_and_foreign_files() {
+        let db = Database::init_in_memory().unwrap();
+        create_group(&db, "Kept");
+
+        let garbage = temp_path("garbage");
+        std::fs::write(&garbage, b"definitely not sqlite").unwrap();
+        assert!(matches!(verify_backup_file(&garbage), Err(DbError::Integrity(_))));
+        assert!(db.restore_backup(&garbage).is_err());
+
+        let foreign = temp_path("foreign");
+        let conn = Connection::open(&foreign).unwrap();
+        conn.execute_batch("CREATE TABLE notes (id INTEGER PRIMARY KEY)").unwrap();
+        drop(conn);
+        assert!(matches!(verify_backup_file(&foreign), Err(DbError::Integrity(_))));
+
+        let newer = temp_path("newer");
+        db.backup(&newer).unwrap();
+        Connection::open(&newer)
+            .unwrap()
+            .execute_batch(&format!("PRAGMA user_version = {}", LATEST_VERSION + 1))
+            .unwrap();
+        assert!(matches!(db.restore_backup(&newer), Err(DbError::SchemaTooNew { .. })));
+
+        // Failed restores leave the live database alone
+        assert!(db.get_groups().unwrap().iter().any(|g| g.name == "Kept"));
+
+        for path in [garbage, foreign, newer] {
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+}
@@ -0,0 +1,533 @@
+//! Database diagnostics and repair
+//!
+//! [`Database::run_diagnostics`] checks the vault for:
+//! - page-level corruption (`PRAGMA integrity_check`)
+//! - broken references (`PRAGMA foreign_key_check`), in particular
+//!   `account_tags` rows whose account or tag is gone and accounts whose
+//!   `raw_import_id` or `group_id` points nowhere
+//! - encrypted fields that do not decrypt (and authenticate) with the
+//!   session key
+//!
+//! With `quarantine` set, broken rows are moved into the `quarantine` table
+//! and dangling references are cleared, all in one transaction:
+//! - accounts, raw imports and undo history entries with a field that does
+//!   not decrypt
+//! - orphaned `account_tags` rows
+//!
+//! Rows are kept as stored, together with the links that deleting them
+//! drops: an account's tag ids and the ids of the accounts that came from a
+//! raw import. Only the blind search index of a quarantined account is
+//! lost; it is rebuilt from the fields.
+//!
+//! Operation log entries that do not decrypt are only reported: removing
+//! them would break the audit log hash chain. Corruption found by
+//! `integrity_check` cannot be repaired here; restore a backup instead.
+
+use super::backup::integrity_check;
+use super::transfer::AccountTagLink;
+use super::{Database, DbResult};
+use crate::crypto::{decrypt_field, is_encrypted, SecretKey};
+use rusqlite::types::ValueRef;
+use rusqlite::{params, Connection, ToSql};
+use serde::Serialize;
+
+/// Encrypted columns of `accounts`
+const ACCOUNT_ENCRYPTED_COLUMNS: [&str; 5] = ["email", "password", "recovery_email", "totp_secret", "notes"];
+
+/// Links lost when a row of a table is deleted, kept with the quarantined
+/// row: (table, key in `row_data`, query for the linked ids by rowid)
+const QUARANTINE_LINKS: [(&str, &str, &str); 2] = [
+    ("accounts", "tag_ids", "SELECT tag_id FROM account_tags WHERE account_id = ?1 ORDER BY tag_id"),
+    ("raw_imports", "account_ids", "SELECT id FROM accounts WHERE raw_import_id = ?1 ORDER BY id"),
+];
+
+/// Nullable references that may point at deleted rows: (table, column,
+/// referenced table)
+const NULLABLE_REFERENCES: [(&str, &str, &str); 3] = [
+    ("accounts", "raw_import_id", "raw_imports"),
+    ("accounts", "group_id", "groups"),
+    ("operation_logs", "account_id", "accounts"),
+];
+
+// ============================================================================
+// Types
+// ============================================================================
+
+/// Row reported by `PRAGMA foreign_key_check`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ForeignKeyViolation {
+    pub table: String,
+    /// None for tables without a rowid
+    pub row_id: Option<i64>,
+    /// Table the missing row belongs to
+    pub parent: String,
+}
+
+/// Encrypted field that does not decrypt with the session key
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct UndecryptableField {
+    pub table: String,
+    pub row_id: i64,
+    pub column: String,
+    pub message: String,
+}
+
+/// Reference to a row that no longer exists
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DanglingReference {
+    pub table: String,
+    pub row_id: i64,
+    pub column: String,
+    pub missing_id: i64,
+}
+
+/// What the repair changed
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct RepairSummary {
+    /// Rows moved into the quarantine table
+    pub quarantined_rows: usize,
+    /// Dangling references set to NULL
+    pub references_cleared: usize,
+    /// Search index entries of missing accounts removed
+    pub search_tokens_removed: usize,
+}
+
+/// Diagnostics result
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DiagnosticsReport {
+    /// True if no problem was found
+    pub healthy: bool,
+    /// Problems reported by `integrity_check`
+    pub integrity_errors: Vec<String>,
+    pub foreign_key_violations: Vec<ForeignKeyViolation>,
+    pub undecryptable_fields: Vec<UndecryptableField>,
+    pub orphaned_account_tags: Vec<AccountTagLink>,
+    pub dangling_references: Vec<DanglingReference>,
+    /// Number of encrypted fields that were decrypted
+    pub fields_checked: usize,
+    /// Set when broken rows were quarantined
+    pub repair: Option<RepairSummary>,
+}
+
+/// Row moved out of the vault by a repair
+#[derive(Debug, Clone, Serialize)]
+pub struct QuarantinedRow {
+    pub id: i64,
+    pub source_table: String,
+    /// Row id in the source table (`account_id:tag_id` for tag links)
+    pub source_id: String,
+    /// JSON object of the row as it was stored (still encrypted), plus
+    /// `tag_ids` for accounts and `account_ids` for raw imports
+    pub row_data: String,
+    pub reason: String,
+    pub quarantined_at: String,
+}
+
+// ============================================================================
+// Checks
+// ============================================================================
+
+/// Problems reported by `PRAGMA foreign_key_check`
+fn foreign_key_violations(conn: &Connection) -> DbResult<Vec<ForeignKeyViolation>> {
+    let mut stmt = conn.prepare("PRAGMA foreign_key_check")?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(ForeignKeyViolation {
+                table: row.get(0)?,
+                row_id: row.get(1)?,
+                parent: row.get(2)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(rows)
+}
+
+/// `account_tags` rows whose account or tag no longer exists
+fn orphaned_account_tags(conn: &Connection) -> DbResult<Vec<AccountTagLink>> {
+    let mut stmt = conn.prepare(
+        "SELECT account_id, tag_id FROM account_tags
+         WHERE account_id NOT IN (SELECT id FROM accounts)
+            OR tag_id NOT IN (SELECT id FROM tags)
+         ORDER BY account_id, tag_id",
+    )?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(AccountTagLink {
+                account_id: row.get(0)?,
+                tag_id: row.get(1)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(rows)
+}
+
+/// Nullable references to rows that no longer exist
+fn dangling_references(conn: &Connection) -> DbResult<Vec<DanglingReference>> {
+    let mut found = Vec::new();
+    for (table, column, parent) in NULLABLE_REFERENCES {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id, {column} FROM {table}
+             WHERE {column} IS NOT NULL AND {column} NOT IN (SELECT id FROM {parent})
+             ORDER BY id"
+        ))?;
+        let rows = stmt.query_map([], |row| {
+            Ok(DanglingReference {
+                table: table.to_string(),
+                row_id: row.get(0)?,
+                column: column.to_string(),
+                missing_id: row.get(1)?,
+            })
+        })?;
+        for row in rows {
+            found.push(row?);
+        }
+    }
+    Ok(found)
+}
+
+/// Try to decrypt every encrypted field
+///
+/// Raw imports and log details written before they were encrypted are
+/// plaintext and skipped. Returns the failures and the number of fields
+/// checked.
+fn undecryptable_fields(conn: &Connection, key: &SecretKey) -> DbResult<(Vec<UndecryptableField>, usize)> {
+    let mut failures = Vec::new();
+    let mut checked = 0;
+
+    let mut check = |table: &str, row_id: i64, column: &str, value: Option<String>, required: bool| {
+        let Some(value) = value.filter(|v| !v.is_empty()) else {
+            return;
+        };
+        if !required && !is_encrypted(&value) {
+            return;
+        }
+        checked += 1;
+        if let Err(e) = decrypt_field(&value, key) {
+            failures.push(UndecryptableField {
+                table: table.to_string(),
+                row_id,
+                column: column.to_string(),
+                message: e.to_string(),
+            });
+        }
+    };
+
+    let mut stmt = conn.prepare(&format!(
+        "SELECT id, {} FROM accounts ORDER BY id",
+        ACCOUNT_ENCRYPTED_COLUMNS.join(", ")
+    ))?;
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        let id: i64 = row.get(0)?;
+        for (i, column) in ACCOUNT_ENCRYPTED_COLUMNS.iter().enumerate() {
+            check("accounts", id, column, row.get(i + 1)?, true);
+        }
+    }
+
+    for (table, column, required) in [
+        ("raw_imports", "raw_text", false),
+        ("undo_stack", "undo_data", true),
+        ("operation_logs", "details", false),
+    ] {
+        let mut stmt = conn.prepare(&format!("SELECT id, {column} FROM {table} ORDER BY id"))?;
+        let mut rows = stmt.query([])?;
+        while let Some(row) = rows.next()? {
+            check(table, row.get(0)?, column, row.get(1)?, required);
+        }
+    }
+
+    Ok((failures, checked))
+}
+
+// ============================================================================
+// Repair
+// ============================================================================
+
+/// Move the rows matched by `condition` into the quarantine table, along
+/// with their [`QUARANTINE_LINKS`]
+fn quarantine_rows(
+    conn: &Connection,
+    table: &str,
+    source_id_sql: &str,
+    condition: &str,
+    condition_params: &[&dyn ToSql],
+    reason: &str,
+) -> DbResult<usize> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT rowid, CAST({source_id_sql} AS TEXT), * FROM {table} WHERE {condition}"
+    ))?;
+    let column_names: Vec<String> = stmt.column_names().iter().map(|c| c.to_string()).collect();
+
+    let mut moved = Vec::new();
+    let mut rows = stmt.query(condition_params)?;
+    while let Some(row) = rows.next()? {
+        let mut data = serde_json::Map::new();
+        for (i, name) in column_names.iter().enumerate().skip(2) {
+            let value = match row.get_ref(i)? {
+                ValueRef::Null => serde_json::Value::Null,
+                ValueRef::Integer(v) => v.into(),
+                ValueRef::Real(v) => v.into(),
+                ValueRef::Text(v) => String::from_utf8_lossy(v).into_owned().into(),
+                ValueRef::Blob(v) => hex::encode(v).into(),
+            };
+            data.insert(name.clone(), value);
+        }
+        moved.push((row.get::<_, i64>(0)?, row.get::<_, String>(1)?, data));
+    }
+    drop(rows);
+
+    let count = moved.len();
+    for (rowid, source_id, mut data) in moved {
+        for (_, name, sql) in QUARANTINE_LINKS.iter().filter(|(t, _, _)| *t == table) {
+            let mut stmt = conn.prepare(sql)?;
+            let ids = stmt
+                .query_map(params![rowid], |row| row.get::<_, i64>(0))?
+                .collect::<Result<Vec<_>, _>>()?;
+            data.insert(name.to_string(), ids.into());
+        }
+
+        let row_data = serde_json::Value::Object(data).to_string();
+        conn.execute(
+            "INSERT INTO quarantine (source_table, source_id, row_data, reason) VALUES (?1, ?2, ?3, ?4)",
+            params![table, source_id, row_data, reason],
+        )?;
+        conn.execute(&format!("DELETE FROM {table} WHERE rowid = ?1"), params![rowid])?;
+    }
+
+    Ok(count)
+}
+
+/// Quarantine broken rows and clear dangling references
+fn repair(conn: &Connection, report: &DiagnosticsReport) -> DbResult<RepairSummary> {
+    let mut summary = RepairSummary::default();
+
+    for field in &report.undecryptable_fields {
+        // Removing log entries would break the audit chain
+        if field.table == "operation_logs" {
+            continue;
+        }
+        summary.quarantined_rows += quarantine_rows(
+            conn,
+            &field.table,
+            "id",
+            "id = ?1",
+            &[&field.row_id],
+            &format!("{} does not decrypt: {}", field.column, field.message),
+        )?;
+    }
+
+    for link in &report.orphaned_account_tags {
+        summary.quarantined_rows += quarantine_rows(
+            conn,
+            "account_tags",
+            "account_id || ':' || tag_id",
+            "account_id = ?1 AND tag_id = ?2",
+            &[&link.account_id, &link.tag_id],
+            "account or tag no longer exists",
+        )?;
+    }
+
+    for reference in &report.dangling_references {
+        summary.references_cleared += conn.execute(
+            &format!(
+                "UPDATE {} SET {} = NULL WHERE id = ?1 AND {} = ?2",
+                reference.table, reference.column, reference.column
+            ),
+            params![reference.row_id, reference.missing_id],
+        )?;
+    }
+
+    summary.search_tokens_removed = conn.execute(
+        "DELETE FROM account_search_tokens WHERE account_id NOT IN (SELECT id FROM accounts)",
+        [],
+    )?;
+
+    Ok(summary)
+}
+
+impl Database {
+    /// Check the whole database and optionally quarantine broken rows
+    ///
+    /// The report always describes the state before any repair.
+    pub fn run_diagnostics(&self, key: &SecretKey, quarantine: bool) -> DbResult<DiagnosticsReport> {
+        let mut conn = self.get_conn()?;
+
+        let integrity_errors = integrity_check(&conn)?;
+        let foreign_key_violations = foreign_key_violations(&conn)?;
+        let orphaned_account_tags = orphaned_account_tags(&conn)?;
+        let dangling_references = dangling_references(&conn)?;
+        let (undecryptable_fields, fields_checked) = undecryptable_fields(&conn, key)?;
+
+        let mut report = DiagnosticsReport {
+            healthy: integrity_errors.is_empty()
+                && foreign_key_violations.is_empty()
+                && orphaned_account_tags.is_empty()
+                && dangling_references.is_empty()
+                && undecryptable_fields.is_empty(),
+            integrity_errors,
+            foreign_key_violations,
+            undecryptable_fields,
+            orphaned_account_tags,
+            dangling_references,
+            fields_checked,
+            repair: None,
+        };
+
+        if quarantine && !report.healthy {
+            let tx = conn.transaction()?;
+            let summary = repair(&tx, &report)?;
+            Self::clear_history_internal(&tx)?;
+            self.log_operation_internal(
+                &tx,
+                None,
+                "REPAIR_DATABASE",
+                Some(&format!(
+                    "Quarantined {} rows, cleared {} references",
+                    summary.quarantined_rows, summary.references_cleared
+                )),
+            )?;
+            tx.commit()?;
+            report.repair = Some(summary);
+        }
+
+        Ok(report)
+    }
+
+    /// Rows moved into quarantine by earlier repairs, newest first
+    pub fn get_quarantined_rows(&self) -> DbResult<Vec<QuarantinedRow>> {
+        let conn = self.get_conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, source_table, source_id, row_data, reason, quarantined_at
+             FROM quarantine ORDER BY id DESC",
+        )?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok(QuarantinedRow {
+                    id: row.get(0)?,
+                    source_table: row.get(1)?,
+                    source_id: row.get(2)?,
+                    row_data: row.get(3)?,
+                    reason: row.get(4)?,
+                    quarantined_at: row.get(5)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::{derive_key, encrypt_field};
+    use crate::db::{CreateAccount, CreateTag};
+
+    fn test_key() -> SecretKey {
+        derive_key(b"test-password", b"test-salt-16byte")
+    }
+
+    fn create_account(db: &Database, key: &SecretKey, email: &str) -> i64 {
+        db.create_account(CreateAccount {
+            raw_import_id: None,
+            email: encrypt_field(email, key).unwrap(),
+            password: encrypt_field("pw", key).unwrap(),
+            recovery_email: None,
+            totp_secret: None,
+            year: None,
+            notes: None,
+            group_id: None,
+            field_order: None,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_healthy_vault() {
+        let key = test_key();
+        let db = Database::init_in_memory().unwrap();
+        create_account(&db, &key, "a@example.com");
+
+        let report = db.run_diagnostics(&key, true).unwrap();
+
+        assert!(report.healthy);
+        assert_eq!(report.fields_checked, 2);
+        assert_eq!(report.repair, None);
+    }
+
+    #[test]
+    fn test_finds_and_quarantines_broken_rows() {
+        let key = test_key();
+        let other_key = derive_key(b"other-password", b"test-salt-16byte");
+        let db = Database::init_in_memory().unwrap();
+
+        let good = create_account(&db, &key, "good@example.com");
+        let broken = create_account(&db, &other_key, "broken@example.com");
+        let tag_id = db
+            .create_tag(CreateTag {
+                name: "vip".to_string(),
+                color: "#ffffff".to_string(),
+            })
+            .unwrap();
+        db.add_tag_to_account(good, tag_id).unwrap();
+        db.add_tag_to_account(broken, tag_id).unwrap();
+
+        {
+            // Simulate damage written while foreign keys were off
+            let conn = db.get_conn().unwrap();
+            conn.execute_batch("PRAGMA foreign_keys = OFF").unwrap();
+            conn.execute("INSERT INTO account_tags (account_id, tag_id) VALUES (999, ?1)", [tag_id])
+                .unwrap();
+            conn.execute("UPDATE accounts SET raw_import_id = 42 WHERE id = ?1", [good]).unwrap();
+            conn.execute_batch("PRAGMA foreign_keys = ON").unwrap();
+        }
+
+        let report = db.run_diagnostics(&key, false).unwrap();
+        assert!(!report.healthy);
+        assert!(report.integrity_errors.is_empty());
+        assert_eq!(report.foreign_key_violations.len(), 2);
+        assert_eq!(report.orphaned_account_tags, vec![AccountTagLink { account_id: 999, tag_id }]);
+        assert_eq!(
+            report.dangling_references,
+            vec![DanglingReference {
+                table: "accounts".to_string(),
+                row_id: good,
+                column: "raw_import_id".to_string(),
+                missing_id: 42,
+            }]
+        );
+        let broken_columns: Vec<&str> = report
+            .undecryptable_fields
+            .iter()
+            .map(|f| {
+                assert_eq!(f.row_id, broken);
+                f.column.as_str()
+            })
+            .collect();
+        assert_eq!(broken_columns, vec!["email", "password"]);
+        assert!(db.get_quarantined_rows().unwrap().is_empty());
+
+        let report = db.run_diagnostics(&key, true).unwrap();
+        let repair = report.repair.unwrap();
+        assert_eq!(repair.quarantined_rows, 2);
+        assert_eq!(repair.references_cleared, 1);
+
+        // The broken account is gone but kept in quarantine as stored
+        assert!(db.get_account(broken).is_err());
+        assert_eq!(db.get_account(good).unwrap().raw_import_id, None);
+        let quarantined = db.get_quarantined_rows().unwrap();
+        assert_eq!(quarantined.len(), 2);
+        let account_row = quarantined.iter().find(|r| r.source_table == "accounts").unwrap();
+        assert_eq!(account_row.source_id, broken.to_string());
+        let row_data: serde_json::Value = serde_json::from_str(&account_row.row_data).unwrap();
+        assert!(row_data["email"].as_str().unwrap().starts_with("enc"));
+        // Its tag links are kept with it, not lost to the cascade
+        assert_eq!(row_data["tag_ids"], serde_json::json!([tag_id]));
+        assert!(quarantined.iter().any(|r| r.source_id == format!("999:{}", tag_id)));
+
+        assert!(db.run_diagnostics(&key, false).unwrap().healthy);
+    }
+}
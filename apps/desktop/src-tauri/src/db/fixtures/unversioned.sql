@@ -0,0 +1,100 @@
+-- Database as written by the app before schema versioning (user_version = 0):
+-- baseline tables, vault created on first password setup without a wrapped
+-- data key, and a small amount of user data.
+
+CREATE TABLE raw_imports (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    raw_text TEXT NOT NULL,
+    source_type TEXT NOT NULL,
+    source_name TEXT,
+    imported_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE groups (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL UNIQUE,
+    color TEXT DEFAULT '#6366f1',
+    sort_order INTEGER DEFAULT 0,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE tags (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL UNIQUE,
+    color TEXT DEFAULT '#10b981',
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE accounts (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    raw_import_id INTEGER,
+    email TEXT NOT NULL,
+    password TEXT NOT NULL,
+    recovery_email TEXT,
+    totp_secret TEXT,
+    year INTEGER,
+    notes TEXT,
+    group_id INTEGER,
+    field_order TEXT DEFAULT 'email,password,recovery_email,totp_secret,year,notes',
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (raw_import_id) REFERENCES raw_imports(id) ON DELETE SET NULL,
+    FOREIGN KEY (group_id) REFERENCES groups(id) ON DELETE SET NULL
+);
+
+CREATE TABLE account_tags (
+    account_id INTEGER NOT NULL,
+    tag_id INTEGER NOT NULL,
+    PRIMARY KEY (account_id, tag_id),
+    FOREIGN KEY (account_id) REFERENCES accounts(id) ON DELETE CASCADE,
+    FOREIGN KEY (tag_id) REFERENCES tags(id) ON DELETE CASCADE
+);
+
+CREATE TABLE operation_logs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    account_id INTEGER,
+    action TEXT NOT NULL,
+    details TEXT,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (account_id) REFERENCES accounts(id) ON DELETE SET NULL
+);
+
+CREATE TABLE undo_stack (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    operation_type TEXT NOT NULL,
+    undo_data TEXT NOT NULL,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_accounts_email ON accounts(email);
+CREATE INDEX idx_accounts_group_id ON accounts(group_id);
+CREATE INDEX idx_operation_logs_account_id ON operation_logs(account_id);
+CREATE INDEX idx_operation_logs_created_at ON operation_logs(created_at);
+
+CREATE TABLE vault (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    salt BLOB NOT NULL,
+    verification_hash TEXT NOT NULL,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+INSERT INTO groups (id, name, color, sort_order) VALUES (1, 'Default', '#6366f1', 0);
+INSERT INTO groups (id, name, color, sort_order) VALUES (2, 'Work', '#f59e0b', 1);
+
+INSERT INTO tags (id, name, color) VALUES (1, 'primary', '#10b981');
+INSERT INTO tags (id, name, color) VALUES (2, '2fa', '#ef4444');
+
+INSERT INTO raw_imports (id, raw_text, source_type, source_name)
+VALUES (1, 'legacy-email legacy-password', 'text', 'paste');
+
+INSERT INTO accounts (id, raw_import_id, email, password, recovery_email, year, notes, group_id)
+VALUES (1, 1, 'enc1:bGVnYWN5LWVtYWls', 'enc1:bGVnYWN5LXBhc3N3b3Jk', NULL, 2019, NULL, 2);
+
+INSERT INTO account_tags (account_id, tag_id) VALUES (1, 1);
+INSERT INTO account_tags (account_id, tag_id) VALUES (1, 2);
+
+INSERT INTO operation_logs (account_id, action, details) VALUES (1, 'create', 'Created account');
+
+INSERT INTO vault (id, salt, verification_hash)
+VALUES (1, '746573742d73616c742d313662797465', 'vault1:dmVyaWZpY2F0aW9u');
@@ -0,0 +1,847 @@
+//! Versioned schema migrations for the SQLite store
+//!
+//! The schema version is tracked in SQLite's `PRAGMA user_version`. Every
+//! migration moves the schema from `version - 1` to `version` and runs in
+//! its own transaction together with the `user_version` bump, so a crash or
+//! a failing step leaves the database at the last fully applied version.
+//!
+//! Databases created before versioning report `user_version = 0` and may
+//! already contain some of the later tables and columns (they used to be
+//! created lazily), so every step is written to be idempotent.
+//!
+//! A database with a version newer than [`LATEST_VERSION`] was written by a
+//! newer build of the app and is refused instead of being modified.
+//!
+//! Foreign key enforcement is off while migrations run, so a step may
+//! rebuild a table that other tables reference without the drop cascading
+//! into them. Rebuilds keep row ids, which keeps those references valid.
+//!
+//! To add a migration, append a new entry to [`MIGRATIONS`] with the next
+//! version number. Never edit or reorder migrations that have shipped.
+
+use super::{DbError, DbResult};
+use rusqlite::{Connection, OptionalExtension};
+
+// ============================================================================
+// Migration Registry
+// ============================================================================
+
+/// A single ordered schema upgrade step
+pub(crate) struct Migration {
+    /// Schema version reached once this migration is applied
+    pub version: i64,
+    /// Short human-readable description (used in error messages)
+    pub description: &'static str,
+    /// Applies the step inside the migration transaction
+    pub up: fn(&Connection) -> DbResult<()>,
+}
+
+/// All migrations in the order they must be applied
+pub(crate) const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "initial schema",
+        up: initial_schema,
+    },
+    Migration {
+        version: 2,
+        description: "vault table",
+        up: create_vault_table,
+    },
+    Migration {
+        version: 3,
+        description: "wrapped vault data key",
+        up: add_vault_wrapped_key,
+    },
+    Migration {
+        version: 4,
+        description: "blind search index",
+        up: create_search_tokens,
+    },
+    Migration {
+        version: 5,
+        description: "vault KDF parameters",
+        up: add_vault_kdf_params,
+    },
+    Migration {
+        version: 6,
+        description: "settings table",
+        up: create_settings,
+    },
+    Migration {
+        version: 7,
+        description: "undo history redo flag",
+        up: add_undo_stack_undone,
+    },
+    Migration {
+        version: 8,
+        description: "audit log hash chain",
+        up: add_operation_log_chain,
+    },
+    Migration {
+        version: 9,
+        description: "quarantine table",
+        up: create_quarantine,
+    },
+    Migration {
+        version: 10,
+        description: "group hierarchy",
+        up: add_group_parent,
+    },
+    Migration {
+        version: 11,
+        description: "default group flag",
+        up: add_group_default_flag,
+    },
+    Migration {
+        version: 12,
+        description: "case-insensitive tag names",
+        up: add_tag_name_nocase_index,
+    },
+    Migration {
+        version: 13,
+        description: "audit log late sealing flag",
+        up: add_operation_log_sealed_late,
+    },
+];
+
+/// Schema version produced by this build of the app
+pub const LATEST_VERSION: i64 = MIGRATIONS.len() as i64;
+
+// ============================================================================
+// Runner
+// ============================================================================
+
+/// Read the schema version stored in the database header
+pub fn schema_version(conn: &Connection) -> DbResult<i64> {
+    Ok(conn.query_row("PRAGMA user_version", [], |row| row.get(0))?)
+}
+
+/// Bring the database up to [`LATEST_VERSION`]
+///
+/// # Errors
+/// - `SchemaTooNew` - The database was written by a newer app version
+/// - `Migration` - A migration step failed (the database is left at the
+///   last successfully applied version)
+pub fn run_migrations(conn: &mut Connection) -> DbResult<()> {
+    apply_migrations(conn, MIGRATIONS)
+}
+
+/// Apply every migration in `migrations` newer than the stored version
+pub(crate) fn apply_migrations(conn: &mut Connection, migrations: &[Migration]) -> DbResult<()> {
+    let supported = migrations.last().map_or(0, |m| m.version);
+    let current = schema_version(conn)?;
+
+    if current > supported {
+        return Err(DbError::SchemaTooNew {
+            found: current,
+            supported,
+        });
+    }
+
+    // The pragma is a no-op inside a transaction, so it is switched around
+    // the whole run rather than per migration
+    let foreign_keys: bool = conn.query_row("PRAGMA foreign_keys", [], |row| row.get(0))?;
+    conn.execute_batch("PRAGMA foreign_keys = OFF")?;
+
+    let result = migrations
+        .iter()
+        .filter(|m| m.version > current)
+        .try_for_each(|migration| {
+            apply_migration(conn, migration).map_err(|e| DbError::Migration {
+                version: migration.version,
+                message: format!("{}: {}", migration.description, e),
+            })
+        });
+
+    if foreign_keys {
+        conn.execute_batch("PRAGMA foreign_keys = ON")?;
+    }
+    result
+}
+
+/// Run one migration and record its version atomically
+fn apply_migration(conn: &mut Connection, migration: &Migration) -> DbResult<()> {
+    let tx = conn.transaction()?;
+    (migration.up)(&tx)?;
+    // PRAGMA does not accept bound parameters; the version is a trusted integer
+    tx.execute_batch(&format!("PRAGMA user_version = {}", migration.version))?;
+    tx.commit()?;
+    Ok(())
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+/// Check whether an index named `index` exists
+fn index_exists(conn: &Connection, index: &str) -> DbResult<bool> {
+    let count: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'index' AND name = ?1",
+        [index],
+        |row| row.get(0),
+    )?;
+    Ok(count > 0)
+}
+
+/// Check whether `table` has a column named `column`
+fn column_exists(conn: &Connection, table: &str, column: &str) -> DbResult<bool> {
+    let count: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM pragma_table_info(?1) WHERE name = ?2",
+        [table, column],
+        |row| row.get(0),
+    )?;
+    Ok(count > 0)
+}
+
+// ============================================================================
+// Migrations
+// ============================================================================
+
+/// v1: accounts, groups, tags, logs and undo tables with the default group
+fn initial_schema(conn: &Connection) -> DbResult<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS raw_imports (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            raw_text TEXT NOT NULL,
+            source_type TEXT NOT NULL,
+            source_name TEXT,
+            imported_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );
+
+        CREATE TABLE IF NOT EXISTS groups (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE,
+            color TEXT DEFAULT '#6366f1',
+            sort_order INTEGER DEFAULT 0,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );
+
+        CREATE TABLE IF NOT EXISTS tags (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE,
+            color TEXT DEFAULT '#10b981',
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );
+
+        CREATE TABLE IF NOT EXISTS accounts (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            raw_import_id INTEGER,
+            email TEXT NOT NULL,
+            password TEXT NOT NULL,
+            recovery_email TEXT,
+            totp_secret TEXT,
+            year INTEGER,
+            notes TEXT,
+            group_id INTEGER,
+            field_order TEXT DEFAULT 'email,password,recovery_email,totp_secret,year,notes',
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (raw_import_id) REFERENCES raw_imports(id) ON DELETE SET NULL,
+            FOREIGN KEY (group_id) REFERENCES groups(id) ON DELETE SET NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS account_tags (
+            account_id INTEGER NOT NULL,
+            tag_id INTEGER NOT NULL,
+            PRIMARY KEY (account_id, tag_id),
+            FOREIGN KEY (account_id) REFERENCES accounts(id) ON DELETE CASCADE,
+            FOREIGN KEY (tag_id) REFERENCES tags(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS operation_logs (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            account_id INTEGER,
+            action TEXT NOT NULL,
+            details TEXT,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (account_id) REFERENCES accounts(id) ON DELETE SET NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS undo_stack (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            operation_type TEXT NOT NULL,
+            undo_data TEXT NOT NULL,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_accounts_email ON accounts(email);
+        CREATE INDEX IF NOT EXISTS idx_accounts_group_id ON accounts(group_id);
+        CREATE INDEX IF NOT EXISTS idx_operation_logs_account_id ON operation_logs(account_id);
+        CREATE INDEX IF NOT EXISTS idx_operation_logs_created_at ON operation_logs(created_at);",
+    )?;
+
+    let group_count: i64 = conn.query_row("SELECT COUNT(*) FROM groups", [], |row| row.get(0))?;
+    if group_count == 0 {
+        conn.execute(
+            "INSERT INTO groups (name, color, sort_order) VALUES ('Default', '#6366f1', 0)",
+            [],
+        )?;
+    }
+
+    Ok(())
+}
+
+/// v2: vault record (previously created lazily on first password setup)
+fn create_vault_table(conn: &Connection) -> DbResult<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS vault (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            salt BLOB NOT NULL,
+            verification_hash TEXT NOT NULL,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+/// v3: data key wrapped by the password key (envelope encryption)
+///
+/// Existing vaults keep a NULL wrapped key and are upgraded on next unlock.
+fn add_vault_wrapped_key(conn: &Connection) -> DbResult<()> {
+    if !column_exists(conn, "vault", "wrapped_key")? {
+        conn.execute("ALTER TABLE vault ADD COLUMN wrapped_key TEXT", [])?;
+    }
+    Ok(())
+}
+
+/// v4: blind index tokens for searching encrypted fields
+///
+/// Also drops the email index: email is encrypted with a random nonce, so
+/// an index on it can never be used.
+fn create_search_tokens(conn: &Connection) -> DbResult<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS account_search_tokens (
+            account_id INTEGER NOT NULL,
+            field TEXT NOT NULL,
+            token TEXT NOT NULL,
+            PRIMARY KEY (account_id, field, token),
+            FOREIGN KEY (account_id) REFERENCES accounts(id) ON DELETE CASCADE
+        ) WITHOUT ROWID;
+
+        CREATE INDEX IF NOT EXISTS idx_account_search_tokens_token ON account_search_tokens(token);
+        DROP INDEX IF EXISTS idx_accounts_email;",
+    )?;
+    Ok(())
+}
+
+/// v5: KDF algorithm and cost parameters of the vault
+///
+/// Existing vaults were derived with PBKDF2 at 100,000 iterations; the
+/// column default records exactly that, and unlock upgrades them later.
+fn add_vault_kdf_params(conn: &Connection) -> DbResult<()> {
+    if !column_exists(conn, "vault", "kdf_params")? {
+        conn.execute(
+            r#"ALTER TABLE vault ADD COLUMN kdf_params TEXT NOT NULL
+               DEFAULT '{"algorithm":"pbkdf2","iterations":100000}'"#,
+            [],
+        )?;
+    }
+    Ok(())
+}
+
+/// v6: key/value store for user preferences (JSON values)
+fn create_settings(conn: &Connection) -> DbResult<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS settings (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+/// v7: `undone` flag so undone entries stay on the stack for redo
+///
+/// Entries written before typed history existed cannot be replayed, so the
+/// stack is cleared.
+fn add_undo_stack_undone(conn: &Connection) -> DbResult<()> {
+    if !column_exists(conn, "undo_stack", "undone")? {
+        conn.execute(
+            "ALTER TABLE undo_stack ADD COLUMN undone INTEGER NOT NULL DEFAULT 0",
+            [],
+        )?;
+        conn.execute("DELETE FROM undo_stack", [])?;
+    }
+    Ok(())
+}
+
+/// v8: hash chain columns for the tamper-evident audit log
+///
+/// Existing entries are left unsealed (plaintext details, no hashes) and get
+/// encrypted and chained at the next unlock. `sealed_account_id` keeps the
+/// account id covered by the MAC, since `account_id` is nulled when the
+/// account is deleted.
+fn add_operation_log_chain(conn: &Connection) -> DbResult<()> {
+    for (column, ty) in [("prev_hash", "TEXT"), ("entry_hash", "TEXT"), ("sealed_account_id", "INTEGER")] {
+        if !column_exists(conn, "operation_logs", column)? {
+            conn.execute(&format!("ALTER TABLE operation_logs ADD COLUMN {} {}", column, ty), [])?;
+        }
+    }
+    Ok(())
+}
+
+/// v9: rows moved out of the vault by the diagnostics repair
+fn create_quarantine(conn: &Connection) -> DbResult<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS quarantine (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            source_table TEXT NOT NULL,
+            source_id TEXT NOT NULL,
+            row_data TEXT NOT NULL,
+            reason TEXT NOT NULL,
+            quarantined_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+/// v10: `parent_id` for nested groups
+///
+/// Deleting a group makes its subgroups top-level groups. Group names are
+/// unique among siblings instead of across the vault, so the table is
+/// rebuilt without the column's `UNIQUE`. `UNIQUE (parent_id, name)` never
+/// matches two NULL parents, so top-level names get a partial index.
+fn add_group_parent(conn: &Connection) -> DbResult<()> {
+    if !column_exists(conn, "groups", "parent_id")? {
+        conn.execute(
+            "ALTER TABLE groups ADD COLUMN parent_id INTEGER REFERENCES groups(id) ON DELETE SET NULL",
+            [],
+        )?;
+    }
+    if !index_exists(conn, "idx_groups_top_level_name")? {
+        rebuild_groups_table(conn)?;
+    }
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_groups_parent ON groups(parent_id)",
+        [],
+    )?;
+    Ok(())
+}
+
+/// Recreate `groups` with names unique per parent, keeping ids
+fn rebuild_groups_table(conn: &Connection) -> DbResult<()> {
+    // Dropping the table forgets its AUTOINCREMENT high-water mark
+    let sequence: Option<i64> = conn
+        .query_row("SELECT seq FROM sqlite_sequence WHERE name = 'groups'", [], |row| row.get(0))
+        .optional()?;
+
+    conn.execute_batch(
+        "CREATE TABLE groups_rebuilt (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            color TEXT DEFAULT '#6366f1',
+            sort_order INTEGER DEFAULT 0,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            parent_id INTEGER REFERENCES groups(id) ON DELETE SET NULL,
+            UNIQUE (parent_id, name)
+        );
+
+        INSERT INTO groups_rebuilt (id, name, color, sort_order, created_at, parent_id)
+            SELECT id, name, color, sort_order, created_at, parent_id FROM groups;
+
+        DROP TABLE groups;
+        ALTER TABLE groups_rebuilt RENAME TO groups;
+
+        CREATE UNIQUE INDEX idx_groups_top_level_name ON groups(name) WHERE parent_id IS NULL;",
+    )?;
+
+    if let Some(sequence) = sequence {
+        conn.execute(
+            "UPDATE sqlite_sequence SET seq = MAX(seq, ?1) WHERE name = 'groups'",
+            [sequence],
+        )?;
+    }
+    Ok(())
+}
+
+/// v11: `is_default` marks the group accounts fall back to
+///
+/// The group used to be recognised by its name, which users can give to
+/// any group. A top-level group named `Default` gets the flag, else the
+/// oldest nested one (the seeded group may have been moved); a vault
+/// without any gets a new group.
+fn add_group_default_flag(conn: &Connection) -> DbResult<()> {
+    if !column_exists(conn, "groups", "is_default")? {
+        conn.execute(
+            "ALTER TABLE groups ADD COLUMN is_default INTEGER NOT NULL DEFAULT 0",
+            [],
+        )?;
+    }
+
+    let flagged: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM groups WHERE is_default = 1)",
+        [],
+        |row| row.get(0),
+    )?;
+    if !flagged {
+        let updated = conn.execute(
+            "UPDATE groups SET is_default = 1 WHERE id = (
+                SELECT id FROM groups WHERE name = 'Default' COLLATE NOCASE
+                ORDER BY parent_id IS NOT NULL, id LIMIT 1
+            )",
+            [],
+        )?;
+        if updated == 0 {
+            conn.execute(
+                "INSERT INTO groups (name, color, sort_order, is_default) VALUES ('Default', '#6366f1', 0, 1)",
+                [],
+            )?;
+        }
+    }
+
+    conn.execute(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_groups_default ON groups(is_default) WHERE is_default = 1",
+        [],
+    )?;
+    Ok(())
+}
+
+/// v12: tag names unique ignoring case
+///
+/// Tags whose names differ only in case are merged into the oldest one
+/// first: its accounts gain the tag and the newer duplicates are deleted.
+/// The undo history is cleared if anything was merged, since it may refer
+/// to the deleted tags.
+fn add_tag_name_nocase_index(conn: &Connection) -> DbResult<()> {
+    conn.execute(
+        "INSERT OR IGNORE INTO account_tags (account_id, tag_id)
+         SELECT account_tags.account_id,
+                (SELECT MIN(keep.id) FROM tags keep WHERE keep.name = tags.name COLLATE NOCASE)
+         FROM account_tags JOIN tags ON tags.id = account_tags.tag_id",
+        [],
+    )?;
+
+    let merged = conn.execute(
+        "DELETE FROM tags WHERE id > (
+            SELECT MIN(keep.id) FROM tags keep WHERE keep.name = tags.name COLLATE NOCASE
+        )",
+        [],
+    )?;
+    if merged > 0 {
+        // Foreign keys are off, so the links of the deleted tags stay behind
+        conn.execute("DELETE FROM account_tags WHERE tag_id NOT IN (SELECT id FROM tags)", [])?;
+        conn.execute("DELETE FROM undo_stack", [])?;
+    }
+
+    conn.execute(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_tags_name_nocase ON tags(name COLLATE NOCASE)",
+        [],
+    )?;
+    Ok(())
+}
+
+/// v13: flag for audit log entries sealed at an unlock after being written
+///
+/// Entries already sealed keep the flag unset, since their MACs were
+/// computed without it.
+fn add_operation_log_sealed_late(conn: &Connection) -> DbResult<()> {
+    if !column_exists(conn, "operation_logs", "sealed_late")? {
+        conn.execute(
+            "ALTER TABLE operation_logs ADD COLUMN sealed_late INTEGER NOT NULL DEFAULT 0",
+            [],
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Database written by the app before versioning, right after first setup
+    const FIXTURE_UNVERSIONED: &str = include_str!("fixtures/unversioned.sql");
+    /// Unversioned database that already had the lazily added vault column
+    /// and search token table
+    const FIXTURE_UNVERSIONED_INDEXED: &str = include_str!("fixtures/unversioned_indexed.sql");
+
+    fn open_fixture(sql: &str) -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(sql).unwrap();
+        conn.execute("PRAGMA foreign_keys = ON", []).unwrap();
+        conn
+    }
+
+    fn table_exists(conn: &Connection, table: &str) -> bool {
+        conn.query_row(
+            "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = ?1",
+            [table],
+            |row| row.get::<_, i64>(0),
+        )
+        .unwrap()
+            > 0
+    }
+
+    fn assert_latest_schema(conn: &Connection) {
+        assert_eq!(schema_version(conn).unwrap(), LATEST_VERSION);
+        for table in ["raw_imports", "groups", "tags", "accounts", "account_tags",
+                      "operation_logs", "undo_stack", "vault", "account_search_tokens", "settings"] {
+            assert!(table_exists(conn, table), "missing table {}", table);
+        }
+        assert!(column_exists(conn, "vault", "wrapped_key").unwrap());
+        assert!(column_exists(conn, "vault", "kdf_params").unwrap());
+        assert!(column_exists(conn, "undo_stack", "undone").unwrap());
+        assert!(column_exists(conn, "operation_logs", "entry_hash").unwrap());
+        assert!(column_exists(conn, "operation_logs", "sealed_account_id").unwrap());
+        assert!(index_exists(conn, "idx_account_search_tokens_token").unwrap());
+        assert!(!index_exists(conn, "idx_accounts_email").unwrap());
+        assert!(index_exists(conn, "idx_groups_top_level_name").unwrap());
+        assert!(column_exists(conn, "groups", "is_default").unwrap());
+        assert!(index_exists(conn, "idx_tags_name_nocase").unwrap());
+        assert!(column_exists(conn, "operation_logs", "sealed_late").unwrap());
+    }
+
+    fn failing_step(_conn: &Connection) -> DbResult<()> {
+        Err(DbError::InvalidInput("boom".to_string()))
+    }
+
+    #[test]
+    fn test_fresh_database_reaches_latest_version() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        run_migrations(&mut conn).unwrap();
+
+        assert_latest_schema(&conn);
+        let groups: Vec<String> = conn
+            .prepare("SELECT name FROM groups")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(groups, vec!["Default".to_string()]);
+    }
+
+    #[test]
+    fn test_migrations_are_idempotent() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        run_migrations(&mut conn).unwrap();
+        run_migrations(&mut conn).unwrap();
+
+        assert_latest_schema(&conn);
+        let group_count: i64 = conn
+            .query_row("SELECT COUNT(*) FROM groups", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(group_count, 1);
+    }
+
+    #[test]
+    fn test_upgrade_unversioned_database() {
+        let mut conn = open_fixture(FIXTURE_UNVERSIONED);
+        assert_eq!(schema_version(&conn).unwrap(), 0);
+
+        run_migrations(&mut conn).unwrap();
+        assert_latest_schema(&conn);
+
+        // Existing data survives and the legacy vault is left for unlock to upgrade
+        let (email, group_id): (String, i64) = conn
+            .query_row("SELECT email, group_id FROM accounts WHERE id = 1", [], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .unwrap();
+        assert_eq!(email, "enc1:bGVnYWN5LWVtYWls");
+        assert_eq!(group_id, 2);
+
+        let wrapped_key: Option<String> = conn
+            .query_row("SELECT wrapped_key FROM vault WHERE id = 1", [], |row| row.get(0))
+            .unwrap();
+        assert!(wrapped_key.is_none());
+
+        // Pre-existing vaults are recorded as PBKDF2 with the legacy iteration count
+        let kdf_params: String = conn
+            .query_row("SELECT kdf_params FROM vault WHERE id = 1", [], |row| row.get(0))
+            .unwrap();
+        let kdf_params: crate::crypto::KdfParams = serde_json::from_str(&kdf_params).unwrap();
+        assert_eq!(kdf_params, crate::crypto::KdfParams::legacy());
+
+        let tag_links: i64 = conn
+            .query_row("SELECT COUNT(*) FROM account_tags", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(tag_links, 2);
+
+        // Foreign keys still cascade after the upgrade, including into the
+        // rebuilt groups table
+        conn.execute("DELETE FROM groups WHERE id = 2", []).unwrap();
+        let group_id: Option<i64> = conn
+            .query_row("SELECT group_id FROM accounts WHERE id = 1", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(group_id, None);
+
+        conn.execute("DELETE FROM accounts WHERE id = 1", []).unwrap();
+        let tag_links: i64 = conn
+            .query_row("SELECT COUNT(*) FROM account_tags", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(tag_links, 0);
+    }
+
+    #[test]
+    fn test_group_names_are_unique_per_parent() {
+        let mut conn = open_fixture(FIXTURE_UNVERSIONED);
+        // A deleted group leaves the id sequence above the highest id
+        conn.execute("INSERT INTO groups (id, name) VALUES (3, 'Gone')", []).unwrap();
+        conn.execute("DELETE FROM groups WHERE id = 3", []).unwrap();
+
+        run_migrations(&mut conn).unwrap();
+        assert!(conn.query_row("PRAGMA foreign_keys", [], |row| row.get::<_, bool>(0)).unwrap());
+
+        let insert = |name: &str, parent_id: Option<i64>| {
+            conn.execute(
+                "INSERT INTO groups (name, parent_id) VALUES (?1, ?2)",
+                rusqlite::params![name, parent_id],
+            )
+        };
+
+        // The same name may appear under different parents
+        insert("Work", Some(1)).unwrap();
+        assert_eq!(conn.last_insert_rowid(), 4);
+        insert("Work", Some(2)).unwrap();
+        assert!(insert("Work", Some(1)).is_err());
+        assert!(insert("Work", None).is_err());
+        insert("Personal", None).unwrap();
+    }
+
+    #[test]
+    fn test_upgrade_unversioned_database_with_lazy_tables() {
+        let mut conn = open_fixture(FIXTURE_UNVERSIONED_INDEXED);
+        assert_eq!(schema_version(&conn).unwrap(), 0);
+
+        run_migrations(&mut conn).unwrap();
+        assert_latest_schema(&conn);
+
+        let wrapped_key: Option<String> = conn
+            .query_row("SELECT wrapped_key FROM vault WHERE id = 1", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(wrapped_key.as_deref(), Some("wrap1:d3JhcHBlZC1rZXk="));
+
+        let tokens: i64 = conn
+            .query_row("SELECT COUNT(*) FROM account_search_tokens", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(tokens, 2);
+    }
+
+    #[test]
+    fn test_default_group_is_flagged() {
+        let flagged = |conn: &Connection| -> Vec<(i64, String)> {
+            conn.prepare("SELECT id, name FROM groups WHERE is_default = 1")
+                .unwrap()
+                .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+                .unwrap()
+                .collect::<Result<_, _>>()
+                .unwrap()
+        };
+
+        // The seeded group keeps its id
+        let mut conn = open_fixture(FIXTURE_UNVERSIONED);
+        run_migrations(&mut conn).unwrap();
+        assert_eq!(flagged(&conn), vec![(1, "Default".to_string())]);
+
+        // A nested "default" is not the Default group, even when it is older
+        let mut conn = open_fixture(FIXTURE_UNVERSIONED);
+        apply_migrations(&mut conn, &MIGRATIONS[..10]).unwrap();
+        conn.execute_batch(
+            "DELETE FROM groups WHERE id = 1;
+             INSERT INTO groups (id, name, parent_id) VALUES (3, 'default', 2);
+             INSERT INTO groups (id, name) VALUES (4, 'DEFAULT');",
+        )
+        .unwrap();
+        run_migrations(&mut conn).unwrap();
+        assert_eq!(flagged(&conn), vec![(4, "DEFAULT".to_string())]);
+
+        // Without a top-level Default group a new one is created
+        let mut conn = open_fixture(FIXTURE_UNVERSIONED);
+        conn.execute("UPDATE groups SET name = 'Personal' WHERE id = 1", []).unwrap();
+        run_migrations(&mut conn).unwrap();
+        let default = flagged(&conn);
+        assert_eq!(default.len(), 1);
+        assert_eq!(default[0].1, "Default");
+        assert!(default[0].0 > 2);
+    }
+
+    #[test]
+    fn test_tags_differing_in_case_are_merged() {
+        let mut conn = open_fixture(FIXTURE_UNVERSIONED);
+        apply_migrations(&mut conn, &MIGRATIONS[..11]).unwrap();
+        // Account 1 has "primary" (1) and "2fa" (2)
+        conn.execute_batch(
+            "INSERT INTO tags (id, name) VALUES (3, 'PRIMARY'), (4, 'Primary'), (5, '2FA-backup');
+             INSERT INTO accounts (id, email, password) VALUES (9, 'e', 'p');
+             INSERT INTO account_tags (account_id, tag_id) VALUES (1, 3), (9, 4);
+             INSERT INTO undo_stack (operation_type, undo_data) VALUES ('delete_tag', '{}');",
+        )
+        .unwrap();
+
+        run_migrations(&mut conn).unwrap();
+
+        let tags: Vec<(i64, String)> = conn
+            .prepare("SELECT id, name FROM tags ORDER BY id")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            tags,
+            vec![(1, "primary".to_string()), (2, "2fa".to_string()), (5, "2FA-backup".to_string())]
+        );
+        let links: Vec<(i64, i64)> = conn
+            .prepare("SELECT account_id, tag_id FROM account_tags ORDER BY account_id, tag_id")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(links, vec![(1, 1), (1, 2), (9, 1)]);
+        let history: i64 = conn.query_row("SELECT COUNT(*) FROM undo_stack", [], |row| row.get(0)).unwrap();
+        assert_eq!(history, 0);
+
+        assert!(conn.execute("INSERT INTO tags (name) VALUES ('2FA')", []).is_err());
+    }
+
+    #[test]
+    fn test_newer_database_is_refused() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(&format!("PRAGMA user_version = {}", LATEST_VERSION + 1))
+            .unwrap();
+
+        let result = run_migrations(&mut conn);
+        assert!(matches!(
+            result,
+            Err(DbError::SchemaTooNew { found, supported })
+                if found == LATEST_VERSION + 1 && supported == LATEST_VERSION
+        ));
+        // Nothing was created
+        assert!(!table_exists(&conn, "accounts"));
+    }
+
+    #[test]
+    fn test_failed_migration_rolls_back_to_previous_version() {
+        fn partial_step(conn: &Connection) -> DbResult<()> {
+            conn.execute("CREATE TABLE half_done (id INTEGER)", [])?;
+            failing_step(conn)
+        }
+
+        let migrations = [
+            Migration { version: 1, description: "initial schema", up: initial_schema },
+            Migration { version: 2, description: "broken step", up: partial_step },
+        ];
+
+        let mut conn = Connection::open_in_memory().unwrap();
+        let result = apply_migrations(&mut conn, &migrations);
+
+        assert!(matches!(result, Err(DbError::Migration { version: 2, .. })));
+        assert_eq!(schema_version(&conn).unwrap(), 1);
+        assert!(table_exists(&conn, "accounts"));
+        assert!(!table_exists(&conn, "half_done"));
+    }
+
+    #[test]
+    fn test_migration_versions_are_sequential() {
+        for (index, migration) in MIGRATIONS.iter().enumerate() {
+            assert_eq!(migration.version, index as i64 + 1, "{}", migration.description);
+        }
+    }
+}
@@ -4,12 +4,26 @@
 //! - Account management (CRUD operations)
 //! - Group management for organizing accounts
 //! - Tag management for categorization
-//! - Operation logging for audit trails
+//! - Operation logging for audit trails (encrypted and hash-chained, see
+//!   [`audit`])
 //! - Undo/redo functionality
-
-use rusqlite::{params, Connection};
-use std::path::PathBuf;
-use std::sync::{Arc, Mutex};
+//! - Whole-vault snapshots for export and import (see [`transfer`])
+//! - Versioned schema migrations (see [`migrations`])
+//! - WAL journaling with a dedicated writer and a read connection pool (see
+//!   [`pool`])
+
+pub mod audit;
+pub mod backup;
+pub mod diagnostics;
+pub mod migrations;
+pub mod pool;
+pub mod transfer;
+
+use crate::crypto::SecretKey;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, Weak};
 use tauri::{AppHandle, Manager};
 
 // ============================================================================
@@ -27,6 +41,20 @@ pub enum DbError {
     InvalidInput(String),
     /// Record not found
     NotFound(String),
+    /// A record with the same name already exists
+    Duplicate(String),
+    /// Database was written by a newer version of the app
+    SchemaTooNew { found: i64, supported: i64 },
+    /// A schema migration step failed and was rolled back
+    Migration { version: i64, message: String },
+    /// An audit log entry could not be sealed
+    Audit(String),
+    /// A database file failed `PRAGMA integrity_check` or is not a vault
+    Integrity(String),
+    /// A thread panicked while holding the writer connection
+    LockPoisoned,
+    /// No read connection could be opened or checked out
+    Pool(String),
 }
 
 impl std::fmt::Display for DbError {
@@ -36,6 +64,22 @@ impl std::fmt::Display for DbError {
             DbError::NotInitialized => write!(f, "Database not initialized"),
             DbError::InvalidInput(msg) => write!(f, "Invalid input: {}", msg),
             DbError::NotFound(msg) => write!(f, "Not found: {}", msg),
+            DbError::Duplicate(msg) => write!(f, "Already exists: {}", msg),
+            DbError::SchemaTooNew { found, supported } => write!(
+                f,
+                "Database schema version {} is newer than supported version {}; please update the app",
+                found, supported
+            ),
+            DbError::Migration { version, message } => {
+                write!(f, "Migration to schema version {} failed: {}", version, message)
+            }
+            DbError::Audit(msg) => write!(f, "Audit log error: {}", msg),
+            DbError::Integrity(msg) => write!(f, "Database integrity error: {}", msg),
+            DbError::LockPoisoned => write!(
+                f,
+                "A previous database operation crashed and was rolled back; please retry"
+            ),
+            DbError::Pool(msg) => write!(f, "Database connection pool error: {}", msg),
         }
     }
 }
@@ -56,14 +100,25 @@ pub type DbResult<T> = Result<T, DbError>;
 // ============================================================================
 
 /// Database connection wrapper using singleton pattern
+///
+/// Writes go through a single connection; reads use a pool of read-only
+/// connections (see [`pool`]).
 pub struct Database {
+    /// Writer connection
     conn: Arc<Mutex<Connection>>,
+    /// Read-only connections (None for in-memory databases)
+    readers: Option<pool::ReadPool>,
+    /// Session key used to seal audit log entries. Held weakly so the
+    /// database never keeps a locked session's key alive.
+    audit_key: Arc<Mutex<Weak<SecretKey>>>,
 }
 
 impl Clone for Database {
     fn clone(&self) -> Self {
         Database {
             conn: Arc::clone(&self.conn),
+            readers: self.readers.clone(),
+            audit_key: Arc::clone(&self.audit_key),
         }
     }
 }
@@ -84,162 +139,39 @@ impl Database {
 
     /// Initialize the database with connection and schema
     pub fn init(app_handle: &AppHandle) -> DbResult<Self> {
-        let db_path = Self::get_db_path(app_handle);
-        let conn = Connection::open(&db_path)?;
+        Self::open(&Self::get_db_path(app_handle))
+    }
 
-        // Enable foreign keys
-        conn.execute("PRAGMA foreign_keys = ON", [])?;
+    /// Open (or create) the database file at `path`
+    ///
+    /// Switches the file to WAL journaling, brings the schema up to date and
+    /// opens the read pool.
+    pub fn open(path: &Path) -> DbResult<Self> {
+        let mut conn = pool::open_writer(path)?;
 
-        // Create schema
-        Self::create_schema(&conn)?;
+        // Bring the schema up to date
+        migrations::run_migrations(&mut conn)?;
 
         Ok(Database {
             conn: Arc::new(Mutex::new(conn)),
+            readers: Some(pool::open_read_pool(path)?),
+            audit_key: Arc::new(Mutex::new(Weak::new())),
         })
     }
 
     /// Initialize database for testing with in-memory database
     #[cfg(test)]
     pub fn init_in_memory() -> DbResult<Self> {
-        let conn = Connection::open_in_memory()?;
+        let mut conn = Connection::open_in_memory()?;
         conn.execute("PRAGMA foreign_keys = ON", [])?;
-        Self::create_schema(&conn)?;
+        migrations::run_migrations(&mut conn)?;
 
         Ok(Database {
             conn: Arc::new(Mutex::new(conn)),
+            readers: None,
+            audit_key: Arc::new(Mutex::new(Weak::new())),
         })
     }
-
-    /// Create all database tables and indexes
-    fn create_schema(conn: &Connection) -> DbResult<()> {
-        // Create raw_imports table
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS raw_imports (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                raw_text TEXT NOT NULL,
-                source_type TEXT NOT NULL,
-                source_name TEXT,
-                imported_at DATETIME DEFAULT CURRENT_TIMESTAMP
-            )",
-            [],
-        )?;
-
-        // Create groups table
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS groups (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                name TEXT NOT NULL UNIQUE,
-                color TEXT DEFAULT '#6366f1',
-                sort_order INTEGER DEFAULT 0,
-                created_at DATETIME DEFAULT CURRENT_TIMESTAMP
-            )",
-            [],
-        )?;
-
-        // Create tags table
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS tags (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                name TEXT NOT NULL UNIQUE,
-                color TEXT DEFAULT '#10b981',
-                created_at DATETIME DEFAULT CURRENT_TIMESTAMP
-            )",
-            [],
-        )?;
-
-        // Create accounts table
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS accounts (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                raw_import_id INTEGER,
-                email TEXT NOT NULL,
-                password TEXT NOT NULL,
-                recovery_email TEXT,
-                totp_secret TEXT,
-                year INTEGER,
-                notes TEXT,
-                group_id INTEGER,
-                field_order TEXT DEFAULT 'email,password,recovery_email,totp_secret,year,notes',
-                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-                updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-                FOREIGN KEY (raw_import_id) REFERENCES raw_imports(id) ON DELETE SET NULL,
-                FOREIGN KEY (group_id) REFERENCES groups(id) ON DELETE SET NULL
-            )",
-            [],
-        )?;
-
-        // Create account_tags junction table (many-to-many)
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS account_tags (
-                account_id INTEGER NOT NULL,
-                tag_id INTEGER NOT NULL,
-                PRIMARY KEY (account_id, tag_id),
-                FOREIGN KEY (account_id) REFERENCES accounts(id) ON DELETE CASCADE,
-                FOREIGN KEY (tag_id) REFERENCES tags(id) ON DELETE CASCADE
-            )",
-            [],
-        )?;
-
-        // Create operation_logs table
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS operation_logs (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                account_id INTEGER,
-                action TEXT NOT NULL,
-                details TEXT,
-                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-                FOREIGN KEY (account_id) REFERENCES accounts(id) ON DELETE SET NULL
-            )",
-            [],
-        )?;
-
-        // Create undo_stack table
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS undo_stack (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                operation_type TEXT NOT NULL,
-                undo_data TEXT NOT NULL,
-                created_at DATETIME DEFAULT CURRENT_TIMESTAMP
-            )",
-            [],
-        )?;
-
-        // Create indexes for better query performance
-        conn.execute(
-            "CREATE INDEX IF NOT EXISTS idx_accounts_email ON accounts(email)",
-            [],
-        )?;
-        conn.execute(
-            "CREATE INDEX IF NOT EXISTS idx_accounts_group_id ON accounts(group_id)",
-            [],
-        )?;
-        conn.execute(
-            "CREATE INDEX IF NOT EXISTS idx_operation_logs_account_id ON operation_logs(account_id)",
-            [],
-        )?;
-        conn.execute(
-            "CREATE INDEX IF NOT EXISTS idx_operation_logs_created_at ON operation_logs(created_at)",
-            [],
-        )?;
-
-        // Create default group if none exists
-        let group_count: i64 = conn.query_row("SELECT COUNT(*) FROM groups", [], |row| row.get(0))?;
-        if group_count == 0 {
-            conn.execute(
-                "INSERT INTO groups (name, color, sort_order) VALUES ('Default', '#6366f1', 0)",
-                [],
-            )?;
-        }
-
-        Ok(())
-    }
-
-    /// Get a connection lock for operations
-    pub(crate) fn get_conn(&self) -> std::sync::MutexGuard<Connection> {
-        self.conn
-            .lock()
-            .expect("Database connection lock poisoned")
-    }
 }
 
 // ============================================================================
@@ -257,7 +189,7 @@ pub struct RawImport {
 }
 
 /// Account record
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct Account {
     pub id: i64,
     pub raw_import_id: Option<i64>,
@@ -288,6 +220,19 @@ pub struct CreateAccount {
     pub field_order: Option<String>,
 }
 
+/// Account written by an import, with labels resolved by name
+#[derive(Debug, Clone)]
+pub struct ImportedAccount {
+    pub account: CreateAccount,
+    /// Group to put the account in, as names from the top level down;
+    /// created if missing. Overrides `account.group_id` unless empty.
+    pub group_path: Vec<String>,
+    /// Tags to attach; created if missing
+    pub tag_names: Vec<String>,
+    /// Blind index tokens per field, stored with the account
+    pub search_tokens: Vec<(&'static str, Vec<String>)>,
+}
+
 /// Account update data
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct UpdateAccount {
@@ -303,13 +248,20 @@ pub struct UpdateAccount {
 }
 
 /// Group for organizing accounts
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct Group {
     pub id: i64,
     pub name: String,
     pub color: String,
     pub sort_order: i32,
     pub created_at: String,
+    /// Parent group (None for top-level groups)
+    #[serde(default)]
+    pub parent_id: Option<i64>,
+    /// The group accounts fall back to; it is always top-level and cannot
+    /// be renamed or deleted
+    #[serde(default)]
+    pub is_default: bool,
 }
 
 /// Group creation/update data
@@ -318,10 +270,35 @@ pub struct CreateGroup {
     pub name: String,
     pub color: String,
     pub sort_order: i32,
+    /// Parent group (None for a top-level group)
+    #[serde(default)]
+    pub parent_id: Option<i64>,
+}
+
+/// What happens to the accounts of a group that is deleted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupMembers {
+    /// Move them to another group
+    MoveTo(i64),
+    /// Move them to the `Default` group
+    MoveToDefault,
+    /// Delete them
+    Delete,
+}
+
+/// Outcome of [`Database::delete_group_with_members`]
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize)]
+pub struct GroupDeletion {
+    /// Group the accounts were moved to
+    pub moved_to: Option<i64>,
+    pub accounts_moved: usize,
+    pub accounts_deleted: usize,
+    /// Subgroups moved up to the deleted group's parent
+    pub subgroups_moved: usize,
 }
 
 /// Tag for categorizing accounts
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct Tag {
     pub id: i64,
     pub name: String,
@@ -362,13 +339,31 @@ impl Pagination {
 /// Account search filters
 #[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
 pub struct AccountSearch {
-    pub query: Option<String>,
+    /// Blind index tokens; an account matches when one of its indexed
+    /// fields carries every token
+    pub query_tokens: Vec<String>,
     pub group_id: Option<i64>,
+    /// Also match accounts in subgroups of `group_id`
+    #[serde(default)]
+    pub include_subgroups: bool,
     pub tag_id: Option<i64>,
     pub year: Option<i32>,
     pub pagination: Pagination,
 }
 
+/// Operation log filters
+///
+/// Dates are compared against `created_at` (`YYYY-MM-DD HH:MM:SS`, UTC).
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct LogSearch {
+    pub account_id: Option<i64>,
+    pub action: Option<String>,
+    /// Inclusive lower bound
+    pub from: Option<String>,
+    /// Exclusive upper bound
+    pub to: Option<String>,
+}
+
 // ============================================================================
 // CRUD Operations for Accounts
 // ============================================================================
@@ -376,9 +371,36 @@ pub struct AccountSearch {
 impl Database {
     /// Create a new account
     pub fn create_account(&self, account: CreateAccount) -> DbResult<i64> {
-        let conn = self.get_conn();
+        self.create_account_with_tokens(account, &[])
+    }
+
+    /// Create a new account and store its blind index tokens in the same transaction
+    ///
+    /// Each entry of `tokens` is `(field, tokens)`, as for `replace_search_tokens`.
+    pub fn create_account_with_tokens(
+        &self,
+        account: CreateAccount,
+        tokens: &[(&str, Vec<String>)],
+    ) -> DbResult<i64> {
+        let mut conn = self.get_conn()?;
+        let tx = conn.transaction()?;
+
+        let account_id = self.insert_account_internal(&tx, &account, "CREATE", "Created account")?;
+        replace_field_tokens_internal(&tx, account_id, tokens)?;
 
-        let field_order = account.field_order.unwrap_or_else(|| {
+        tx.commit()?;
+        Ok(account_id)
+    }
+
+    /// Internal insert helper that accepts a connection reference
+    fn insert_account_internal(
+        &self,
+        conn: &Connection,
+        account: &CreateAccount,
+        action: &str,
+        log_prefix: &str,
+    ) -> DbResult<i64> {
+        let field_order = account.field_order.clone().unwrap_or_else(|| {
             "email,password,recovery_email,totp_secret,year,notes".to_string()
         });
 
@@ -404,10 +426,10 @@ impl Database {
 
         // Log the operation
         self.log_operation_internal(
-            &conn,
+            conn,
             Some(account_id),
-            "CREATE",
-            Some(&format!("Created account: {}", account.email)),
+            action,
+            Some(&format!("{}: {}", log_prefix, account.email)),
         )?;
 
         Ok(account_id)
@@ -415,7 +437,7 @@ impl Database {
 
     /// Get account by ID with tags
     pub fn get_account(&self, id: i64) -> DbResult<Account> {
-        let conn = self.get_conn();
+        let conn = self.read_conn()?;
 
         let mut stmt = conn.prepare(
             "SELECT id, raw_import_id, email, password, recovery_email,
@@ -427,7 +449,7 @@ impl Database {
 
         let row = rows.next()?.ok_or(DbError::NotFound(format!("Account {}", id)))?;
 
-        let account = Account {
+        let mut account = Account {
             id: row.get(0)?,
             raw_import_id: row.get(1)?,
             email: row.get(2)?,
@@ -440,22 +462,23 @@ impl Database {
             field_order: row.get(9)?,
             created_at: row.get(10)?,
             updated_at: row.get(11)?,
-            tags: self.get_tags_for_account(&conn, id)?,
+            tags: Vec::new(),
         };
+        Self::attach_tags(&conn, std::slice::from_mut(&mut account))?;
 
         Ok(account)
     }
 
     /// Get all accounts with pagination
     pub fn get_accounts(&self, pagination: Option<Pagination>) -> DbResult<Vec<Account>> {
-        let conn = self.get_conn();
+        let conn = self.read_conn()?;
         let pag = pagination.unwrap_or_default();
 
         let mut stmt = conn.prepare(
             "SELECT id, raw_import_id, email, password, recovery_email,
                     totp_secret, year, notes, group_id, field_order, created_at, updated_at
              FROM accounts
-             ORDER BY created_at DESC
+             ORDER BY created_at DESC, id DESC
              LIMIT ?1 OFFSET ?2"
         )?;
 
@@ -477,25 +500,54 @@ impl Database {
                 field_order: row.get(9)?,
                 created_at: row.get(10)?,
                 updated_at: row.get(11)?,
-                tags: self.get_tags_for_account(&conn, account_id)?,
+                tags: Vec::new(),
             };
             accounts.push(account);
         }
+        Self::attach_tags(&conn, &mut accounts)?;
 
         Ok(accounts)
     }
 
     /// Get total count of accounts
     pub fn get_accounts_count(&self) -> DbResult<i64> {
-        let conn = self.get_conn();
+        let conn = self.read_conn()?;
         let count: i64 = conn.query_row("SELECT COUNT(*) FROM accounts", [], |row| row.get(0))?;
         Ok(count)
     }
 
     /// Update an existing account
     pub fn update_account(&self, account: UpdateAccount) -> DbResult<()> {
-        let conn = self.get_conn();
+        self.update_account_with_tokens(account, &[])
+    }
+
+    /// Update an existing account and replace the blind index tokens of the
+    /// listed fields in the same transaction
+    pub fn update_account_with_tokens(
+        &self,
+        account: UpdateAccount,
+        tokens: &[(&str, Vec<String>)],
+    ) -> DbResult<()> {
+        let mut conn = self.get_conn()?;
+        let tx = conn.transaction()?;
+
+        self.update_account_internal(&tx, &account)?;
+        replace_field_tokens_internal(&tx, account.id, tokens)?;
+
+        // Log the operation
+        self.log_operation_internal(
+            &tx,
+            Some(account.id),
+            "UPDATE",
+            Some(&format!("Updated account {}", account.id)),
+        )?;
+
+        tx.commit()?;
+        Ok(())
+    }
 
+    /// Internal update helper that accepts a connection reference (not logged)
+    fn update_account_internal(&self, conn: &Connection, account: &UpdateAccount) -> DbResult<()> {
         // Build dynamic UPDATE query based on provided fields
         let mut updates = Vec::new();
         let mut params: Vec<&dyn rusqlite::ToSql> = Vec::new();
@@ -548,26 +600,14 @@ impl Database {
             return Err(DbError::NotFound(format!("Account {}", account.id)));
         }
 
-        // Log the operation
-        self.log_operation_internal(
-            &conn,
-            Some(account.id),
-            "UPDATE",
-            Some(&format!("Updated account {}", account.id)),
-        )?;
-
         Ok(())
     }
 
     /// Delete an account by ID (cascades to account_tags)
     pub fn delete_account(&self, id: i64) -> DbResult<()> {
-        let conn = self.get_conn();
+        let conn = self.get_conn()?;
 
-        let affected = conn.execute("DELETE FROM accounts WHERE id = ?1", params![id])?;
-
-        if affected == 0 {
-            return Err(DbError::NotFound(format!("Account {}", id)));
-        }
+        self.delete_account_internal(&conn, id)?;
 
         // Log the operation (account_id will be NULL in logs since account is deleted)
         self.log_operation_internal(
@@ -580,33 +620,62 @@ impl Database {
         Ok(())
     }
 
+    /// Internal delete helper that accepts a connection reference (not logged)
+    fn delete_account_internal(&self, conn: &Connection, id: i64) -> DbResult<()> {
+        let affected = conn.execute("DELETE FROM accounts WHERE id = ?1", params![id])?;
+
+        if affected == 0 {
+            return Err(DbError::NotFound(format!("Account {}", id)));
+        }
+
+        Ok(())
+    }
+
     /// Search accounts with filters
     pub fn search_accounts(&self, search: AccountSearch) -> DbResult<Vec<Account>> {
-        let conn = self.get_conn();
+        let conn = self.read_conn()?;
 
         let mut conditions = Vec::new();
         let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
 
-        // Text search across multiple fields
-        if let Some(ref query) = search.query {
-            if !query.trim().is_empty() {
-                conditions.push("(email LIKE ? OR recovery_email LIKE ? OR notes LIKE ?)");
-                let like_pattern = format!("%{}%", query);
-                params.push(Box::new(like_pattern.clone()));
-                params.push(Box::new(like_pattern.clone()));
-                params.push(Box::new(like_pattern));
+        // Text search through the blind index (encrypted columns cannot be matched directly)
+        let token_condition;
+        if !search.query_tokens.is_empty() {
+            let placeholders = vec!["?"; search.query_tokens.len()].join(", ");
+            token_condition = format!(
+                "accounts.id IN (
+                    SELECT account_id FROM account_search_tokens
+                    WHERE token IN ({})
+                    GROUP BY account_id, field
+                    HAVING COUNT(DISTINCT token) = ?
+                )",
+                placeholders
+            );
+            conditions.push(token_condition.as_str());
+            for token in &search.query_tokens {
+                params.push(Box::new(token.clone()));
             }
+            params.push(Box::new(search.query_tokens.len() as i64));
         }
 
-        // Filter by group
+        // Filter by group, optionally with its subgroups
+        let subtree_condition;
         if let Some(group_id) = search.group_id {
-            conditions.push("group_id = ?");
+            if search.include_subgroups {
+                subtree_condition = format!(
+                    "accounts.group_id IN (WITH RECURSIVE {} SELECT id FROM subtree)",
+                    GROUP_SUBTREE_CTE
+                );
+                conditions.push(subtree_condition.as_str());
+            } else {
+                conditions.push("accounts.group_id = ?");
+            }
             params.push(Box::new(group_id));
         }
 
         // Filter by year
         if let Some(year) = search.year {
-            conditions.push("year = ?");
+            conditions.push("accounts.year = ?");
             params.push(Box::new(year));
         }
 
@@ -636,7 +705,7 @@ impl Database {
                     accounts.created_at, accounts.updated_at
              FROM {}
              {}
-             ORDER BY accounts.created_at DESC
+             ORDER BY accounts.created_at DESC, accounts.id DESC
              LIMIT ? OFFSET ?",
             from_clause, where_clause
         );
@@ -668,14 +737,55 @@ impl Database {
                 field_order: row.get(9)?,
                 created_at: row.get(10)?,
                 updated_at: row.get(11)?,
-                tags: self.get_tags_for_account(&conn, account_id)?,
+                tags: Vec::new(),
             };
             accounts.push(account);
         }
+        Self::attach_tags(&conn, &mut accounts)?;
 
         Ok(accounts)
     }
 
+    /// Load the tags of every given account with one query
+    ///
+    /// Listing a page of accounts used to run one tag query per account.
+    /// The ids are passed as a single JSON array, so there is no limit on
+    /// the number of accounts.
+    fn attach_tags(conn: &Connection, accounts: &mut [Account]) -> DbResult<()> {
+        if accounts.is_empty() {
+            return Ok(());
+        }
+
+        let ids: Vec<i64> = accounts.iter().map(|a| a.id).collect();
+        let ids_json = serde_json::to_string(&ids)
+            .map_err(|e| DbError::InvalidInput(e.to_string()))?;
+
+        let mut stmt = conn.prepare(
+            "SELECT at.account_id, t.id, t.name, t.color, t.created_at
+             FROM account_tags at
+             INNER JOIN tags t ON t.id = at.tag_id
+             WHERE at.account_id IN (SELECT value FROM json_each(?1))
+             ORDER BY t.name"
+        )?;
+
+        let mut tags_by_account: HashMap<i64, Vec<Tag>> = HashMap::new();
+        let mut rows = stmt.query(params![ids_json])?;
+        while let Some(row) = rows.next()? {
+            tags_by_account.entry(row.get(0)?).or_default().push(Tag {
+                id: row.get(1)?,
+                name: row.get(2)?,
+                color: row.get(3)?,
+                created_at: row.get(4)?,
+            });
+        }
+
+        for account in accounts.iter_mut() {
+            account.tags = tags_by_account.remove(&account.id).unwrap_or_default();
+        }
+
+        Ok(())
+    }
+
     /// Get tags for a specific account
     fn get_tags_for_account(&self, conn: &Connection, account_id: i64) -> DbResult<Vec<Tag>> {
         let mut stmt = conn.prepare(
@@ -703,150 +813,870 @@ impl Database {
 }
 
 // ============================================================================
-// Group Operations
+// Batch Operations
 // ============================================================================
 
-impl Database {
-    /// Create a new group
-    pub fn create_group(&self, group: CreateGroup) -> DbResult<i64> {
-        let conn = self.get_conn();
-
-        conn.execute(
-            "INSERT INTO groups (name, color, sort_order) VALUES (?1, ?2, ?3)",
-            params![group.name, group.color, group.sort_order],
-        )?;
-
-        Ok(conn.last_insert_rowid())
-    }
-
-    /// Get group by ID
-    pub fn get_group(&self, id: i64) -> DbResult<Group> {
-        let conn = self.get_conn();
+/// How a batch treats items that fail
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchMode {
+    /// Any failed item rolls back the whole batch
+    #[default]
+    AllOrNothing,
+    /// Failed items are skipped and the rest is committed
+    BestEffort,
+}
 
-        let mut stmt = conn.prepare("SELECT id, name, color, sort_order, created_at FROM groups WHERE id = ?1")?;
+/// Outcome of a single batch item
+#[derive(Debug, Clone, PartialEq)]
+pub enum BatchItemOutcome {
+    /// The item was applied (and kept, if the batch was committed)
+    Applied,
+    /// The target record does not exist
+    NotFound,
+    /// The item was rejected (bad input or constraint violation)
+    Invalid(String),
+}
 
-        let mut rows = stmt.query(params![id])?;
+/// Outcome of a batch
+#[derive(Debug, Clone)]
+pub struct BatchOutcome {
+    /// Whether the transaction was committed
+    pub committed: bool,
+    /// Per-item outcomes in input order
+    pub items: Vec<BatchItemOutcome>,
+}
 
-        let row = rows.next()?.ok_or(DbError::NotFound(format!("Group {}", id)))?;
+/// How a tag batch changes the tags of each account
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TagChange {
+    /// Add the tags, keeping the account's other tags
+    Add,
+    /// Remove the tags
+    Remove,
+    /// Make the tags the account's only tags
+    Replace,
+}
 
-        Ok(Group {
-            id: row.get(0)?,
-            name: row.get(1)?,
-            color: row.get(2)?,
-            sort_order: row.get(3)?,
-            created_at: row.get(4)?,
-        })
+impl BatchOutcome {
+    /// Number of items that were applied
+    pub fn applied_count(&self) -> usize {
+        self.items.iter().filter(|o| **o == BatchItemOutcome::Applied).count()
     }
+}
 
-    /// Get all groups ordered by sort_order
-    pub fn get_groups(&self) -> DbResult<Vec<Group>> {
-        let conn = self.get_conn();
-
-        let mut stmt = conn.prepare("SELECT id, name, color, sort_order, created_at FROM groups ORDER BY sort_order, name")?;
+impl Database {
+    /// Delete several accounts in one transaction
+    pub fn batch_delete_accounts(&self, ids: &[i64], mode: BatchMode) -> DbResult<BatchOutcome> {
+        self.run_batch(
+            ids,
+            mode,
+            "BATCH_DELETE",
+            "Deleted",
+            |id| *id,
+            |conn, id| self.delete_account_internal(conn, *id),
+        )
+    }
 
-        let mut rows = stmt.query([])?;
-        let mut groups = Vec::new();
+    /// Update several accounts in one transaction
+    ///
+    /// `tokens` replaces the blind index tokens of the listed fields on every
+    /// updated account, inside the same transaction.
+    pub fn batch_update_accounts(
+        &self,
+        updates: &[UpdateAccount],
+        tokens: &[(&str, Vec<String>)],
+        mode: BatchMode,
+    ) -> DbResult<BatchOutcome> {
+        self.run_batch(
+            updates,
+            mode,
+            "BATCH_UPDATE",
+            "Updated",
+            |update| update.id,
+            |conn, update| {
+                self.update_account_internal(conn, update)?;
+                replace_field_tokens_internal(conn, update.id, tokens)
+            },
+        )
+    }
 
-        while let Some(row) = rows.next()? {
-            groups.push(Group {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                color: row.get(2)?,
-                sort_order: row.get(3)?,
-                created_at: row.get(4)?,
-            });
+    /// Add, remove or replace tags on several accounts in one transaction
+    ///
+    /// Every tag must exist: a missing tag fails the whole call with
+    /// `NotFound` before any account is touched. Adding a tag an account
+    /// already has, or removing one it does not have, counts as applied.
+    pub fn batch_change_account_tags(
+        &self,
+        account_ids: &[i64],
+        tag_ids: &[i64],
+        change: TagChange,
+        mode: BatchMode,
+    ) -> DbResult<BatchOutcome> {
+        let mut tag_ids = tag_ids.to_vec();
+        tag_ids.sort_unstable();
+        tag_ids.dedup();
+        let tag_ids_json = serde_json::to_string(&tag_ids)
+            .map_err(|e| DbError::InvalidInput(e.to_string()))?;
+
+        {
+            let conn = self.read_conn()?;
+            let missing: Option<i64> = conn
+                .query_row(
+                    "SELECT value FROM json_each(?1) WHERE value NOT IN (SELECT id FROM tags) LIMIT 1",
+                    params![tag_ids_json],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            if let Some(tag_id) = missing {
+                return Err(DbError::NotFound(format!("Tag {}", tag_id)));
+            }
         }
 
-        Ok(groups)
-    }
+        let tag_list = tag_ids.iter().map(|id| id.to_string()).collect::<Vec<_>>().join(", ");
+        let (action, verb) = match change {
+            TagChange::Add => ("BATCH_ADD_TAGS", format!("Added tags [{}] to", tag_list)),
+            TagChange::Remove => ("BATCH_REMOVE_TAGS", format!("Removed tags [{}] from", tag_list)),
+            TagChange::Replace => ("BATCH_REPLACE_TAGS", format!("Set tags [{}] on", tag_list)),
+        };
 
-    /// Update a group
-    pub fn update_group(&self, id: i64, group: CreateGroup) -> DbResult<()> {
-        let conn = self.get_conn();
+        self.run_batch(
+            account_ids,
+            mode,
+            action,
+            &verb,
+            |id| *id,
+            |conn, id| Self::change_account_tags_internal(conn, *id, &tag_ids_json, change),
+        )
+    }
 
-        let affected = conn.execute(
-            "UPDATE groups SET name = ?1, color = ?2, sort_order = ?3 WHERE id = ?4",
-            params![group.name, group.color, group.sort_order, id],
+    /// Apply a tag change to one account; `tag_ids_json` is a JSON array
+    fn change_account_tags_internal(
+        conn: &Connection,
+        account_id: i64,
+        tag_ids_json: &str,
+        change: TagChange,
+    ) -> DbResult<()> {
+        let exists: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM accounts WHERE id = ?1)",
+            params![account_id],
+            |row| row.get(0),
         )?;
+        if !exists {
+            return Err(DbError::NotFound(format!("Account {}", account_id)));
+        }
 
-        if affected == 0 {
-            return Err(DbError::NotFound(format!("Group {}", id)));
+        match change {
+            TagChange::Remove => conn.execute(
+                "DELETE FROM account_tags WHERE account_id = ?1 AND tag_id IN (SELECT value FROM json_each(?2))",
+                params![account_id, tag_ids_json],
+            )?,
+            TagChange::Replace => conn.execute(
+                "DELETE FROM account_tags WHERE account_id = ?1 AND tag_id NOT IN (SELECT value FROM json_each(?2))",
+                params![account_id, tag_ids_json],
+            )?,
+            TagChange::Add => 0,
+        };
+
+        if change != TagChange::Remove {
+            conn.execute(
+                "INSERT OR IGNORE INTO account_tags (account_id, tag_id) SELECT ?1, value FROM json_each(?2)",
+                params![account_id, tag_ids_json],
+            )?;
         }
 
         Ok(())
     }
 
-    /// Delete a group (accounts' group_id will be set to NULL)
-    pub fn delete_group(&self, id: i64) -> DbResult<()> {
-        let conn = self.get_conn();
+    /// Apply `apply` to every item inside one transaction
+    ///
+    /// Each item runs in its own savepoint so a failed item leaves no partial
+    /// writes behind. Every item is attempted so the caller gets a complete
+    /// report; in all-or-nothing mode the transaction is then rolled back if
+    /// any item failed. A committed batch writes a single grouped entry to
+    /// `operation_logs`. Errors other than not-found, invalid input and
+    /// constraint violations abort the batch.
+    fn run_batch<T>(
+        &self,
+        items: &[T],
+        mode: BatchMode,
+        action: &str,
+        verb: &str,
+        item_id: impl Fn(&T) -> i64,
+        apply: impl Fn(&Connection, &T) -> DbResult<()>,
+    ) -> DbResult<BatchOutcome> {
+        let mut conn = self.get_conn()?;
+        let mut tx = conn.transaction()?;
+
+        let mut outcomes = Vec::with_capacity(items.len());
+        for item in items {
+            let savepoint = tx.savepoint()?;
+            let outcome = match apply(&savepoint, item) {
+                Ok(()) => {
+                    savepoint.commit()?;
+                    BatchItemOutcome::Applied
+                }
+                // Dropping the savepoint rolls the item back
+                Err(e) => batch_item_outcome(e)?,
+            };
+            outcomes.push(outcome);
+        }
 
-        let affected = conn.execute("DELETE FROM groups WHERE id = ?1", params![id])?;
+        let outcome = BatchOutcome { committed: false, items: outcomes };
+        if mode == BatchMode::AllOrNothing && outcome.applied_count() < items.len() {
+            // Dropping the transaction rolls back the applied items
+            return Ok(outcome);
+        }
 
-        if affected == 0 {
-            return Err(DbError::NotFound(format!("Group {}", id)));
+        let applied_ids: Vec<String> = items
+            .iter()
+            .zip(&outcome.items)
+            .filter(|(_, o)| **o == BatchItemOutcome::Applied)
+            .map(|(item, _)| item_id(item).to_string())
+            .collect();
+
+        if !applied_ids.is_empty() {
+            self.log_operation_internal(
+                &tx,
+                None,
+                action,
+                Some(&format!(
+                    "{} {} of {} accounts: {}",
+                    verb,
+                    applied_ids.len(),
+                    items.len(),
+                    applied_ids.join(", ")
+                )),
+            )?;
         }
 
-        Ok(())
+        tx.commit()?;
+
+        Ok(BatchOutcome { committed: true, ..outcome })
     }
+}
 
-    /// Get accounts count for a group
-    pub fn get_group_accounts_count(&self, group_id: i64) -> DbResult<i64> {
-        let conn = self.get_conn();
-        let count: i64 = conn.query_row(
-            "SELECT COUNT(*) FROM accounts WHERE group_id = ?1",
-            params![group_id],
-            |row| row.get(0),
-        )?;
-        Ok(count)
+/// Classify an item error, passing through errors that should abort the batch
+fn batch_item_outcome(err: DbError) -> DbResult<BatchItemOutcome> {
+    match err {
+        DbError::NotFound(_) => Ok(BatchItemOutcome::NotFound),
+        DbError::InvalidInput(msg) => Ok(BatchItemOutcome::Invalid(msg)),
+        DbError::Sqlite(rusqlite::Error::SqliteFailure(e, msg))
+            if e.code == rusqlite::ErrorCode::ConstraintViolation =>
+        {
+            Ok(BatchItemOutcome::Invalid(msg.unwrap_or_else(|| e.to_string())))
+        }
+        other => Err(other),
     }
 }
 
 // ============================================================================
-// Tag Operations
+// Search Index Operations
 // ============================================================================
 
 impl Database {
-    /// Create a new tag
-    pub fn create_tag(&self, tag: CreateTag) -> DbResult<i64> {
-        let conn = self.get_conn();
+    /// Replace the blind index tokens stored for one field of an account
+    pub fn replace_search_tokens(&self, account_id: i64, field: &str, tokens: &[String]) -> DbResult<()> {
+        let mut conn = self.get_conn()?;
+        let tx = conn.transaction()?;
 
-        conn.execute(
-            "INSERT INTO tags (name, color) VALUES (?1, ?2)",
-            params![tag.name, tag.color],
-        )?;
+        replace_search_tokens_internal(&tx, account_id, field, tokens)?;
 
-        Ok(conn.last_insert_rowid())
+        tx.commit()?;
+        Ok(())
     }
 
-    /// Get tag by ID
-    pub fn get_tag(&self, id: i64) -> DbResult<Tag> {
-        let conn = self.get_conn();
+    /// Remove every blind index token (used before a full rebuild)
+    pub fn clear_search_tokens(&self) -> DbResult<()> {
+        let conn = self.get_conn()?;
+        conn.execute("DELETE FROM account_search_tokens", [])?;
+        Ok(())
+    }
 
-        let mut stmt = conn.prepare("SELECT id, name, color, created_at FROM tags WHERE id = ?1")?;
+    /// Get IDs of accounts that have no blind index tokens yet
+    pub fn get_unindexed_account_ids(&self) -> DbResult<Vec<i64>> {
+        let conn = self.read_conn()?;
 
-        let mut rows = stmt.query(params![id])?;
+        let mut stmt = conn.prepare(
+            "SELECT id FROM accounts
+             WHERE id NOT IN (SELECT DISTINCT account_id FROM account_search_tokens)
+             ORDER BY id"
+        )?;
 
-        let row = rows.next()?.ok_or(DbError::NotFound(format!("Tag {}", id)))?;
+        let ids = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<Vec<i64>, _>>()?;
 
-        Ok(Tag {
-            id: row.get(0)?,
-            name: row.get(1)?,
-            color: row.get(2)?,
-            created_at: row.get(3)?,
-        })
+        Ok(ids)
     }
+}
 
-    /// Get all tags
-    pub fn get_tags(&self) -> DbResult<Vec<Tag>> {
-        let conn = self.get_conn();
+/// Replace the tokens of one field (runs on the caller's transaction)
+fn replace_search_tokens_internal(
+    conn: &Connection,
+    account_id: i64,
+    field: &str,
+    tokens: &[String],
+) -> DbResult<()> {
+    conn.execute(
+        "DELETE FROM account_search_tokens WHERE account_id = ?1 AND field = ?2",
+        params![account_id, field],
+    )?;
+
+    let mut stmt = conn.prepare(
+        "INSERT OR IGNORE INTO account_search_tokens (account_id, field, token) VALUES (?1, ?2, ?3)"
+    )?;
+    for token in tokens {
+        stmt.execute(params![account_id, field, token])?;
+    }
 
-        let mut stmt = conn.prepare("SELECT id, name, color, created_at FROM tags ORDER BY name")?;
+    Ok(())
+}
 
-        let mut rows = stmt.query([])?;
-        let mut tags = Vec::new();
+/// Replace the tokens of every listed `(field, tokens)` entry
+fn replace_field_tokens_internal(
+    conn: &Connection,
+    account_id: i64,
+    tokens: &[(&str, Vec<String>)],
+) -> DbResult<()> {
+    for (field, field_tokens) in tokens {
+        replace_search_tokens_internal(conn, account_id, field, field_tokens)?;
+    }
+    Ok(())
+}
 
-        while let Some(row) = rows.next()? {
-            tags.push(Tag {
+// ============================================================================
+// Group Operations
+// ============================================================================
+
+/// Recursive CTE `subtree(id)`: the group bound to its `?` parameter and
+/// every group below it. `UNION` (not `UNION ALL`) makes it terminate even
+/// if the stored hierarchy somehow contains a cycle.
+const GROUP_SUBTREE_CTE: &str = "subtree(id) AS (
+    SELECT ?
+    UNION
+    SELECT g.id FROM groups g INNER JOIN subtree s ON g.parent_id = s.id
+)";
+
+/// Name of the group created with every vault
+///
+/// Only used when creating the group; afterwards it is identified by its
+/// `is_default` flag.
+pub const DEFAULT_GROUP_NAME: &str = "Default";
+
+/// Distance between the `sort_order` of neighbouring groups after a reorder
+///
+/// The gap leaves room to move a group between two others by changing only
+/// its own `sort_order`.
+pub const GROUP_SORT_GAP: i32 = 1024;
+
+/// Columns read into a [`Group`], in `row_to_group` order
+const GROUP_COLUMNS: &str = "id, name, color, sort_order, created_at, parent_id, is_default";
+
+fn row_to_group(row: &rusqlite::Row) -> rusqlite::Result<Group> {
+    Ok(Group {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        color: row.get(2)?,
+        sort_order: row.get(3)?,
+        created_at: row.get(4)?,
+        parent_id: row.get(5)?,
+        is_default: row.get(6)?,
+    })
+}
+
+fn group_internal(conn: &Connection, id: i64) -> DbResult<Group> {
+    conn.query_row(
+        &format!("SELECT {} FROM groups WHERE id = ?1", GROUP_COLUMNS),
+        params![id],
+        row_to_group,
+    )
+    .optional()?
+    .ok_or(DbError::NotFound(format!("Group {}", id)))
+}
+
+/// Id of the group flagged `is_default`, if there is one
+pub(crate) fn find_default_group(conn: &Connection) -> DbResult<Option<i64>> {
+    Ok(conn
+        .query_row("SELECT id FROM groups WHERE is_default = 1", [], |row| row.get(0))
+        .optional()?)
+}
+
+/// Id of the group flagged `is_default`
+fn default_group_id(conn: &Connection) -> DbResult<i64> {
+    find_default_group(conn)?.ok_or(DbError::NotFound("Default group".to_string()))
+}
+
+impl Database {
+    /// Create a new group
+    pub fn create_group(&self, group: CreateGroup) -> DbResult<i64> {
+        let conn = self.get_conn()?;
+        Self::check_group_parent(&conn, None, group.parent_id)?;
+        Self::check_group_name(&conn, None, group.parent_id, &group.name)?;
+
+        conn.execute(
+            "INSERT INTO groups (name, color, sort_order, parent_id) VALUES (?1, ?2, ?3, ?4)",
+            params![group.name, group.color, group.sort_order, group.parent_id],
+        )?;
+
+        Ok(conn.last_insert_rowid())
+    }
+
+    /// Get group by ID
+    pub fn get_group(&self, id: i64) -> DbResult<Group> {
+        let conn = self.read_conn()?;
+        group_internal(&conn, id)
+    }
+
+    /// Get all groups ordered by sort_order
+    ///
+    /// Parents and children come in one flat list; build the tree from
+    /// `parent_id`.
+    pub fn get_groups(&self) -> DbResult<Vec<Group>> {
+        let conn = self.read_conn()?;
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM groups ORDER BY sort_order, name",
+            GROUP_COLUMNS
+        ))?;
+        let groups = stmt
+            .query_map([], row_to_group)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(groups)
+    }
+
+    /// Update a group
+    ///
+    /// The Default group keeps its name and stays at the top level; only
+    /// its color and sort order can change.
+    pub fn update_group(&self, id: i64, group: CreateGroup) -> DbResult<()> {
+        let conn = self.get_conn()?;
+        let current = group_internal(&conn, id)?;
+        if current.is_default && (group.name != current.name || group.parent_id.is_some()) {
+            return Err(DbError::InvalidInput(
+                "The Default group cannot be renamed or moved".to_string(),
+            ));
+        }
+        Self::check_group_parent(&conn, Some(id), group.parent_id)?;
+        Self::check_group_name(&conn, Some(id), group.parent_id, &group.name)?;
+
+        conn.execute(
+            "UPDATE groups SET name = ?1, color = ?2, sort_order = ?3, parent_id = ?4 WHERE id = ?5",
+            params![group.name, group.color, group.sort_order, group.parent_id, id],
+        )?;
+
+        Ok(())
+    }
+
+    /// Move a group (with its subgroups) under another group, or to the top
+    /// level when `parent_id` is None
+    pub fn move_group(&self, id: i64, parent_id: Option<i64>) -> DbResult<()> {
+        let conn = self.get_conn()?;
+        let group = group_internal(&conn, id)?;
+        if group.is_default && parent_id.is_some() {
+            return Err(DbError::InvalidInput("The Default group cannot be moved".to_string()));
+        }
+        Self::check_group_parent(&conn, Some(id), parent_id)?;
+        Self::check_group_name(&conn, Some(id), parent_id, &group.name)?;
+
+        conn.execute(
+            "UPDATE groups SET parent_id = ?1 WHERE id = ?2",
+            params![parent_id, id],
+        )?;
+
+        Ok(())
+    }
+
+    /// Rewrite `sort_order` so that groups come in the given order
+    ///
+    /// The groups get `GROUP_SORT_GAP` apart values starting at
+    /// `GROUP_SORT_GAP`; groups not listed keep theirs. Runs in one
+    /// transaction, so a missing group leaves the order untouched.
+    pub fn reorder_groups(&self, ids: &[i64]) -> DbResult<()> {
+        let mut conn = self.get_conn()?;
+        let tx = conn.transaction()?;
+
+        let mut seen = HashSet::new();
+        for (position, &id) in ids.iter().enumerate() {
+            if !seen.insert(id) {
+                return Err(DbError::InvalidInput(format!("Group {} is listed twice", id)));
+            }
+            let sort_order = Self::group_sort_position(position)?;
+            let affected = tx.execute(
+                "UPDATE groups SET sort_order = ?1 WHERE id = ?2",
+                params![sort_order, id],
+            )?;
+            if affected == 0 {
+                return Err(DbError::NotFound(format!("Group {}", id)));
+            }
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Move a group just before `before_id` among its siblings, or after the
+    /// last sibling when `before_id` is None
+    ///
+    /// Usually only the moved group's `sort_order` changes, set halfway
+    /// between its new neighbours. When there is no integer left between
+    /// them the siblings are renumbered `GROUP_SORT_GAP` apart.
+    ///
+    /// # Returns
+    /// Number of groups whose `sort_order` was rewritten
+    pub fn place_group(&self, id: i64, before_id: Option<i64>) -> DbResult<usize> {
+        let mut conn = self.get_conn()?;
+        let tx = conn.transaction()?;
+
+        let parent_id: Option<i64> = tx
+            .query_row("SELECT parent_id FROM groups WHERE id = ?1", params![id], |row| row.get(0))
+            .optional()?
+            .ok_or(DbError::NotFound(format!("Group {}", id)))?;
+
+        // Siblings in display order, without the moved group
+        let siblings = {
+            let mut stmt = tx.prepare(
+                "SELECT id, sort_order FROM groups WHERE parent_id IS ?1 AND id != ?2 ORDER BY sort_order, name",
+            )?;
+            let siblings = stmt
+                .query_map(params![parent_id, id], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i32>(1)?)))?
+                .collect::<Result<Vec<_>, _>>()?;
+            siblings
+        };
+
+        let position = match before_id {
+            None => siblings.len(),
+            Some(before_id) if before_id == id => {
+                return Err(DbError::InvalidInput("A group cannot be placed before itself".to_string()));
+            }
+            Some(before_id) => match siblings.iter().position(|&(sibling, _)| sibling == before_id) {
+                Some(position) => position,
+                None => {
+                    let exists: bool = tx.query_row(
+                        "SELECT EXISTS(SELECT 1 FROM groups WHERE id = ?1)",
+                        params![before_id],
+                        |row| row.get(0),
+                    )?;
+                    return Err(if exists {
+                        DbError::InvalidInput(format!("Group {} is not in the same parent group as group {}", before_id, id))
+                    } else {
+                        DbError::NotFound(format!("Group {}", before_id))
+                    });
+                }
+            },
+        };
+
+        let previous = position.checked_sub(1).map(|i| i64::from(siblings[i].1));
+        let next = siblings.get(position).map(|&(_, sort_order)| i64::from(sort_order));
+        let gap = i64::from(GROUP_SORT_GAP);
+        let sort_order = match (previous, next) {
+            (None, None) => None,
+            (Some(previous), None) => Some(previous + gap),
+            (None, Some(next)) => Some(next - gap),
+            (Some(previous), Some(next)) if next - previous >= 2 => Some(previous + (next - previous) / 2),
+            (Some(_), Some(_)) => None,
+        }
+        .and_then(|sort_order| i32::try_from(sort_order).ok());
+
+        let rewritten = match sort_order {
+            Some(sort_order) => {
+                tx.execute("UPDATE groups SET sort_order = ?1 WHERE id = ?2", params![sort_order, id])?;
+                1
+            }
+            None if siblings.is_empty() => 0,
+            None => {
+                let mut order: Vec<i64> = siblings.iter().map(|&(sibling, _)| sibling).collect();
+                order.insert(position, id);
+                for (position, sibling) in order.iter().enumerate() {
+                    tx.execute(
+                        "UPDATE groups SET sort_order = ?1 WHERE id = ?2",
+                        params![Self::group_sort_position(position)?, sibling],
+                    )?;
+                }
+                order.len()
+            }
+        };
+
+        tx.commit()?;
+        Ok(rewritten)
+    }
+
+    /// `sort_order` of the group at `position` after a renumbering
+    fn group_sort_position(position: usize) -> DbResult<i32> {
+        i32::try_from(position + 1)
+            .ok()
+            .and_then(|n| n.checked_mul(GROUP_SORT_GAP))
+            .ok_or_else(|| DbError::InvalidInput("Too many groups to order".to_string()))
+    }
+
+    /// Check that `parent_id` exists and is neither group `id` itself nor
+    /// one of its descendants
+    fn check_group_parent(conn: &Connection, id: Option<i64>, parent_id: Option<i64>) -> DbResult<()> {
+        let Some(parent_id) = parent_id else {
+            return Ok(());
+        };
+
+        let exists: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM groups WHERE id = ?1)",
+            params![parent_id],
+            |row| row.get(0),
+        )?;
+        if !exists {
+            return Err(DbError::NotFound(format!("Parent group {}", parent_id)));
+        }
+
+        if let Some(id) = id {
+            let in_subtree: bool = conn.query_row(
+                &format!(
+                    "WITH RECURSIVE {} SELECT EXISTS(SELECT 1 FROM subtree WHERE id = ?)",
+                    GROUP_SUBTREE_CTE
+                ),
+                params![id, parent_id],
+                |row| row.get(0),
+            )?;
+            if in_subtree {
+                return Err(DbError::InvalidInput(
+                    "A group cannot be moved into itself or one of its subgroups".to_string(),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check that no group other than `id` is named `name` under `parent_id`
+    ///
+    /// Names are unique among siblings (matching the schema's constraint),
+    /// so the same name may be used under different parents.
+    fn check_group_name(conn: &Connection, id: Option<i64>, parent_id: Option<i64>, name: &str) -> DbResult<()> {
+        let taken: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM groups WHERE parent_id IS ?1 AND name = ?2 AND id IS NOT ?3)",
+            params![parent_id, name, id],
+            |row| row.get(0),
+        )?;
+        if taken {
+            return Err(DbError::Duplicate(format!("Group \"{}\"", name)));
+        }
+        Ok(())
+    }
+
+    /// Check that the subgroups of group `id` can move up to `parent_id`
+    /// without taking a sibling's name
+    fn check_subgroups_can_move(conn: &Connection, id: i64, parent_id: Option<i64>) -> DbResult<()> {
+        let clash: Option<String> = conn
+            .query_row(
+                "SELECT child.name FROM groups child
+                 INNER JOIN groups sibling
+                    ON sibling.name = child.name AND sibling.parent_id IS ?2 AND sibling.id != ?1
+                 WHERE child.parent_id = ?1
+                 LIMIT 1",
+                params![id, parent_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        match clash {
+            Some(name) => Err(DbError::Duplicate(format!("Group \"{}\"", name))),
+            None => Ok(()),
+        }
+    }
+
+    /// Get the direct subgroups of a group
+    pub fn get_child_group_ids(&self, group_id: i64) -> DbResult<Vec<i64>> {
+        let conn = self.read_conn()?;
+        let mut stmt = conn.prepare("SELECT id FROM groups WHERE parent_id = ?1 ORDER BY id")?;
+        let ids = stmt
+            .query_map(params![group_id], |row| row.get(0))?
+            .collect::<Result<Vec<i64>, _>>()?;
+        Ok(ids)
+    }
+
+    /// Delete a group, deciding what happens to its accounts
+    ///
+    /// Runs in one transaction and is recorded in the operation log. Direct
+    /// subgroups move up to the deleted group's parent. The `Default` group
+    /// and the last remaining group cannot be deleted.
+    pub fn delete_group_with_members(&self, id: i64, members: GroupMembers) -> DbResult<GroupDeletion> {
+        let mut conn = self.get_conn()?;
+        let tx = conn.transaction()?;
+
+        let group = group_internal(&tx, id)?;
+
+        if group.is_default {
+            return Err(DbError::InvalidInput("The Default group cannot be deleted".to_string()));
+        }
+        let group_count: i64 = tx.query_row("SELECT COUNT(*) FROM groups", [], |row| row.get(0))?;
+        if group_count <= 1 {
+            return Err(DbError::InvalidInput("The last group cannot be deleted".to_string()));
+        }
+
+        let target = match members {
+            GroupMembers::MoveTo(target) if target == id => {
+                return Err(DbError::InvalidInput(
+                    "Accounts cannot be moved to the group being deleted".to_string(),
+                ));
+            }
+            GroupMembers::MoveTo(target) => {
+                let exists: bool = tx.query_row(
+                    "SELECT EXISTS(SELECT 1 FROM groups WHERE id = ?1)",
+                    params![target],
+                    |row| row.get(0),
+                )?;
+                if !exists {
+                    return Err(DbError::NotFound(format!("Group {}", target)));
+                }
+                Some(target)
+            }
+            GroupMembers::MoveToDefault => Some(default_group_id(&tx)?),
+            GroupMembers::Delete => None,
+        };
+
+        let mut outcome = GroupDeletion {
+            moved_to: target,
+            ..Default::default()
+        };
+
+        Self::check_subgroups_can_move(&tx, id, group.parent_id)?;
+        outcome.subgroups_moved = tx.execute(
+            "UPDATE groups SET parent_id = ?1 WHERE parent_id = ?2",
+            params![group.parent_id, id],
+        )?;
+
+        match target {
+            Some(target) => {
+                outcome.accounts_moved = tx.execute(
+                    "UPDATE accounts SET group_id = ?1, updated_at = CURRENT_TIMESTAMP WHERE group_id = ?2",
+                    params![target, id],
+                )?;
+            }
+            None => {
+                let account_ids: Vec<i64> = {
+                    let mut stmt = tx.prepare("SELECT id FROM accounts WHERE group_id = ?1 ORDER BY id")?;
+                    let ids = stmt
+                        .query_map(params![id], |row| row.get(0))?
+                        .collect::<Result<Vec<_>, _>>()?;
+                    ids
+                };
+                for account_id in &account_ids {
+                    self.delete_account_internal(&tx, *account_id)?;
+                }
+                outcome.accounts_deleted = account_ids.len();
+            }
+        }
+
+        tx.execute("DELETE FROM groups WHERE id = ?1", params![id])?;
+
+        let members_detail = match target {
+            Some(target) => format!("moved {} accounts to group {}", outcome.accounts_moved, target),
+            None => format!("deleted {} accounts", outcome.accounts_deleted),
+        };
+        self.log_operation_internal(
+            &tx,
+            None,
+            "DELETE_GROUP",
+            Some(&format!("Deleted group {} ({}); {}", id, group.name, members_detail)),
+        )?;
+
+        tx.commit()?;
+        Ok(outcome)
+    }
+
+    /// Get the IDs of the accounts in a group
+    pub fn get_group_account_ids(&self, group_id: i64) -> DbResult<Vec<i64>> {
+        let conn = self.read_conn()?;
+        let mut stmt = conn.prepare("SELECT id FROM accounts WHERE group_id = ?1 ORDER BY id")?;
+        let ids = stmt
+            .query_map(params![group_id], |row| row.get(0))?
+            .collect::<Result<Vec<i64>, _>>()?;
+        Ok(ids)
+    }
+
+    /// Get accounts count for a group
+    pub fn get_group_accounts_count(&self, group_id: i64) -> DbResult<i64> {
+        let conn = self.read_conn()?;
+        let count: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM accounts WHERE group_id = ?1",
+            params![group_id],
+            |row| row.get(0),
+        )?;
+        Ok(count)
+    }
+
+    /// Get accounts count for a group and all of its subgroups
+    pub fn get_group_subtree_accounts_count(&self, group_id: i64) -> DbResult<i64> {
+        let conn = self.read_conn()?;
+        let count: i64 = conn.query_row(
+            &format!(
+                "WITH RECURSIVE {} SELECT COUNT(*) FROM accounts WHERE group_id IN (SELECT id FROM subtree)",
+                GROUP_SUBTREE_CTE
+            ),
+            params![group_id],
+            |row| row.get(0),
+        )?;
+        Ok(count)
+    }
+}
+
+// ============================================================================
+// Tag Operations
+// ============================================================================
+
+/// Outcome of merging one tag into another
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize)]
+pub struct TagMerge {
+    /// Accounts that got the target tag from the source tag
+    pub accounts_moved: usize,
+    /// Accounts that already had both tags
+    pub accounts_already_tagged: usize,
+}
+
+impl Database {
+    /// Create a new tag
+    ///
+    /// Fails with `Duplicate` if a tag with the same name, ignoring case,
+    /// already exists.
+    pub fn create_tag(&self, tag: CreateTag) -> DbResult<i64> {
+        let conn = self.get_conn()?;
+        Self::check_tag_name(&conn, None, &tag.name)?;
+
+        conn.execute(
+            "INSERT INTO tags (name, color) VALUES (?1, ?2)",
+            params![tag.name, tag.color],
+        )?;
+
+        Ok(conn.last_insert_rowid())
+    }
+
+    /// Get tag by ID
+    pub fn get_tag(&self, id: i64) -> DbResult<Tag> {
+        let conn = self.read_conn()?;
+
+        let mut stmt = conn.prepare("SELECT id, name, color, created_at FROM tags WHERE id = ?1")?;
+
+        let mut rows = stmt.query(params![id])?;
+
+        let row = rows.next()?.ok_or(DbError::NotFound(format!("Tag {}", id)))?;
+
+        Ok(Tag {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            color: row.get(2)?,
+            created_at: row.get(3)?,
+        })
+    }
+
+    /// Get all tags
+    pub fn get_tags(&self) -> DbResult<Vec<Tag>> {
+        let conn = self.read_conn()?;
+
+        let mut stmt = conn.prepare("SELECT id, name, color, created_at FROM tags ORDER BY name")?;
+
+        let mut rows = stmt.query([])?;
+        let mut tags = Vec::new();
+
+        while let Some(row) = rows.next()? {
+            tags.push(Tag {
                 id: row.get(0)?,
                 name: row.get(1)?,
                 color: row.get(2)?,
@@ -858,8 +1688,12 @@ impl Database {
     }
 
     /// Update a tag
+    ///
+    /// A tag can be renamed to a different case of its own name, but not to
+    /// the name of another tag (ignoring case).
     pub fn update_tag(&self, id: i64, tag: CreateTag) -> DbResult<()> {
-        let conn = self.get_conn();
+        let conn = self.get_conn()?;
+        Self::check_tag_name(&conn, Some(id), &tag.name)?;
 
         let affected = conn.execute(
             "UPDATE tags SET name = ?1, color = ?2 WHERE id = ?3",
@@ -873,9 +1707,79 @@ impl Database {
         Ok(())
     }
 
+    /// Check that no tag other than `id` is named `name`, ignoring case
+    fn check_tag_name(conn: &Connection, id: Option<i64>, name: &str) -> DbResult<()> {
+        let existing: Option<String> = conn
+            .query_row(
+                "SELECT name FROM tags WHERE name = ?1 COLLATE NOCASE AND id IS NOT ?2 LIMIT 1",
+                params![name, id],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        match existing {
+            Some(existing) => Err(DbError::Duplicate(format!("Tag \"{}\"", existing))),
+            None => Ok(()),
+        }
+    }
+
+    /// Merge tag `source_id` into tag `target_id`
+    ///
+    /// Every account tagged with the source gets the target tag (accounts
+    /// that already have it are counted, not duplicated), then the source
+    /// tag is deleted. Runs in one transaction and is recorded in the
+    /// operation log.
+    pub fn merge_tags(&self, source_id: i64, target_id: i64) -> DbResult<TagMerge> {
+        if source_id == target_id {
+            return Err(DbError::InvalidInput("A tag cannot be merged into itself".to_string()));
+        }
+
+        let mut conn = self.get_conn()?;
+        let tx = conn.transaction()?;
+
+        let name_of = |id: i64| -> DbResult<String> {
+            tx.query_row("SELECT name FROM tags WHERE id = ?1", params![id], |row| row.get(0))
+                .optional()?
+                .ok_or(DbError::NotFound(format!("Tag {}", id)))
+        };
+        let source_name = name_of(source_id)?;
+        let target_name = name_of(target_id)?;
+
+        let linked: usize = tx.query_row(
+            "SELECT COUNT(*) FROM account_tags WHERE tag_id = ?1",
+            params![source_id],
+            |row| row.get(0),
+        )?;
+        let accounts_moved = tx.execute(
+            "INSERT OR IGNORE INTO account_tags (account_id, tag_id)
+             SELECT account_id, ?2 FROM account_tags WHERE tag_id = ?1",
+            params![source_id, target_id],
+        )?;
+
+        // Cascades to the source tag's remaining account_tags rows
+        tx.execute("DELETE FROM tags WHERE id = ?1", params![source_id])?;
+
+        self.log_operation_internal(
+            &tx,
+            None,
+            "MERGE_TAGS",
+            Some(&format!(
+                "Merged tag {} ({}) into tag {} ({}); {} of {} accounts moved",
+                source_id, source_name, target_id, target_name, accounts_moved, linked
+            )),
+        )?;
+
+        tx.commit()?;
+
+        Ok(TagMerge {
+            accounts_moved,
+            accounts_already_tagged: linked - accounts_moved,
+        })
+    }
+
     /// Delete a tag (cascades to account_tags)
     pub fn delete_tag(&self, id: i64) -> DbResult<()> {
-        let conn = self.get_conn();
+        let conn = self.get_conn()?;
 
         let affected = conn.execute("DELETE FROM tags WHERE id = ?1", params![id])?;
 
@@ -888,7 +1792,7 @@ impl Database {
 
     /// Add a tag to an account
     pub fn add_tag_to_account(&self, account_id: i64, tag_id: i64) -> DbResult<()> {
-        let conn = self.get_conn();
+        let conn = self.get_conn()?;
 
         // Verify account exists
         let account_exists: i64 = conn.query_row(
@@ -930,7 +1834,7 @@ impl Database {
 
     /// Remove a tag from an account
     pub fn remove_tag_from_account(&self, account_id: i64, tag_id: i64) -> DbResult<()> {
-        let conn = self.get_conn();
+        let conn = self.get_conn()?;
 
         let affected = conn.execute(
             "DELETE FROM account_tags WHERE account_id = ?1 AND tag_id = ?2",
@@ -957,13 +1861,34 @@ impl Database {
 
     /// Get all tags for an account
     pub fn get_account_tags(&self, account_id: i64) -> DbResult<Vec<Tag>> {
-        let conn = self.get_conn();
+        let conn = self.read_conn()?;
         self.get_tags_for_account(&conn, account_id)
     }
 
+    /// Get the IDs of the accounts that have a tag
+    pub fn get_tag_account_ids(&self, tag_id: i64) -> DbResult<Vec<i64>> {
+        let conn = self.read_conn()?;
+        let mut stmt = conn.prepare("SELECT account_id FROM account_tags WHERE tag_id = ?1 ORDER BY account_id")?;
+        let ids = stmt
+            .query_map(params![tag_id], |row| row.get(0))?
+            .collect::<Result<Vec<i64>, _>>()?;
+        Ok(ids)
+    }
+
+    /// Check whether an account has a tag
+    pub fn account_has_tag(&self, account_id: i64, tag_id: i64) -> DbResult<bool> {
+        let conn = self.read_conn()?;
+        let count: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM account_tags WHERE account_id = ?1 AND tag_id = ?2",
+            params![account_id, tag_id],
+            |row| row.get(0),
+        )?;
+        Ok(count > 0)
+    }
+
     /// Get accounts count for a tag
     pub fn get_tag_accounts_count(&self, tag_id: i64) -> DbResult<i64> {
-        let conn = self.get_conn();
+        let conn = self.read_conn()?;
         let count: i64 = conn.query_row(
             "SELECT COUNT(*) FROM account_tags WHERE tag_id = ?1",
             params![tag_id],
@@ -985,7 +1910,7 @@ impl Database {
         source_type: String,
         source_name: Option<String>,
     ) -> DbResult<i64> {
-        let conn = self.get_conn();
+        let conn = self.get_conn()?;
 
         conn.execute(
             "INSERT INTO raw_imports (raw_text, source_type, source_name) VALUES (?1, ?2, ?3)",
@@ -995,9 +1920,64 @@ impl Database {
         Ok(conn.last_insert_rowid())
     }
 
+    /// Store a raw import together with the accounts parsed from it.
+    ///
+    /// Runs in a single transaction: either the raw import and every account
+    /// (with its groups, tags and tag links) are written, or nothing is.
+    /// Groups are matched by path and tags by name, ignoring case. Each account's
+    /// `raw_import_id` is set to the new raw import. Returns the raw import
+    /// ID and the account IDs in input order.
+    pub fn import_accounts(
+        &self,
+        raw_text: String,
+        source_type: String,
+        source_name: Option<String>,
+        accounts: Vec<ImportedAccount>,
+    ) -> DbResult<(i64, Vec<i64>)> {
+        let mut conn = self.get_conn()?;
+        let tx = conn.transaction()?;
+
+        tx.execute(
+            "INSERT INTO raw_imports (raw_text, source_type, source_name) VALUES (?1, ?2, ?3)",
+            params![raw_text, source_type, source_name],
+        )?;
+        let raw_import_id = tx.last_insert_rowid();
+
+        let mut account_ids = Vec::with_capacity(accounts.len());
+        for imported in accounts {
+            let mut account = imported.account;
+            account.raw_import_id = Some(raw_import_id);
+            if !imported.group_path.is_empty() {
+                account.group_id = Some(find_or_create_group_path(&tx, &imported.group_path)?);
+            }
+
+            let id = self.insert_account_internal(
+                &tx,
+                &account,
+                "IMPORT",
+                &format!("Imported account from raw import {}", raw_import_id),
+            )?;
+            replace_field_tokens_internal(&tx, id, &imported.search_tokens)?;
+
+            for name in &imported.tag_names {
+                let tag_id = find_or_create_tag(&tx, name)?;
+                tx.execute(
+                    "INSERT OR IGNORE INTO account_tags (account_id, tag_id) VALUES (?1, ?2)",
+                    params![id, tag_id],
+                )?;
+            }
+            account_ids.push(id);
+        }
+
+        Self::clear_history_internal(&tx)?;
+        tx.commit()?;
+
+        Ok((raw_import_id, account_ids))
+    }
+
     /// Get raw import by ID
     pub fn get_raw_import(&self, id: i64) -> DbResult<RawImport> {
-        let conn = self.get_conn();
+        let conn = self.read_conn()?;
 
         let mut stmt = conn.prepare(
             "SELECT id, raw_text, source_type, source_name, imported_at
@@ -1019,7 +1999,7 @@ impl Database {
 
     /// Delete a raw import
     pub fn delete_raw_import(&self, id: i64) -> DbResult<()> {
-        let conn = self.get_conn();
+        let conn = self.get_conn()?;
 
         let affected = conn.execute("DELETE FROM raw_imports WHERE id = ?1", params![id])?;
 
@@ -1031,6 +2011,51 @@ impl Database {
     }
 }
 
+/// Id of the tag named `name` (ignoring case), creating it with the
+/// default color if there is none
+fn find_or_create_tag(conn: &Connection, name: &str) -> DbResult<i64> {
+    let existing = conn
+        .query_row("SELECT id FROM tags WHERE name = ?1 COLLATE NOCASE", params![name], |row| row.get(0))
+        .optional()?;
+
+    match existing {
+        Some(id) => Ok(id),
+        None => {
+            conn.execute("INSERT INTO tags (name) VALUES (?1)", params![name])?;
+            Ok(conn.last_insert_rowid())
+        }
+    }
+}
+
+/// Id of the group at `path` (names from the top level down, ignoring
+/// case), creating the missing groups along it with the default color
+fn find_or_create_group_path(conn: &Connection, path: &[String]) -> DbResult<i64> {
+    let mut parent_id: Option<i64> = None;
+    for name in path {
+        let existing = conn
+            .query_row(
+                "SELECT id FROM groups WHERE parent_id IS ?1 AND name = ?2 COLLATE NOCASE
+                 ORDER BY id LIMIT 1",
+                params![parent_id, name],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        parent_id = Some(match existing {
+            Some(id) => id,
+            None => {
+                conn.execute(
+                    "INSERT INTO groups (name, parent_id) VALUES (?1, ?2)",
+                    params![name, parent_id],
+                )?;
+                conn.last_insert_rowid()
+            }
+        });
+    }
+
+    parent_id.ok_or_else(|| DbError::InvalidInput("Empty group path".to_string()))
+}
+
 // ============================================================================
 // Operation Logging
 // ============================================================================
@@ -1043,11 +2068,14 @@ impl Database {
         action: &str,
         details: Option<&str>,
     ) -> DbResult<()> {
-        let conn = self.get_conn();
+        let conn = self.get_conn()?;
         self.log_operation_internal(&conn, account_id, action, details)
     }
 
     /// Internal logging helper that accepts a connection reference
+    ///
+    /// The entry is sealed (details encrypted, hash-chained) when the audit
+    /// key is available; see [`audit`].
     fn log_operation_internal(
         &self,
         conn: &Connection,
@@ -1055,12 +2083,7 @@ impl Database {
         action: &str,
         details: Option<&str>,
     ) -> DbResult<()> {
-        conn.execute(
-            "INSERT INTO operation_logs (account_id, action, details) VALUES (?1, ?2, ?3)",
-            params![account_id, action, details],
-        )?;
-
-        Ok(())
+        audit::append_entry(conn, self.audit_key().as_deref(), account_id, action, details)
     }
 
     /// Get operation logs for an account
@@ -1069,7 +2092,7 @@ impl Database {
         account_id: Option<i64>,
         limit: i64,
     ) -> DbResult<Vec<OperationLog>> {
-        let conn = self.get_conn();
+        let conn = self.read_conn()?;
 
         let query = if let Some(aid) = account_id {
             "SELECT id, account_id, action, details, created_at
@@ -1107,19 +2130,123 @@ impl Database {
         Ok(logs)
     }
 
+    /// Search operation logs, newest first
+    ///
+    /// Returns all matching entries when `pagination` is None.
+    pub fn search_operation_logs(
+        &self,
+        search: &LogSearch,
+        pagination: Option<&Pagination>,
+    ) -> DbResult<Vec<OperationLog>> {
+        let conn = self.read_conn()?;
+
+        let (where_clause, mut params) = log_search_conditions(search);
+        let limit_clause = match pagination {
+            Some(pag) => {
+                params.push(Box::new(pag.limit));
+                params.push(Box::new(pag.offset));
+                "LIMIT ? OFFSET ?"
+            }
+            None => "",
+        };
+
+        let query = format!(
+            "SELECT id, account_id, action, details, created_at
+             FROM operation_logs
+             {}
+             ORDER BY id DESC
+             {}",
+            where_clause, limit_clause
+        );
+
+        let params_refs: Vec<&dyn rusqlite::ToSql> = params.iter().map(|p| p.as_ref()).collect();
+        let mut stmt = conn.prepare(&query)?;
+        let logs = stmt
+            .query_map(params_refs.as_slice(), |row| {
+                Ok(OperationLog {
+                    id: row.get(0)?,
+                    account_id: row.get(1)?,
+                    action: row.get(2)?,
+                    details: row.get(3)?,
+                    created_at: row.get(4)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(logs)
+    }
+
+    /// Count operation logs matching the filters
+    pub fn count_operation_logs(&self, search: &LogSearch) -> DbResult<i64> {
+        let conn = self.read_conn()?;
+
+        let (where_clause, params) = log_search_conditions(search);
+        let query = format!("SELECT COUNT(*) FROM operation_logs {}", where_clause);
+        let params_refs: Vec<&dyn rusqlite::ToSql> = params.iter().map(|p| p.as_ref()).collect();
+
+        let count = conn.query_row(&query, params_refs.as_slice(), |row| row.get(0))?;
+        Ok(count)
+    }
+
     /// Clear old operation logs (for maintenance)
+    ///
+    /// Moves the audit chain anchor past the removed entries so the
+    /// remaining log still verifies.
     pub fn clear_old_logs(&self, days: i64) -> DbResult<i64> {
-        let conn = self.get_conn();
+        let mut conn = self.get_conn()?;
+        let tx = conn.transaction()?;
 
-        let affected = conn.execute(
+        let affected = tx.execute(
             "DELETE FROM operation_logs WHERE created_at < datetime('now', '-' || ?1 || ' days')",
             params![days],
         )?;
 
+        if affected > 0 {
+            audit::advance_anchor(&tx)?;
+            self.log_operation_internal(
+                &tx,
+                None,
+                "PRUNE_LOGS",
+                Some(&format!("Removed {} entries older than {} days", affected, days)),
+            )?;
+        }
+
+        tx.commit()?;
+
         Ok(affected as i64)
     }
 }
 
+/// WHERE clause and parameters for a log search
+fn log_search_conditions(search: &LogSearch) -> (String, Vec<Box<dyn rusqlite::ToSql>>) {
+    let mut conditions = Vec::new();
+    let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+
+    if let Some(account_id) = search.account_id {
+        conditions.push("account_id = ?");
+        params.push(Box::new(account_id));
+    }
+    if let Some(action) = &search.action {
+        conditions.push("action = ?");
+        params.push(Box::new(action.clone()));
+    }
+    if let Some(from) = &search.from {
+        conditions.push("created_at >= ?");
+        params.push(Box::new(from.clone()));
+    }
+    if let Some(to) = &search.to {
+        conditions.push("created_at < ?");
+        params.push(Box::new(to.clone()));
+    }
+
+    let where_clause = if conditions.is_empty() {
+        String::new()
+    } else {
+        format!("WHERE {}", conditions.join(" AND "))
+    };
+    (where_clause, params)
+}
+
 // ============================================================================
 // Undo/Redo Stack Operations
 // ============================================================================
@@ -1127,7 +2254,7 @@ impl Database {
 impl Database {
     /// Push an operation to the undo stack
     pub fn push_undo(&self, operation_type: &str, undo_data: &str) -> DbResult<i64> {
-        let conn = self.get_conn();
+        let conn = self.get_conn()?;
 
         conn.execute(
             "INSERT INTO undo_stack (operation_type, undo_data) VALUES (?1, ?2)",
@@ -1139,7 +2266,7 @@ impl Database {
 
     /// Get the latest undo operation
     pub fn pop_undo(&self) -> DbResult<Option<(i64, String, String)>> {
-        let conn = self.get_conn();
+        let conn = self.get_conn()?;
 
         let mut stmt = conn.prepare(
             "SELECT id, operation_type, undo_data
@@ -1155,41 +2282,305 @@ impl Database {
             let operation_type: String = row.get(1)?;
             let undo_data: String = row.get(2)?;
 
-            // Delete the popped entry
-            conn.execute("DELETE FROM undo_stack WHERE id = ?1", params![id])?;
+            // Delete the popped entry
+            conn.execute("DELETE FROM undo_stack WHERE id = ?1", params![id])?;
+
+            Ok(Some((id, operation_type, undo_data)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Get all undo operations
+    pub fn get_undo_stack(&self, limit: i64) -> DbResult<Vec<(i64, String, String)>> {
+        let conn = self.read_conn()?;
+
+        let mut stmt = conn.prepare(
+            "SELECT id, operation_type, undo_data
+             FROM undo_stack
+             ORDER BY id DESC
+             LIMIT ?1"
+        )?;
+
+        let mut rows = stmt.query(params![limit])?;
+        let mut result = Vec::new();
+
+        while let Some(row) = rows.next()? {
+            result.push((row.get(0)?, row.get(1)?, row.get(2)?));
+        }
+
+        Ok(result)
+    }
+
+    /// Clear the undo stack
+    pub fn clear_undo_stack(&self) -> DbResult<i64> {
+        let conn = self.get_conn()?;
+        let affected = conn.execute("DELETE FROM undo_stack", [])?;
+        Ok(affected as i64)
+    }
+
+    /// Drop the whole history inside a write that is not recorded in it
+    ///
+    /// Replaying an entry overwrites rows without checking for later
+    /// changes, so after an untracked bulk write (import, repair) an old
+    /// entry could clobber or cascade-delete the new data.
+    pub(crate) fn clear_history_internal(conn: &Connection) -> DbResult<()> {
+        conn.execute("DELETE FROM undo_stack", [])?;
+        Ok(())
+    }
+}
+
+/// Stored state of one row, as recorded in the undo history
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RowState {
+    /// Account row (encrypted columns as stored) and its tag set
+    Account(Account),
+    Group(Group),
+    Tag(Tag),
+    /// Presence of an account/tag association
+    AccountTag { account_id: i64, tag_id: i64 },
+}
+
+/// A row before and after a mutation (`None` = row did not exist)
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct RowChange {
+    pub before: Option<RowState>,
+    pub after: Option<RowState>,
+}
+
+/// Entry of the undo history
+#[derive(Debug, Clone)]
+pub struct UndoEntry {
+    pub id: i64,
+    pub operation_type: String,
+    pub undo_data: String,
+    pub created_at: String,
+}
+
+/// Direction of a history replay
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplayDirection {
+    /// Restore the `before` states, last change first
+    Undo,
+    /// Restore the `after` states, first change first
+    Redo,
+}
+
+impl Database {
+    /// Record a new history entry
+    ///
+    /// Clears the redo side of the stack (a new mutation invalidates it) and
+    /// drops the oldest entries beyond `max_entries`.
+    pub fn push_history_entry(&self, operation_type: &str, undo_data: &str, max_entries: i64) -> DbResult<i64> {
+        let mut conn = self.get_conn()?;
+        let tx = conn.transaction()?;
+
+        tx.execute("DELETE FROM undo_stack WHERE undone = 1", [])?;
+        tx.execute(
+            "INSERT INTO undo_stack (operation_type, undo_data) VALUES (?1, ?2)",
+            params![operation_type, undo_data],
+        )?;
+        let id = tx.last_insert_rowid();
+
+        tx.execute(
+            "DELETE FROM undo_stack WHERE id NOT IN (
+                SELECT id FROM undo_stack ORDER BY id DESC LIMIT ?1
+            )",
+            params![max_entries],
+        )?;
+
+        tx.commit()?;
+        Ok(id)
+    }
+
+    /// Most recent entry that can be undone
+    pub fn next_undo_entry(&self) -> DbResult<Option<UndoEntry>> {
+        let conn = self.read_conn()?;
+        Self::query_undo_entry(
+            &conn,
+            "SELECT id, operation_type, undo_data, created_at FROM undo_stack
+             WHERE undone = 0 ORDER BY id DESC LIMIT 1",
+        )
+    }
 
-            Ok(Some((id, operation_type, undo_data)))
-        } else {
-            Ok(None)
-        }
+    /// Most recently undone entry, which is the next one to redo
+    pub fn next_redo_entry(&self) -> DbResult<Option<UndoEntry>> {
+        let conn = self.read_conn()?;
+        Self::query_undo_entry(
+            &conn,
+            "SELECT id, operation_type, undo_data, created_at FROM undo_stack
+             WHERE undone = 1 ORDER BY id ASC LIMIT 1",
+        )
     }
 
-    /// Get all undo operations
-    pub fn get_undo_stack(&self, limit: i64) -> DbResult<Vec<(i64, String, String)>> {
-        let conn = self.get_conn();
+    /// Number of entries that can be undone and redone
+    pub fn history_counts(&self) -> DbResult<(i64, i64)> {
+        let conn = self.read_conn()?;
+        Ok(conn.query_row(
+            "SELECT COALESCE(SUM(undone = 0), 0), COALESCE(SUM(undone = 1), 0) FROM undo_stack",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?)
+    }
 
-        let mut stmt = conn.prepare(
-            "SELECT id, operation_type, undo_data
-             FROM undo_stack
-             ORDER BY id DESC
-             LIMIT ?1"
-        )?;
+    fn query_undo_entry(conn: &Connection, query: &str) -> DbResult<Option<UndoEntry>> {
+        Ok(conn
+            .query_row(query, [], |row| {
+                Ok(UndoEntry {
+                    id: row.get(0)?,
+                    operation_type: row.get(1)?,
+                    undo_data: row.get(2)?,
+                    created_at: row.get(3)?,
+                })
+            })
+            .optional()?)
+    }
 
-        let mut rows = stmt.query(params![limit])?;
-        let mut result = Vec::new();
+    /// Replay a history entry in one transaction and move it to the other
+    /// side of the stack
+    ///
+    /// Fails without changing anything if a step cannot be applied (e.g. a
+    /// foreign key target is gone). Rows are not compared with the states
+    /// the entry expects to overwrite: writes that bypass the history must
+    /// clear it (see `clear_history_internal`).
+    pub fn replay_history_entry(
+        &self,
+        entry: &UndoEntry,
+        changes: &[RowChange],
+        direction: ReplayDirection,
+    ) -> DbResult<()> {
+        let mut conn = self.get_conn()?;
+        let tx = conn.transaction()?;
 
-        while let Some(row) = rows.next()? {
-            result.push((row.get(0)?, row.get(1)?, row.get(2)?));
+        let steps: Box<dyn Iterator<Item = &RowChange>> = match direction {
+            ReplayDirection::Undo => Box::new(changes.iter().rev()),
+            ReplayDirection::Redo => Box::new(changes.iter()),
+        };
+
+        for change in steps {
+            let (target, current) = match direction {
+                ReplayDirection::Undo => (&change.before, &change.after),
+                ReplayDirection::Redo => (&change.after, &change.before),
+            };
+            Self::restore_row_state(&tx, target.as_ref(), current.as_ref())?;
         }
 
-        Ok(result)
+        let (undone, action) = match direction {
+            ReplayDirection::Undo => (1, "UNDO"),
+            ReplayDirection::Redo => (0, "REDO"),
+        };
+        tx.execute(
+            "UPDATE undo_stack SET undone = ?1 WHERE id = ?2",
+            params![undone, entry.id],
+        )?;
+        self.log_operation_internal(
+            &tx,
+            None,
+            action,
+            Some(&format!("{} {}", action, entry.operation_type)),
+        )?;
+
+        tx.commit()?;
+        Ok(())
     }
 
-    /// Clear the undo stack
-    pub fn clear_undo_stack(&self) -> DbResult<i64> {
-        let conn = self.get_conn();
-        let affected = conn.execute("DELETE FROM undo_stack", [])?;
-        Ok(affected as i64)
+    /// Put a row into `target` state, deleting it when the target is absent
+    fn restore_row_state(conn: &Connection, target: Option<&RowState>, current: Option<&RowState>) -> DbResult<()> {
+        match (target, current) {
+            (Some(RowState::Account(account)), _) => {
+                conn.execute(
+                    "INSERT INTO accounts (
+                        id, raw_import_id, email, password, recovery_email, totp_secret,
+                        year, notes, group_id, field_order, created_at, updated_at
+                    ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)
+                    ON CONFLICT(id) DO UPDATE SET
+                        raw_import_id = excluded.raw_import_id,
+                        email = excluded.email,
+                        password = excluded.password,
+                        recovery_email = excluded.recovery_email,
+                        totp_secret = excluded.totp_secret,
+                        year = excluded.year,
+                        notes = excluded.notes,
+                        group_id = excluded.group_id,
+                        field_order = excluded.field_order,
+                        created_at = excluded.created_at,
+                        updated_at = excluded.updated_at",
+                    params![
+                        account.id,
+                        account.raw_import_id,
+                        account.email,
+                        account.password,
+                        account.recovery_email,
+                        account.totp_secret,
+                        account.year,
+                        account.notes,
+                        account.group_id,
+                        account.field_order,
+                        account.created_at,
+                        account.updated_at,
+                    ],
+                )?;
+
+                conn.execute("DELETE FROM account_tags WHERE account_id = ?1", params![account.id])?;
+                for tag in &account.tags {
+                    conn.execute(
+                        "INSERT INTO account_tags (account_id, tag_id) VALUES (?1, ?2)",
+                        params![account.id, tag.id],
+                    )?;
+                }
+            }
+            (Some(RowState::Group(group)), _) => {
+                conn.execute(
+                    "INSERT INTO groups (id, name, color, sort_order, created_at, parent_id)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+                     ON CONFLICT(id) DO UPDATE SET
+                        name = excluded.name,
+                        color = excluded.color,
+                        sort_order = excluded.sort_order,
+                        created_at = excluded.created_at,
+                        parent_id = excluded.parent_id",
+                    params![group.id, group.name, group.color, group.sort_order, group.created_at, group.parent_id],
+                )?;
+            }
+            (Some(RowState::Tag(tag)), _) => {
+                // A tag created since may have taken the name
+                Self::check_tag_name(conn, Some(tag.id), &tag.name)?;
+                conn.execute(
+                    "INSERT INTO tags (id, name, color, created_at)
+                     VALUES (?1, ?2, ?3, ?4)
+                     ON CONFLICT(id) DO UPDATE SET
+                        name = excluded.name,
+                        color = excluded.color,
+                        created_at = excluded.created_at",
+                    params![tag.id, tag.name, tag.color, tag.created_at],
+                )?;
+            }
+            (Some(RowState::AccountTag { account_id, tag_id }), _) => {
+                conn.execute(
+                    "INSERT OR IGNORE INTO account_tags (account_id, tag_id) VALUES (?1, ?2)",
+                    params![account_id, tag_id],
+                )?;
+            }
+            (None, Some(RowState::Account(account))) => {
+                conn.execute("DELETE FROM accounts WHERE id = ?1", params![account.id])?;
+            }
+            (None, Some(RowState::Group(group))) => {
+                conn.execute("DELETE FROM groups WHERE id = ?1", params![group.id])?;
+            }
+            (None, Some(RowState::Tag(tag))) => {
+                conn.execute("DELETE FROM tags WHERE id = ?1", params![tag.id])?;
+            }
+            (None, Some(RowState::AccountTag { account_id, tag_id })) => {
+                conn.execute(
+                    "DELETE FROM account_tags WHERE account_id = ?1 AND tag_id = ?2",
+                    params![account_id, tag_id],
+                )?;
+            }
+            (None, None) => {}
+        }
+
+        Ok(())
     }
 }
 
@@ -1200,7 +2591,7 @@ impl Database {
 impl Database {
     /// Get database statistics
     pub fn get_stats(&self) -> DbResult<DatabaseStats> {
-        let conn = self.get_conn();
+        let conn = self.read_conn()?;
 
         let accounts_count: i64 = conn.query_row("SELECT COUNT(*) FROM accounts", [], |row| row.get(0))?;
         let groups_count: i64 = conn.query_row("SELECT COUNT(*) FROM groups", [], |row| row.get(0))?;
@@ -1300,6 +2691,32 @@ pub struct DatabaseStats {
     pub accounts_per_tag: Vec<(String, i64, i64)>,
 }
 
+// ============================================================================
+// Settings Operations
+// ============================================================================
+
+impl Database {
+    /// Get a setting value by key
+    pub fn get_setting(&self, key: &str) -> DbResult<Option<String>> {
+        let conn = self.read_conn()?;
+        let value = conn
+            .query_row("SELECT value FROM settings WHERE key = ?1", params![key], |row| row.get(0))
+            .optional()?;
+        Ok(value)
+    }
+
+    /// Insert or replace a setting value
+    pub fn set_setting(&self, key: &str, value: &str) -> DbResult<()> {
+        let conn = self.get_conn()?;
+        conn.execute(
+            "INSERT INTO settings (key, value) VALUES (?1, ?2)
+             ON CONFLICT(key) DO UPDATE SET value = excluded.value, updated_at = CURRENT_TIMESTAMP",
+            params![key, value],
+        )?;
+        Ok(())
+    }
+}
+
 // ============================================================================
 // Maintenance Operations
 // ============================================================================
@@ -1307,14 +2724,14 @@ pub struct DatabaseStats {
 impl Database {
     /// Vacuum the database to reclaim space
     pub fn vacuum(&self) -> DbResult<()> {
-        let conn = self.get_conn();
+        let conn = self.get_conn()?;
         conn.execute("VACUUM", [])?;
         Ok(())
     }
 
     /// Analyze the database for query optimization
     pub fn analyze(&self) -> DbResult<()> {
-        let conn = self.get_conn();
+        let conn = self.get_conn()?;
         conn.execute("ANALYZE", [])?;
         Ok(())
     }
@@ -1327,17 +2744,9 @@ impl Database {
         Ok(metadata.len())
     }
 
-    /// Export all accounts as JSON
-    pub fn export_accounts_json(&self) -> DbResult<String> {
-        let accounts = self.get_accounts(None)?;
-        let json = serde_json::to_string(&accounts)
-            .map_err(|e| DbError::InvalidInput(format!("JSON serialization error: {}", e)))?;
-        Ok(json)
-    }
-
     /// Backup the database to a specified path
     pub fn backup(&self, backup_path: &PathBuf) -> DbResult<()> {
-        let conn = self.get_conn();
+        let conn = self.get_conn()?;
 
         // Use SQLite's VACUUM INTO command for backup
         let backup_str = backup_path.to_str()
@@ -1451,6 +2860,7 @@ mod tests {
             name: "Work".to_string(),
             color: "#ff0000".to_string(),
             sort_order: 1,
+            parent_id: None,
         };
 
         let id = db.create_group(group).unwrap();
@@ -1460,6 +2870,281 @@ mod tests {
         assert_eq!(retrieved.color, "#ff0000");
     }
 
+    #[test]
+    fn test_delete_group_with_members() {
+        let db = get_test_db();
+        let create = |name: &str, parent_id: Option<i64>| {
+            db.create_group(CreateGroup {
+                name: name.to_string(),
+                color: "#000000".to_string(),
+                sort_order: 0,
+                parent_id,
+            })
+            .unwrap()
+        };
+        let add_account = |email: &str, group_id: i64| {
+            db.create_account(CreateAccount {
+                raw_import_id: None,
+                email: email.to_string(),
+                password: "pw".to_string(),
+                recovery_email: None,
+                totp_secret: None,
+                year: None,
+                notes: None,
+                group_id: Some(group_id),
+                field_order: None,
+            })
+            .unwrap()
+        };
+        let default_id = db.get_groups().unwrap()[0].id;
+
+        // Reassign: accounts move, subgroups move up to the parent
+        let work = create("Work", None);
+        let team = create("Team", Some(work));
+        let servers = create("Servers", Some(team));
+        let alice = add_account("alice@example.com", team);
+        let outcome = db.delete_group_with_members(team, GroupMembers::MoveTo(work)).unwrap();
+        assert_eq!(
+            outcome,
+            GroupDeletion {
+                moved_to: Some(work),
+                accounts_moved: 1,
+                accounts_deleted: 0,
+                subgroups_moved: 1,
+            }
+        );
+        assert_eq!(db.get_account(alice).unwrap().group_id, Some(work));
+        assert_eq!(db.get_group(servers).unwrap().parent_id, Some(work));
+
+        // Move to Default
+        let outcome = db.delete_group_with_members(servers, GroupMembers::MoveToDefault).unwrap();
+        assert_eq!(outcome.moved_to, Some(default_id));
+
+        // Delete members
+        let bob = add_account("bob@example.com", work);
+        let outcome = db.delete_group_with_members(work, GroupMembers::Delete).unwrap();
+        assert_eq!(outcome.accounts_deleted, 2);
+        assert!(db.get_account(bob).is_err());
+        assert!(db.get_account(alice).is_err());
+
+        let search = LogSearch {
+            action: Some("DELETE_GROUP".to_string()),
+            ..Default::default()
+        };
+        let logs = db.search_operation_logs(&search, None).unwrap();
+        assert_eq!(logs.len(), 3);
+
+        // Refused deletions change nothing
+        let other = create("Other", None);
+        for (id, members) in [
+            (other, GroupMembers::MoveTo(other)),
+            (other, GroupMembers::MoveTo(999)),
+            (default_id, GroupMembers::MoveTo(other)),
+        ] {
+            assert!(db.delete_group_with_members(id, members).is_err());
+        }
+        assert!(db.get_group(other).is_ok());
+
+        // The Default group is known by its flag, not by its name
+        let rename = |name: &str, parent_id: Option<i64>| CreateGroup {
+            name: name.to_string(),
+            color: "#123456".to_string(),
+            sort_order: 5,
+            parent_id,
+        };
+        assert!(matches!(db.update_group(default_id, rename("Inbox", None)), Err(DbError::InvalidInput(_))));
+        assert!(matches!(db.update_group(default_id, rename("Default", Some(other))), Err(DbError::InvalidInput(_))));
+        assert!(matches!(db.move_group(default_id, Some(other)), Err(DbError::InvalidInput(_))));
+        db.update_group(default_id, rename("Default", None)).unwrap();
+        assert_eq!(db.get_group(default_id).unwrap().color, "#123456");
+
+        let nested = create("default", Some(other));
+        assert!(!db.get_group(nested).unwrap().is_default);
+        let outcome = db.delete_group_with_members(nested, GroupMembers::MoveToDefault).unwrap();
+        assert_eq!(outcome.moved_to, Some(default_id));
+    }
+
+    #[test]
+    fn test_group_hierarchy() {
+        let db = get_test_db();
+        let create = |name: &str, parent_id: Option<i64>| {
+            db.create_group(CreateGroup {
+                name: name.to_string(),
+                color: "#000000".to_string(),
+                sort_order: 0,
+                parent_id,
+            })
+            .unwrap()
+        };
+        let add_account = |email: &str, group_id: i64| {
+            db.create_account(CreateAccount {
+                raw_import_id: None,
+                email: email.to_string(),
+                password: "pw".to_string(),
+                recovery_email: None,
+                totp_secret: None,
+                year: None,
+                notes: None,
+                group_id: Some(group_id),
+                field_order: None,
+            })
+            .unwrap()
+        };
+
+        // Work > Servers > Prod, and a separate Personal group
+        let work = create("Work", None);
+        let servers = create("Servers", Some(work));
+        let prod = create("Prod", Some(servers));
+        let personal = create("Personal", None);
+        assert_eq!(db.get_group(prod).unwrap().parent_id, Some(servers));
+        assert!(matches!(
+            db.create_group(CreateGroup {
+                name: "Orphan".to_string(),
+                color: "#000000".to_string(),
+                sort_order: 0,
+                parent_id: Some(999),
+            }),
+            Err(DbError::NotFound(_))
+        ));
+
+        add_account("work@example.com", work);
+        add_account("server@example.com", servers);
+        let prod_account = add_account("prod@example.com", prod);
+        add_account("me@example.com", personal);
+
+        assert_eq!(db.get_group_accounts_count(work).unwrap(), 1);
+        assert_eq!(db.get_group_subtree_accounts_count(work).unwrap(), 3);
+        assert_eq!(db.get_group_subtree_accounts_count(prod).unwrap(), 1);
+
+        let search = |group_id: i64, include_subgroups: bool| {
+            db.search_accounts(AccountSearch {
+                group_id: Some(group_id),
+                include_subgroups,
+                pagination: Pagination::new(0, 50),
+                ..Default::default()
+            })
+            .unwrap()
+            .len()
+        };
+        assert_eq!(search(work, false), 1);
+        assert_eq!(search(work, true), 3);
+        assert_eq!(search(servers, true), 2);
+
+        // Cycles are refused
+        for (id, parent) in [(work, work), (work, servers), (work, prod), (servers, prod)] {
+            assert!(matches!(db.move_group(id, Some(parent)), Err(DbError::InvalidInput(_))));
+        }
+
+        // Moving a group takes its subtree along
+        db.move_group(servers, Some(personal)).unwrap();
+        assert_eq!(db.get_group_subtree_accounts_count(work).unwrap(), 1);
+        assert_eq!(db.get_group_subtree_accounts_count(personal).unwrap(), 3);
+        db.move_group(servers, None).unwrap();
+        assert_eq!(db.get_group(servers).unwrap().parent_id, None);
+
+        // Deleting a group makes its subgroups top-level
+        db.delete_group_with_members(servers, GroupMembers::MoveToDefault).unwrap();
+        assert_eq!(db.get_group(prod).unwrap().parent_id, None);
+        assert_eq!(db.get_account(prod_account).unwrap().group_id, Some(prod));
+    }
+
+    #[test]
+    fn test_group_names_are_unique_per_parent() {
+        let db = get_test_db();
+        let group = |name: &str, parent_id: Option<i64>| CreateGroup {
+            name: name.to_string(),
+            color: "#000000".to_string(),
+            sort_order: 0,
+            parent_id,
+        };
+        let is_duplicate = |result: DbResult<()>| matches!(result, Err(DbError::Duplicate(_)));
+
+        let work = db.create_group(group("Work", None)).unwrap();
+        let personal = db.create_group(group("Personal", None)).unwrap();
+        let nested = db.create_group(group("Work", Some(personal))).unwrap();
+        db.create_group(group("Work", Some(work))).unwrap();
+
+        assert!(is_duplicate(db.create_group(group("Work", None)).map(drop)));
+        assert!(is_duplicate(db.create_group(group("Work", Some(personal))).map(drop)));
+        assert!(is_duplicate(db.update_group(personal, group("Work", None))));
+        assert!(is_duplicate(db.move_group(nested, None)));
+        // Renaming a group to its own name is not a clash
+        db.update_group(nested, group("Work", Some(personal))).unwrap();
+
+        // Subgroups cannot move up next to a group with their name
+        assert!(is_duplicate(db.delete_group_with_members(personal, GroupMembers::Delete).map(drop)));
+        assert!(matches!(
+            db.delete_group_with_members(personal, GroupMembers::MoveToDefault),
+            Err(DbError::Duplicate(_))
+        ));
+        assert_eq!(db.get_group(nested).unwrap().parent_id, Some(personal));
+
+        db.update_group(nested, group("Archive", Some(personal))).unwrap();
+        db.delete_group_with_members(personal, GroupMembers::MoveToDefault).unwrap();
+        assert_eq!(db.get_group(nested).unwrap().parent_id, None);
+    }
+
+    #[test]
+    fn test_group_ordering() {
+        let db = get_test_db();
+        let create = |name: &str, sort_order: i32, parent_id: Option<i64>| {
+            db.create_group(CreateGroup {
+                name: name.to_string(),
+                color: "#000000".to_string(),
+                sort_order,
+                parent_id,
+            })
+            .unwrap()
+        };
+        let top_level = || -> Vec<String> {
+            db.get_groups()
+                .unwrap()
+                .into_iter()
+                .filter(|g| g.parent_id.is_none())
+                .map(|g| g.name)
+                .collect()
+        };
+
+        let default = db.get_groups().unwrap()[0].id;
+        let a = create("A", 0, None);
+        let b = create("B", 0, None);
+        let c = create("C", 0, None);
+        let child = create("Child", 0, Some(a));
+        assert_eq!(top_level(), ["A", "B", "C", "Default"]);
+
+        db.reorder_groups(&[default, a, b, c]).unwrap();
+        assert_eq!(top_level(), ["Default", "A", "B", "C"]);
+        assert_eq!(db.get_group(c).unwrap().sort_order, 4 * GROUP_SORT_GAP);
+
+        // A single move rewrites only the moved group
+        assert_eq!(db.place_group(c, Some(a)).unwrap(), 1);
+        assert_eq!(top_level(), ["Default", "C", "A", "B"]);
+        assert!(matches!(db.place_group(a, Some(999)), Err(DbError::NotFound(_))));
+        assert_eq!(db.place_group(a, None).unwrap(), 1);
+        assert_eq!(top_level(), ["Default", "C", "B", "A"]);
+        assert_eq!(db.place_group(default, Some(c)).unwrap(), 1);
+        assert_eq!(top_level(), ["Default", "C", "B", "A"]);
+
+        // No room between neighbours: the siblings are renumbered
+        let e = create("E", 10_000, None);
+        let f = create("F", 10_001, None);
+        assert_eq!(db.place_group(b, Some(f)).unwrap(), 6);
+        assert_eq!(top_level(), ["Default", "C", "A", "E", "B", "F"]);
+        assert_eq!(db.get_group(f).unwrap().sort_order, 6 * GROUP_SORT_GAP);
+        assert_eq!(db.get_group(e).unwrap().sort_order, 4 * GROUP_SORT_GAP);
+
+        // An only child has nothing to move around
+        assert_eq!(db.place_group(child, None).unwrap(), 0);
+        assert!(matches!(db.place_group(child, Some(a)), Err(DbError::InvalidInput(_))));
+        assert!(matches!(db.place_group(a, Some(a)), Err(DbError::InvalidInput(_))));
+
+        // A bad list leaves the order untouched
+        let before = top_level();
+        assert!(matches!(db.reorder_groups(&[a, b, a]), Err(DbError::InvalidInput(_))));
+        assert!(matches!(db.reorder_groups(&[f, e, 999]), Err(DbError::NotFound(_))));
+        assert_eq!(top_level(), before);
+    }
+
     #[test]
     fn test_create_and_get_tag() {
         let db = get_test_db();
@@ -1476,6 +3161,47 @@ mod tests {
         assert_eq!(retrieved.color, "#00ff00");
     }
 
+    #[test]
+    fn test_tag_names_are_unique_ignoring_case() {
+        let db = get_test_db();
+        let tag = |name: &str| CreateTag { name: name.to_string(), color: "#000000".to_string() };
+
+        let work = db.create_tag(tag("Work")).unwrap();
+        let home = db.create_tag(tag("Home")).unwrap();
+        assert!(matches!(db.create_tag(tag("work")), Err(DbError::Duplicate(_))));
+        assert!(matches!(db.update_tag(home, tag("WORK")), Err(DbError::Duplicate(_))));
+
+        // Changing the case of a tag's own name is fine
+        db.update_tag(work, tag("work")).unwrap();
+        assert_eq!(db.get_tag(work).unwrap().name, "work");
+    }
+
+    #[test]
+    fn test_merge_tags() {
+        let db = get_test_db();
+        let ids = create_batch_accounts(&db, 3);
+        let tag = |name: &str| db.create_tag(CreateTag { name: name.to_string(), color: "#000000".to_string() }).unwrap();
+        let (source, target) = (tag("wrk"), tag("work"));
+
+        db.add_tag_to_account(ids[0], source).unwrap();
+        db.add_tag_to_account(ids[1], source).unwrap();
+        db.add_tag_to_account(ids[1], target).unwrap();
+        db.add_tag_to_account(ids[2], target).unwrap();
+
+        let merge = db.merge_tags(source, target).unwrap();
+        assert_eq!(merge, TagMerge { accounts_moved: 1, accounts_already_tagged: 1 });
+        assert!(matches!(db.get_tag(source), Err(DbError::NotFound(_))));
+        assert_eq!(db.get_tag_account_ids(target).unwrap(), ids);
+        assert!(ids.iter().all(|&id| db.get_account_tags(id).unwrap().len() == 1));
+
+        let logs = db.get_operation_logs(None, 10).unwrap();
+        assert!(logs.iter().any(|l| l.action == "MERGE_TAGS"));
+
+        assert!(matches!(db.merge_tags(target, target), Err(DbError::InvalidInput(_))));
+        assert!(matches!(db.merge_tags(source, target), Err(DbError::NotFound(_))));
+        assert!(matches!(db.merge_tags(target, 999), Err(DbError::NotFound(_))));
+    }
+
     #[test]
     fn test_add_and_remove_tag_from_account() {
         let db = get_test_db();
@@ -1520,7 +3246,7 @@ mod tests {
         let db = get_test_db();
 
         // Create test accounts
-        let _ = db.create_account(CreateAccount {
+        let work_id = db.create_account(CreateAccount {
             raw_import_id: None,
             email: "work@example.com".to_string(),
             password: "pass1".to_string(),
@@ -1530,7 +3256,7 @@ mod tests {
             notes: None,
             group_id: None,
             field_order: None,
-        });
+        }).unwrap();
 
         let _ = db.create_account(CreateAccount {
             raw_import_id: None,
@@ -1544,11 +3270,15 @@ mod tests {
             field_order: None,
         });
 
-        // Search by email
+        // Search by blind index tokens
+        db.replace_search_tokens(work_id, "email", &["tok-a".to_string(), "tok-b".to_string()])
+            .unwrap();
+
         let results = db
             .search_accounts(AccountSearch {
-                query: Some("work".to_string()),
+                query_tokens: vec!["tok-a".to_string(), "tok-b".to_string()],
                 group_id: None,
+                include_subgroups: false,
                 tag_id: None,
                 year: None,
                 pagination: Pagination { offset: 0, limit: 10 },
@@ -1558,11 +3288,22 @@ mod tests {
         assert_eq!(results.len(), 1);
         assert_eq!(results[0].email, "work@example.com");
 
+        // Every token must be present
+        let results = db
+            .search_accounts(AccountSearch {
+                query_tokens: vec!["tok-a".to_string(), "tok-c".to_string()],
+                pagination: Pagination { offset: 0, limit: 10 },
+                ..Default::default()
+            })
+            .unwrap();
+        assert!(results.is_empty());
+
         // Search by year
         let results = db
             .search_accounts(AccountSearch {
-                query: None,
+                query_tokens: Vec::new(),
                 group_id: None,
+                include_subgroups: false,
                 tag_id: None,
                 year: Some(2024),
                 pagination: Pagination { offset: 0, limit: 10 },
@@ -1573,6 +3314,230 @@ mod tests {
         assert_eq!(results[0].year, Some(2024));
     }
 
+    #[test]
+    fn test_unindexed_accounts_and_token_cascade() {
+        let db = get_test_db();
+
+        let id = db.create_account(CreateAccount {
+            raw_import_id: None,
+            email: "test@example.com".to_string(),
+            password: "password123".to_string(),
+            recovery_email: None,
+            totp_secret: None,
+            year: None,
+            notes: None,
+            group_id: None,
+            field_order: None,
+        }).unwrap();
+
+        assert_eq!(db.get_unindexed_account_ids().unwrap(), vec![id]);
+
+        db.replace_search_tokens(id, "email", &["tok".to_string()]).unwrap();
+        assert!(db.get_unindexed_account_ids().unwrap().is_empty());
+
+        // Deleting the account removes its tokens
+        db.delete_account(id).unwrap();
+        let remaining: i64 = db.get_conn().unwrap()
+            .query_row("SELECT COUNT(*) FROM account_search_tokens", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(remaining, 0);
+    }
+
+    #[test]
+    fn test_import_accounts_links_raw_import() {
+        let db = get_test_db();
+
+        let accounts = ["a@example.com", "b@example.com"]
+            .iter()
+            .map(|email| ImportedAccount {
+                account: CreateAccount {
+                    raw_import_id: None,
+                    email: email.to_string(),
+                    password: "password123".to_string(),
+                    recovery_email: None,
+                    totp_secret: None,
+                    year: None,
+                    notes: None,
+                    group_id: None,
+                    field_order: None,
+                },
+                group_path: vec!["default".to_string()],
+                tag_names: vec!["new".to_string(), "New".to_string()],
+                search_tokens: Vec::new(),
+            })
+            .collect();
+
+        let (raw_import_id, ids) = db
+            .import_accounts("raw".to_string(), "text".to_string(), None, accounts)
+            .unwrap();
+
+        assert_eq!(ids.len(), 2);
+        assert_eq!(db.get_raw_import(raw_import_id).unwrap().raw_text, "raw");
+        for id in ids {
+            let account = db.get_account(id).unwrap();
+            assert_eq!(account.raw_import_id, Some(raw_import_id));
+            // Labels are matched ignoring case, so both accounts share one tag
+            // and land in the existing "Default" group
+            assert_eq!(db.get_group(account.group_id.unwrap()).unwrap().name, "Default");
+            assert_eq!(account.tags.len(), 1);
+            let logs = db.get_operation_logs(Some(id), 10).unwrap();
+            assert!(logs.iter().any(|l| l.action == "IMPORT"));
+        }
+    }
+
+    fn create_batch_accounts(db: &Database, count: usize) -> Vec<i64> {
+        (0..count)
+            .map(|i| {
+                db.create_account(CreateAccount {
+                    raw_import_id: None,
+                    email: format!("user{}@example.com", i),
+                    password: "password123".to_string(),
+                    recovery_email: None,
+                    totp_secret: None,
+                    year: None,
+                    notes: None,
+                    group_id: None,
+                    field_order: None,
+                })
+                .unwrap()
+            })
+            .collect()
+    }
+
+    fn batch_log_count(db: &Database) -> i64 {
+        db.get_conn().unwrap()
+            .query_row(
+                "SELECT COUNT(*) FROM operation_logs WHERE action LIKE 'BATCH_%'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn test_batch_delete_all_or_nothing_rolls_back() {
+        let db = get_test_db();
+        let ids = create_batch_accounts(&db, 2);
+
+        let outcome = db
+            .batch_delete_accounts(&[ids[0], 999, ids[1]], BatchMode::AllOrNothing)
+            .unwrap();
+
+        assert!(!outcome.committed);
+        assert_eq!(
+            outcome.items,
+            vec![BatchItemOutcome::Applied, BatchItemOutcome::NotFound, BatchItemOutcome::Applied]
+        );
+        assert_eq!(db.get_accounts_count().unwrap(), 2);
+        assert_eq!(batch_log_count(&db), 0);
+    }
+
+    #[test]
+    fn test_batch_delete_best_effort_commits_the_rest() {
+        let db = get_test_db();
+        let ids = create_batch_accounts(&db, 2);
+
+        let outcome = db
+            .batch_delete_accounts(&[ids[0], 999, ids[1]], BatchMode::BestEffort)
+            .unwrap();
+
+        assert!(outcome.committed);
+        assert_eq!(outcome.applied_count(), 2);
+        assert_eq!(db.get_accounts_count().unwrap(), 0);
+
+        // One grouped log entry for the whole batch
+        assert_eq!(batch_log_count(&db), 1);
+        let logs = db.get_operation_logs(None, 10).unwrap();
+        let entry = logs.iter().find(|l| l.action == "BATCH_DELETE").unwrap();
+        assert_eq!(
+            entry.details.as_deref(),
+            Some(format!("Deleted 2 of 3 accounts: {}, {}", ids[0], ids[1]).as_str())
+        );
+    }
+
+    #[test]
+    fn test_batch_update_reports_invalid_items() {
+        let db = get_test_db();
+        let ids = create_batch_accounts(&db, 2);
+
+        let update = |id: i64, group_id: i64| UpdateAccount {
+            id,
+            email: None,
+            password: None,
+            recovery_email: None,
+            totp_secret: None,
+            year: Some(2020),
+            notes: None,
+            group_id: Some(group_id),
+            field_order: None,
+        };
+
+        // Group 999 does not exist, so the foreign key rejects the second item
+        let outcome = db
+            .batch_update_accounts(&[update(ids[0], 1), update(ids[1], 999)], &[], BatchMode::BestEffort)
+            .unwrap();
+
+        assert!(outcome.committed);
+        assert_eq!(outcome.items[0], BatchItemOutcome::Applied);
+        assert!(matches!(outcome.items[1], BatchItemOutcome::Invalid(_)));
+
+        // The failed item left no partial update behind
+        assert_eq!(db.get_account(ids[0]).unwrap().year, Some(2020));
+        assert_eq!(db.get_account(ids[1]).unwrap().year, None);
+    }
+
+    #[test]
+    fn test_batch_change_account_tags() {
+        let db = get_test_db();
+        let ids = create_batch_accounts(&db, 3);
+        let tag = |name: &str| db.create_tag(CreateTag { name: name.to_string(), color: "#000000".to_string() }).unwrap();
+        let (work, old, new) = (tag("work"), tag("old"), tag("new"));
+        let tag_ids = |id: i64| -> Vec<i64> { db.get_account_tags(id).unwrap().iter().map(|t| t.id).collect() };
+
+        db.add_tag_to_account(ids[0], old).unwrap();
+        db.add_tag_to_account(ids[0], work).unwrap();
+
+        // Add keeps existing tags; a missing account fails only its own item
+        let outcome = db
+            .batch_change_account_tags(&[ids[0], 999, ids[1]], &[work, new], TagChange::Add, BatchMode::BestEffort)
+            .unwrap();
+        assert!(outcome.committed);
+        assert_eq!(
+            outcome.items,
+            vec![BatchItemOutcome::Applied, BatchItemOutcome::NotFound, BatchItemOutcome::Applied]
+        );
+        assert_eq!(tag_ids(ids[0]), vec![new, old, work]);
+        assert_eq!(tag_ids(ids[1]), vec![new, work]);
+
+        // Removing a tag an account does not have is not an error
+        let outcome = db
+            .batch_change_account_tags(&ids, &[old, work], TagChange::Remove, BatchMode::AllOrNothing)
+            .unwrap();
+        assert_eq!(outcome.applied_count(), 3);
+        assert_eq!(tag_ids(ids[0]), vec![new]);
+        assert_eq!(tag_ids(ids[2]), Vec::<i64>::new());
+
+        let outcome = db
+            .batch_change_account_tags(&ids, &[old, old], TagChange::Replace, BatchMode::AllOrNothing)
+            .unwrap();
+        assert!(outcome.committed);
+        assert!(ids.iter().all(|&id| tag_ids(id) == vec![old]));
+
+        // A missing tag fails the whole call up front
+        assert!(matches!(
+            db.batch_change_account_tags(&ids, &[new, 999], TagChange::Add, BatchMode::BestEffort),
+            Err(DbError::NotFound(_))
+        ));
+        assert!(ids.iter().all(|&id| tag_ids(id) == vec![old]));
+
+        let logs = db.get_operation_logs(None, 10).unwrap();
+        let entry = logs.iter().find(|l| l.action == "BATCH_REPLACE_TAGS").unwrap();
+        assert_eq!(
+            entry.details.as_deref(),
+            Some(format!("Set tags [{}] on 3 of 3 accounts: {}, {}, {}", old, ids[0], ids[1], ids[2]).as_str())
+        );
+    }
+
     #[test]
     fn test_operation_logging() {
         let db = get_test_db();
@@ -1604,6 +3569,46 @@ mod tests {
         assert!(create_log.is_some());
     }
 
+    #[test]
+    fn test_search_operation_logs() {
+        let db = get_test_db();
+        let conn = db.get_conn().unwrap();
+        for (account_id, action, created_at) in [
+            (None, "CREATE", "2024-01-01 10:00:00"),
+            (None, "UPDATE", "2024-01-02 10:00:00"),
+            (None, "UPDATE", "2024-01-03 10:00:00"),
+        ] {
+            conn.execute(
+                "INSERT INTO operation_logs (account_id, action, created_at) VALUES (?1, ?2, ?3)",
+                params![account_id as Option<i64>, action, created_at],
+            )
+            .unwrap();
+        }
+        drop(conn);
+
+        let updates = LogSearch {
+            action: Some("UPDATE".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(db.count_operation_logs(&updates).unwrap(), 2);
+
+        // Newest first, paginated
+        let page = db
+            .search_operation_logs(&updates, Some(&Pagination::new(0, 1)))
+            .unwrap();
+        assert_eq!(page.len(), 1);
+        assert_eq!(page[0].created_at, "2024-01-03 10:00:00");
+
+        let range = LogSearch {
+            from: Some("2024-01-02 00:00:00".to_string()),
+            to: Some("2024-01-03 00:00:00".to_string()),
+            ..Default::default()
+        };
+        let logs = db.search_operation_logs(&range, None).unwrap();
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].created_at, "2024-01-02 10:00:00");
+    }
+
     #[test]
     fn test_undo_stack() {
         let db = get_test_db();
@@ -1652,4 +3657,125 @@ mod tests {
         let stats = db.get_stats().unwrap();
         assert_eq!(stats.accounts_count, 1);
     }
+
+    #[test]
+    fn test_settings() {
+        let db = get_test_db();
+
+        assert_eq!(db.get_setting("theme").unwrap(), None);
+
+        db.set_setting("theme", "dark").unwrap();
+        assert_eq!(db.get_setting("theme").unwrap().as_deref(), Some("dark"));
+
+        db.set_setting("theme", "light").unwrap();
+        assert_eq!(db.get_setting("theme").unwrap().as_deref(), Some("light"));
+    }
+
+    /// Insert `accounts` accounts, each tagged with two of `tags` tags
+    fn seed_tagged_accounts(db: &Database, accounts: i64, tags: i64) {
+        let mut conn = db.get_conn().unwrap();
+        let tx = conn.transaction().unwrap();
+        for t in 0..tags {
+            tx.execute(
+                "INSERT INTO tags (id, name, color) VALUES (?1, ?2, '#000000')",
+                params![t + 1, format!("tag-{:02}", t)],
+            )
+            .unwrap();
+        }
+        for a in 1..=accounts {
+            tx.execute(
+                "INSERT INTO accounts (id, email, password) VALUES (?1, ?2, 'pw')",
+                params![a, format!("user{}@example.com", a)],
+            )
+            .unwrap();
+            for t in [a % tags, (a + 7) % tags] {
+                tx.execute(
+                    "INSERT INTO account_tags (account_id, tag_id) VALUES (?1, ?2)",
+                    params![a, t + 1],
+                )
+                .unwrap();
+            }
+        }
+        tx.commit().unwrap();
+    }
+
+    fn tag_search(tag_id: i64, limit: i64) -> AccountSearch {
+        AccountSearch {
+            query_tokens: Vec::new(),
+            group_id: None,
+            include_subgroups: false,
+            tag_id: Some(tag_id),
+            year: None,
+            pagination: Pagination::new(0, limit),
+        }
+    }
+
+    #[test]
+    fn test_list_accounts_with_tags() {
+        const ACCOUNTS: i64 = 200;
+        const TAGS: i64 = 20;
+
+        let db = get_test_db();
+        seed_tagged_accounts(&db, ACCOUNTS, TAGS);
+
+        let accounts = db.get_accounts(Some(Pagination::new(0, ACCOUNTS))).unwrap();
+        assert_eq!(accounts.len(), ACCOUNTS as usize);
+        assert!(accounts.iter().all(|a| a.tags.len() == 2));
+        let account = accounts.iter().find(|a| a.id == 3).unwrap();
+        let names: Vec<&str> = account.tags.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, vec!["tag-03", "tag-10"]);
+
+        // Batch-loaded tags agree with the per-account query
+        let conn = db.read_conn().unwrap();
+        for account in &accounts {
+            let tags = db.get_tags_for_account(&conn, account.id).unwrap();
+            let ids: Vec<i64> = tags.iter().map(|t| t.id).collect();
+            let listed: Vec<i64> = account.tags.iter().map(|t| t.id).collect();
+            assert_eq!(ids, listed);
+        }
+        drop(conn);
+
+        let found = db.search_accounts(tag_search(1, ACCOUNTS)).unwrap();
+        assert_eq!(found.len(), 20);
+        assert!(found.iter().all(|a| a.tags.iter().any(|t| t.id == 1)));
+    }
+
+    /// Listing 10,000 tagged accounts loads their tags in one query. Run
+    /// with `cargo test -- --ignored --nocapture` to see the timings.
+    #[test]
+    #[ignore = "benchmark"]
+    fn bench_list_10k_accounts_with_tags() {
+        const ACCOUNTS: i64 = 10_000;
+        const TAGS: i64 = 20;
+
+        let db = get_test_db();
+        seed_tagged_accounts(&db, ACCOUNTS, TAGS);
+
+        let started = std::time::Instant::now();
+        let accounts = db.get_accounts(Some(Pagination::new(0, ACCOUNTS))).unwrap();
+        let listed = started.elapsed();
+
+        let started = std::time::Instant::now();
+        let found = db.search_accounts(tag_search(1, ACCOUNTS)).unwrap();
+        let searched = started.elapsed();
+
+        // What listing cost with one tag query per account
+        let started = std::time::Instant::now();
+        {
+            let conn = db.read_conn().unwrap();
+            for account in &accounts {
+                db.get_tags_for_account(&conn, account.id).unwrap();
+            }
+        }
+        let per_account = started.elapsed();
+
+        eprintln!(
+            "{} accounts: list {:?}, search by tag {:?} ({} found), per-account tag queries {:?}",
+            ACCOUNTS,
+            listed,
+            searched,
+            found.len(),
+            per_account
+        );
+    }
 }
@@ -0,0 +1,358 @@
+//! Connection handling
+//!
+//! A file-backed [`Database`] runs in WAL mode with one writer connection
+//! behind a mutex and a small pool of read-only connections. Readers never
+//! wait for the writer: a search during a long import reads the last
+//! committed state instead of blocking until the import finishes.
+//!
+//! In-memory databases (tests) cannot be shared between connections, so
+//! they have no read pool and reads go through the writer.
+//!
+//! A panic while the writer is held poisons its mutex. The next caller gets
+//! [`DbError::LockPoisoned`] instead of a panic; any transaction the
+//! panicking thread left open is rolled back and the lock is released for
+//! later callers.
+
+use super::{Database, DbError, DbResult};
+use r2d2::{Pool, PooledConnection};
+use r2d2_sqlite::SqliteConnectionManager;
+use rusqlite::{Connection, OpenFlags};
+use std::ops::Deref;
+use std::path::Path;
+use std::sync::MutexGuard;
+use std::time::Duration;
+
+/// Number of read connections kept open
+const READ_POOL_SIZE: u32 = 4;
+
+/// How long a connection waits for a lock held by another connection
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long a reader waits for a free pooled connection
+const READ_CONNECTION_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Pool of read-only connections to the database file
+pub(crate) type ReadPool = Pool<SqliteConnectionManager>;
+
+/// Connection used for a read: pooled, or the writer for in-memory databases
+pub(crate) enum ReadConn<'a> {
+    Pooled(PooledConnection<SqliteConnectionManager>),
+    Writer(MutexGuard<'a, Connection>),
+}
+
+impl Deref for ReadConn<'_> {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        match self {
+            ReadConn::Pooled(conn) => conn,
+            ReadConn::Writer(conn) => conn,
+        }
+    }
+}
+
+/// Settings every connection needs
+fn configure(conn: &Connection) -> rusqlite::Result<()> {
+    conn.busy_timeout(BUSY_TIMEOUT)?;
+    conn.execute_batch("PRAGMA foreign_keys = ON")
+}
+
+/// Open the writer connection and switch the file to WAL journaling
+///
+/// WAL mode is stored in the file, so the pragma only does work the first
+/// time and after a restore (which copies the backup's header over it).
+pub(crate) fn open_writer(path: &Path) -> DbResult<Connection> {
+    let conn = Connection::open(path)?;
+    configure(&conn)?;
+    enable_wal(&conn)?;
+    Ok(conn)
+}
+
+/// Switch the database to WAL journaling
+pub(crate) fn enable_wal(conn: &Connection) -> DbResult<()> {
+    let mode: String = conn.query_row("PRAGMA journal_mode = WAL", [], |row| row.get(0))?;
+    if !mode.eq_ignore_ascii_case("wal") {
+        return Err(DbError::InvalidInput(format!("Cannot enable WAL journaling (mode is {})", mode)));
+    }
+    // Safe in WAL mode: a crash can only lose the last transactions, never
+    // corrupt the file
+    conn.execute_batch("PRAGMA synchronous = NORMAL")?;
+    Ok(())
+}
+
+/// Open the pool of read-only connections
+///
+/// Must be called after the writer has put the file in WAL mode.
+pub(crate) fn open_read_pool(path: &Path) -> DbResult<ReadPool> {
+    let manager = SqliteConnectionManager::file(path)
+        .with_flags(OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX | OpenFlags::SQLITE_OPEN_URI)
+        .with_init(|conn| configure(conn));
+
+    Pool::builder()
+        .max_size(READ_POOL_SIZE)
+        .connection_timeout(READ_CONNECTION_TIMEOUT)
+        .build(manager)
+        .map_err(|e| DbError::Pool(e.to_string()))
+}
+
+impl Database {
+    /// Lock the writer connection
+    ///
+    /// Every statement that changes the database runs on this connection.
+    ///
+    /// # Errors
+    /// `LockPoisoned` if a thread panicked while holding the writer. The
+    /// connection is cleaned up so that the next call succeeds.
+    pub(crate) fn get_conn(&self) -> DbResult<MutexGuard<'_, Connection>> {
+        match self.conn.lock() {
+            Ok(conn) => Ok(conn),
+            Err(poisoned) => {
+                let conn = poisoned.into_inner();
+                if !conn.is_autocommit() {
+                    // Undo whatever the panicking thread left half done
+                    let _ = conn.execute_batch("ROLLBACK");
+                }
+                drop(conn);
+                self.conn.clear_poison();
+                Err(DbError::LockPoisoned)
+            }
+        }
+    }
+
+    /// Get a connection for queries that do not write
+    ///
+    /// Reads see the last committed state and do not wait for the writer.
+    pub(crate) fn read_conn(&self) -> DbResult<ReadConn<'_>> {
+        match &self.readers {
+            Some(pool) => pool
+                .get()
+                .map(ReadConn::Pooled)
+                .map_err(|e| DbError::Pool(e.to_string())),
+            None => self.get_conn().map(ReadConn::Writer),
+        }
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use crate::crypto::{derive_key, encrypt_field, SecretKey};
+    use crate::db::{AccountSearch, CreateAccount, Database, DbError, ImportedAccount, Pagination};
+    use crate::search_index::{self, MatchMode};
+    use std::path::{Path, PathBuf};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+
+    fn temp_path(name: &str) -> PathBuf {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        std::env::temp_dir().join(format!("gmanager-{}-{}-{}.db", name, std::process::id(), nanos))
+    }
+
+    fn remove_database(path: &Path) {
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+        }
+    }
+
+    fn test_account(key: &SecretKey, i: usize) -> CreateAccount {
+        CreateAccount {
+            raw_import_id: None,
+            email: encrypt_field(&format!("user{}@example.com", i), key).unwrap(),
+            password: encrypt_field("pw", key).unwrap(),
+            recovery_email: None,
+            totp_secret: None,
+            year: Some(2024),
+            notes: None,
+            group_id: None,
+            field_order: None,
+        }
+    }
+
+    #[test]
+    fn test_file_database_uses_wal_and_read_pool() {
+        let path = temp_path("wal");
+        let db = Database::open(&path).unwrap();
+        let key = derive_key(b"test-password", b"test-salt-16byte");
+
+        let mode: String = db
+            .get_conn()
+            .unwrap()
+            .query_row("PRAGMA journal_mode", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(mode, "wal");
+
+        let id = db.create_account(test_account(&key, 1)).unwrap();
+        assert_eq!(db.get_account(id).unwrap().id, id);
+
+        // Pooled connections are read-only
+        let reader = db.read_conn().unwrap();
+        assert!(reader.execute("DELETE FROM accounts", []).is_err());
+        drop(reader);
+
+        // A restore keeps WAL mode and readers see the restored state
+        let backup_path = temp_path("wal-backup");
+        db.backup(&backup_path).unwrap();
+        let later = db.create_account(test_account(&key, 2)).unwrap();
+        db.restore_backup(&backup_path).unwrap();
+        assert!(db.get_account(later).is_err());
+        let mode: String = db
+            .get_conn()
+            .unwrap()
+            .query_row("PRAGMA journal_mode", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(mode, "wal");
+
+        drop(db);
+        remove_database(&path);
+        remove_database(&backup_path);
+    }
+
+    #[test]
+    fn test_poisoned_writer_is_an_error_then_recovers() {
+        let db = Database::init_in_memory().unwrap();
+
+        let poisoner = db.clone();
+        let result = std::thread::spawn(move || {
+            let conn = poisoner.get_conn().unwrap();
+            conn.execute_batch("BEGIN; INSERT INTO tags (name, color) VALUES ('half', '#000000');")
+                .unwrap();
+            panic!("simulated panic while holding the writer");
+        })
+        .join();
+        assert!(result.is_err());
+
+        assert!(matches!(db.get_tags(), Err(DbError::LockPoisoned)));
+
+        // The open transaction was rolled back and the lock works again
+        assert!(db.get_tags().unwrap().iter().all(|t| t.name != "half"));
+    }
+
+    #[test]
+    fn test_reads_do_not_wait_for_an_open_write() {
+        let path = temp_path("open-write");
+        let db = Database::open(&path).unwrap();
+        let key = derive_key(b"test-password", b"test-salt-16byte");
+        let id = db.create_account(test_account(&key, 1)).unwrap();
+        search_index::index_account_fields(&db, &key, id, &[("email", Some("user1@example.com"))]).unwrap();
+
+        // Hold the writer with an uncommitted write for the whole read
+        let writer = db.get_conn().unwrap();
+        writer
+            .execute_batch("BEGIN IMMEDIATE; DELETE FROM accounts;")
+            .unwrap();
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let reader = {
+            let db = db.clone();
+            std::thread::spawn(move || {
+                let found = db
+                    .search_accounts(AccountSearch {
+                        query_tokens: search_index::query_tokens(&key, "user1", MatchMode::Contains),
+                        group_id: None,
+                        include_subgroups: false,
+                        tag_id: None,
+                        year: None,
+                        pagination: Pagination::new(0, 50),
+                    })
+                    .unwrap();
+                sender.send(found.iter().map(|a| a.id).collect::<Vec<_>>()).unwrap();
+            })
+        };
+
+        // The timeout only bounds a failing run; a read that needed the
+        // writer could never finish while it is held here
+        let found = receiver.recv_timeout(Duration::from_secs(30));
+        writer.execute_batch("ROLLBACK").unwrap();
+        drop(writer);
+        reader.join().unwrap();
+        assert_eq!(found.expect("read blocked behind the open write"), vec![id]);
+
+        drop(db);
+        remove_database(&path);
+    }
+
+    /// Searches keep being served while a single large import transaction
+    /// holds the writer. Run with `cargo test -- --ignored --nocapture` to
+    /// see the timings.
+    #[test]
+    #[ignore = "benchmark"]
+    fn bench_search_during_bulk_import() {
+        const IMPORT_SIZE: usize = 3000;
+
+        let path = temp_path("bench");
+        let db = Database::open(&path).unwrap();
+        let key = derive_key(b"test-password", b"test-salt-16byte");
+        for i in 0..200 {
+            let id = db.create_account(test_account(&key, i)).unwrap();
+            let email = format!("user{}@example.com", i);
+            search_index::index_account_fields(&db, &key, id, &[("email", Some(email.as_str()))]).unwrap();
+        }
+
+        let accounts: Vec<ImportedAccount> = (0..IMPORT_SIZE)
+            .map(|i| ImportedAccount {
+                account: test_account(&key, 10_000 + i),
+                group_path: Vec::new(),
+                tag_names: Vec::new(),
+                search_tokens: Vec::new(),
+            })
+            .collect();
+
+        let importing = Arc::new(AtomicBool::new(true));
+        let importer = {
+            let db = db.clone();
+            let importing = Arc::clone(&importing);
+            std::thread::spawn(move || {
+                let started = Instant::now();
+                db.import_accounts("bench".to_string(), "text".to_string(), None, accounts)
+                    .unwrap();
+                importing.store(false, Ordering::SeqCst);
+                started.elapsed()
+            })
+        };
+
+        let mut latencies = Vec::new();
+        let mut during_import = 0;
+        while importing.load(Ordering::SeqCst) || latencies.is_empty() {
+            let was_importing = importing.load(Ordering::SeqCst);
+            let started = Instant::now();
+            let found = db
+                .search_accounts(AccountSearch {
+                    query_tokens: search_index::query_tokens(&key, "user1", MatchMode::Contains),
+                    group_id: None,
+                    include_subgroups: false,
+                    tag_id: None,
+                    year: None,
+                    pagination: Pagination::new(0, 50),
+                })
+                .unwrap();
+            latencies.push(started.elapsed());
+            if was_importing && importing.load(Ordering::SeqCst) {
+                during_import += 1;
+            }
+            assert!(!found.is_empty());
+        }
+        let import_time = importer.join().unwrap();
+
+        latencies.sort();
+        let max = *latencies.last().unwrap();
+        eprintln!(
+            "bulk import of {} accounts: {:?}; {} searches ({} during import), median {:?}, max {:?}",
+            IMPORT_SIZE,
+            import_time,
+            latencies.len(),
+            during_import,
+            latencies[latencies.len() / 2],
+            max
+        );
+
+        drop(db);
+        remove_database(&path);
+    }
+}
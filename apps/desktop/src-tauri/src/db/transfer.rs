@@ -0,0 +1,498 @@
+//! Whole-vault snapshots for export and import
+//!
+//! A [`VaultSnapshot`] holds every user-owned row (groups, tags, raw
+//! imports, accounts and account-tag links) as stored, i.e. with account
+//! fields and raw import text encrypted under the session key. Encrypting
+//! the snapshot for transport is the caller's job.
+//!
+//! Importing assigns fresh ids to every row and rewrites the references
+//! between them, so a snapshot can be merged into a vault whose ids overlap.
+
+use super::{
+    find_default_group, row_to_group, Account, Database, DbResult, Group, RawImport, Tag, DEFAULT_GROUP_NAME,
+    GROUP_COLUMNS,
+};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::{HashMap, HashSet};
+
+// ============================================================================
+// Types
+// ============================================================================
+
+/// Link between an account and a tag
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct AccountTagLink {
+    pub account_id: i64,
+    pub tag_id: i64,
+}
+
+/// Every user-owned row of the vault
+///
+/// `Account::tags` is left empty; links are in `account_tags`.
+#[derive(Debug, Clone, Default)]
+pub struct VaultSnapshot {
+    pub groups: Vec<Group>,
+    pub tags: Vec<Tag>,
+    pub raw_imports: Vec<RawImport>,
+    pub accounts: Vec<Account>,
+    pub account_tags: Vec<AccountTagLink>,
+}
+
+/// How an imported snapshot is combined with the existing vault
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransferMode {
+    /// Keep existing data; groups and tags are matched by name
+    #[default]
+    Merge,
+    /// Delete all groups, tags, raw imports and accounts first
+    Replace,
+}
+
+/// Ids assigned to the rows of an imported snapshot (snapshot id -> new id)
+#[derive(Debug, Clone, Default)]
+pub struct SnapshotIdMap {
+    pub groups: HashMap<i64, i64>,
+    pub tags: HashMap<i64, i64>,
+    pub raw_imports: HashMap<i64, i64>,
+    pub accounts: HashMap<i64, i64>,
+    /// Groups that matched an existing group by name and parent
+    pub groups_matched: usize,
+    /// Tags that matched an existing tag by name
+    pub tags_matched: usize,
+}
+
+// ============================================================================
+// Database Integration
+// ============================================================================
+
+impl Database {
+    /// Read a consistent snapshot of the whole vault
+    pub fn export_snapshot(&self) -> DbResult<VaultSnapshot> {
+        let mut conn = self.get_conn()?;
+        let tx = conn.transaction()?;
+
+        let snapshot = VaultSnapshot {
+            groups: query_all(&tx, &format!("SELECT {} FROM groups ORDER BY id", GROUP_COLUMNS), row_to_group)?,
+            tags: query_all(&tx, "SELECT id, name, color, created_at FROM tags ORDER BY id", |row| {
+                Ok(Tag {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    color: row.get(2)?,
+                    created_at: row.get(3)?,
+                })
+            })?,
+            raw_imports: query_all(
+                &tx,
+                "SELECT id, raw_text, source_type, source_name, imported_at FROM raw_imports ORDER BY id",
+                |row| {
+                    Ok(RawImport {
+                        id: row.get(0)?,
+                        raw_text: row.get(1)?,
+                        source_type: row.get(2)?,
+                        source_name: row.get(3)?,
+                        imported_at: row.get(4)?,
+                    })
+                },
+            )?,
+            accounts: query_all(
+                &tx,
+                "SELECT id, raw_import_id, email, password, recovery_email, totp_secret,
+                        year, notes, group_id, field_order, created_at, updated_at
+                 FROM accounts ORDER BY id",
+                |row| {
+                    Ok(Account {
+                        id: row.get(0)?,
+                        raw_import_id: row.get(1)?,
+                        email: row.get(2)?,
+                        password: row.get(3)?,
+                        recovery_email: row.get(4)?,
+                        totp_secret: row.get(5)?,
+                        year: row.get(6)?,
+                        notes: row.get(7)?,
+                        group_id: row.get(8)?,
+                        field_order: row.get(9)?,
+                        created_at: row.get(10)?,
+                        updated_at: row.get(11)?,
+                        tags: Vec::new(),
+                    })
+                },
+            )?,
+            account_tags: query_all(
+                &tx,
+                "SELECT account_id, tag_id FROM account_tags ORDER BY account_id, tag_id",
+                |row| {
+                    Ok(AccountTagLink {
+                        account_id: row.get(0)?,
+                        tag_id: row.get(1)?,
+                    })
+                },
+            )?,
+        };
+
+        tx.commit()?;
+        Ok(snapshot)
+    }
+
+    /// Write a snapshot into the vault in a single transaction
+    ///
+    /// Rows get new ids; references to rows missing from the snapshot
+    /// (e.g. a group that was not exported) are cleared. Timestamps are
+    /// kept. `Replace` also clears the undo history, whose entries refer to
+    /// the deleted rows, and recreates the `Default` group if the snapshot
+    /// has none.
+    pub fn import_snapshot(&self, snapshot: &VaultSnapshot, mode: TransferMode) -> DbResult<SnapshotIdMap> {
+        let mut conn = self.get_conn()?;
+        let tx = conn.transaction()?;
+
+        if mode == TransferMode::Replace {
+            tx.execute_batch(
+                "DELETE FROM account_tags;
+                 DELETE FROM account_search_tokens;
+                 DELETE FROM accounts;
+                 DELETE FROM raw_imports;
+                 DELETE FROM tags;
+                 DELETE FROM groups;",
+            )?;
+        }
+        // Imported rows are not recorded in the history
+        Self::clear_history_internal(&tx)?;
+
+        let mut ids = SnapshotIdMap::default();
+
+        // Parents get their id first, so a group is matched by name among
+        // its parent's subgroups (names are only unique per parent). The
+        // snapshot's Default group is the vault's Default group.
+        for group in groups_parents_first(&snapshot.groups) {
+            let parent_id = group.parent_id.and_then(|p| ids.groups.get(&p).copied());
+            let existing = if group.is_default {
+                find_default_group(&tx)?
+            } else {
+                find_group(&tx, parent_id, &group.name)?
+            };
+            let id = match existing {
+                Some(id) => {
+                    ids.groups_matched += 1;
+                    id
+                }
+                None => {
+                    tx.execute(
+                        "INSERT INTO groups (name, color, sort_order, created_at, parent_id, is_default)
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                        params![
+                            group.name,
+                            group.color,
+                            group.sort_order,
+                            group.created_at,
+                            parent_id,
+                            group.is_default
+                        ],
+                    )?;
+                    tx.last_insert_rowid()
+                }
+            };
+            ids.groups.insert(group.id, id);
+        }
+
+        // Files written before the flag existed have a plain "Default" group
+        if find_default_group(&tx)?.is_none() {
+            match find_group(&tx, None, DEFAULT_GROUP_NAME)? {
+                Some(id) => {
+                    tx.execute("UPDATE groups SET is_default = 1 WHERE id = ?1", params![id])?;
+                }
+                None => {
+                    tx.execute(
+                        "INSERT INTO groups (name, color, sort_order, is_default) VALUES ('Default', '#6366f1', 0, 1)",
+                        [],
+                    )?;
+                }
+            }
+        }
+
+        for tag in &snapshot.tags {
+            let id = match find_tag(&tx, &tag.name)? {
+                Some(id) => {
+                    ids.tags_matched += 1;
+                    id
+                }
+                None => {
+                    tx.execute(
+                        "INSERT INTO tags (name, color, created_at) VALUES (?1, ?2, ?3)",
+                        params![tag.name, tag.color, tag.created_at],
+                    )?;
+                    tx.last_insert_rowid()
+                }
+            };
+            ids.tags.insert(tag.id, id);
+        }
+
+        for raw in &snapshot.raw_imports {
+            tx.execute(
+                "INSERT INTO raw_imports (raw_text, source_type, source_name, imported_at) VALUES (?1, ?2, ?3, ?4)",
+                params![raw.raw_text, raw.source_type, raw.source_name, raw.imported_at],
+            )?;
+            ids.raw_imports.insert(raw.id, tx.last_insert_rowid());
+        }
+
+        for account in &snapshot.accounts {
+            let raw_import_id = account.raw_import_id.and_then(|id| ids.raw_imports.get(&id).copied());
+            let group_id = account.group_id.and_then(|id| ids.groups.get(&id).copied());
+
+            tx.execute(
+                "INSERT INTO accounts (
+                    raw_import_id, email, password, recovery_email, totp_secret,
+                    year, notes, group_id, field_order, created_at, updated_at
+                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+                params![
+                    raw_import_id,
+                    account.email,
+                    account.password,
+                    account.recovery_email,
+                    account.totp_secret,
+                    account.year,
+                    account.notes,
+                    group_id,
+                    account.field_order,
+                    account.created_at,
+                    account.updated_at,
+                ],
+            )?;
+            ids.accounts.insert(account.id, tx.last_insert_rowid());
+        }
+
+        for link in &snapshot.account_tags {
+            if let (Some(account_id), Some(tag_id)) =
+                (ids.accounts.get(&link.account_id), ids.tags.get(&link.tag_id))
+            {
+                tx.execute(
+                    "INSERT OR IGNORE INTO account_tags (account_id, tag_id) VALUES (?1, ?2)",
+                    params![account_id, tag_id],
+                )?;
+            }
+        }
+
+        let action = match mode {
+            TransferMode::Merge => "IMPORT_VAULT",
+            TransferMode::Replace => "REPLACE_VAULT",
+        };
+        self.log_operation_internal(
+            &tx,
+            None,
+            action,
+            Some(&format!(
+                "Imported {} accounts, {} groups, {} tags, {} raw imports",
+                ids.accounts.len(),
+                ids.groups.len(),
+                ids.tags.len(),
+                ids.raw_imports.len()
+            )),
+        )?;
+
+        tx.commit()?;
+        Ok(ids)
+    }
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+fn query_all<T>(
+    conn: &Connection,
+    sql: &str,
+    map: impl FnMut(&rusqlite::Row) -> rusqlite::Result<T>,
+) -> DbResult<Vec<T>> {
+    let mut stmt = conn.prepare(sql)?;
+    let rows = stmt.query_map([], map)?.collect::<Result<Vec<_>, _>>()?;
+    Ok(rows)
+}
+
+/// Id of the group named `name` under `parent_id`
+fn find_group(conn: &Connection, parent_id: Option<i64>, name: &str) -> DbResult<Option<i64>> {
+    let id = conn
+        .query_row(
+            "SELECT id FROM groups WHERE parent_id IS ?1 AND name = ?2",
+            params![parent_id, name],
+            |row| row.get(0),
+        )
+        .optional()?;
+    Ok(id)
+}
+
+/// Order snapshot groups so that every parent comes before its subgroups
+///
+/// A parent missing from the snapshot, or one that would close a cycle,
+/// is ignored and the group is imported at the top level.
+fn groups_parents_first(groups: &[Group]) -> Vec<&Group> {
+    let by_id: HashMap<i64, &Group> = groups.iter().map(|g| (g.id, g)).collect();
+    let mut seen = HashSet::new();
+    let mut ordered = Vec::with_capacity(groups.len());
+
+    for group in groups {
+        // Walk up to the first ancestor already ordered, then add the
+        // chain top-down
+        let mut chain = Vec::new();
+        let mut next = Some(group);
+        while let Some(g) = next {
+            if !seen.insert(g.id) {
+                break;
+            }
+            chain.push(g);
+            next = g.parent_id.and_then(|p| by_id.get(&p).copied());
+        }
+        ordered.extend(chain.into_iter().rev());
+    }
+
+    ordered
+}
+
+/// Id of the tag named `name`, ignoring case like the tag name index
+fn find_tag(conn: &Connection, name: &str) -> DbResult<Option<i64>> {
+    let id = conn
+        .query_row("SELECT id FROM tags WHERE name = ?1 COLLATE NOCASE", params![name], |row| {
+            row.get(0)
+        })
+        .optional()?;
+    Ok(id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{CreateAccount, CreateGroup, CreateTag};
+
+    fn create_account(db: &Database, email: &str, group_id: Option<i64>) -> i64 {
+        db.create_account(CreateAccount {
+            raw_import_id: None,
+            email: email.to_string(),
+            password: "password123".to_string(),
+            recovery_email: None,
+            totp_secret: None,
+            year: None,
+            notes: None,
+            group_id,
+            field_order: None,
+        })
+        .unwrap()
+    }
+
+    fn populated_db() -> Database {
+        let db = Database::init_in_memory().unwrap();
+        let group_id = db
+            .create_group(CreateGroup {
+                name: "Work".to_string(),
+                color: "#000000".to_string(),
+                sort_order: 1,
+                parent_id: None,
+            })
+            .unwrap();
+        let tag_id = db
+            .create_tag(CreateTag {
+                name: "vip".to_string(),
+                color: "#ffffff".to_string(),
+            })
+            .unwrap();
+        let account_id = create_account(&db, "a@example.com", Some(group_id));
+        db.add_tag_to_account(account_id, tag_id).unwrap();
+        db
+    }
+
+    #[test]
+    fn test_merge_maps_ids_and_matches_names() {
+        let source = populated_db();
+        let snapshot = source.export_snapshot().unwrap();
+        assert_eq!(snapshot.account_tags.len(), 1);
+
+        // The target already has a "Work" group and a "VIP" tag with other ids
+        let target = Database::init_in_memory().unwrap();
+        create_account(&target, "existing@example.com", None);
+        target
+            .create_tag(CreateTag { name: "VIP".to_string(), color: "#000000".to_string() })
+            .unwrap();
+        let work_id = target
+            .create_group(CreateGroup {
+                name: "Work".to_string(),
+                color: "#111111".to_string(),
+                sort_order: 0,
+                parent_id: None,
+            })
+            .unwrap();
+
+        let ids = target.import_snapshot(&snapshot, TransferMode::Merge).unwrap();
+
+        assert_eq!(ids.groups_matched, 2); // Default and Work
+        assert_eq!(ids.tags_matched, 1);
+        assert_eq!(target.get_accounts_count().unwrap(), 2);
+
+        let new_id = ids.accounts[&snapshot.accounts[0].id];
+        let account = target.get_account(new_id).unwrap();
+        assert_eq!(account.group_id, Some(work_id));
+        assert_eq!(account.tags.len(), 1);
+        assert_eq!(account.tags[0].name, "VIP");
+    }
+
+    #[test]
+    fn test_replace_discards_existing_rows() {
+        let snapshot = populated_db().export_snapshot().unwrap();
+
+        let target = Database::init_in_memory().unwrap();
+        create_account(&target, "old@example.com", None);
+        target.push_undo("TEST", "{}").unwrap();
+
+        target.import_snapshot(&snapshot, TransferMode::Replace).unwrap();
+
+        assert_eq!(target.get_accounts_count().unwrap(), 1);
+        assert_eq!(target.get_groups().unwrap().len(), 2);
+        assert_eq!(target.get_undo_stack(10).unwrap().len(), 0);
+    }
+
+    #[test]
+    fn test_import_keeps_group_nesting() {
+        let source = Database::init_in_memory().unwrap();
+        let group = |db: &Database, name: &str, parent_id: Option<i64>| {
+            db.create_group(CreateGroup {
+                name: name.to_string(),
+                color: "#000000".to_string(),
+                sort_order: 0,
+                parent_id,
+            })
+            .unwrap()
+        };
+        let work = group(&source, "Work", None);
+        let servers = group(&source, "Servers", Some(work));
+        group(&source, "Prod", Some(servers));
+        group(&source, "Prod", Some(work));
+        let snapshot = source.export_snapshot().unwrap();
+
+        // A top-level group is not matched by a nested one of the same name
+        let target = Database::init_in_memory().unwrap();
+        let top_level_servers = group(&target, "Servers", None);
+        let ids = target.import_snapshot(&snapshot, TransferMode::Merge).unwrap();
+        assert_eq!(ids.groups_matched, 1); // Default
+
+        let groups = target.get_groups().unwrap();
+        let find = |name: &str, parent_id: Option<i64>| {
+            groups
+                .iter()
+                .find(|g| g.name == name && g.parent_id == parent_id)
+                .map(|g| g.id)
+        };
+        let work = find("Work", None).unwrap();
+        let servers = find("Servers", Some(work)).unwrap();
+        assert_ne!(servers, top_level_servers);
+        assert!(find("Prod", Some(servers)).is_some());
+        assert!(find("Prod", Some(work)).is_some());
+        assert_eq!(groups.len(), 6);
+    }
+
+    #[test]
+    fn test_replace_keeps_a_default_group() {
+        let target = Database::init_in_memory().unwrap();
+        target.import_snapshot(&VaultSnapshot::default(), TransferMode::Replace).unwrap();
+
+        let groups = target.get_groups().unwrap();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].name, "Default");
+        assert!(groups[0].is_default);
+    }
+}
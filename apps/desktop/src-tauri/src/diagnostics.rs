@@ -0,0 +1,49 @@
+//! Database diagnostics for GManager Desktop
+//!
+//! Exposes the checks in [`crate::db::diagnostics`] to the frontend. Running
+//! them needs the session key, since every encrypted field is test-decrypted.
+//! A repair only moves rows into the `quarantine` table, so it is safe to
+//! offer from a settings screen; taking a backup first is still advised.
+
+use crate::auth::SessionManager;
+use crate::db::diagnostics::{DiagnosticsReport, QuarantinedRow};
+use crate::db::Database;
+
+// ============================================================================
+// Tauri Commands
+// ============================================================================
+
+/// Check the database for corruption, broken references and fields that do
+/// not decrypt
+///
+/// # Arguments
+/// * `quarantine` - Move broken rows into quarantine and clear dangling
+///   references (default false: report only)
+///
+/// # Returns
+/// The problems found, as they were before any repair
+#[tauri::command]
+pub fn run_diagnostics_command(
+    db: tauri::State<Database>,
+    session_manager: tauri::State<SessionManager>,
+    quarantine: Option<bool>,
+) -> Result<DiagnosticsReport, String> {
+    let key = session_manager.get_session_key()
+        .map_err(|e| e.to_string())?;
+
+    db.run_diagnostics(&key, quarantine.unwrap_or(false))
+        .map_err(|e| e.to_string())
+}
+
+/// List rows moved into quarantine by earlier repairs, newest first
+#[tauri::command]
+pub fn get_quarantine_command(
+    db: tauri::State<Database>,
+    session_manager: tauri::State<SessionManager>,
+) -> Result<Vec<QuarantinedRow>, String> {
+    session_manager.get_session_key()
+        .map_err(|e| e.to_string())?;
+
+    db.get_quarantined_rows()
+        .map_err(|e| e.to_string())
+}
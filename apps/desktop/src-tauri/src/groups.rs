@@ -6,8 +6,22 @@
 //!
 //! Groups are used to organize accounts into categories.
 //! Each account can belong to at most one group.
+//!
+//! Groups can be nested: `parent_id` points at the containing group. A
+//! group can never be moved below itself or one of its own subgroups.
+//!
+//! Siblings are shown by `sort_order`. Values are kept
+//! [`GROUP_SORT_GAP`](crate::db::GROUP_SORT_GAP) apart so that dragging a
+//! group to a new position usually rewrites only that group.
+//!
+//! Deleting a group takes a [`DeleteGroupStrategy`] for its accounts, so
+//! they are never silently left without a group. The `Default` group and
+//! the last remaining group cannot be deleted.
 
-use crate::db::{CreateGroup, Database, DbError, Group};
+use crate::auth::SessionManager;
+use crate::crypto::SecretKey;
+use crate::db::{CreateGroup, Database, DbError, Group, GroupDeletion, GroupMembers};
+use crate::history::{HistoryError, MutationKind, Target, Tracker};
 
 // ============================================================================
 // Type Definitions for Frontend/Backend Communication
@@ -21,6 +35,11 @@ pub struct GroupDto {
     pub color: String,
     pub sort_order: i32,
     pub created_at: String,
+    /// Containing group (None for top-level groups)
+    pub parent_id: Option<String>,
+    /// Whether this is the Default group, which cannot be renamed, moved or
+    /// deleted
+    pub is_default: bool,
 }
 
 /// Group creation payload from frontend
@@ -31,6 +50,9 @@ pub struct CreateGroupPayload {
     pub color: Option<String>,
     #[serde(default)]
     pub sort_order: Option<i32>,
+    /// Group to create the new group in (top level when absent)
+    #[serde(default)]
+    pub parent_id: Option<String>,
 }
 
 /// Group update payload from frontend
@@ -42,6 +64,22 @@ pub struct UpdateGroupPayload {
     pub sort_order: Option<i32>,
 }
 
+/// What `delete_group_command` does with the group's accounts
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+#[serde(tag = "strategy", rename_all = "snake_case")]
+pub enum DeleteGroupStrategy {
+    /// Move the accounts to another group
+    Reassign { target_id: String },
+    /// Move the accounts to the Default group
+    #[default]
+    MoveToDefault,
+    /// Delete the accounts too; `confirm` must be true
+    DeleteAccounts {
+        #[serde(default)]
+        confirm: bool,
+    },
+}
+
 // ============================================================================
 // Error Types
 // ============================================================================
@@ -53,6 +91,10 @@ pub enum GroupError {
     #[error("Database error: {0}")]
     Database(#[from] DbError),
 
+    /// Undo history error
+    #[error("History error: {0}")]
+    History(#[from] HistoryError),
+
     /// Invalid input
     #[error("Invalid input: {0}")]
     InvalidInput(String),
@@ -60,6 +102,24 @@ pub enum GroupError {
     /// Group not found
     #[error("Group not found: {0}")]
     NotFound(String),
+
+    /// Another group under the same parent already has the name
+    #[error("{0} already exists")]
+    Duplicate(String),
+
+    /// A destructive operation was requested without confirmation
+    #[error("Confirmation required: {0}")]
+    ConfirmationRequired(String),
+}
+
+/// Surface missing groups, name clashes and refused changes as group errors
+fn group_db_error(e: DbError) -> GroupError {
+    match e {
+        DbError::NotFound(what) => GroupError::NotFound(what),
+        DbError::InvalidInput(msg) => GroupError::InvalidInput(msg),
+        DbError::Duplicate(what) => GroupError::Duplicate(what),
+        other => GroupError::Database(other),
+    }
 }
 
 /// Result type for group operations
@@ -77,9 +137,20 @@ fn db_to_frontend_group(db_group: Group) -> GroupDto {
         color: db_group.color,
         sort_order: db_group.sort_order,
         created_at: db_group.created_at,
+        parent_id: db_group.parent_id.map(|id| id.to_string()),
+        is_default: db_group.is_default,
     }
 }
 
+/// Parse an optional group ID sent by the frontend
+fn parse_optional_group_id(id: Option<&str>) -> GroupResult<Option<i64>> {
+    id.map(|id| {
+        id.parse::<i64>()
+            .map_err(|_| GroupError::InvalidInput("Invalid group ID".to_string()))
+    })
+    .transpose()
+}
+
 /// Convert frontend creation payload to database creation payload
 fn frontend_to_db_create(payload: CreateGroupPayload) -> GroupResult<CreateGroup> {
     // Validate required fields
@@ -100,6 +171,7 @@ fn frontend_to_db_create(payload: CreateGroupPayload) -> GroupResult<CreateGroup
         name: name.to_string(),
         color,
         sort_order: payload.sort_order.unwrap_or(0),
+        parent_id: parse_optional_group_id(payload.parent_id.as_deref())?,
     })
 }
 
@@ -128,9 +200,98 @@ fn frontend_to_db_update(payload: UpdateGroupPayload) -> GroupResult<(i64, Creat
         name: name.unwrap_or_default(),
         color: payload.color.unwrap_or_default(),
         sort_order: payload.sort_order.unwrap_or(0),
+        parent_id: None,
     }))
 }
 
+// ============================================================================
+// Group Operations
+// ============================================================================
+
+/// Delete a group and record it in the undo history
+///
+/// The group's accounts and direct subgroups are tracked too, since
+/// deleting the group moves or deletes them. The group comes after them so
+/// that an undo restores it first.
+pub fn delete_group(
+    db: &Database,
+    key: &SecretKey,
+    group_id: i64,
+    strategy: &DeleteGroupStrategy,
+) -> GroupResult<GroupDeletion> {
+    let members = match strategy {
+        DeleteGroupStrategy::Reassign { target_id } => GroupMembers::MoveTo(
+            target_id.parse::<i64>()
+                .map_err(|_| GroupError::InvalidInput("Invalid target group ID".to_string()))?,
+        ),
+        DeleteGroupStrategy::MoveToDefault => GroupMembers::MoveToDefault,
+        DeleteGroupStrategy::DeleteAccounts { confirm: false } => {
+            return Err(GroupError::ConfirmationRequired(
+                "set confirm to delete the group's accounts".to_string(),
+            ));
+        }
+        DeleteGroupStrategy::DeleteAccounts { confirm: true } => GroupMembers::Delete,
+    };
+
+    let mut targets: Vec<Target> = db.get_child_group_ids(group_id)?
+        .into_iter()
+        .map(Target::Group)
+        .collect();
+    targets.extend(db.get_group_account_ids(group_id)?.into_iter().map(Target::Account));
+    targets.push(Target::Group(group_id));
+
+    let tracker = Tracker::capture(db, targets)?;
+
+    let deletion = db.delete_group_with_members(group_id, members)
+        .map_err(group_db_error)?;
+
+    tracker.record(db, key, MutationKind::DeleteGroup)?;
+    Ok(deletion)
+}
+
+/// Move a group under another group (or to the top level) and record it in
+/// the undo history
+pub fn move_group(db: &Database, key: &SecretKey, group_id: i64, parent_id: Option<i64>) -> GroupResult<()> {
+    let tracker = Tracker::capture(db, vec![Target::Group(group_id)])?;
+
+    db.move_group(group_id, parent_id).map_err(group_db_error)?;
+
+    tracker.record(db, key, MutationKind::MoveGroup)?;
+    Ok(())
+}
+
+/// Give groups the order of `group_ids` in one transaction, recording the
+/// change in the undo history
+pub fn reorder_groups(db: &Database, key: &SecretKey, group_ids: &[i64]) -> GroupResult<()> {
+    let targets = group_ids.iter().map(|&id| Target::Group(id)).collect();
+    let tracker = Tracker::capture(db, targets)?;
+
+    db.reorder_groups(group_ids).map_err(group_db_error)?;
+
+    tracker.record(db, key, MutationKind::ReorderGroups)?;
+    Ok(())
+}
+
+/// Move a group before `before_id` among its siblings (last when None),
+/// recording the change in the undo history
+pub fn place_group(db: &Database, key: &SecretKey, group_id: i64, before_id: Option<i64>) -> GroupResult<()> {
+    let parent_id = db.get_group(group_id).map_err(group_db_error)?.parent_id;
+
+    // Siblings are renumbered when there is no room left between two of them
+    let targets = db
+        .get_groups()?
+        .into_iter()
+        .filter(|group| group.parent_id == parent_id)
+        .map(|group| Target::Group(group.id))
+        .collect();
+    let tracker = Tracker::capture(db, targets)?;
+
+    if db.place_group(group_id, before_id).map_err(group_db_error)? > 0 {
+        tracker.record(db, key, MutationKind::ReorderGroups)?;
+    }
+    Ok(())
+}
+
 // ============================================================================
 // Tauri Commands
 // ============================================================================
@@ -173,12 +334,20 @@ pub fn get_group_command(
 #[tauri::command]
 pub fn create_group_command(
     db: tauri::State<Database>,
+    session_manager: tauri::State<SessionManager>,
     group: CreateGroupPayload,
 ) -> Result<String, String> {
+    let key = session_manager.get_session_key()
+        .map_err(|e| e.to_string())?;
+
     let create_group = frontend_to_db_create(group)
         .map_err(|e| e.to_string())?;
 
     let id = db.create_group(create_group)
+        .map_err(|e| group_db_error(e).to_string())?;
+
+    Tracker::created(Target::Group(id))
+        .record(&db, &key, MutationKind::CreateGroup)
         .map_err(|e| e.to_string())?;
 
     Ok(id.to_string())
@@ -190,8 +359,12 @@ pub fn create_group_command(
 #[tauri::command]
 pub fn update_group_command(
     db: tauri::State<Database>,
+    session_manager: tauri::State<SessionManager>,
     group: UpdateGroupPayload,
 ) -> Result<(), String> {
+    let key = session_manager.get_session_key()
+        .map_err(|e| e.to_string())?;
+
     // Extract fields before moving group
     let name = group.name.clone();
     let color = group.color.clone();
@@ -209,9 +382,16 @@ pub fn update_group_command(
         name: name.unwrap_or_else(|| existing.name),
         color: color.unwrap_or_else(|| existing.color),
         sort_order: sort_order.unwrap_or(existing.sort_order),
+        parent_id: existing.parent_id,
     };
 
+    let tracker = Tracker::capture(&db, vec![Target::Group(group_id)])
+        .map_err(|e| e.to_string())?;
+
     db.update_group(group_id, update_payload)
+        .map_err(|e| group_db_error(e).to_string())?;
+
+    tracker.record(&db, &key, MutationKind::UpdateGroup)
         .map_err(|e| e.to_string())?;
 
     Ok(())
@@ -219,32 +399,121 @@ pub fn update_group_command(
 
 /// Delete a group by ID
 ///
-/// Note: Accounts associated with this group will have their group_id set to NULL.
+/// # Arguments
+/// * `id` - Group to delete
+/// * `strategy` - What to do with the group's accounts (default: move them
+///   to the Default group)
+///
+/// # Returns
+/// Where the accounts went and how many subgroups moved up a level
 #[tauri::command]
 pub fn delete_group_command(
     db: tauri::State<Database>,
+    session_manager: tauri::State<SessionManager>,
     id: String,
+    strategy: Option<DeleteGroupStrategy>,
+) -> Result<GroupDeletion, String> {
+    let key = session_manager.get_session_key()
+        .map_err(|e| e.to_string())?;
+
+    let group_id = id.parse::<i64>()
+        .map_err(|_| "Invalid group ID".to_string())?;
+
+    delete_group(&db, &key, group_id, &strategy.unwrap_or_default())
+        .map_err(|e| e.to_string())
+}
+
+/// Move a group into another group
+///
+/// # Arguments
+/// * `id` - Group to move (its subgroups move with it)
+/// * `parent_id` - New containing group, or None for the top level
+#[tauri::command]
+pub fn move_group_command(
+    db: tauri::State<Database>,
+    session_manager: tauri::State<SessionManager>,
+    id: String,
+    parent_id: Option<String>,
 ) -> Result<(), String> {
+    let key = session_manager.get_session_key()
+        .map_err(|e| e.to_string())?;
+
     let group_id = id.parse::<i64>()
         .map_err(|_| "Invalid group ID".to_string())?;
+    let parent_id = parse_optional_group_id(parent_id.as_deref())
+        .map_err(|e| e.to_string())?;
+
+    move_group(&db, &key, group_id, parent_id)
+        .map_err(|e| e.to_string())
+}
 
-    db.delete_group(group_id)
+/// Set the order of groups, e.g. after a drag and drop
+///
+/// # Arguments
+/// * `ids` - Groups in their new order (usually the siblings of one parent);
+///   groups not listed keep their position
+#[tauri::command]
+pub fn reorder_groups_command(
+    db: tauri::State<Database>,
+    session_manager: tauri::State<SessionManager>,
+    ids: Vec<String>,
+) -> Result<(), String> {
+    let key = session_manager.get_session_key()
         .map_err(|e| e.to_string())?;
 
-    Ok(())
+    let group_ids = ids
+        .iter()
+        .map(|id| id.parse::<i64>().map_err(|_| "Invalid group ID".to_string()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    reorder_groups(&db, &key, &group_ids)
+        .map_err(|e| e.to_string())
+}
+
+/// Move a group to a new position among its siblings
+///
+/// Usually only the moved group is rewritten.
+///
+/// # Arguments
+/// * `id` - Group to move
+/// * `before_id` - Sibling to place it before, or None to make it the last
+#[tauri::command]
+pub fn place_group_command(
+    db: tauri::State<Database>,
+    session_manager: tauri::State<SessionManager>,
+    id: String,
+    before_id: Option<String>,
+) -> Result<(), String> {
+    let key = session_manager.get_session_key()
+        .map_err(|e| e.to_string())?;
+
+    let group_id = id.parse::<i64>()
+        .map_err(|_| "Invalid group ID".to_string())?;
+    let before_id = parse_optional_group_id(before_id.as_deref())
+        .map_err(|e| e.to_string())?;
+
+    place_group(&db, &key, group_id, before_id)
+        .map_err(|e| e.to_string())
 }
 
 /// Get the number of accounts in a group
+///
+/// With `include_subgroups`, accounts in nested groups are counted too.
 #[tauri::command]
 pub fn get_group_accounts_count_command(
     db: tauri::State<Database>,
     id: String,
+    include_subgroups: Option<bool>,
 ) -> Result<i64, String> {
     let group_id = id.parse::<i64>()
         .map_err(|_| "Invalid group ID".to_string())?;
 
-    db.get_group_accounts_count(group_id)
-        .map_err(|e| e.to_string())
+    if include_subgroups.unwrap_or(false) {
+        db.get_group_subtree_accounts_count(group_id)
+    } else {
+        db.get_group_accounts_count(group_id)
+    }
+    .map_err(|e| e.to_string())
 }
 
 // ============================================================================
@@ -261,6 +530,7 @@ mod tests {
             name: "Work".to_string(),
             color: Some("#ff0000".to_string()),
             sort_order: Some(1),
+            parent_id: Some("7".to_string()),
         };
 
         let result = frontend_to_db_create(payload).unwrap();
@@ -268,6 +538,7 @@ mod tests {
         assert_eq!(result.name, "Work");
         assert_eq!(result.color, "#ff0000");
         assert_eq!(result.sort_order, 1);
+        assert_eq!(result.parent_id, Some(7));
     }
 
     #[test]
@@ -276,6 +547,7 @@ mod tests {
             name: "Personal".to_string(),
             color: None,
             sort_order: None,
+            parent_id: None,
         };
 
         let result = frontend_to_db_create(payload).unwrap();
@@ -291,6 +563,7 @@ mod tests {
             name: "".to_string(),
             color: None,
             sort_order: None,
+            parent_id: None,
         };
 
         let result = frontend_to_db_create(payload);
@@ -303,6 +576,7 @@ mod tests {
             name: "   ".to_string(),
             color: None,
             sort_order: None,
+            parent_id: None,
         };
 
         let result = frontend_to_db_create(payload);
@@ -315,12 +589,71 @@ mod tests {
             name: "Test".to_string(),
             color: Some("invalid".to_string()),
             sort_order: None,
+            parent_id: None,
         };
 
         let result = frontend_to_db_create(payload);
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_delete_strategy_payloads() {
+        let parse = |json: &str| serde_json::from_str::<DeleteGroupStrategy>(json).unwrap();
+
+        assert!(matches!(
+            parse(r#"{"strategy":"reassign","target_id":"4"}"#),
+            DeleteGroupStrategy::Reassign { target_id } if target_id == "4"
+        ));
+        assert!(matches!(parse(r#"{"strategy":"move_to_default"}"#), DeleteGroupStrategy::MoveToDefault));
+        assert!(matches!(
+            parse(r#"{"strategy":"delete_accounts"}"#),
+            DeleteGroupStrategy::DeleteAccounts { confirm: false }
+        ));
+    }
+
+    #[test]
+    fn test_delete_accounts_requires_confirmation() {
+        let db = Database::init_in_memory().unwrap();
+        let key = crate::crypto::derive_key(b"test-password", b"test-salt-16byte");
+        let group_id = db.create_group(CreateGroup {
+            name: "Work".to_string(),
+            color: "#000000".to_string(),
+            sort_order: 0,
+            parent_id: None,
+        })
+        .unwrap();
+
+        let result = delete_group(&db, &key, group_id, &DeleteGroupStrategy::DeleteAccounts { confirm: false });
+        assert!(matches!(result, Err(GroupError::ConfirmationRequired(_))));
+        assert!(db.get_group(group_id).is_ok());
+
+        let default_id = db.get_groups().unwrap().into_iter().find(|g| g.is_default).unwrap().id;
+        let result = delete_group(&db, &key, default_id, &DeleteGroupStrategy::MoveToDefault);
+        assert!(matches!(result, Err(GroupError::InvalidInput(_))));
+    }
+
+    #[test]
+    fn test_duplicate_name_is_a_group_error() {
+        let db = Database::init_in_memory().unwrap();
+        let key = crate::crypto::derive_key(b"test-password", b"test-salt-16byte");
+        let group = |name: &str, parent_id: Option<i64>| {
+            db.create_group(CreateGroup {
+                name: name.to_string(),
+                color: "#000000".to_string(),
+                sort_order: 0,
+                parent_id,
+            })
+            .unwrap()
+        };
+        let parent = group("Personal", None);
+        group("Work", None);
+        let nested = group("Work", Some(parent));
+
+        let err = move_group(&db, &key, nested, None).unwrap_err();
+        assert!(matches!(err, GroupError::Duplicate(_)));
+        assert_eq!(err.to_string(), "Group \"Work\" already exists");
+    }
+
     #[test]
     fn test_db_to_frontend_conversion() {
         let db_group = Group {
@@ -329,6 +662,8 @@ mod tests {
             color: "#3b82f6".to_string(),
             sort_order: 1,
             created_at: "2024-01-01T00:00:00Z".to_string(),
+            parent_id: Some(4),
+            is_default: false,
         };
 
         let result = db_to_frontend_group(db_group);
@@ -338,5 +673,6 @@ mod tests {
         assert_eq!(result.color, "#3b82f6");
         assert_eq!(result.sort_order, 1);
         assert_eq!(result.created_at, "2024-01-01T00:00:00Z");
+        assert_eq!(result.parent_id.as_deref(), Some("4"));
     }
 }
@@ -0,0 +1,839 @@
+//! Undo/redo history for GManager Desktop
+//!
+//! Mutating commands record what they changed as a list of `RowChange`s:
+//! the stored state of every touched row before and after the mutation.
+//! Undo restores the `before` states (last change first) and redo the
+//! `after` states (first change first), each in a single transaction.
+//!
+//! # Storage
+//!
+//! Entries live in `undo_stack`. Entries with `undone = 0` form the undo
+//! side (newest first) and entries with `undone = 1` the redo side (most
+//! recently undone first). Recording a new mutation discards the redo side,
+//! and only the newest [`MAX_HISTORY_ENTRIES`] entries are kept.
+//!
+//! The change list is serialized to JSON and encrypted with the session
+//! key like any account field: besides the (already encrypted) account
+//! columns it contains group and tag names.
+//!
+//! An entry is recorded right after its mutation commits, so a crash in
+//! between can lose the history entry but never the mutation itself.
+//!
+//! Replaying an entry does not check whether its rows changed since. Bulk
+//! writes that are not recorded (imports, database repairs) therefore
+//! clear the history.
+
+use crate::auth::SessionManager;
+use crate::crypto::{decrypt_field, encrypt_field, CryptoError, SecretKey};
+use crate::db::{Database, DbError, ReplayDirection, RowChange, RowState};
+use crate::search_index::{self, SearchIndexError};
+
+// ============================================================================
+// Constants
+// ============================================================================
+
+/// Number of entries kept in the history
+pub const MAX_HISTORY_ENTRIES: i64 = 100;
+
+// ============================================================================
+// Type Definitions
+// ============================================================================
+
+/// Kind of mutation an entry undoes (stored in `undo_stack.operation_type`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum MutationKind {
+    CreateAccount,
+    UpdateAccount,
+    DeleteAccount,
+    BatchDeleteAccounts,
+    BatchUpdateAccounts,
+    CreateGroup,
+    UpdateGroup,
+    DeleteGroup,
+    MoveGroup,
+    ReorderGroups,
+    CreateTag,
+    UpdateTag,
+    DeleteTag,
+    AddTagToAccount,
+    RemoveTagFromAccount,
+    SetAccountTags,
+    BatchUpdateAccountTags,
+    MergeTags,
+}
+
+/// A row whose state is tracked across a mutation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    Account(i64),
+    Group(i64),
+    Tag(i64),
+    /// Association between an account and a tag
+    AccountTag { account_id: i64, tag_id: i64 },
+}
+
+/// Encrypted payload of a history entry
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct HistoryPayload {
+    kind: MutationKind,
+    changes: Vec<RowChange>,
+}
+
+/// Result of an undo or redo, sent to frontend
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct HistoryStep {
+    pub kind: MutationKind,
+    /// Number of rows that were restored
+    pub changed_rows: usize,
+}
+
+/// Current state of the history, sent to frontend
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct HistoryStatus {
+    pub undo_count: i64,
+    pub redo_count: i64,
+    /// Mutation the next undo reverts
+    pub next_undo: Option<MutationKind>,
+    /// Mutation the next redo re-applies
+    pub next_redo: Option<MutationKind>,
+}
+
+// ============================================================================
+// Error Types
+// ============================================================================
+
+/// History error type
+#[derive(Debug, thiserror::Error)]
+pub enum HistoryError {
+    /// Database error
+    #[error("Database error: {0}")]
+    Database(#[from] DbError),
+
+    /// Cryptographic error
+    #[error("Crypto error: {0}")]
+    Crypto(#[from] CryptoError),
+
+    /// Search index error
+    #[error("Search index error: {0}")]
+    SearchIndex(#[from] SearchIndexError),
+
+    /// History entry could not be (de)serialized
+    #[error("Invalid history entry: {0}")]
+    InvalidEntry(String),
+}
+
+/// Result type for history operations
+pub type HistoryResult<T> = std::result::Result<T, HistoryError>;
+
+// ============================================================================
+// Recording
+// ============================================================================
+
+impl MutationKind {
+    /// Value stored in `undo_stack.operation_type`
+    fn as_operation_type(self) -> String {
+        serde_json::to_value(self)
+            .ok()
+            .and_then(|v| v.as_str().map(str::to_string))
+            .unwrap_or_default()
+    }
+
+    fn from_operation_type(value: &str) -> Option<Self> {
+        serde_json::from_value(serde_json::Value::String(value.to_string())).ok()
+    }
+}
+
+/// Snapshot of tracked rows taken before a mutation
+///
+/// ```ignore
+/// let tracker = Tracker::capture(&db, vec![Target::Account(id)])?;
+/// db.delete_account(id)?;
+/// tracker.record(&db, &key, MutationKind::DeleteAccount)?;
+/// ```
+///
+/// Targets are replayed in order on redo and in reverse order on undo, so
+/// rows that others depend on (groups, tags) should be listed last.
+pub struct Tracker {
+    targets: Vec<Target>,
+    before: Vec<Option<RowState>>,
+}
+
+impl Tracker {
+    /// Capture the current state of `targets`
+    pub fn capture(db: &Database, targets: Vec<Target>) -> HistoryResult<Self> {
+        let before = targets
+            .iter()
+            .map(|target| capture_state(db, *target))
+            .collect::<HistoryResult<Vec<_>>>()?;
+
+        Ok(Tracker { targets, before })
+    }
+
+    /// Track a row that was just created (it did not exist before)
+    pub fn created(target: Target) -> Self {
+        Tracker {
+            targets: vec![target],
+            before: vec![None],
+        }
+    }
+
+    /// Capture the new state of the targets and record what changed
+    ///
+    /// Rows whose state did not change are left out; nothing is recorded
+    /// when no row changed.
+    pub fn record(self, db: &Database, key: &SecretKey, kind: MutationKind) -> HistoryResult<()> {
+        let mut changes = Vec::new();
+        for (target, before) in self.targets.into_iter().zip(self.before) {
+            let after = capture_state(db, target)?;
+            if before != after {
+                changes.push(RowChange { before, after });
+            }
+        }
+
+        if changes.is_empty() {
+            return Ok(());
+        }
+
+        let payload = serde_json::to_string(&HistoryPayload { kind, changes })
+            .map_err(|e| HistoryError::InvalidEntry(e.to_string()))?;
+        let encrypted = encrypt_field(&payload, key)?;
+
+        db.push_history_entry(&kind.as_operation_type(), &encrypted, MAX_HISTORY_ENTRIES)?;
+        Ok(())
+    }
+}
+
+/// Read the stored state of a target (`None` if the row does not exist)
+fn capture_state(db: &Database, target: Target) -> HistoryResult<Option<RowState>> {
+    let state = match target {
+        Target::Account(id) => not_found_as_none(db.get_account(id))?.map(RowState::Account),
+        Target::Group(id) => not_found_as_none(db.get_group(id))?.map(RowState::Group),
+        Target::Tag(id) => not_found_as_none(db.get_tag(id))?.map(RowState::Tag),
+        Target::AccountTag { account_id, tag_id } => db
+            .account_has_tag(account_id, tag_id)?
+            .then_some(RowState::AccountTag { account_id, tag_id }),
+    };
+
+    Ok(state)
+}
+
+fn not_found_as_none<T>(result: Result<T, DbError>) -> HistoryResult<Option<T>> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(DbError::NotFound(_)) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+// ============================================================================
+// Replay
+// ============================================================================
+
+/// Undo the most recent mutation (`None` if there is nothing to undo)
+pub fn undo(db: &Database, key: &SecretKey) -> HistoryResult<Option<HistoryStep>> {
+    match db.next_undo_entry()? {
+        Some(entry) => replay(db, key, entry, ReplayDirection::Undo).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Redo the most recently undone mutation (`None` if there is nothing to redo)
+pub fn redo(db: &Database, key: &SecretKey) -> HistoryResult<Option<HistoryStep>> {
+    match db.next_redo_entry()? {
+        Some(entry) => replay(db, key, entry, ReplayDirection::Redo).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Report how many steps can be undone and redone
+pub fn status(db: &Database) -> HistoryResult<HistoryStatus> {
+    let (undo_count, redo_count) = db.history_counts()?;
+
+    Ok(HistoryStatus {
+        undo_count,
+        redo_count,
+        next_undo: db
+            .next_undo_entry()?
+            .and_then(|e| MutationKind::from_operation_type(&e.operation_type)),
+        next_redo: db
+            .next_redo_entry()?
+            .and_then(|e| MutationKind::from_operation_type(&e.operation_type)),
+    })
+}
+
+fn replay(
+    db: &Database,
+    key: &SecretKey,
+    entry: crate::db::UndoEntry,
+    direction: ReplayDirection,
+) -> HistoryResult<HistoryStep> {
+    let json = decrypt_field(&entry.undo_data, key)?;
+    let payload: HistoryPayload = serde_json::from_str(&json)
+        .map_err(|e| HistoryError::InvalidEntry(e.to_string()))?;
+
+    db.replay_history_entry(&entry, &payload.changes, direction)?;
+
+    // Restored accounts need their blind index tokens back
+    for change in &payload.changes {
+        let restored = match direction {
+            ReplayDirection::Undo => &change.before,
+            ReplayDirection::Redo => &change.after,
+        };
+        if let Some(RowState::Account(account)) = restored {
+            search_index::index_stored_account(db, key, account.id)?;
+        }
+    }
+
+    Ok(HistoryStep {
+        kind: payload.kind,
+        changed_rows: payload.changes.len(),
+    })
+}
+
+// ============================================================================
+// Tauri Commands
+// ============================================================================
+
+/// Undo the most recent mutation
+///
+/// Returns `None` when there is nothing to undo.
+#[tauri::command]
+pub fn undo_command(
+    db: tauri::State<Database>,
+    session_manager: tauri::State<SessionManager>,
+) -> Result<Option<HistoryStep>, String> {
+    let key = session_manager.get_session_key()
+        .map_err(|e| e.to_string())?;
+
+    undo(&db, &key)
+        .map_err(|e| e.to_string())
+}
+
+/// Redo the most recently undone mutation
+///
+/// Returns `None` when there is nothing to redo.
+#[tauri::command]
+pub fn redo_command(
+    db: tauri::State<Database>,
+    session_manager: tauri::State<SessionManager>,
+) -> Result<Option<HistoryStep>, String> {
+    let key = session_manager.get_session_key()
+        .map_err(|e| e.to_string())?;
+
+    redo(&db, &key)
+        .map_err(|e| e.to_string())
+}
+
+/// Get the number of undo/redo steps and the next mutation on each side
+#[tauri::command]
+pub fn get_history_status_command(
+    db: tauri::State<Database>,
+) -> Result<HistoryStatus, String> {
+    status(&db)
+        .map_err(|e| e.to_string())
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::accounts::{self, BatchDeleteRequest, BatchUpdateRequest, CreateAccountPayload, UpdateAccountPayload};
+    use crate::crypto::derive_key;
+    use crate::db::{BatchMode, CreateAccount, CreateGroup, CreateTag, GroupMembers, ImportedAccount, TagChange, UpdateAccount};
+    use crate::{groups, tags};
+
+    fn get_test_db() -> Database {
+        Database::init_in_memory().expect("Failed to create test database")
+    }
+
+    fn get_test_key() -> SecretKey {
+        derive_key(b"test-password", b"test-salt-16byte")
+    }
+
+    /// Create an indexed account the way `create_account_command` does
+    fn create_account(db: &Database, key: &SecretKey, email: &str) -> i64 {
+        let payload = CreateAccountPayload {
+            raw_import_id: None,
+            email: email.to_string(),
+            password: "secret123".to_string(),
+            recovery_email: None,
+            totp_secret: None,
+            year: None,
+            notes: None,
+            group_id: None,
+            field_order: None,
+        };
+
+        let create = accounts::frontend_to_db_create(payload, key).unwrap();
+        let id = db.create_account(create).unwrap();
+        search_index::index_stored_account(db, key, id).unwrap();
+        id
+    }
+
+    fn create_tag(db: &Database, name: &str) -> i64 {
+        db.create_tag(CreateTag { name: name.to_string(), color: "#10b981".to_string() }).unwrap()
+    }
+
+    fn create_group(db: &Database, name: &str) -> i64 {
+        db.create_group(CreateGroup { name: name.to_string(), color: "#6366f1".to_string(), sort_order: 0, parent_id: None })
+            .unwrap()
+    }
+
+    fn email_of(db: &Database, key: &SecretKey, id: i64) -> String {
+        decrypt_field(&db.get_account(id).unwrap().email, key).unwrap()
+    }
+
+    fn tag_ids(db: &Database, account_id: i64) -> Vec<i64> {
+        db.get_account_tags(account_id).unwrap().iter().map(|t| t.id).collect()
+    }
+
+    fn account_exists(db: &Database, id: i64) -> bool {
+        db.get_account(id).is_ok()
+    }
+
+    #[test]
+    fn test_undo_redo_create_account() {
+        let db = get_test_db();
+        let key = get_test_key();
+
+        let id = create_account(&db, &key, "a@example.com");
+        Tracker::created(Target::Account(id)).record(&db, &key, MutationKind::CreateAccount).unwrap();
+
+        let step = undo(&db, &key).unwrap().unwrap();
+        assert_eq!(step.kind, MutationKind::CreateAccount);
+        assert!(!account_exists(&db, id));
+
+        redo(&db, &key).unwrap().unwrap();
+        assert_eq!(email_of(&db, &key, id), "a@example.com");
+        // The restored account is searchable again
+        assert!(db.get_unindexed_account_ids().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_import_clears_history() {
+        let db = get_test_db();
+        let key = get_test_key();
+
+        let tag = create_tag(&db, "Work");
+        Tracker::created(Target::Tag(tag)).record(&db, &key, MutationKind::CreateTag).unwrap();
+
+        // Undoing CreateTag now would cascade-delete the imported link
+        let (_, ids) = db
+            .import_accounts(
+                "raw".to_string(),
+                "text".to_string(),
+                None,
+                vec![ImportedAccount {
+                    account: CreateAccount {
+                        raw_import_id: None,
+                        email: encrypt_field("a@example.com", &key).unwrap(),
+                        password: encrypt_field("pw", &key).unwrap(),
+                        recovery_email: None,
+                        totp_secret: None,
+                        year: None,
+                        notes: None,
+                        group_id: None,
+                        field_order: None,
+                    },
+                    group_path: Vec::new(),
+                    tag_names: vec!["Work".to_string()],
+                    search_tokens: Vec::new(),
+                }],
+            )
+            .unwrap();
+
+        assert!(undo(&db, &key).unwrap().is_none());
+        assert_eq!(tag_ids(&db, ids[0]), vec![tag]);
+    }
+
+    #[test]
+    fn test_undo_redo_update_account() {
+        let db = get_test_db();
+        let key = get_test_key();
+        let id = create_account(&db, &key, "old@example.com");
+
+        let tracker = Tracker::capture(&db, vec![Target::Account(id)]).unwrap();
+        db.update_account(UpdateAccount {
+            id,
+            email: Some(encrypt_field("new@example.com", &key).unwrap()),
+            password: None,
+            recovery_email: None,
+            totp_secret: None,
+            year: Some(2020),
+            notes: None,
+            group_id: None,
+            field_order: None,
+        })
+        .unwrap();
+        tracker.record(&db, &key, MutationKind::UpdateAccount).unwrap();
+
+        undo(&db, &key).unwrap().unwrap();
+        assert_eq!(email_of(&db, &key, id), "old@example.com");
+        assert_eq!(db.get_account(id).unwrap().year, None);
+
+        redo(&db, &key).unwrap().unwrap();
+        assert_eq!(email_of(&db, &key, id), "new@example.com");
+        assert_eq!(db.get_account(id).unwrap().year, Some(2020));
+    }
+
+    #[test]
+    fn test_undo_delete_account_restores_tags() {
+        let db = get_test_db();
+        let key = get_test_key();
+        let id = create_account(&db, &key, "a@example.com");
+        let tag = create_tag(&db, "Work");
+        db.add_tag_to_account(id, tag).unwrap();
+
+        let tracker = Tracker::capture(&db, vec![Target::Account(id)]).unwrap();
+        db.delete_account(id).unwrap();
+        tracker.record(&db, &key, MutationKind::DeleteAccount).unwrap();
+
+        undo(&db, &key).unwrap().unwrap();
+        assert_eq!(email_of(&db, &key, id), "a@example.com");
+        assert_eq!(tag_ids(&db, id), vec![tag]);
+        assert!(db.get_unindexed_account_ids().unwrap().is_empty());
+
+        redo(&db, &key).unwrap().unwrap();
+        assert!(!account_exists(&db, id));
+    }
+
+    #[test]
+    fn test_undo_batch_delete_and_update() {
+        let db = get_test_db();
+        let key = get_test_key();
+        let first = create_account(&db, &key, "a@example.com");
+        let second = create_account(&db, &key, "b@example.com");
+
+        accounts::batch_update_accounts(&db, &key, BatchUpdateRequest {
+            ids: vec![first.to_string(), second.to_string()],
+            updates: UpdateAccountPayload {
+                id: String::new(),
+                email: None,
+                password: None,
+                recovery_email: None,
+                totp_secret: None,
+                year: Some("2021".to_string()),
+                notes: None,
+                group_id: None,
+                field_order: None,
+            },
+            mode: BatchMode::AllOrNothing,
+        })
+        .unwrap();
+
+        // Missing IDs are not part of the recorded batch
+        accounts::batch_delete_accounts(&db, &key, BatchDeleteRequest {
+            ids: vec![first.to_string(), "999".to_string(), second.to_string()],
+            mode: BatchMode::BestEffort,
+        })
+        .unwrap();
+        assert_eq!(db.get_accounts_count().unwrap(), 0);
+
+        let step = undo(&db, &key).unwrap().unwrap();
+        assert_eq!(step.kind, MutationKind::BatchDeleteAccounts);
+        assert_eq!(step.changed_rows, 2);
+        assert_eq!(db.get_account(first).unwrap().year, Some(2021));
+        assert_eq!(db.get_account(second).unwrap().year, Some(2021));
+
+        let step = undo(&db, &key).unwrap().unwrap();
+        assert_eq!(step.kind, MutationKind::BatchUpdateAccounts);
+        assert_eq!(db.get_account(first).unwrap().year, None);
+        assert_eq!(db.get_account(second).unwrap().year, None);
+    }
+
+    #[test]
+    fn test_undo_group_mutations() {
+        let db = get_test_db();
+        let key = get_test_key();
+
+        let group = create_group(&db, "Work");
+        Tracker::created(Target::Group(group)).record(&db, &key, MutationKind::CreateGroup).unwrap();
+
+        let tracker = Tracker::capture(&db, vec![Target::Group(group)]).unwrap();
+        db.update_group(group, CreateGroup { name: "Office".to_string(), color: "#000000".to_string(), sort_order: 3, parent_id: None })
+            .unwrap();
+        tracker.record(&db, &key, MutationKind::UpdateGroup).unwrap();
+
+        let account = create_account(&db, &key, "a@example.com");
+        db.update_account(UpdateAccount {
+            id: account,
+            email: None,
+            password: None,
+            recovery_email: None,
+            totp_secret: None,
+            year: None,
+            notes: None,
+            group_id: Some(group),
+            field_order: None,
+        })
+        .unwrap();
+
+        let subgroup = create_group(&db, "Servers");
+        groups::move_group(&db, &key, subgroup, Some(group)).unwrap();
+
+        let deletion = groups::delete_group(&db, &key, group, &groups::DeleteGroupStrategy::MoveToDefault).unwrap();
+        assert_eq!(db.get_account(account).unwrap().group_id, deletion.moved_to);
+        assert_eq!(db.get_group(subgroup).unwrap().parent_id, None);
+
+        // Undoing the delete brings back the group, its members and subgroups
+        assert_eq!(undo(&db, &key).unwrap().unwrap().kind, MutationKind::DeleteGroup);
+        assert_eq!(db.get_group(group).unwrap().name, "Office");
+        assert_eq!(db.get_account(account).unwrap().group_id, Some(group));
+        assert_eq!(db.get_group(subgroup).unwrap().parent_id, Some(group));
+
+        assert_eq!(undo(&db, &key).unwrap().unwrap().kind, MutationKind::MoveGroup);
+        assert_eq!(db.get_group(subgroup).unwrap().parent_id, None);
+
+        assert_eq!(undo(&db, &key).unwrap().unwrap().kind, MutationKind::UpdateGroup);
+        let restored = db.get_group(group).unwrap();
+        assert_eq!((restored.name.as_str(), restored.sort_order), ("Work", 0));
+
+        assert_eq!(undo(&db, &key).unwrap().unwrap().kind, MutationKind::CreateGroup);
+        assert!(db.get_group(group).is_err());
+    }
+
+    #[test]
+    fn test_undo_group_reorder() {
+        let db = get_test_db();
+        let key = get_test_key();
+        let order = |db: &Database| -> Vec<i64> {
+            db.get_groups().unwrap().into_iter().map(|g| g.id).collect()
+        };
+
+        let first = create_group(&db, "First");
+        let second = create_group(&db, "Second");
+        let default = order(&db)[0];
+        let initial = order(&db);
+
+        groups::reorder_groups(&db, &key, &[second, default, first]).unwrap();
+        assert_eq!(order(&db), vec![second, default, first]);
+
+        groups::place_group(&db, &key, first, Some(default)).unwrap();
+        assert_eq!(order(&db), vec![second, first, default]);
+
+        assert_eq!(undo(&db, &key).unwrap().unwrap().kind, MutationKind::ReorderGroups);
+        assert_eq!(order(&db), vec![second, default, first]);
+        assert_eq!(undo(&db, &key).unwrap().unwrap().kind, MutationKind::ReorderGroups);
+        assert_eq!(order(&db), initial);
+    }
+
+    #[test]
+    fn test_undo_tag_mutations() {
+        let db = get_test_db();
+        let key = get_test_key();
+        let account = create_account(&db, &key, "a@example.com");
+
+        let work = create_tag(&db, "Work");
+        Tracker::created(Target::Tag(work)).record(&db, &key, MutationKind::CreateTag).unwrap();
+        let home = create_tag(&db, "Home");
+
+        let tracker = Tracker::capture(&db, vec![Target::Tag(work)]).unwrap();
+        db.update_tag(work, CreateTag { name: "Job".to_string(), color: "#ff0000".to_string() }).unwrap();
+        tracker.record(&db, &key, MutationKind::UpdateTag).unwrap();
+
+        let pair = Target::AccountTag { account_id: account, tag_id: work };
+        let tracker = Tracker::capture(&db, vec![pair]).unwrap();
+        db.add_tag_to_account(account, work).unwrap();
+        tracker.record(&db, &key, MutationKind::AddTagToAccount).unwrap();
+
+        tags::set_account_tags(&db, &key, account, vec![home]).unwrap();
+        assert_eq!(tag_ids(&db, account), vec![home]);
+
+        let tracker = Tracker::capture(&db, vec![Target::AccountTag { account_id: account, tag_id: home }]).unwrap();
+        db.remove_tag_from_account(account, home).unwrap();
+        tracker.record(&db, &key, MutationKind::RemoveTagFromAccount).unwrap();
+
+        assert_eq!(undo(&db, &key).unwrap().unwrap().kind, MutationKind::RemoveTagFromAccount);
+        assert_eq!(tag_ids(&db, account), vec![home]);
+
+        assert_eq!(undo(&db, &key).unwrap().unwrap().kind, MutationKind::SetAccountTags);
+        assert_eq!(tag_ids(&db, account), vec![work]);
+
+        // Deleting a tag and undoing it restores its associations
+        tags::delete_tag(&db, &key, work).unwrap();
+        assert!(tag_ids(&db, account).is_empty());
+        assert_eq!(undo(&db, &key).unwrap().unwrap().kind, MutationKind::DeleteTag);
+        assert_eq!(tag_ids(&db, account), vec![work]);
+        assert_eq!(db.get_tag(work).unwrap().name, "Job");
+
+        assert_eq!(undo(&db, &key).unwrap().unwrap().kind, MutationKind::AddTagToAccount);
+        assert!(tag_ids(&db, account).is_empty());
+
+        assert_eq!(undo(&db, &key).unwrap().unwrap().kind, MutationKind::UpdateTag);
+        assert_eq!(db.get_tag(work).unwrap().name, "Work");
+
+        assert_eq!(undo(&db, &key).unwrap().unwrap().kind, MutationKind::CreateTag);
+        assert!(db.get_tag(work).is_err());
+        assert!(undo(&db, &key).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_undo_tag_merge() {
+        let db = get_test_db();
+        let key = get_test_key();
+        let first = create_account(&db, &key, "a@example.com");
+        let second = create_account(&db, &key, "b@example.com");
+        let source = create_tag(&db, "wrk");
+        let target = create_tag(&db, "Work");
+        db.add_tag_to_account(first, source).unwrap();
+        db.add_tag_to_account(second, source).unwrap();
+        db.add_tag_to_account(second, target).unwrap();
+
+        let merge = tags::merge_tags(&db, &key, source, target).unwrap();
+        assert_eq!((merge.accounts_moved, merge.accounts_already_tagged), (1, 1));
+        assert_eq!(tag_ids(&db, first), vec![target]);
+
+        assert_eq!(undo(&db, &key).unwrap().unwrap().kind, MutationKind::MergeTags);
+        assert_eq!(db.get_tag(source).unwrap().name, "wrk");
+        assert_eq!(tag_ids(&db, first), vec![source]);
+        assert_eq!(tag_ids(&db, second), vec![target, source]);
+    }
+
+    #[test]
+    fn test_undo_refuses_a_tag_name_taken_since() {
+        let db = get_test_db();
+        let key = get_test_key();
+        let work = create_tag(&db, "Work");
+        tags::delete_tag(&db, &key, work).unwrap();
+        let other = create_tag(&db, "WORK");
+
+        let result = undo(&db, &key);
+        assert!(matches!(result, Err(HistoryError::Database(DbError::Duplicate(_)))));
+        assert!(db.get_tag(work).is_err());
+        assert_eq!(db.get_tag(other).unwrap().name, "WORK");
+    }
+
+    #[test]
+    fn test_undo_batch_tag_changes() {
+        let db = get_test_db();
+        let key = get_test_key();
+        let first = create_account(&db, &key, "a@example.com");
+        let second = create_account(&db, &key, "b@example.com");
+        let work = create_tag(&db, "Work");
+        let home = create_tag(&db, "Home");
+        db.add_tag_to_account(first, home).unwrap();
+
+        let payload = |account_ids: Vec<i64>, tag_ids: Vec<i64>| tags::BatchTagsPayload {
+            account_ids: account_ids.iter().map(|id| id.to_string()).collect(),
+            tag_ids: tag_ids.iter().map(|id| id.to_string()).collect(),
+            mode: BatchMode::AllOrNothing,
+        };
+
+        let result = tags::batch_change_account_tags(&db, &key, payload(vec![first, second], vec![work]), TagChange::Add)
+            .unwrap();
+        assert_eq!(result.succeeded, 2);
+        assert_eq!(tag_ids(&db, first), vec![home, work]);
+
+        tags::batch_change_account_tags(&db, &key, payload(vec![first, second], vec![home]), TagChange::Replace)
+            .unwrap();
+        assert_eq!(tag_ids(&db, second), vec![home]);
+
+        // An unknown account rolls back the batch and records nothing
+        let result = tags::batch_change_account_tags(&db, &key, payload(vec![first, 999], vec![home]), TagChange::Remove)
+            .unwrap();
+        assert!(!result.committed);
+        assert_eq!(tag_ids(&db, first), vec![home]);
+
+        assert_eq!(undo(&db, &key).unwrap().unwrap().kind, MutationKind::BatchUpdateAccountTags);
+        assert_eq!(tag_ids(&db, first), vec![home, work]);
+        assert_eq!(tag_ids(&db, second), vec![work]);
+
+        assert_eq!(undo(&db, &key).unwrap().unwrap().kind, MutationKind::BatchUpdateAccountTags);
+        assert_eq!(tag_ids(&db, first), vec![home]);
+        assert!(tag_ids(&db, second).is_empty());
+    }
+
+    #[test]
+    fn test_new_mutation_clears_redo() {
+        let db = get_test_db();
+        let key = get_test_key();
+
+        let first = create_tag(&db, "First");
+        Tracker::created(Target::Tag(first)).record(&db, &key, MutationKind::CreateTag).unwrap();
+        undo(&db, &key).unwrap().unwrap();
+
+        let current = status(&db).unwrap();
+        assert_eq!((current.undo_count, current.redo_count), (0, 1));
+        assert_eq!(current.next_redo, Some(MutationKind::CreateTag));
+
+        let second = create_tag(&db, "Second");
+        Tracker::created(Target::Tag(second)).record(&db, &key, MutationKind::CreateTag).unwrap();
+
+        let current = status(&db).unwrap();
+        assert_eq!((current.undo_count, current.redo_count), (1, 0));
+        assert!(redo(&db, &key).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_unchanged_rows_are_not_recorded() {
+        let db = get_test_db();
+        let key = get_test_key();
+        let tag = create_tag(&db, "Work");
+
+        let tracker = Tracker::capture(&db, vec![Target::Tag(tag)]).unwrap();
+        tracker.record(&db, &key, MutationKind::UpdateTag).unwrap();
+
+        assert_eq!(status(&db).unwrap().undo_count, 0);
+    }
+
+    #[test]
+    fn test_history_is_bounded() {
+        let db = get_test_db();
+        let key = get_test_key();
+
+        for i in 0..MAX_HISTORY_ENTRIES + 5 {
+            let tag = create_tag(&db, &format!("Tag {}", i));
+            Tracker::created(Target::Tag(tag)).record(&db, &key, MutationKind::CreateTag).unwrap();
+        }
+
+        assert_eq!(status(&db).unwrap().undo_count, MAX_HISTORY_ENTRIES);
+    }
+
+    #[test]
+    fn test_entries_are_encrypted() {
+        let db = get_test_db();
+        let key = get_test_key();
+
+        let tag = create_tag(&db, "VerySecretProject");
+        Tracker::created(Target::Tag(tag)).record(&db, &key, MutationKind::CreateTag).unwrap();
+
+        let entry = db.next_undo_entry().unwrap().unwrap();
+        assert_eq!(entry.operation_type, "CREATE_TAG");
+        assert!(!entry.undo_data.contains("VerySecretProject"));
+    }
+
+    #[test]
+    fn test_failed_replay_changes_nothing() {
+        let db = get_test_db();
+        let key = get_test_key();
+        let account = create_account(&db, &key, "a@example.com");
+        let group = create_group(&db, "Temp");
+
+        let tracker = Tracker::capture(&db, vec![Target::Account(account)]).unwrap();
+        db.update_account(UpdateAccount {
+            id: account,
+            email: None,
+            password: None,
+            recovery_email: None,
+            totp_secret: None,
+            year: Some(2020),
+            notes: None,
+            group_id: Some(group),
+            field_order: None,
+        })
+        .unwrap();
+        tracker.record(&db, &key, MutationKind::UpdateAccount).unwrap();
+        undo(&db, &key).unwrap().unwrap();
+
+        // Redo would point the account at a group that no longer exists
+        db.delete_group_with_members(group, GroupMembers::MoveToDefault).unwrap();
+        assert!(redo(&db, &key).is_err());
+
+        assert_eq!(db.get_account(account).unwrap().year, None);
+        assert_eq!(status(&db).unwrap().redo_count, 1);
+    }
+}
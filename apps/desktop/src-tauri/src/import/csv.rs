@@ -0,0 +1,399 @@
+//! CSV account parser
+//!
+//! The first row is a header. Columns are mapped to account fields either
+//! by a user-supplied list of [`CsvColumn`]s or, when none is given, by
+//! header name (`email`, `password`, `recovery_email`, `totp_secret`,
+//! `year`, `notes`, `group`, `tags`; case, spaces and dashes are ignored).
+//! Unmapped columns are ignored.
+//!
+//! The `tags` column holds a `;`-separated list of tag names and the
+//! `group` column a group path (`Clients/Work`, see
+//! [`super::split_group_path`]); both are created on import if missing.
+//!
+//! Passwords, TOTP secrets and notes are imported byte for byte, so that a
+//! file written by the CSV export imports unchanged. Other cells are
+//! trimmed. The `'` the export puts before cells that would start a
+//! spreadsheet formula is removed again (see [`escape_formula`]).
+
+use super::{ParsedAccount, ParsedLine};
+use serde::{Deserialize, Serialize};
+
+// ============================================================================
+// Constants
+// ============================================================================
+
+/// Separator between tag names in the `tags` column
+pub const TAG_SEPARATOR: char = ';';
+
+/// Characters that make spreadsheet apps evaluate a cell as a formula
+const FORMULA_PREFIXES: [char; 4] = ['=', '+', '-', '@'];
+
+/// Prefix that keeps a cell from being evaluated
+const FORMULA_ESCAPE: char = '\'';
+
+// ============================================================================
+// Types
+// ============================================================================
+
+/// Account field a CSV column maps to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CsvField {
+    Email,
+    Password,
+    RecoveryEmail,
+    TotpSecret,
+    Year,
+    Notes,
+    Group,
+    Tags,
+}
+
+impl CsvField {
+    /// Every field, in the default column order
+    pub const ALL: [CsvField; 8] = [
+        CsvField::Email,
+        CsvField::Password,
+        CsvField::RecoveryEmail,
+        CsvField::TotpSecret,
+        CsvField::Year,
+        CsvField::Notes,
+        CsvField::Group,
+        CsvField::Tags,
+    ];
+
+    /// Default header name
+    pub fn name(self) -> &'static str {
+        match self {
+            CsvField::Email => "email",
+            CsvField::Password => "password",
+            CsvField::RecoveryEmail => "recovery_email",
+            CsvField::TotpSecret => "totp_secret",
+            CsvField::Year => "year",
+            CsvField::Notes => "notes",
+            CsvField::Group => "group",
+            CsvField::Tags => "tags",
+        }
+    }
+
+    /// Field whose default header matches `header`
+    fn from_header(header: &str) -> Option<Self> {
+        let normalized = normalize_header(header);
+        CsvField::ALL.into_iter().find(|field| field.name() == normalized)
+    }
+}
+
+/// Mapping of one CSV column to an account field
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CsvColumn {
+    /// Header of the column (matched ignoring case)
+    pub column: String,
+    pub field: CsvField,
+}
+
+impl CsvColumn {
+    /// Columns for every field under its default header
+    pub fn defaults() -> Vec<CsvColumn> {
+        CsvField::ALL
+            .into_iter()
+            .map(|field| CsvColumn {
+                column: field.name().to_string(),
+                field,
+            })
+            .collect()
+    }
+}
+
+// ============================================================================
+// Parsing
+// ============================================================================
+
+/// Parse a CSV file into accounts
+///
+/// # Errors
+/// Returns a message for problems with the file as a whole: a missing
+/// header, a mapped column that does not exist, a field mapped twice, or no
+/// column mapped to `email` or `password`. Problems with single rows are
+/// reported in the returned [`ParsedLine`]s instead.
+pub fn parse_csv(
+    contents: &str,
+    columns: Option<&[CsvColumn]>,
+    delimiter: Option<u8>,
+) -> Result<Vec<ParsedLine>, String> {
+    // Spreadsheet apps often prepend a byte order mark
+    let contents = contents.strip_prefix('\u{feff}').unwrap_or(contents);
+
+    let mut reader = ::csv::ReaderBuilder::new()
+        .delimiter(delimiter.unwrap_or(b','))
+        .flexible(true)
+        .trim(::csv::Trim::Headers)
+        .from_reader(contents.as_bytes());
+
+    let headers = reader.headers().map_err(|e| format!("Invalid CSV header: {}", e))?.clone();
+    let mapping = resolve_columns(&headers, columns)?;
+
+    let mut lines = Vec::new();
+    let mut last_line = 1;
+    for record in reader.records() {
+        match record {
+            Ok(record) => {
+                let line = record.position().map(|p| p.line() as usize).unwrap_or(last_line + 1);
+                last_line = line;
+                if record.iter().all(|c| c.trim().is_empty()) {
+                    continue;
+                }
+                lines.push(ParsedLine {
+                    line,
+                    result: parse_record(&record, &mapping),
+                });
+            }
+            Err(e) => {
+                let line = e.position().map(|p| p.line() as usize).unwrap_or(last_line + 1);
+                last_line = line;
+                lines.push(ParsedLine {
+                    line,
+                    result: Err(format!("Invalid CSV row: {}", e)),
+                });
+            }
+        }
+    }
+
+    Ok(lines)
+}
+
+/// Column index of every mapped field
+fn resolve_columns(
+    headers: &::csv::StringRecord,
+    columns: Option<&[CsvColumn]>,
+) -> Result<Vec<(usize, CsvField)>, String> {
+    let mut mapping: Vec<(usize, CsvField)> = Vec::new();
+
+    match columns {
+        Some(columns) => {
+            for column in columns {
+                let index = headers
+                    .iter()
+                    .position(|h| h.eq_ignore_ascii_case(column.column.trim()))
+                    .ok_or_else(|| format!("Column '{}' not found", column.column))?;
+                mapping.push((index, column.field));
+            }
+        }
+        None => {
+            for (index, header) in headers.iter().enumerate() {
+                if let Some(field) = CsvField::from_header(header) {
+                    mapping.push((index, field));
+                }
+            }
+        }
+    }
+
+    for field in CsvField::ALL {
+        let count = mapping.iter().filter(|(_, f)| *f == field).count();
+        if count > 1 {
+            return Err(format!("More than one column is mapped to {}", field.name()));
+        }
+    }
+    for required in [CsvField::Email, CsvField::Password] {
+        if !mapping.iter().any(|(_, f)| *f == required) {
+            return Err(format!("No column is mapped to {}", required.name()));
+        }
+    }
+
+    Ok(mapping)
+}
+
+/// Turn one row into an account
+fn parse_record(record: &::csv::StringRecord, mapping: &[(usize, CsvField)]) -> Result<ParsedAccount, String> {
+    let mut account = ParsedAccount::default();
+
+    for (index, field) in mapping {
+        let raw = record.get(*index).unwrap_or("");
+        let value = unescape_formula(match field {
+            CsvField::Password | CsvField::TotpSecret | CsvField::Notes => raw,
+            _ => raw.trim(),
+        });
+        if value.is_empty() {
+            continue;
+        }
+        let value = value.to_string();
+
+        match field {
+            CsvField::Email => account.email = value,
+            CsvField::Password => account.password = value,
+            CsvField::RecoveryEmail => account.recovery_email = Some(value),
+            CsvField::TotpSecret => account.totp_secret = Some(value),
+            CsvField::Year => {
+                let year = value.parse().map_err(|_| format!("Invalid year: {}", value))?;
+                account.year = Some(year);
+            }
+            CsvField::Notes => account.notes = Some(value),
+            CsvField::Group => account.group = Some(value),
+            CsvField::Tags => {
+                account.tags = value
+                    .split(TAG_SEPARATOR)
+                    .map(str::trim)
+                    .filter(|t| !t.is_empty())
+                    .map(str::to_string)
+                    .collect();
+            }
+        }
+    }
+
+    if account.email.is_empty() {
+        return Err("Email is required".to_string());
+    }
+    if !account.email.contains('@') {
+        return Err(format!("Invalid email address: {}", account.email));
+    }
+    if account.password.is_empty() {
+        return Err("Password is required".to_string());
+    }
+
+    Ok(account)
+}
+
+/// Keep spreadsheets from evaluating a cell as a formula
+///
+/// Cells starting with `=`, `+`, `-` or `@` get a leading `'`. So that
+/// [`unescape_formula`] can undo it exactly, values that already start with
+/// `'`s followed by one of those characters get one more.
+pub fn escape_formula(value: &str) -> String {
+    if looks_like_formula(value) {
+        format!("{}{}", FORMULA_ESCAPE, value)
+    } else {
+        value.to_string()
+    }
+}
+
+/// Reverse [`escape_formula`]
+fn unescape_formula(value: &str) -> &str {
+    match value.strip_prefix(FORMULA_ESCAPE) {
+        Some(rest) if looks_like_formula(rest) => rest,
+        _ => value,
+    }
+}
+
+/// The value starts with a formula character, after any `'`s
+fn looks_like_formula(value: &str) -> bool {
+    value.trim_start_matches(FORMULA_ESCAPE).starts_with(FORMULA_PREFIXES)
+}
+
+/// Lowercase and use `_` for spaces and dashes
+fn normalize_header(header: &str) -> String {
+    header
+        .trim()
+        .to_lowercase()
+        .replace([' ', '-'], "_")
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn accounts(lines: &[ParsedLine]) -> Vec<&ParsedAccount> {
+        lines.iter().filter_map(|l| l.result.as_ref().ok()).collect()
+    }
+
+    #[test]
+    fn test_maps_headers_by_name() {
+        let csv = "\u{feff}Email,Password,Recovery Email,Year,Group,Tags,Extra\n\
+                   a@example.com,pw1,r@example.com,2019,Work,vip; old ,ignored\n";
+
+        let lines = parse_csv(csv, None, None).unwrap();
+        let account = accounts(&lines)[0];
+
+        assert_eq!(lines[0].line, 2);
+        assert_eq!(account.email, "a@example.com");
+        assert_eq!(account.recovery_email.as_deref(), Some("r@example.com"));
+        assert_eq!(account.year, Some(2019));
+        assert_eq!(account.group.as_deref(), Some("Work"));
+        assert_eq!(account.tags, vec!["vip", "old"]);
+    }
+
+    #[test]
+    fn test_explicit_mapping_and_delimiter() {
+        let csv = "login;secret;2fa\na@example.com;pw;JBSWY3DPEHPK3PXP\n";
+        let columns = vec![
+            CsvColumn { column: "Login".to_string(), field: CsvField::Email },
+            CsvColumn { column: "secret".to_string(), field: CsvField::Password },
+            CsvColumn { column: "2fa".to_string(), field: CsvField::TotpSecret },
+        ];
+
+        let lines = parse_csv(csv, Some(&columns), Some(b';')).unwrap();
+        let account = accounts(&lines)[0];
+
+        assert_eq!(account.password, "pw");
+        assert_eq!(account.totp_secret.as_deref(), Some("JBSWY3DPEHPK3PXP"));
+    }
+
+    #[test]
+    fn test_reports_row_errors() {
+        let csv = "email,password,year\n\
+                   a@example.com,pw,2020\n\
+                   ,pw,\n\
+                   b@example.com,,\n\
+                   c@example.com,pw,someday\n\
+                   ,,\n\
+                   d@example.com,pw\n";
+
+        let lines = parse_csv(csv, None, None).unwrap();
+        let errors: Vec<(usize, &str)> = lines
+            .iter()
+            .filter_map(|l| l.result.as_ref().err().map(|e| (l.line, e.as_str())))
+            .collect();
+
+        assert_eq!(accounts(&lines).len(), 2);
+        assert_eq!(
+            errors,
+            vec![
+                (3, "Email is required"),
+                (4, "Password is required"),
+                (5, "Invalid year: someday"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_keeps_secrets_and_notes_verbatim() {
+        let csv = "email,password,totp_secret,notes,group\n  \
+                   a@example.com ,\" secret \", JBSWY3DPEHPK3PXP,\" padded note \", Work \n";
+
+        let lines = parse_csv(csv, None, None).unwrap();
+        let account = accounts(&lines)[0];
+
+        assert_eq!(account.email, "a@example.com");
+        assert_eq!(account.group.as_deref(), Some("Work"));
+        assert_eq!(account.password, " secret ");
+        assert_eq!(account.totp_secret.as_deref(), Some(" JBSWY3DPEHPK3PXP"));
+        assert_eq!(account.notes.as_deref(), Some(" padded note "));
+    }
+
+    #[test]
+    fn test_formula_escape_round_trips() {
+        for value in ["=SUM(A1)", "+1", "-x", "@cmd", "'=x", "''-x", "'plain", "plain", "a=b", ""] {
+            let escaped = escape_formula(value);
+            assert!(!escaped.starts_with(FORMULA_PREFIXES), "{}", escaped);
+            assert_eq!(unescape_formula(&escaped), value);
+        }
+        assert_eq!(escape_formula("=1+1"), "'=1+1");
+        assert_eq!(escape_formula("'plain"), "'plain");
+    }
+
+    #[test]
+    fn test_rejects_bad_mappings() {
+        assert!(parse_csv("user,pass\n", None, None).unwrap_err().contains("email"));
+
+        let missing = vec![CsvColumn { column: "nope".to_string(), field: CsvField::Email }];
+        assert!(parse_csv("email,password\n", Some(&missing), None).is_err());
+
+        let twice = vec![
+            CsvColumn { column: "email".to_string(), field: CsvField::Email },
+            CsvColumn { column: "password".to_string(), field: CsvField::Email },
+        ];
+        assert!(parse_csv("email,password\n", Some(&twice), None).is_err());
+    }
+}
@@ -0,0 +1,173 @@
+//! Bitwarden JSON export parser
+//!
+//! Reads the unencrypted JSON export (`File > Export vault > .json`).
+//! Folders map to groups (a `Work/Servers` folder is nested under `Work`, as
+//! in Bitwarden) and organization collections to tags. Password
+//! protected (`"encrypted": true`) exports cannot be read and are rejected.
+
+use super::{year_of_rfc3339, LoginItem};
+use crate::import::ParsedLine;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// Bitwarden item type for logins
+const ITEM_TYPE_LOGIN: u8 = 1;
+
+#[derive(Debug, Deserialize)]
+struct BitwardenExport {
+    #[serde(default)]
+    encrypted: bool,
+    folders: Option<Vec<NamedEntry>>,
+    collections: Option<Vec<NamedEntry>>,
+    /// Kept as values so a malformed item only rejects itself
+    items: Option<Vec<serde_json::Value>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NamedEntry {
+    id: String,
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BitwardenItem {
+    #[serde(rename = "type")]
+    kind: u8,
+    name: Option<String>,
+    notes: Option<String>,
+    folder_id: Option<String>,
+    collection_ids: Option<Vec<String>>,
+    login: Option<BitwardenLogin>,
+    creation_date: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BitwardenLogin {
+    uris: Option<Vec<BitwardenUri>>,
+    username: Option<String>,
+    password: Option<String>,
+    totp: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BitwardenUri {
+    uri: Option<String>,
+}
+
+/// Parse a Bitwarden JSON export
+///
+/// Items are numbered from 1 in file order.
+///
+/// # Errors
+/// Returns a message if the file is not a Bitwarden export or is encrypted.
+pub fn parse_json(contents: &str) -> Result<Vec<ParsedLine>, String> {
+    let export: BitwardenExport =
+        serde_json::from_str(contents).map_err(|e| format!("Invalid Bitwarden export: {}", e))?;
+    if export.encrypted {
+        return Err("Encrypted Bitwarden exports are not supported; export as unencrypted JSON".to_string());
+    }
+
+    let folders = names_by_id(export.folders);
+    let collections = names_by_id(export.collections);
+
+    let lines = export
+        .items
+        .unwrap_or_default()
+        .into_iter()
+        .enumerate()
+        .map(|(index, value)| ParsedLine {
+            line: index + 1,
+            result: serde_json::from_value::<BitwardenItem>(value)
+                .map_err(|e| format!("Invalid item: {}", e))
+                .and_then(|item| to_login(item, &folders, &collections))
+                .and_then(LoginItem::into_account),
+        })
+        .collect();
+
+    Ok(lines)
+}
+
+fn to_login(
+    item: BitwardenItem,
+    folders: &HashMap<String, String>,
+    collections: &HashMap<String, String>,
+) -> Result<LoginItem, String> {
+    let login = match item.login {
+        Some(login) if item.kind == ITEM_TYPE_LOGIN => login,
+        _ => return Err("Not a login item".to_string()),
+    };
+
+    Ok(LoginItem {
+        title: item.name,
+        urls: login
+            .uris
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|u| u.uri)
+            .collect(),
+        username: login.username,
+        password: login.password,
+        totp: login.totp,
+        notes: item.notes,
+        group: item.folder_id.and_then(|id| folders.get(&id).cloned()),
+        tags: item
+            .collection_ids
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|id| collections.get(id).cloned())
+            .collect(),
+        created_year: item.creation_date.as_deref().and_then(year_of_rfc3339),
+    })
+}
+
+fn names_by_id(entries: Option<Vec<NamedEntry>>) -> HashMap<String, String> {
+    entries
+        .unwrap_or_default()
+        .into_iter()
+        .map(|e| (e.id, e.name))
+        .collect()
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = include_str!("fixtures/bitwarden.json");
+
+    #[test]
+    fn test_parse_fixture() {
+        let lines = parse_json(FIXTURE).unwrap();
+        assert_eq!(lines.len(), 4);
+
+        let google = lines[0].result.as_ref().unwrap();
+        assert_eq!(google.email, "alice@example.com");
+        assert_eq!(google.password, "g00gle-pw");
+        assert!(google.totp_secret.as_deref().unwrap().starts_with("otpauth://totp/"));
+        assert_eq!(google.group.as_deref(), Some("Work"));
+        assert_eq!(google.tags, vec!["Shared", "Finance"]);
+        assert_eq!(google.year, Some(2021));
+        assert_eq!(
+            google.notes.as_deref(),
+            Some("Google\nhttps://accounts.google.com\nMain account")
+        );
+
+        let forum = lines[1].result.as_ref().unwrap();
+        assert_eq!(forum.group, None);
+        assert!(forum.tags.is_empty());
+
+        assert_eq!(lines[2].result.as_ref().unwrap_err(), "Not a login item");
+        assert_eq!(lines[3].line, 4);
+        assert_eq!(lines[3].result.as_ref().unwrap_err(), "Missing password");
+    }
+
+    #[test]
+    fn test_rejects_encrypted_and_invalid_files() {
+        assert!(parse_json(r#"{"encrypted": true, "data": "..."}"#).unwrap_err().contains("Encrypted"));
+        assert!(parse_json("not json").is_err());
+    }
+}
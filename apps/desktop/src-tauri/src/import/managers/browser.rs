@@ -0,0 +1,104 @@
+//! Browser password CSV parsers
+//!
+//! Chrome (and other Chromium browsers) export `name,url,username,password`
+//! with an optional `note` column; Firefox exports
+//! `url,username,password,httpRealm,formActionOrigin,guid,timeCreated,...`.
+//! Browsers have no folders or labels, so items get no group or tags. The
+//! Firefox creation time is used for the account year.
+
+use super::{CsvTable, LoginItem};
+use crate::import::ParsedLine;
+
+/// Parse a Chrome password CSV export
+///
+/// # Errors
+/// Returns a message if a required column is missing.
+pub fn parse_chrome_csv(contents: &str) -> Result<Vec<ParsedLine>, String> {
+    let table = CsvTable::read(contents, &["url", "username", "password"])?;
+
+    Ok(table.parse_rows(|table, record| LoginItem {
+        title: table.get(record, &["name"]),
+        urls: table.get(record, &["url"]).into_iter().collect(),
+        username: table.get(record, &["username"]),
+        password: table.get(record, &["password"]),
+        notes: table.get(record, &["note"]),
+        ..Default::default()
+    }))
+}
+
+/// Parse a Firefox password CSV export
+///
+/// # Errors
+/// Returns a message if a required column is missing.
+pub fn parse_firefox_csv(contents: &str) -> Result<Vec<ParsedLine>, String> {
+    let table = CsvTable::read(contents, &["url", "username", "password"])?;
+
+    Ok(table.parse_rows(|table, record| LoginItem {
+        urls: table.get(record, &["url"]).into_iter().collect(),
+        username: table.get(record, &["username"]),
+        password: table.get(record, &["password"]),
+        created_year: table
+            .get(record, &["timecreated"])
+            .and_then(|ms| ms.trim().parse::<i64>().ok())
+            .and_then(chrono::DateTime::from_timestamp_millis)
+            .map(|t| chrono::Datelike::year(&t)),
+        ..Default::default()
+    }))
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHROME_FIXTURE: &str = include_str!("fixtures/chrome.csv");
+    const FIREFOX_FIXTURE: &str = include_str!("fixtures/firefox.csv");
+
+    #[test]
+    fn test_parse_chrome_fixture() {
+        let lines = parse_chrome_csv(CHROME_FIXTURE).unwrap();
+        assert_eq!(lines.len(), 4);
+
+        let google = lines[0].result.as_ref().unwrap();
+        assert_eq!(google.email, "alice@example.com");
+        assert_eq!(google.password, "g00gle-pw");
+        assert_eq!(
+            google.notes.as_deref(),
+            Some("accounts.google.com\nhttps://accounts.google.com/signin")
+        );
+
+        let shop = lines[1].result.as_ref().unwrap();
+        assert_eq!(shop.password, "quote\"d");
+        assert!(shop.notes.as_deref().unwrap().ends_with("Saved from phone"));
+
+        assert_eq!(lines[2].line, 4);
+        assert_eq!(lines[2].result.as_ref().unwrap_err(), "Missing username");
+
+        // Passwords are imported byte for byte, surrounding spaces included
+        assert_eq!(lines[3].result.as_ref().unwrap().password, " secret ");
+    }
+
+    #[test]
+    fn test_parse_firefox_fixture() {
+        let lines = parse_firefox_csv(FIREFOX_FIXTURE).unwrap();
+        assert_eq!(lines.len(), 3);
+
+        let google = lines[0].result.as_ref().unwrap();
+        assert_eq!(google.email, "alice@example.com");
+        assert_eq!(google.year, Some(2019));
+        assert_eq!(google.notes.as_deref(), Some("https://accounts.google.com"));
+        assert_eq!(google.group, None);
+
+        assert_eq!(lines[1].result.as_ref().unwrap().year, Some(2021));
+        assert_eq!(lines[2].result.as_ref().unwrap_err(), "Missing username");
+    }
+
+    #[test]
+    fn test_rejects_other_csv() {
+        assert!(parse_chrome_csv("email,password\na@example.com,pw\n").unwrap_err().contains("url"));
+        assert!(parse_firefox_csv("").is_err());
+    }
+}
@@ -0,0 +1,151 @@
+//! KeePass XML export parser
+//!
+//! Reads the KeePass 2 XML export (`File > Export > KeePass XML (2.x)`),
+//! which KeePassXC writes as well. Groups below the database's root group
+//! map to account groups, named by their path (`Work/Servers`); entries in
+//! the root group get no group. Entry tags map to tags. The OTP secret is
+//! taken from the KeePassXC `otp` field or the `TOTP Seed` /
+//! `TimeOtp-Secret-Base32` fields used by KeePass plugins.
+//!
+//! Entry history and the recycle bin are skipped.
+
+use super::{year_of_rfc3339, LoginItem};
+use crate::import::{escape_group_name, ParsedLine, GROUP_PATH_SEPARATOR};
+use roxmltree::{Document, Node};
+
+/// Entry fields that may hold the OTP secret, in order of preference
+const OTP_KEYS: [&str; 3] = ["otp", "TOTP Seed", "TimeOtp-Secret-Base32"];
+
+/// Parse a KeePass XML export
+///
+/// Entries are numbered by the line their `<Entry>` element starts on.
+///
+/// # Errors
+/// Returns a message if the file is not well-formed KeePass XML.
+pub fn parse_xml(contents: &str) -> Result<Vec<ParsedLine>, String> {
+    let doc = Document::parse(contents).map_err(|e| format!("Invalid KeePass XML: {}", e))?;
+    let file = doc.root_element();
+    if !file.has_tag_name("KeePassFile") {
+        return Err("Invalid KeePass XML: missing KeePassFile element".to_string());
+    }
+
+    let recycle_bin = child(file, "Meta")
+        .filter(|meta| child_text(*meta, "RecycleBinEnabled").as_deref() != Some("False"))
+        .and_then(|meta| child_text(meta, "RecycleBinUUID"));
+
+    let root_group = child(file, "Root")
+        .and_then(|root| child(root, "Group"))
+        .ok_or_else(|| "Invalid KeePass XML: missing root group".to_string())?;
+
+    let mut lines = Vec::new();
+    walk_group(&doc, root_group, None, recycle_bin.as_deref(), &mut lines);
+    Ok(lines)
+}
+
+/// Collect the entries of a group and its subgroups
+fn walk_group(
+    doc: &Document,
+    group: Node,
+    path: Option<&str>,
+    recycle_bin: Option<&str>,
+    lines: &mut Vec<ParsedLine>,
+) {
+    for node in group.children().filter(Node::is_element) {
+        if node.has_tag_name("Entry") {
+            lines.push(ParsedLine {
+                line: doc.text_pos_at(node.range().start).row as usize,
+                result: to_login(node, path).into_account(),
+            });
+        } else if node.has_tag_name("Group") {
+            if recycle_bin.is_some() && child_text(node, "UUID").as_deref() == recycle_bin {
+                continue;
+            }
+            let name = escape_group_name(&child_text(node, "Name").unwrap_or_default());
+            let sub_path = match path {
+                Some(parent) => format!("{}{}{}", parent, GROUP_PATH_SEPARATOR, name),
+                None => name,
+            };
+            walk_group(doc, node, Some(&sub_path), recycle_bin, lines);
+        }
+    }
+}
+
+fn to_login(entry: Node, group: Option<&str>) -> LoginItem {
+    let field = |key: &str| -> Option<String> {
+        entry
+            .children()
+            .filter(|n| n.has_tag_name("String"))
+            .find(|n| child_text(*n, "Key").as_deref() == Some(key))
+            .and_then(|n| child_text(n, "Value"))
+    };
+
+    LoginItem {
+        title: field("Title"),
+        urls: field("URL").into_iter().collect(),
+        username: field("UserName"),
+        password: field("Password"),
+        totp: OTP_KEYS.iter().find_map(|key| field(key)),
+        notes: field("Notes"),
+        group: group.map(str::to_string),
+        tags: child_text(entry, "Tags")
+            .map(|tags| tags.split([';', ',']).map(|t| t.trim().to_string()).collect())
+            .unwrap_or_default(),
+        created_year: child(entry, "Times")
+            .and_then(|times| child_text(times, "CreationTime"))
+            .and_then(|time| year_of_rfc3339(&time)),
+    }
+}
+
+fn child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|n| n.has_tag_name(name))
+}
+
+fn child_text(node: Node, name: &str) -> Option<String> {
+    child(node, name).map(|n| n.text().unwrap_or_default().to_string())
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = include_str!("fixtures/keepass.xml");
+
+    #[test]
+    fn test_parse_fixture() {
+        let lines = parse_xml(FIXTURE).unwrap();
+
+        // History entries and the recycle bin are skipped
+        assert_eq!(lines.len(), 3);
+
+        let mail = lines[0].result.as_ref().unwrap();
+        assert_eq!(lines[0].line, 13);
+        assert_eq!(mail.email, "alice@example.com");
+        assert_eq!(mail.password, "m&il-pw");
+        assert!(mail.totp_secret.as_deref().unwrap().contains("secret=JBSWY3DPEHPK3PXP"));
+        assert_eq!(mail.group, None);
+        assert_eq!(mail.tags, vec!["vip", "mail"]);
+        assert_eq!(mail.year, Some(2019));
+        assert_eq!(
+            mail.notes.as_deref(),
+            Some("Mail\nhttps://mail.example.com\nLine one\nLine two")
+        );
+
+        let server = lines[1].result.as_ref().unwrap();
+        assert_eq!(server.group.as_deref(), Some("Work/Servers"));
+        assert_eq!(server.tags, vec!["ops", "infra"]);
+        assert_eq!(server.totp_secret.as_deref(), Some("GEZDGNBVGY3TQOJQ"));
+
+        assert_eq!(lines[2].result.as_ref().unwrap_err(), "Missing username");
+    }
+
+    #[test]
+    fn test_rejects_other_xml() {
+        assert!(parse_xml("<html></html>").is_err());
+        assert!(parse_xml("<KeePassFile><Root/></KeePassFile>").is_err());
+        assert!(parse_xml("<KeePassFile>").is_err());
+    }
+}
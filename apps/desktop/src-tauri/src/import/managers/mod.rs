@@ -0,0 +1,302 @@
+//! Parsers for other password managers' export files
+//!
+//! Supported formats:
+//! - Bitwarden unencrypted JSON export ([`bitwarden`])
+//! - KeePass 2 XML export ([`keepass`])
+//! - 1Password 1PUX archive and CSV export ([`onepassword`])
+//! - Chrome and Firefox password CSV exports ([`browser`])
+//!
+//! Every parser turns login items into [`LoginItem`]s, which are mapped to
+//! accounts the same way for all formats:
+//! - the username becomes the account email
+//! - the item title and URLs are kept in the notes, above the item's own
+//!   notes
+//! - folders (Bitwarden folders, KeePass groups, 1Password vaults) become
+//!   the account group, labels (collections, tags) become tags
+//! - OTP fields become `totp_secret`, as a bare secret or `otpauth://` URI
+//!
+//! Items that are not logins (cards, notes, identities) are reported as
+//! errors so the user can see what was left behind. Record numbers in
+//! structured formats are 1-based item positions; XML and CSV formats use
+//! line numbers.
+
+pub mod bitwarden;
+pub mod browser;
+pub mod keepass;
+pub mod onepassword;
+
+use super::{ParsedAccount, ParsedLine};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// ============================================================================
+// Types
+// ============================================================================
+
+/// Supported export formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ManagerFormat {
+    #[serde(rename = "bitwarden_json")]
+    BitwardenJson,
+    #[serde(rename = "keepass_xml")]
+    KeepassXml,
+    /// `.1pux` archive; the request contents are base64-encoded
+    #[serde(rename = "1password_1pux")]
+    OnePassword1pux,
+    #[serde(rename = "1password_csv")]
+    OnePasswordCsv,
+    #[serde(rename = "chrome_csv")]
+    ChromeCsv,
+    #[serde(rename = "firefox_csv")]
+    FirefoxCsv,
+}
+
+impl ManagerFormat {
+    /// `raw_imports.source_type` for this format
+    pub fn source_type(self) -> &'static str {
+        match self {
+            ManagerFormat::BitwardenJson => "bitwarden_json",
+            ManagerFormat::KeepassXml => "keepass_xml",
+            ManagerFormat::OnePassword1pux => "1password_1pux",
+            ManagerFormat::OnePasswordCsv => "1password_csv",
+            ManagerFormat::ChromeCsv => "chrome_csv",
+            ManagerFormat::FirefoxCsv => "firefox_csv",
+        }
+    }
+}
+
+/// Parsed export file
+#[derive(Debug, Clone)]
+pub struct ManagerExport {
+    /// Text stored in `raw_imports` (the JSON inside a 1PUX archive, the
+    /// file itself otherwise)
+    pub raw_text: String,
+    pub lines: Vec<ParsedLine>,
+}
+
+/// Login fields shared by all export formats
+#[derive(Debug, Clone, Default)]
+pub struct LoginItem {
+    pub title: Option<String>,
+    pub urls: Vec<String>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub totp: Option<String>,
+    pub notes: Option<String>,
+    pub group: Option<String>,
+    pub tags: Vec<String>,
+    pub created_year: Option<i32>,
+}
+
+impl LoginItem {
+    /// Map the item to an account
+    ///
+    /// The password is kept exactly as exported; other fields are trimmed.
+    ///
+    /// # Errors
+    /// Returns a message if the username or password is missing.
+    pub fn into_account(self) -> Result<ParsedAccount, String> {
+        let username = non_empty(self.username).ok_or_else(|| "Missing username".to_string())?;
+        let password = self
+            .password
+            .filter(|p| !p.is_empty())
+            .ok_or_else(|| "Missing password".to_string())?;
+
+        let notes: Vec<String> = non_empty(self.title)
+            .into_iter()
+            .chain(self.urls.into_iter().filter_map(|u| non_empty(Some(u))))
+            .chain(non_empty(self.notes))
+            .collect();
+
+        let mut tags: Vec<String> = Vec::new();
+        for tag in self.tags.into_iter().filter_map(|t| non_empty(Some(t))) {
+            if !tags.iter().any(|t| t.eq_ignore_ascii_case(&tag)) {
+                tags.push(tag);
+            }
+        }
+
+        Ok(ParsedAccount {
+            email: username,
+            password,
+            recovery_email: None,
+            totp_secret: non_empty(self.totp),
+            year: self.created_year,
+            notes: if notes.is_empty() { None } else { Some(notes.join("\n")) },
+            group: non_empty(self.group),
+            tags,
+        })
+    }
+}
+
+// ============================================================================
+// Parsing
+// ============================================================================
+
+/// Parse an export file in the given format
+///
+/// # Errors
+/// Returns a message if the file as a whole cannot be read; problems with
+/// single items are reported in [`ManagerExport::lines`].
+pub fn parse_export(format: ManagerFormat, contents: &str) -> Result<ManagerExport, String> {
+    let (raw_text, lines) = match format {
+        ManagerFormat::BitwardenJson => (contents.to_string(), bitwarden::parse_json(contents)?),
+        ManagerFormat::KeepassXml => (contents.to_string(), keepass::parse_xml(contents)?),
+        ManagerFormat::OnePassword1pux => {
+            let data = onepassword::read_1pux(contents)?;
+            let lines = onepassword::parse_export_data(&data)?;
+            (data, lines)
+        }
+        ManagerFormat::OnePasswordCsv => (contents.to_string(), onepassword::parse_csv(contents)?),
+        ManagerFormat::ChromeCsv => (contents.to_string(), browser::parse_chrome_csv(contents)?),
+        ManagerFormat::FirefoxCsv => (contents.to_string(), browser::parse_firefox_csv(contents)?),
+    };
+
+    Ok(ManagerExport { raw_text, lines })
+}
+
+/// Trimmed value, or None if it is empty
+fn non_empty(value: Option<String>) -> Option<String> {
+    value
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+/// Year of an RFC 3339 timestamp
+fn year_of_rfc3339(value: &str) -> Option<i32> {
+    use chrono::Datelike;
+
+    chrono::DateTime::parse_from_rfc3339(value.trim())
+        .ok()
+        .map(|t| t.year())
+}
+
+// ============================================================================
+// CSV Exports
+// ============================================================================
+
+/// Rows of a CSV export, addressed by header name
+struct CsvTable {
+    /// Lowercased header -> column index
+    columns: HashMap<String, usize>,
+    /// Line number and row (or the reason it could not be read)
+    rows: Vec<(usize, Result<::csv::StringRecord, String>)>,
+}
+
+impl CsvTable {
+    /// Read a CSV export, requiring the given headers (case-insensitive)
+    fn read(contents: &str, required: &[&str]) -> Result<Self, String> {
+        let contents = contents.strip_prefix('\u{feff}').unwrap_or(contents);
+        let mut reader = ::csv::ReaderBuilder::new()
+            .flexible(true)
+            .from_reader(contents.as_bytes());
+
+        let columns: HashMap<String, usize> = reader
+            .headers()
+            .map_err(|e| format!("Invalid CSV header: {}", e))?
+            .iter()
+            .enumerate()
+            .map(|(i, h)| (h.trim().to_lowercase(), i))
+            .collect();
+
+        for header in required {
+            if !columns.contains_key(*header) {
+                return Err(format!("Missing CSV column: {}", header));
+            }
+        }
+
+        let mut rows = Vec::new();
+        let mut last_line = 1;
+        for record in reader.records() {
+            let (line, row) = match record {
+                Ok(record) => {
+                    if record.iter().all(|c| c.trim().is_empty()) {
+                        continue;
+                    }
+                    let line = record.position().map(|p| p.line() as usize);
+                    (line, Ok(record))
+                }
+                Err(e) => {
+                    let line = e.position().map(|p| p.line() as usize);
+                    (line, Err(format!("Invalid CSV row: {}", e)))
+                }
+            };
+            last_line = line.unwrap_or(last_line + 1);
+            rows.push((last_line, row));
+        }
+
+        Ok(CsvTable { columns, rows })
+    }
+
+    /// Value of the first of `headers` present in the file
+    fn get(&self, record: &::csv::StringRecord, headers: &[&str]) -> Option<String> {
+        headers
+            .iter()
+            .find_map(|h| self.columns.get(*h))
+            .and_then(|i| record.get(*i))
+            .map(str::to_string)
+    }
+
+    /// Map every row to a parsed line
+    fn parse_rows<F>(self, to_item: F) -> Vec<ParsedLine>
+    where
+        F: Fn(&CsvTable, &::csv::StringRecord) -> LoginItem,
+    {
+        self.rows
+            .iter()
+            .map(|(line, row)| ParsedLine {
+                line: *line,
+                result: row
+                    .as_ref()
+                    .map_err(Clone::clone)
+                    .and_then(|record| to_item(&self, record).into_account()),
+            })
+            .collect()
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_login_item_mapping() {
+        let item = LoginItem {
+            title: Some("Example".to_string()),
+            urls: vec!["https://example.com".to_string(), " ".to_string()],
+            username: Some(" a@example.com ".to_string()),
+            password: Some("pw".to_string()),
+            totp: Some("".to_string()),
+            notes: Some("note".to_string()),
+            group: Some("Work".to_string()),
+            tags: vec!["vip".to_string(), "VIP".to_string(), "old".to_string()],
+            created_year: Some(2021),
+        };
+
+        let account = item.into_account().unwrap();
+        assert_eq!(account.email, "a@example.com");
+        assert_eq!(account.notes.as_deref(), Some("Example\nhttps://example.com\nnote"));
+        assert_eq!(account.totp_secret, None);
+        assert_eq!(account.tags, vec!["vip", "old"]);
+        assert_eq!(account.year, Some(2021));
+
+        let missing = LoginItem {
+            username: Some("a@example.com".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(missing.into_account().unwrap_err(), "Missing password");
+    }
+
+    #[test]
+    fn test_format_names() {
+        let format: ManagerFormat = serde_json::from_str("\"1password_1pux\"").unwrap();
+        assert_eq!(format, ManagerFormat::OnePassword1pux);
+        assert_eq!(
+            serde_json::to_string(&ManagerFormat::KeepassXml).unwrap(),
+            format!("\"{}\"", ManagerFormat::KeepassXml.source_type())
+        );
+    }
+}
@@ -0,0 +1,237 @@
+//! 1Password export parsers
+//!
+//! Two formats are supported:
+//! - 1PUX: a zip archive whose `export.data` file holds every account's
+//!   vaults as JSON. Vaults map to groups and item tags to tags; the OTP
+//!   secret is read from the item's one-time password field.
+//! - CSV (`Title,Url,Username,Password,OTPAuth,Favorite,Archived,Tags,Notes`),
+//!   which has no vaults, so items get no group.
+//!
+//! Login and Password items are imported; other categories are reported
+//! as errors.
+
+use super::{CsvTable, LoginItem};
+use crate::import::{escape_group_name, ParsedLine};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde_json::Value;
+use std::io::{Cursor, Read};
+
+/// Path of the JSON document inside a 1PUX archive
+const EXPORT_DATA_PATH: &str = "export.data";
+
+/// Largest `export.data` that is read (guards against zip bombs)
+const MAX_EXPORT_DATA_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Item categories that hold credentials (Login, Password)
+const LOGIN_CATEGORIES: [&str; 2] = ["001", "005"];
+
+// ============================================================================
+// 1PUX
+// ============================================================================
+
+/// Extract `export.data` from a base64-encoded 1PUX archive
+///
+/// # Errors
+/// Returns a message if the contents are not a 1PUX archive.
+pub fn read_1pux(contents_base64: &str) -> Result<String, String> {
+    let bytes = BASE64
+        .decode(contents_base64.trim())
+        .map_err(|_| "1PUX contents must be base64-encoded".to_string())?;
+    let mut archive =
+        zip::ZipArchive::new(Cursor::new(bytes)).map_err(|e| format!("Invalid 1PUX archive: {}", e))?;
+    let file = archive
+        .by_name(EXPORT_DATA_PATH)
+        .map_err(|_| format!("Invalid 1PUX archive: missing {}", EXPORT_DATA_PATH))?;
+
+    let mut data = String::new();
+    file.take(MAX_EXPORT_DATA_SIZE + 1)
+        .read_to_string(&mut data)
+        .map_err(|e| format!("Invalid 1PUX archive: {}", e))?;
+    if data.len() as u64 > MAX_EXPORT_DATA_SIZE {
+        return Err("1PUX export is too large".to_string());
+    }
+
+    Ok(data)
+}
+
+/// Parse the `export.data` JSON of a 1PUX archive
+///
+/// Items are numbered from 1 across all accounts and vaults.
+///
+/// # Errors
+/// Returns a message if the document is not a 1Password export.
+pub fn parse_export_data(data: &str) -> Result<Vec<ParsedLine>, String> {
+    let export: Value = serde_json::from_str(data).map_err(|e| format!("Invalid 1PUX export: {}", e))?;
+    let accounts = export["accounts"]
+        .as_array()
+        .ok_or_else(|| "Invalid 1PUX export: missing accounts".to_string())?;
+
+    let mut lines = Vec::new();
+    for vault in accounts.iter().flat_map(|a| array(&a["vaults"])) {
+        let vault_name = string(&vault["attrs"]["name"]).map(|name| escape_group_name(&name));
+        for item in array(&vault["items"]) {
+            lines.push(ParsedLine {
+                line: lines.len() + 1,
+                result: item_to_login(item, vault_name.clone()).and_then(LoginItem::into_account),
+            });
+        }
+    }
+
+    Ok(lines)
+}
+
+fn item_to_login(item: &Value, vault_name: Option<String>) -> Result<LoginItem, String> {
+    let category = item["categoryUuid"].as_str().unwrap_or_default();
+    if !LOGIN_CATEGORIES.contains(&category) {
+        return Err("Not a login item".to_string());
+    }
+
+    let details = &item["details"];
+    let overview = &item["overview"];
+    let login_field = |designation: &str| {
+        array(&details["loginFields"])
+            .iter()
+            .find(|f| f["designation"].as_str() == Some(designation))
+            .and_then(|f| string(&f["value"]))
+    };
+
+    let mut urls: Vec<String> = array(&overview["urls"])
+        .iter()
+        .filter_map(|u| string(&u["url"]))
+        .collect();
+    if urls.is_empty() {
+        urls.extend(string(&overview["url"]));
+    }
+
+    Ok(LoginItem {
+        title: string(&overview["title"]),
+        urls,
+        username: login_field("username"),
+        password: login_field("password").or_else(|| string(&details["password"])),
+        totp: array(&details["sections"])
+            .iter()
+            .flat_map(|s| array(&s["fields"]))
+            .find_map(|f| string(&f["value"]["totp"])),
+        notes: string(&details["notesPlain"]),
+        group: vault_name,
+        tags: array(&overview["tags"]).iter().filter_map(string).collect(),
+        created_year: item["createdAt"]
+            .as_i64()
+            .and_then(|secs| chrono::DateTime::from_timestamp(secs, 0))
+            .map(|t| chrono::Datelike::year(&t)),
+    })
+}
+
+fn array(value: &Value) -> &[Value] {
+    value.as_array().map(Vec::as_slice).unwrap_or_default()
+}
+
+fn string(value: &Value) -> Option<String> {
+    value.as_str().map(str::to_string)
+}
+
+// ============================================================================
+// CSV
+// ============================================================================
+
+/// Parse a 1Password CSV export
+///
+/// # Errors
+/// Returns a message if the header lacks the username or password column.
+pub fn parse_csv(contents: &str) -> Result<Vec<ParsedLine>, String> {
+    let table = CsvTable::read(contents, &["username", "password"])?;
+
+    Ok(table.parse_rows(|table, record| LoginItem {
+        title: table.get(record, &["title"]),
+        urls: table.get(record, &["url", "website"]).into_iter().collect(),
+        username: table.get(record, &["username"]),
+        password: table.get(record, &["password"]),
+        totp: table.get(record, &["otpauth", "one-time password"]),
+        notes: table.get(record, &["notes", "notesplain"]),
+        group: None,
+        tags: table
+            .get(record, &["tags"])
+            .map(|tags| tags.split([';', ',']).map(str::to_string).collect())
+            .unwrap_or_default(),
+        created_year: None,
+    }))
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    const EXPORT_DATA: &str = include_str!("fixtures/1password_export.data");
+    const CSV_FIXTURE: &str = include_str!("fixtures/1password.csv");
+
+    /// Wrap the fixture in a 1PUX archive, as the frontend would send it
+    fn fixture_1pux() -> String {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Stored);
+        writer.start_file("export.attributes", options).unwrap();
+        writer.write_all(br#"{"version":3,"description":"1Password Unencrypted Export"}"#).unwrap();
+        writer.start_file(EXPORT_DATA_PATH, options).unwrap();
+        writer.write_all(EXPORT_DATA.as_bytes()).unwrap();
+        BASE64.encode(writer.finish().unwrap().into_inner())
+    }
+
+    #[test]
+    fn test_parse_1pux_fixture() {
+        let data = read_1pux(&fixture_1pux()).unwrap();
+        assert_eq!(data, EXPORT_DATA);
+
+        let lines = parse_export_data(&data).unwrap();
+        assert_eq!(lines.len(), 4);
+
+        let shop = lines[0].result.as_ref().unwrap();
+        assert_eq!(shop.email, "alice@example.com");
+        assert_eq!(shop.password, "1p-secret");
+        assert!(shop.totp_secret.as_deref().unwrap().contains("JBSWY3DPEHPK3PXP"));
+        assert_eq!(shop.group.as_deref(), Some("Personal"));
+        assert_eq!(shop.tags, vec!["shopping", "family"]);
+        assert_eq!(shop.year, Some(2021));
+        assert_eq!(shop.notes.as_deref(), Some("Shop\nhttps://shop.example.com\nShopping account"));
+
+        // Password items have no username
+        assert_eq!(lines[1].result.as_ref().unwrap_err(), "Missing username");
+        assert_eq!(lines[2].result.as_ref().unwrap_err(), "Not a login item");
+
+        let intranet = lines[3].result.as_ref().unwrap();
+        assert_eq!(lines[3].line, 4);
+        assert_eq!(intranet.group.as_deref(), Some("Work"));
+        assert_eq!(intranet.notes.as_deref(), Some("Intranet\nhttps://intranet.example.com"));
+    }
+
+    #[test]
+    fn test_rejects_invalid_1pux() {
+        assert!(read_1pux("not base64!").is_err());
+        assert!(read_1pux(&BASE64.encode(b"not a zip")).is_err());
+        assert!(parse_export_data(r#"{"vaults": []}"#).is_err());
+    }
+
+    #[test]
+    fn test_parse_csv_fixture() {
+        let lines = parse_csv(CSV_FIXTURE).unwrap();
+        assert_eq!(lines.len(), 3);
+
+        let shop = lines[0].result.as_ref().unwrap();
+        assert_eq!(lines[0].line, 2);
+        assert_eq!(shop.email, "alice@example.com");
+        assert!(shop.totp_secret.is_some());
+        assert_eq!(shop.tags, vec!["shopping", "family"]);
+        assert_eq!(shop.group, None);
+
+        let intranet = lines[1].result.as_ref().unwrap();
+        assert_eq!(intranet.password, "pw,with,commas");
+        assert_eq!(intranet.notes.as_deref(), Some("Intranet\nhttps://intranet.example.com\nTwo\nlines"));
+
+        assert_eq!(lines[2].line, 5);
+        assert_eq!(lines[2].result.as_ref().unwrap_err(), "Missing username");
+    }
+}
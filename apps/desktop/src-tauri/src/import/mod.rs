@@ -0,0 +1,646 @@
+//! Account import pipeline for GManager Desktop
+//!
+//! Imports run in two steps:
+//! 1. A format parser turns the source into `ParsedAccount`s, reporting
+//!    every line it could not use instead of failing the whole import
+//! 2. `persist_import` stores the original source in `raw_imports` and
+//!    creates the encrypted accounts linked to it by `raw_import_id`, all in
+//!    one transaction
+//!
+//! A dry run stops after step 1 and only returns the preview.
+//!
+//! # Security
+//!
+//! The original source contains plaintext passwords, so it is encrypted with
+//! the session key before being stored in `raw_imports.raw_text`. Previews
+//! never include passwords or TOTP secrets, only whether they were found.
+
+pub mod csv;
+pub mod managers;
+pub mod text;
+
+use crate::accounts::{self, AccountError, CreateAccountPayload};
+use crate::auth::SessionManager;
+use crate::crypto::{encrypt_field, CryptoError, SecretKey};
+use crate::db::{Database, DbError, ImportedAccount};
+use chrono::Datelike;
+
+// ============================================================================
+// Constants
+// ============================================================================
+
+/// `raw_imports.source_type` for pasted text
+pub const SOURCE_TYPE_TEXT: &str = "text";
+
+/// `raw_imports.source_type` for CSV files
+pub const SOURCE_TYPE_CSV: &str = "csv";
+
+/// Separator between nested group names in a group path (`Work/Servers`)
+pub const GROUP_PATH_SEPARATOR: char = '/';
+
+/// Escapes a separator or itself inside a group name in a group path
+const GROUP_PATH_ESCAPE: char = '\\';
+
+// ============================================================================
+// Type Definitions
+// ============================================================================
+
+/// Plaintext account produced by a format parser
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ParsedAccount {
+    pub email: String,
+    pub password: String,
+    pub recovery_email: Option<String>,
+    pub totp_secret: Option<String>,
+    pub year: Option<i32>,
+    pub notes: Option<String>,
+    /// Group path from the source, see [`split_group_path`] (overrides the
+    /// import's group)
+    pub group: Option<String>,
+    /// Tag names from the source
+    pub tags: Vec<String>,
+}
+
+/// Result of parsing a single record of the source
+#[derive(Debug, Clone)]
+pub struct ParsedLine {
+    /// 1-based line number in the original source (item number for
+    /// JSON sources)
+    pub line: usize,
+    /// Parsed account or the reason the record was rejected
+    pub result: Result<ParsedAccount, String>,
+}
+
+/// Text import request from frontend
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct TextImportRequest {
+    pub text: String,
+    /// Field delimiter; detected when omitted
+    pub delimiter: Option<String>,
+    pub source_name: Option<String>,
+    /// Group for the imported accounts
+    pub group_id: Option<String>,
+    /// Only parse and preview, without writing anything
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// CSV import request from frontend
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct CsvImportRequest {
+    /// File contents; the first row must be a header
+    pub contents: String,
+    /// Header-to-field mapping; headers named like the fields are mapped
+    /// automatically when omitted
+    pub columns: Option<Vec<csv::CsvColumn>>,
+    /// Single-character delimiter (default `,`)
+    pub delimiter: Option<String>,
+    pub source_name: Option<String>,
+    /// Group for rows without a group column value
+    pub group_id: Option<String>,
+    /// Only parse and preview, without writing anything
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// Password manager export import request from frontend
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct ManagerImportRequest {
+    pub format: managers::ManagerFormat,
+    /// File contents; base64-encoded for binary formats (1PUX)
+    pub contents: String,
+    pub source_name: Option<String>,
+    /// Group for items without a folder
+    pub group_id: Option<String>,
+    /// Only parse and preview, without writing anything
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// Original source of an import, stored encrypted in `raw_imports`
+#[derive(Debug, Clone)]
+pub struct ImportSource<'a> {
+    pub raw_text: &'a str,
+    pub source_type: &'a str,
+    pub source_name: Option<String>,
+}
+
+/// A line that could not be imported
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct LineError {
+    pub line: usize,
+    pub message: String,
+}
+
+/// Preview of a parsed account (secrets omitted)
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ImportPreview {
+    pub line: usize,
+    pub email: String,
+    pub recovery_email: Option<String>,
+    pub year: Option<String>,
+    pub notes: Option<String>,
+    pub group: Option<String>,
+    pub tags: Vec<String>,
+    pub has_password: bool,
+    pub has_totp_secret: bool,
+}
+
+/// Outcome of an import or dry run
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    /// Raw import the accounts are linked to (None for dry runs or when
+    /// no line could be imported)
+    pub raw_import_id: Option<String>,
+    /// Number of records (non-empty lines for text)
+    pub total_lines: usize,
+    pub imported_count: usize,
+    /// IDs of the created accounts, in line order
+    pub account_ids: Vec<String>,
+    pub accounts: Vec<ImportPreview>,
+    pub errors: Vec<LineError>,
+}
+
+// ============================================================================
+// Error Types
+// ============================================================================
+
+/// Import error type
+#[derive(Debug, thiserror::Error)]
+pub enum ImportError {
+    /// Database error
+    #[error("Database error: {0}")]
+    Database(#[from] DbError),
+
+    /// Cryptographic error
+    #[error("Crypto error: {0}")]
+    Crypto(#[from] CryptoError),
+
+    /// Error converting or indexing an account
+    #[error("Account error: {0}")]
+    Account(#[from] AccountError),
+
+    /// Invalid input
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+}
+
+/// Result type for import operations
+pub type ImportResult<T> = std::result::Result<T, ImportError>;
+
+// ============================================================================
+// Import Operations
+// ============================================================================
+
+impl ParsedAccount {
+    fn preview(&self, line: usize) -> ImportPreview {
+        ImportPreview {
+            line,
+            email: self.email.clone(),
+            recovery_email: self.recovery_email.clone(),
+            year: self.year.map(|y| y.to_string()),
+            notes: self.notes.clone(),
+            group: self.group.clone(),
+            tags: self.tags.clone(),
+            has_password: !self.password.is_empty(),
+            has_totp_secret: self.totp_secret.is_some(),
+        }
+    }
+
+    fn into_payload(self, group_id: Option<i64>) -> CreateAccountPayload {
+        CreateAccountPayload {
+            raw_import_id: None,
+            email: self.email,
+            password: self.password,
+            recovery_email: self.recovery_email,
+            totp_secret: self.totp_secret,
+            year: self.year.map(|y| y.to_string()),
+            notes: self.notes,
+            group_id: group_id.map(|id| id.to_string()),
+            field_order: None,
+        }
+    }
+}
+
+/// Parse pasted text and, unless it is a dry run, import the valid lines
+pub fn import_text(db: &Database, key: &SecretKey, request: TextImportRequest) -> ImportResult<ImportReport> {
+    let group_id = parse_group_id(db, request.group_id.as_deref())?;
+    let current_year = chrono::Local::now().year();
+
+    let lines = text::parse_text(&request.text, request.delimiter.as_deref(), current_year);
+
+    let source = ImportSource {
+        raw_text: &request.text,
+        source_type: SOURCE_TYPE_TEXT,
+        source_name: request.source_name,
+    };
+    finish_import(db, key, source, group_id, lines, request.dry_run)
+}
+
+/// Parse a CSV file and, unless it is a dry run, import the valid rows
+pub fn import_csv(db: &Database, key: &SecretKey, request: CsvImportRequest) -> ImportResult<ImportReport> {
+    let group_id = parse_group_id(db, request.group_id.as_deref())?;
+    let delimiter = match request.delimiter.as_deref() {
+        None => None,
+        Some(d) if d.len() == 1 => d.bytes().next(),
+        Some(d) => {
+            return Err(ImportError::InvalidInput(format!("Invalid CSV delimiter: {:?}", d)));
+        }
+    };
+
+    let rows = csv::parse_csv(&request.contents, request.columns.as_deref(), delimiter)
+        .map_err(ImportError::InvalidInput)?;
+
+    let source = ImportSource {
+        raw_text: &request.contents,
+        source_type: SOURCE_TYPE_CSV,
+        source_name: request.source_name,
+    };
+    finish_import(db, key, source, group_id, rows, request.dry_run)
+}
+
+/// Parse another password manager's export and, unless it is a dry run,
+/// import its login items
+pub fn import_manager_export(
+    db: &Database,
+    key: &SecretKey,
+    request: ManagerImportRequest,
+) -> ImportResult<ImportReport> {
+    let group_id = parse_group_id(db, request.group_id.as_deref())?;
+
+    let export = managers::parse_export(request.format, &request.contents)
+        .map_err(ImportError::InvalidInput)?;
+
+    let source = ImportSource {
+        raw_text: &export.raw_text,
+        source_type: request.format.source_type(),
+        source_name: request.source_name,
+    };
+    finish_import(db, key, source, group_id, export.lines, request.dry_run)
+}
+
+/// Split parsed records into previews and errors and, unless it is a dry
+/// run, persist the valid ones
+pub fn finish_import(
+    db: &Database,
+    key: &SecretKey,
+    source: ImportSource,
+    group_id: Option<i64>,
+    lines: Vec<ParsedLine>,
+    dry_run: bool,
+) -> ImportResult<ImportReport> {
+    let total_lines = lines.len();
+
+    let mut accounts = Vec::new();
+    let mut errors = Vec::new();
+    for parsed in lines {
+        match parsed.result {
+            Ok(account) => accounts.push((parsed.line, account)),
+            Err(message) => errors.push(LineError { line: parsed.line, message }),
+        }
+    }
+
+    let previews = accounts.iter().map(|(line, account)| account.preview(*line)).collect();
+
+    if dry_run || accounts.is_empty() {
+        return Ok(ImportReport {
+            dry_run,
+            raw_import_id: None,
+            total_lines,
+            imported_count: 0,
+            account_ids: Vec::new(),
+            accounts: previews,
+            errors,
+        });
+    }
+
+    let parsed: Vec<ParsedAccount> = accounts.into_iter().map(|(_, account)| account).collect();
+    let (raw_import_id, ids) = persist_import(
+        db,
+        key,
+        source.raw_text,
+        source.source_type,
+        source.source_name,
+        group_id,
+        parsed,
+    )?;
+
+    Ok(ImportReport {
+        dry_run: false,
+        raw_import_id: Some(raw_import_id.to_string()),
+        total_lines,
+        imported_count: ids.len(),
+        account_ids: ids.iter().map(|id| id.to_string()).collect(),
+        accounts: previews,
+        errors,
+    })
+}
+
+/// Store the encrypted source and its accounts in one transaction, then
+/// index the new accounts for search
+///
+/// Returns the raw import ID and the account IDs in input order.
+pub fn persist_import(
+    db: &Database,
+    key: &SecretKey,
+    raw_text: &str,
+    source_type: &str,
+    source_name: Option<String>,
+    group_id: Option<i64>,
+    accounts: Vec<ParsedAccount>,
+) -> ImportResult<(i64, Vec<i64>)> {
+    let encrypted_raw_text = encrypt_field(raw_text, key)?;
+
+    let creates = accounts
+        .into_iter()
+        .map(|mut account| {
+            let group_path = account.group.take().map(|path| split_group_path(&path)).unwrap_or_default();
+            let tag_names = std::mem::take(&mut account.tags);
+            let search_tokens = accounts::written_field_tokens(
+                key,
+                Some(&account.email),
+                account.recovery_email.as_deref(),
+                account.notes.as_deref(),
+            );
+            Ok(ImportedAccount {
+                account: accounts::frontend_to_db_create(account.into_payload(group_id), key)?,
+                group_path,
+                tag_names,
+                search_tokens,
+            })
+        })
+        .collect::<ImportResult<Vec<_>>>()?;
+
+    let (raw_import_id, ids) = db.import_accounts(
+        encrypted_raw_text,
+        source_type.to_string(),
+        source_name,
+        creates,
+    )?;
+
+    Ok((raw_import_id, ids))
+}
+
+/// Escape a group name for use as one segment of a group path
+pub fn escape_group_name(name: &str) -> String {
+    let mut escaped = String::with_capacity(name.len());
+    for c in name.chars() {
+        if c == GROUP_PATH_SEPARATOR || c == GROUP_PATH_ESCAPE {
+            escaped.push(GROUP_PATH_ESCAPE);
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Split a group path (`Work/Servers`) into group names, top level first
+///
+/// Reverses [`escape_group_name`]. Names are trimmed and empty ones are
+/// dropped, so a blank path yields no group.
+pub fn split_group_path(path: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut current = String::new();
+    let mut chars = path.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            GROUP_PATH_ESCAPE => current.extend(chars.next()),
+            GROUP_PATH_SEPARATOR => names.push(std::mem::take(&mut current)),
+            _ => current.push(c),
+        }
+    }
+    names.push(current);
+
+    names
+        .into_iter()
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+/// Parse an optional group ID and check that the group exists
+fn parse_group_id(db: &Database, group_id: Option<&str>) -> ImportResult<Option<i64>> {
+    let Some(group_id) = group_id else {
+        return Ok(None);
+    };
+
+    let id = group_id
+        .parse::<i64>()
+        .map_err(|_| ImportError::InvalidInput("Invalid group ID".to_string()))?;
+
+    match db.get_group(id) {
+        Ok(_) => Ok(Some(id)),
+        Err(DbError::NotFound(_)) => Err(ImportError::InvalidInput(format!("Group {} not found", id))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+// ============================================================================
+// Tauri Commands
+// ============================================================================
+
+/// Import accounts from pasted text
+///
+/// With `dry_run` set, returns the preview and per-line errors without
+/// writing anything.
+#[tauri::command]
+pub fn import_text_command(
+    db: tauri::State<Database>,
+    session_manager: tauri::State<SessionManager>,
+    request: TextImportRequest,
+) -> Result<ImportReport, String> {
+    let key = session_manager.get_session_key()
+        .map_err(|e| e.to_string())?;
+
+    import_text(&db, &key, request)
+        .map_err(|e| e.to_string())
+}
+
+/// Import accounts from a CSV file
+///
+/// Each row becomes an account; rows that cannot be used are reported with
+/// their line number. With `dry_run` set, nothing is written.
+#[tauri::command]
+pub fn import_csv_command(
+    db: tauri::State<Database>,
+    session_manager: tauri::State<SessionManager>,
+    request: CsvImportRequest,
+) -> Result<ImportReport, String> {
+    let key = session_manager.get_session_key()
+        .map_err(|e| e.to_string())?;
+
+    import_csv(&db, &key, request)
+        .map_err(|e| e.to_string())
+}
+
+/// Import accounts from another password manager's export file
+///
+/// Supports Bitwarden JSON, KeePass XML, 1Password 1PUX/CSV and Chrome or
+/// Firefox CSV exports. Items that are not logins or lack a username or
+/// password are reported as errors. With `dry_run` set, nothing is written.
+#[tauri::command]
+pub fn import_manager_export_command(
+    db: tauri::State<Database>,
+    session_manager: tauri::State<SessionManager>,
+    request: ManagerImportRequest,
+) -> Result<ImportReport, String> {
+    let key = session_manager.get_session_key()
+        .map_err(|e| e.to_string())?;
+
+    import_manager_export(&db, &key, request)
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::{decrypt_field, derive_key};
+
+    fn get_test_db() -> Database {
+        Database::init_in_memory().expect("Failed to create test database")
+    }
+
+    fn get_test_key() -> SecretKey {
+        derive_key(b"test-password", b"test-salt-16byte")
+    }
+
+    fn request(text: &str, dry_run: bool) -> TextImportRequest {
+        TextImportRequest {
+            text: text.to_string(),
+            delimiter: None,
+            source_name: Some("paste".to_string()),
+            group_id: None,
+            dry_run,
+        }
+    }
+
+    const SAMPLE: &str = "a@example.com----pw1----JBSWY3DPEHPK3PXP\nbroken line\nb@example.com----pw2----2020";
+
+    #[test]
+    fn test_dry_run_writes_nothing() {
+        let db = get_test_db();
+        let key = get_test_key();
+
+        let report = import_text(&db, &key, request(SAMPLE, true)).unwrap();
+
+        assert!(report.dry_run);
+        assert_eq!(report.total_lines, 3);
+        assert_eq!(report.imported_count, 0);
+        assert_eq!(report.raw_import_id, None);
+        assert_eq!(report.accounts.len(), 2);
+        assert!(report.accounts[0].has_totp_secret);
+        assert_eq!(report.accounts[1].year.as_deref(), Some("2020"));
+        assert_eq!(report.errors, vec![LineError { line: 2, message: "No email address found".to_string() }]);
+
+        assert_eq!(db.get_accounts_count().unwrap(), 0);
+        let raw_imports: i64 = db.get_conn().unwrap()
+            .query_row("SELECT COUNT(*) FROM raw_imports", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(raw_imports, 0);
+    }
+
+    #[test]
+    fn test_import_creates_encrypted_linked_accounts() {
+        let db = get_test_db();
+        let key = get_test_key();
+
+        let report = import_text(&db, &key, request(SAMPLE, false)).unwrap();
+
+        assert_eq!(report.imported_count, 2);
+        assert_eq!(report.errors.len(), 1);
+        let raw_import_id: i64 = report.raw_import_id.unwrap().parse().unwrap();
+
+        // The original text is stored encrypted
+        let raw_import = db.get_raw_import(raw_import_id).unwrap();
+        assert_ne!(raw_import.raw_text, SAMPLE);
+        assert_eq!(decrypt_field(&raw_import.raw_text, &key).unwrap(), SAMPLE);
+        assert_eq!(raw_import.source_type, SOURCE_TYPE_TEXT);
+        assert_eq!(raw_import.source_name.as_deref(), Some("paste"));
+
+        let id: i64 = report.account_ids[0].parse().unwrap();
+        let account = db.get_account(id).unwrap();
+        assert_eq!(account.raw_import_id, Some(raw_import_id));
+        assert_eq!(decrypt_field(&account.email, &key).unwrap(), "a@example.com");
+        assert_eq!(decrypt_field(&account.password, &key).unwrap(), "pw1");
+        assert_eq!(
+            decrypt_field(account.totp_secret.as_ref().unwrap(), &key).unwrap(),
+            "JBSWY3DPEHPK3PXP"
+        );
+
+        // Imported accounts are searchable
+        assert!(db.get_unindexed_account_ids().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_nothing_stored_when_no_line_is_valid() {
+        let db = get_test_db();
+        let key = get_test_key();
+
+        let report = import_text(&db, &key, request("nope\nstill nope", false)).unwrap();
+
+        assert_eq!(report.imported_count, 0);
+        assert_eq!(report.raw_import_id, None);
+        assert_eq!(report.errors.len(), 2);
+    }
+
+    #[test]
+    fn test_unknown_group_rejected() {
+        let db = get_test_db();
+        let key = get_test_key();
+
+        let mut req = request(SAMPLE, false);
+        req.group_id = Some("999".to_string());
+
+        assert!(matches!(import_text(&db, &key, req), Err(ImportError::InvalidInput(_))));
+        assert_eq!(db.get_accounts_count().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_manager_export_records_source_and_labels() {
+        let db = get_test_db();
+        let key = get_test_key();
+        let contents = include_str!("managers/fixtures/bitwarden.json");
+
+        let report = import_manager_export(
+            &db,
+            &key,
+            ManagerImportRequest {
+                format: managers::ManagerFormat::BitwardenJson,
+                contents: contents.to_string(),
+                source_name: Some("bitwarden_export.json".to_string()),
+                group_id: None,
+                dry_run: false,
+            },
+        )
+        .unwrap();
+
+        assert_eq!(report.imported_count, 2);
+        assert_eq!(report.errors.len(), 2);
+
+        let raw_import = db.get_raw_import(report.raw_import_id.unwrap().parse().unwrap()).unwrap();
+        assert_eq!(raw_import.source_type, "bitwarden_json");
+        assert_eq!(raw_import.source_name.as_deref(), Some("bitwarden_export.json"));
+        assert_eq!(decrypt_field(&raw_import.raw_text, &key).unwrap(), contents);
+
+        // The folder and collections were created and linked
+        let id: i64 = report.account_ids[0].parse().unwrap();
+        let account = db.get_account(id).unwrap();
+        assert_eq!(db.get_group(account.group_id.unwrap()).unwrap().name, "Work");
+        let mut tags: Vec<String> = account.tags.into_iter().map(|t| t.name).collect();
+        tags.sort();
+        assert_eq!(tags, vec!["Finance", "Shared"]);
+        assert!(decrypt_field(account.totp_secret.as_ref().unwrap(), &key).unwrap().starts_with("otpauth://"));
+    }
+
+    #[test]
+    fn test_group_paths_round_trip_escaped_names() {
+        assert_eq!(split_group_path("Work / Servers"), vec!["Work", "Servers"]);
+        assert_eq!(split_group_path(" /Work//"), vec!["Work"]);
+        assert!(split_group_path("  ").is_empty());
+
+        let names = ["A/B", "C\\D", "E"];
+        let path = names.map(escape_group_name).join("/");
+        assert_eq!(path, "A\\/B/C\\\\D/E");
+        assert_eq!(split_group_path(&path), names);
+    }
+}
@@ -0,0 +1,379 @@
+//! Plain text account list parser
+//!
+//! Parses pasted account lists with one account per line, for example:
+//!
+//! ```text
+//! user@gmail.com----hunter2----backup@mail.com----JBSWY3DPEHPK3PXP----2019
+//! other@gmail.com|p4ss|2021
+//! ```
+//!
+//! # Delimiters
+//!
+//! The delimiter is detected once for the whole text from `----`, tab, `|`,
+//! `;`, `,` and `:` (in that order of preference): the candidate found on
+//! the most lines wins, unless more lines contain whitespace, in which case
+//! fields are split on whitespace. `otpauth://` URIs are kept whole even
+//! when the delimiter is `:`.
+//!
+//! # Field Classification
+//!
+//! Fields are classified by content rather than position:
+//! - the first email address is the login, the second the recovery email
+//! - a four digit year between 1990 and the current year is the year
+//! - an `otpauth://` URI, or a base32 string of at least 16 characters after
+//!   the password, is the TOTP secret
+//! - the first remaining field is the password
+//! - anything else is collected into the notes
+
+use super::{ParsedAccount, ParsedLine};
+use crate::totp::TotpConfig;
+
+// ============================================================================
+// Constants
+// ============================================================================
+
+/// Delimiter candidates in order of preference
+const DELIMITERS: &[&str] = &["----", "\t", "|", ";", ",", ":"];
+
+/// Start of a TOTP provisioning URI
+const OTPAUTH_PREFIX: &str = "otpauth://";
+
+/// Earliest year accepted as an account creation year
+const MIN_YEAR: i32 = 1990;
+
+/// Minimum length of a bare base32 TOTP secret (80 bits)
+const MIN_TOTP_SECRET_LEN: usize = 16;
+
+// ============================================================================
+// Types
+// ============================================================================
+
+/// Field delimiter used to split lines
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Delimiter {
+    /// A fixed separator string
+    Literal(&'static str),
+    /// Runs of whitespace
+    Whitespace,
+}
+
+// ============================================================================
+// Parsing
+// ============================================================================
+
+/// Parse a pasted account list
+///
+/// `delimiter` overrides detection when set; blank lines are skipped.
+pub fn parse_text(text: &str, delimiter: Option<&str>, current_year: i32) -> Vec<ParsedLine> {
+    let lines: Vec<(usize, &str)> = text
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty())
+        .collect();
+
+    let delimiter = match delimiter.filter(|d| !d.is_empty()) {
+        Some(explicit) => DelimiterRef::Explicit(explicit),
+        None => {
+            let texts: Vec<&str> = lines.iter().map(|(_, line)| *line).collect();
+            DelimiterRef::Detected(detect_delimiter(&texts))
+        }
+    };
+
+    lines
+        .into_iter()
+        .map(|(line, text)| {
+            let fields = delimiter.split(text);
+            ParsedLine {
+                line,
+                result: classify_fields(&fields, current_year),
+            }
+        })
+        .collect()
+}
+
+/// Pick the delimiter used by most lines
+pub fn detect_delimiter(lines: &[&str]) -> Delimiter {
+    let mut best: Option<(&'static str, usize)> = None;
+
+    for candidate in DELIMITERS {
+        let count = lines
+            .iter()
+            .filter(|line| strip_otpauth(line).contains(candidate))
+            .count();
+
+        if count > 0 && best.is_none_or(|(_, best_count)| count > best_count) {
+            best = Some((candidate, count));
+        }
+    }
+
+    let whitespace_count = lines
+        .iter()
+        .filter(|line| strip_otpauth(line).contains(char::is_whitespace))
+        .count();
+
+    match best {
+        Some((candidate, count)) if count >= whitespace_count => Delimiter::Literal(candidate),
+        _ => Delimiter::Whitespace,
+    }
+}
+
+/// Classify the fields of one line into an account
+pub fn classify_fields(fields: &[String], current_year: i32) -> Result<ParsedAccount, String> {
+    let mut email: Option<String> = None;
+    let mut recovery_email: Option<String> = None;
+    let mut password: Option<String> = None;
+    let mut totp_secret: Option<String> = None;
+    let mut year: Option<i32> = None;
+    let mut notes: Vec<String> = Vec::new();
+
+    for field in fields.iter().map(|f| f.trim()).filter(|f| !f.is_empty()) {
+        // Provisioning URIs usually contain the account email, so they are
+        // recognised before emails
+        if totp_secret.is_none() && field.starts_with(OTPAUTH_PREFIX) && is_totp_secret(field) {
+            totp_secret = Some(field.to_string());
+        } else if is_email(field) {
+            if email.is_none() {
+                email = Some(field.to_string());
+            } else if recovery_email.is_none() {
+                recovery_email = Some(field.to_string());
+            } else {
+                notes.push(field.to_string());
+            }
+        } else if year.is_none() && parse_year(field, current_year).is_some() {
+            year = parse_year(field, current_year);
+        } else if totp_secret.is_none() && password.is_some() && is_totp_secret(field) {
+            totp_secret = Some(field.to_string());
+        } else if password.is_none() {
+            password = Some(field.to_string());
+        } else {
+            notes.push(field.to_string());
+        }
+    }
+
+    let email = email.ok_or_else(|| "No email address found".to_string())?;
+    let password = password.ok_or_else(|| "No password found".to_string())?;
+
+    Ok(ParsedAccount {
+        email,
+        password,
+        recovery_email,
+        totp_secret,
+        year,
+        notes: if notes.is_empty() { None } else { Some(notes.join(" ")) },
+        ..Default::default()
+    })
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+/// Delimiter chosen for a parse, either given by the caller or detected
+enum DelimiterRef<'a> {
+    Explicit(&'a str),
+    Detected(Delimiter),
+}
+
+impl DelimiterRef<'_> {
+    /// Split a line into fields, keeping an `otpauth://` URI in one piece
+    fn split(&self, line: &str) -> Vec<String> {
+        let (head, uri, tail) = match line.find(OTPAUTH_PREFIX) {
+            Some(start) => {
+                let rest = &line[start..];
+                // With ':' the URI itself contains the delimiter, so it runs
+                // to the end of the line
+                let end = match self {
+                    DelimiterRef::Explicit(d) | DelimiterRef::Detected(Delimiter::Literal(d))
+                        if *d != ":" =>
+                    {
+                        rest.find(d).unwrap_or(rest.len())
+                    }
+                    DelimiterRef::Detected(Delimiter::Whitespace) => {
+                        rest.find(char::is_whitespace).unwrap_or(rest.len())
+                    }
+                    _ => rest.len(),
+                };
+                (&line[..start], Some(&rest[..end]), &rest[end..])
+            }
+            None => (line, None, ""),
+        };
+
+        let mut fields = self.split_plain(head);
+        fields.extend(uri.map(str::to_string));
+        fields.extend(self.split_plain(tail));
+        fields.retain(|f| !f.trim().is_empty());
+        fields
+    }
+
+    fn split_plain(&self, text: &str) -> Vec<String> {
+        match self {
+            DelimiterRef::Explicit(d) | DelimiterRef::Detected(Delimiter::Literal(d)) => {
+                text.split(d).map(|f| f.trim().to_string()).collect()
+            }
+            DelimiterRef::Detected(Delimiter::Whitespace) => {
+                text.split_whitespace().map(str::to_string).collect()
+            }
+        }
+    }
+}
+
+/// Line text without any `otpauth://` URI (its ':' and '&' are not delimiters)
+fn strip_otpauth(line: &str) -> &str {
+    match line.find(OTPAUTH_PREFIX) {
+        Some(start) => &line[..start],
+        None => line,
+    }
+}
+
+/// Loose email check: one '@', non-empty local part, dotted domain
+fn is_email(value: &str) -> bool {
+    if value.chars().any(char::is_whitespace) {
+        return false;
+    }
+
+    match value.split_once('@') {
+        Some((local, domain)) => {
+            !local.is_empty()
+                && !domain.contains('@')
+                && domain.contains('.')
+                && !domain.starts_with('.')
+                && !domain.ends_with('.')
+        }
+        None => false,
+    }
+}
+
+/// Parse a plausible account creation year
+fn parse_year(value: &str, current_year: i32) -> Option<i32> {
+    if value.len() != 4 || !value.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    value
+        .parse::<i32>()
+        .ok()
+        .filter(|year| (MIN_YEAR..=current_year).contains(year))
+}
+
+/// Whether a field looks like a TOTP secret
+///
+/// Bare secrets must be single-case base32 so ordinary words in the notes
+/// are not mistaken for secrets.
+fn is_totp_secret(value: &str) -> bool {
+    if value.starts_with(OTPAUTH_PREFIX) {
+        return TotpConfig::parse(value).is_ok();
+    }
+
+    let compact: String = value.chars().filter(|c| *c != ' ' && *c != '-').collect();
+    let single_case = compact.chars().all(|c| !c.is_ascii_lowercase())
+        || compact.chars().all(|c| !c.is_ascii_uppercase());
+
+    compact.trim_end_matches('=').len() >= MIN_TOTP_SECRET_LEN
+        && single_case
+        && compact.chars().all(|c| c.is_ascii_alphabetic() || ('2'..='7').contains(&c) || c == '=')
+        && TotpConfig::parse(value).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const YEAR: i32 = 2024;
+
+    #[test]
+    fn test_detect_delimiter() {
+        assert_eq!(detect_delimiter(&["a@b.com----pw", "c@d.com----pw2"]), Delimiter::Literal("----"));
+        assert_eq!(detect_delimiter(&["a@b.com\tpw", "c@d.com\tpw2"]), Delimiter::Literal("\t"));
+        assert_eq!(detect_delimiter(&["a@b.com|pw|2020", "c@d.com|pw,x"]), Delimiter::Literal("|"));
+        assert_eq!(detect_delimiter(&["a@b.com:pw", "c@d.com:pw2"]), Delimiter::Literal(":"));
+        assert_eq!(detect_delimiter(&["a@b.com pw", "c@d.com pw2"]), Delimiter::Whitespace);
+
+        // A stray separator in one line of many does not win
+        assert_eq!(
+            detect_delimiter(&["a@b.com pw", "c@d.com pw2", "e@f.com pw3 a,b"]),
+            Delimiter::Whitespace
+        );
+    }
+
+    #[test]
+    fn test_classifies_fields_by_content() {
+        let lines = parse_text(
+            "user@gmail.com----hunter2----backup@mail.com----JBSWY3DPEHPK3PXP----2019",
+            None,
+            YEAR,
+        );
+        let account = lines[0].result.as_ref().unwrap();
+
+        assert_eq!(account.email, "user@gmail.com");
+        assert_eq!(account.password, "hunter2");
+        assert_eq!(account.recovery_email.as_deref(), Some("backup@mail.com"));
+        assert_eq!(account.totp_secret.as_deref(), Some("JBSWY3DPEHPK3PXP"));
+        assert_eq!(account.year, Some(2019));
+        assert_eq!(account.notes, None);
+
+        // Order does not matter
+        let lines = parse_text("2019|backup@mail.com|user@gmail.com|hunter2", None, YEAR);
+        let account = lines[0].result.as_ref().unwrap();
+        assert_eq!(account.email, "backup@mail.com");
+        assert_eq!(account.recovery_email.as_deref(), Some("user@gmail.com"));
+        assert_eq!(account.password, "hunter2");
+        assert_eq!(account.year, Some(2019));
+    }
+
+    #[test]
+    fn test_extra_fields_go_to_notes() {
+        let lines = parse_text("a@b.com,pw,phone verified,2099", None, YEAR);
+        let account = lines[0].result.as_ref().unwrap();
+
+        assert_eq!(account.password, "pw");
+        // A future year is not a creation year
+        assert_eq!(account.year, None);
+        assert_eq!(account.notes.as_deref(), Some("phone verified 2099"));
+    }
+
+    #[test]
+    fn test_password_is_never_taken_as_totp_secret() {
+        let lines = parse_text("a@b.com|ABCDEFGHIJKLMNOP", None, YEAR);
+        let account = lines[0].result.as_ref().unwrap();
+
+        assert_eq!(account.password, "ABCDEFGHIJKLMNOP");
+        assert_eq!(account.totp_secret, None);
+    }
+
+    #[test]
+    fn test_otpauth_uri_kept_whole_with_colon_delimiter() {
+        let uri = "otpauth://totp/Google:a@b.com?secret=JBSWY3DPEHPK3PXP&issuer=Google";
+        let text = format!("a@b.com:pw:{}\nc@d.com:pw2", uri);
+        let lines = parse_text(&text, None, YEAR);
+
+        let account = lines[0].result.as_ref().unwrap();
+        assert_eq!(account.email, "a@b.com");
+        assert_eq!(account.password, "pw");
+        assert_eq!(account.totp_secret.as_deref(), Some(uri));
+    }
+
+    #[test]
+    fn test_per_line_errors_and_line_numbers() {
+        let text = "a@b.com----pw\n\nnot an account\nc@d.com\n";
+        let lines = parse_text(text, None, YEAR);
+
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0].line, 1);
+        assert!(lines[0].result.is_ok());
+        assert_eq!(lines[1].line, 3);
+        assert_eq!(lines[1].result.as_ref().unwrap_err(), "No email address found");
+        assert_eq!(lines[2].line, 4);
+        assert_eq!(lines[2].result.as_ref().unwrap_err(), "No password found");
+    }
+
+    #[test]
+    fn test_explicit_delimiter() {
+        let lines = parse_text("a@b.com pass word", Some(","), YEAR);
+        // Nothing to split on, so the whole line is one field
+        assert!(lines[0].result.is_err());
+
+        let lines = parse_text("a@b.com,pass word", Some(","), YEAR);
+        assert_eq!(lines[0].result.as_ref().unwrap().password, "pass word");
+    }
+}
@@ -15,3 +15,27 @@ pub mod groups;
 
 // Tags operations module
 pub mod tags;
+
+// Blind search index module
+pub mod search_index;
+
+// TOTP code generation module
+pub mod totp;
+
+// Account import module
+pub mod import;
+
+// Undo/redo history module
+pub mod history;
+
+// Audit log module
+pub mod audit;
+
+// Vault export/import module
+pub mod transfer;
+
+// Database backup module
+pub mod backup;
+
+// Database diagnostics module
+pub mod diagnostics;
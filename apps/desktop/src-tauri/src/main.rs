@@ -2,11 +2,19 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 mod accounts;
+mod audit;
 mod auth;
+mod backup;
 mod crypto;
 mod db;
+mod diagnostics;
 mod groups;
+mod history;
+mod import;
+mod search_index;
 mod tags;
+mod totp;
+mod transfer;
 
 use accounts::{
     batch_delete_accounts_command, batch_update_accounts_command, create_account_command,
@@ -18,7 +26,8 @@ use auth::SessionManager;
 use db::Database;
 use groups::{
     create_group_command, delete_group_command, get_group_accounts_count_command,
-    get_group_command, get_groups_command, update_group_command,
+    get_group_command, get_groups_command, move_group_command, place_group_command,
+    reorder_groups_command, update_group_command,
 };
 use tauri::Manager;
 
@@ -28,11 +37,17 @@ fn main() {
         .setup(|app| {
             // Initialize database
             let db = Database::init(app.handle())?;
-            app.manage(db);
 
-            // Initialize session manager
-            let session_manager = SessionManager::new();
+            // Prune old log entries; a bad policy must not block startup
+            let _ = audit::apply_retention_policy(&db);
+
+            // Initialize session manager with the stored lock policy
+            let policy = auth::load_session_policy(&db).unwrap_or_default();
+            let session_manager = SessionManager::with_policy(policy);
+            auth::spawn_session_watcher(app.handle().clone(), session_manager.clone());
+            backup::spawn_backup_scheduler(app.handle().clone(), db.clone());
             app.manage(session_manager);
+            app.manage(db);
 
             Ok(())
         })
@@ -43,6 +58,8 @@ fn main() {
             auth::unlock_vault_command,
             auth::logout_command,
             auth::change_password_command,
+            auth::get_session_policy_command,
+            auth::set_session_policy_command,
             // Account commands
             get_accounts_command,
             get_account_command,
@@ -60,6 +77,9 @@ fn main() {
             create_group_command,
             update_group_command,
             delete_group_command,
+            move_group_command,
+            reorder_groups_command,
+            place_group_command,
             get_group_accounts_count_command,
             // Tag commands
             tags::get_tags_command,
@@ -71,7 +91,43 @@ fn main() {
             tags::remove_tag_from_account_command,
             tags::get_account_tags_command,
             tags::set_account_tags_command,
+            tags::batch_add_tags_command,
+            tags::batch_remove_tags_command,
+            tags::batch_replace_tags_command,
+            tags::merge_tags_command,
             tags::get_tag_accounts_count_command,
+            // Search index commands
+            search_index::rebuild_search_index_command,
+            // TOTP commands
+            totp::get_totp_code_command,
+            // Import commands
+            import::import_text_command,
+            import::import_csv_command,
+            import::import_manager_export_command,
+            // History commands
+            history::undo_command,
+            history::redo_command,
+            history::get_history_status_command,
+            // Audit log commands
+            audit::get_operation_logs_command,
+            audit::export_operation_logs_command,
+            audit::clear_old_logs_command,
+            audit::get_log_retention_command,
+            audit::set_log_retention_command,
+            audit::verify_audit_log_command,
+            // Export/import commands
+            transfer::export_vault_command,
+            transfer::import_vault_command,
+            transfer::export_csv_command,
+            // Backup commands
+            backup::create_backup_command,
+            backup::list_backups_command,
+            backup::restore_backup_command,
+            backup::get_backup_policy_command,
+            backup::set_backup_policy_command,
+            // Diagnostics commands
+            diagnostics::run_diagnostics_command,
+            diagnostics::get_quarantine_command,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
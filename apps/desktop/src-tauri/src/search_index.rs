@@ -0,0 +1,385 @@
+//! Blind search index for GManager Desktop
+//!
+//! Account email, recovery_email and notes are encrypted with a random nonce,
+//! so SQL `LIKE` can never match them. Instead, every indexed field is split
+//! into search terms that are turned into keyed HMAC tokens and stored in the
+//! `account_search_tokens` side table.
+//!
+//! # Token Kinds
+//!
+//! - `eq` - the whole normalized value (exact match)
+//! - `pre` - the first one and two characters (prefix match for short queries)
+//! - `tri` - every 3-character window (prefix/substring match)
+//!
+//! Tokens are keyed with a search key derived from the session key, so they
+//! reveal nothing without it. A trigram lookup can return a rare false
+//! positive (all trigrams present, but not contiguous), so candidates are
+//! re-checked with `matches_query` after decryption.
+
+use crate::auth::SessionManager;
+use crate::crypto::{blind_token, decrypt_field, derive_search_key, CryptoError, SecretKey};
+use crate::db::{Database, DbError};
+use std::collections::BTreeSet;
+
+// ============================================================================
+// Constants
+// ============================================================================
+
+/// Account fields covered by the blind index
+pub const INDEXED_FIELDS: [&str; 3] = ["email", "recovery_email", "notes"];
+
+/// Token kind for whole-value matches
+const EXACT_KIND: &str = "eq";
+
+/// Token kind for one- and two-character prefixes
+const PREFIX_KIND: &str = "pre";
+
+/// Token kind for 3-character windows
+const TRIGRAM_KIND: &str = "tri";
+
+/// Length of the windows used for substring matching
+const TRIGRAM_LEN: usize = 3;
+
+// ============================================================================
+// Type Definitions
+// ============================================================================
+
+/// How a text query is matched against indexed fields
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MatchMode {
+    /// The field contains the query (queries under 3 characters match as prefix)
+    #[default]
+    Contains,
+    /// The field starts with the query
+    Prefix,
+    /// The field equals the query (case-insensitive)
+    Exact,
+}
+
+// ============================================================================
+// Error Types
+// ============================================================================
+
+/// Search index error type
+#[derive(Debug, thiserror::Error)]
+pub enum SearchIndexError {
+    /// Database error
+    #[error("Database error: {0}")]
+    Database(#[from] DbError),
+
+    /// Cryptographic error
+    #[error("Crypto error: {0}")]
+    Crypto(#[from] CryptoError),
+}
+
+/// Result type for search index operations
+pub type SearchIndexResult<T> = std::result::Result<T, SearchIndexError>;
+
+// ============================================================================
+// Tokenization
+// ============================================================================
+
+/// Normalize a value before tokenization (trimmed, lowercase)
+fn normalize(value: &str) -> String {
+    value.trim().to_lowercase()
+}
+
+/// Compute every blind index token for a field value
+pub fn field_tokens(key: &SecretKey, value: &str) -> Vec<String> {
+    let normalized = normalize(value);
+    if normalized.is_empty() {
+        return Vec::new();
+    }
+
+    let search_key = derive_search_key(key);
+    let chars: Vec<char> = normalized.chars().collect();
+
+    let mut tokens = BTreeSet::new();
+    tokens.insert(blind_token(&search_key, EXACT_KIND, &normalized));
+
+    for len in 1..TRIGRAM_LEN.min(chars.len() + 1) {
+        let prefix: String = chars[..len].iter().collect();
+        tokens.insert(blind_token(&search_key, PREFIX_KIND, &prefix));
+    }
+
+    for window in chars.windows(TRIGRAM_LEN) {
+        let trigram: String = window.iter().collect();
+        tokens.insert(blind_token(&search_key, TRIGRAM_KIND, &trigram));
+    }
+
+    tokens.into_iter().collect()
+}
+
+/// Compute the tokens an indexed field must carry to match a query
+///
+/// Returns an empty list for blank queries.
+pub fn query_tokens(key: &SecretKey, query: &str, mode: MatchMode) -> Vec<String> {
+    let normalized = normalize(query);
+    if normalized.is_empty() {
+        return Vec::new();
+    }
+
+    let search_key = derive_search_key(key);
+    let chars: Vec<char> = normalized.chars().collect();
+
+    if mode == MatchMode::Exact {
+        return vec![blind_token(&search_key, EXACT_KIND, &normalized)];
+    }
+
+    if chars.len() < TRIGRAM_LEN {
+        return vec![blind_token(&search_key, PREFIX_KIND, &normalized)];
+    }
+
+    let mut tokens = BTreeSet::new();
+    if mode == MatchMode::Prefix {
+        let prefix: String = chars[..TRIGRAM_LEN - 1].iter().collect();
+        tokens.insert(blind_token(&search_key, PREFIX_KIND, &prefix));
+    }
+    for window in chars.windows(TRIGRAM_LEN) {
+        let trigram: String = window.iter().collect();
+        tokens.insert(blind_token(&search_key, TRIGRAM_KIND, &trigram));
+    }
+
+    tokens.into_iter().collect()
+}
+
+/// Check a decrypted value against a query
+///
+/// Used to drop the rare false positives of trigram lookups.
+pub fn matches_query(value: &str, query: &str, mode: MatchMode) -> bool {
+    let value = normalize(value);
+    let query = normalize(query);
+
+    match mode {
+        MatchMode::Exact => value == query,
+        MatchMode::Prefix => value.starts_with(&query),
+        MatchMode::Contains if query.chars().count() < TRIGRAM_LEN => value.starts_with(&query),
+        MatchMode::Contains => value.contains(&query),
+    }
+}
+
+// ============================================================================
+// Index Maintenance
+// ============================================================================
+
+/// Index the given plaintext fields of an account
+///
+/// Each entry is `(field, value)`; `None` or empty values clear the field's
+/// tokens. Fields that are not listed are left untouched.
+pub fn index_account_fields(
+    db: &Database,
+    key: &SecretKey,
+    account_id: i64,
+    fields: &[(&str, Option<&str>)],
+) -> SearchIndexResult<()> {
+    for (field, value) in fields {
+        let tokens = value
+            .map(|v| field_tokens(key, v))
+            .unwrap_or_default();
+        db.replace_search_tokens(account_id, field, &tokens)?;
+    }
+
+    Ok(())
+}
+
+/// Index a stored account by decrypting its indexed fields
+pub fn index_stored_account(db: &Database, key: &SecretKey, account_id: i64) -> SearchIndexResult<()> {
+    let account = db.get_account(account_id)?;
+
+    let email = decrypt_field(&account.email, key)?;
+    let recovery_email = decrypt_optional(account.recovery_email.as_deref(), key)?;
+    let notes = decrypt_optional(account.notes.as_deref(), key)?;
+
+    index_account_fields(
+        db,
+        key,
+        account_id,
+        &[
+            ("email", Some(email.as_str())),
+            ("recovery_email", recovery_email.as_deref()),
+            ("notes", notes.as_deref()),
+        ],
+    )
+}
+
+/// Decrypt an optional column, treating empty strings as absent
+fn decrypt_optional(value: Option<&str>, key: &SecretKey) -> SearchIndexResult<Option<String>> {
+    match value {
+        Some(v) if !v.is_empty() => Ok(Some(decrypt_field(v, key)?)),
+        _ => Ok(None),
+    }
+}
+
+/// Index every account that has no tokens yet
+///
+/// Covers accounts created before the blind index existed.
+///
+/// # Returns
+/// The number of accounts that were indexed
+pub fn index_missing_accounts(db: &Database, key: &SecretKey) -> SearchIndexResult<usize> {
+    let ids = db.get_unindexed_account_ids()?;
+
+    for id in &ids {
+        index_stored_account(db, key, *id)?;
+    }
+
+    Ok(ids.len())
+}
+
+/// Drop and rebuild the whole blind index
+///
+/// # Returns
+/// The number of accounts that were indexed
+pub fn rebuild_index(db: &Database, key: &SecretKey) -> SearchIndexResult<usize> {
+    db.clear_search_tokens()?;
+    index_missing_accounts(db, key)
+}
+
+// ============================================================================
+// Tauri Commands
+// ============================================================================
+
+/// Rebuild the blind search index from the decrypted accounts
+///
+/// Returns the number of indexed accounts.
+#[tauri::command]
+pub fn rebuild_search_index_command(
+    db: tauri::State<Database>,
+    session_manager: tauri::State<SessionManager>,
+) -> Result<usize, String> {
+    let key = session_manager.get_session_key()
+        .map_err(|e| e.to_string())?;
+
+    rebuild_index(&db, &key)
+        .map_err(|e| e.to_string())
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::{derive_key, encrypt_field};
+    use crate::db::{AccountSearch, CreateAccount, Pagination};
+
+    fn get_test_db() -> Database {
+        Database::init_in_memory().expect("Failed to create test database")
+    }
+
+    fn get_test_key() -> SecretKey {
+        derive_key(b"test-password", b"test-salt-16byte")
+    }
+
+    fn create_indexed_account(db: &Database, key: &SecretKey, email: &str, notes: Option<&str>) -> i64 {
+        let id = db.create_account(CreateAccount {
+            raw_import_id: None,
+            email: encrypt_field(email, key).unwrap(),
+            password: encrypt_field("secret", key).unwrap(),
+            recovery_email: None,
+            totp_secret: None,
+            year: None,
+            notes: notes.map(|n| encrypt_field(n, key).unwrap()),
+            group_id: None,
+            field_order: None,
+        }).unwrap();
+
+        index_account_fields(db, key, id, &[("email", Some(email)), ("notes", notes)]).unwrap();
+        id
+    }
+
+    fn search(db: &Database, key: &SecretKey, query: &str, mode: MatchMode) -> Vec<i64> {
+        db.search_accounts(AccountSearch {
+            query_tokens: query_tokens(key, query, mode),
+            pagination: Pagination { offset: 0, limit: 50 },
+            ..Default::default()
+        })
+        .unwrap()
+        .into_iter()
+        .map(|a| a.id)
+        .collect()
+    }
+
+    #[test]
+    fn test_field_tokens_do_not_reveal_plaintext() {
+        let key = get_test_key();
+        let tokens = field_tokens(&key, "User@Example.com");
+
+        assert!(!tokens.is_empty());
+        assert!(tokens.iter().all(|t| !t.contains("user") && !t.contains("example")));
+        assert_eq!(tokens, field_tokens(&key, "  user@example.com "));
+        assert!(field_tokens(&key, "   ").is_empty());
+    }
+
+    #[test]
+    fn test_exact_prefix_and_substring_search() {
+        let db = get_test_db();
+        let key = get_test_key();
+
+        let work = create_indexed_account(&db, &key, "work@example.com", Some("Main office login"));
+        let personal = create_indexed_account(&db, &key, "personal@example.com", None);
+
+        assert_eq!(search(&db, &key, "WORK@example.com", MatchMode::Exact), vec![work]);
+        assert!(search(&db, &key, "work@example", MatchMode::Exact).is_empty());
+
+        assert_eq!(search(&db, &key, "pers", MatchMode::Prefix), vec![personal]);
+        assert_eq!(search(&db, &key, "wo", MatchMode::Contains), vec![work]);
+
+        let mut both = search(&db, &key, "@example.", MatchMode::Contains);
+        both.sort();
+        assert_eq!(both, vec![work, personal]);
+
+        // Notes are indexed as well
+        assert_eq!(search(&db, &key, "office", MatchMode::Contains), vec![work]);
+        assert!(search(&db, &key, "nowhere", MatchMode::Contains).is_empty());
+    }
+
+    #[test]
+    fn test_tokens_depend_on_session_key() {
+        let db = get_test_db();
+        let key = get_test_key();
+        let other_key = derive_key(b"other-password", b"test-salt-16byte");
+
+        create_indexed_account(&db, &key, "work@example.com", None);
+
+        assert!(search(&db, &other_key, "work", MatchMode::Contains).is_empty());
+    }
+
+    #[test]
+    fn test_index_missing_accounts() {
+        let db = get_test_db();
+        let key = get_test_key();
+
+        let id = db.create_account(CreateAccount {
+            raw_import_id: None,
+            email: encrypt_field("legacy@example.com", &key).unwrap(),
+            password: encrypt_field("secret", &key).unwrap(),
+            recovery_email: Some(encrypt_field("backup@example.org", &key).unwrap()),
+            totp_secret: None,
+            year: None,
+            notes: None,
+            group_id: None,
+            field_order: None,
+        }).unwrap();
+
+        assert!(search(&db, &key, "legacy", MatchMode::Contains).is_empty());
+
+        assert_eq!(index_missing_accounts(&db, &key).unwrap(), 1);
+        assert_eq!(index_missing_accounts(&db, &key).unwrap(), 0);
+
+        assert_eq!(search(&db, &key, "legacy", MatchMode::Contains), vec![id]);
+        assert_eq!(search(&db, &key, "backup@example.org", MatchMode::Exact), vec![id]);
+    }
+
+    #[test]
+    fn test_matches_query() {
+        assert!(matches_query("Work@Example.com", "example", MatchMode::Contains));
+        assert!(!matches_query("abcxbc", "abcbc", MatchMode::Contains));
+        assert!(matches_query("work@example.com", "wo", MatchMode::Contains));
+        assert!(!matches_query("work@example.com", "or", MatchMode::Contains));
+        assert!(matches_query("work@example.com", "work@", MatchMode::Prefix));
+        assert!(!matches_query("work@example.com", "work", MatchMode::Exact));
+    }
+}
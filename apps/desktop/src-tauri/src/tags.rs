@@ -6,8 +6,20 @@
 //!
 //! Tags are used to categorize accounts with flexible labeling.
 //! Each account can have multiple tags attached to it.
+//!
+//! Tags can be added to, removed from or replaced on many accounts at once.
+//! The batch runs in one transaction and reports a result per account, like
+//! the account batch commands.
+//!
+//! Tag names are unique ignoring case. Two tags that mean the same thing
+//! can be merged: the source tag's accounts move to the target tag and the
+//! source tag is deleted.
 
-use crate::db::{CreateTag, Database, DbError, Tag};
+use crate::accounts::{self, BatchResult};
+use crate::auth::SessionManager;
+use crate::crypto::SecretKey;
+use crate::db::{BatchMode, CreateTag, Database, DbError, Tag, TagChange, TagMerge};
+use crate::history::{HistoryError, MutationKind, Target, Tracker};
 
 // ============================================================================
 // Type Definitions for Frontend/Backend Communication
@@ -45,6 +57,15 @@ pub struct SetAccountTagsPayload {
     pub tag_ids: Vec<String>,
 }
 
+/// Payload for changing the tags of several accounts at once
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct BatchTagsPayload {
+    pub account_ids: Vec<String>,
+    pub tag_ids: Vec<String>,
+    #[serde(default)]
+    pub mode: BatchMode,
+}
+
 // ============================================================================
 // Error Types
 // ============================================================================
@@ -56,6 +77,10 @@ pub enum TagError {
     #[error("Database error: {0}")]
     Database(#[from] DbError),
 
+    /// Undo history error
+    #[error("History error: {0}")]
+    History(#[from] HistoryError),
+
     /// Invalid input
     #[error("Invalid input: {0}")]
     InvalidInput(String),
@@ -63,6 +88,20 @@ pub enum TagError {
     /// Tag not found
     #[error("Tag not found: {0}")]
     NotFound(String),
+
+    /// Another tag has the same name (ignoring case)
+    #[error("{0} already exists")]
+    Duplicate(String),
+}
+
+/// Surface missing tags and name collisions as tag errors
+fn tag_db_error(e: DbError) -> TagError {
+    match e {
+        DbError::NotFound(what) => TagError::NotFound(what),
+        DbError::Duplicate(what) => TagError::Duplicate(what),
+        DbError::InvalidInput(msg) => TagError::InvalidInput(msg),
+        other => TagError::Database(other),
+    }
 }
 
 /// Result type for tag operations
@@ -104,6 +143,127 @@ fn frontend_to_db_create(payload: CreateTagPayload) -> TagResult<CreateTag> {
     })
 }
 
+// ============================================================================
+// Tag Operations
+// ============================================================================
+
+/// Delete a tag and record it in the undo history
+///
+/// The tag's account associations are tracked too, since they are removed
+/// along with the tag.
+pub fn delete_tag(db: &Database, key: &SecretKey, tag_id: i64) -> TagResult<()> {
+    let mut targets: Vec<Target> = db.get_tag_account_ids(tag_id)?
+        .into_iter()
+        .map(|account_id| Target::AccountTag { account_id, tag_id })
+        .collect();
+    targets.push(Target::Tag(tag_id));
+
+    let tracker = Tracker::capture(db, targets)?;
+
+    db.delete_tag(tag_id)?;
+
+    tracker.record(db, key, MutationKind::DeleteTag)?;
+    Ok(())
+}
+
+/// Replace the tags of an account and record it in the undo history
+pub fn set_account_tags(db: &Database, key: &SecretKey, account_id: i64, tag_ids: Vec<i64>) -> TagResult<()> {
+    // Get current tags for the account
+    let current_tags = db.get_account_tags(account_id)?;
+
+    let targets = current_tags
+        .iter()
+        .map(|t| t.id)
+        .chain(tag_ids.iter().copied())
+        .map(|tag_id| Target::AccountTag { account_id, tag_id })
+        .collect();
+    let tracker = Tracker::capture(db, targets)?;
+
+    // Remove tags that are not in the new list
+    for current_tag in &current_tags {
+        if !tag_ids.contains(&current_tag.id) {
+            db.remove_tag_from_account(account_id, current_tag.id)?;
+        }
+    }
+
+    // Add tags that are in the new list but not currently associated
+    for tag_id in tag_ids {
+        // Check if already associated
+        let is_associated = current_tags.iter().any(|t| t.id == tag_id);
+        if !is_associated {
+            db.add_tag_to_account(account_id, tag_id)?;
+        }
+    }
+
+    tracker.record(db, key, MutationKind::SetAccountTags)?;
+    Ok(())
+}
+
+/// Add, remove or replace tags on several accounts in one transaction and
+/// record it in the undo history
+///
+/// Invalid or unknown tag IDs fail the whole request; account IDs that are
+/// invalid or unknown are reported per account.
+pub fn batch_change_account_tags(
+    db: &Database,
+    key: &SecretKey,
+    payload: BatchTagsPayload,
+    change: TagChange,
+) -> TagResult<BatchResult> {
+    let mut tag_ids = payload.tag_ids
+        .iter()
+        .map(|id| id.parse::<i64>().map_err(|_| TagError::InvalidInput("Invalid tag ID".to_string())))
+        .collect::<TagResult<Vec<_>>>()?;
+    tag_ids.sort_unstable();
+    tag_ids.dedup();
+
+    let parsed: Vec<Result<i64, String>> = payload.account_ids
+        .iter()
+        .map(|id| id.parse::<i64>().map_err(|_| "Invalid account ID".to_string()))
+        .collect();
+
+    // Replacing also removes each account's current tags
+    let mut targets = Vec::new();
+    for &account_id in parsed.iter().flatten() {
+        let mut touched = tag_ids.clone();
+        if change == TagChange::Replace {
+            touched.extend(db.get_account_tags(account_id)?.iter().map(|t| t.id));
+        }
+        targets.extend(touched.into_iter().map(|tag_id| Target::AccountTag { account_id, tag_id }));
+    }
+    let tracker = Tracker::capture(db, targets)?;
+
+    let mode = payload.mode;
+    let results = accounts::run_batch(payload.account_ids, mode, parsed, |ids| {
+        db.batch_change_account_tags(ids, &tag_ids, change, mode)
+    })
+    .map_err(tag_db_error)?;
+
+    if results.committed {
+        tracker.record(db, key, MutationKind::BatchUpdateAccountTags)?;
+    }
+
+    Ok(results)
+}
+
+/// Merge one tag into another and record it in the undo history
+pub fn merge_tags(db: &Database, key: &SecretKey, source_id: i64, target_id: i64) -> TagResult<TagMerge> {
+    // The source tag goes last so that undo restores it before its links
+    let mut targets = Vec::new();
+    for account_id in db.get_tag_account_ids(source_id)? {
+        targets.push(Target::AccountTag { account_id, tag_id: source_id });
+        targets.push(Target::AccountTag { account_id, tag_id: target_id });
+    }
+    targets.push(Target::Tag(source_id));
+
+    let tracker = Tracker::capture(db, targets)?;
+
+    let merge = db.merge_tags(source_id, target_id).map_err(tag_db_error)?;
+
+    tracker.record(db, key, MutationKind::MergeTags)?;
+    Ok(merge)
+}
+
 // ============================================================================
 // Tauri Commands
 // ============================================================================
@@ -143,12 +303,20 @@ pub fn get_tag_command(
 #[tauri::command]
 pub fn create_tag_command(
     db: tauri::State<Database>,
+    session_manager: tauri::State<SessionManager>,
     tag: CreateTagPayload,
 ) -> Result<String, String> {
+    let key = session_manager.get_session_key()
+        .map_err(|e| e.to_string())?;
+
     let create_tag = frontend_to_db_create(tag)
         .map_err(|e| e.to_string())?;
 
     let id = db.create_tag(create_tag)
+        .map_err(|e| tag_db_error(e).to_string())?;
+
+    Tracker::created(Target::Tag(id))
+        .record(&db, &key, MutationKind::CreateTag)
         .map_err(|e| e.to_string())?;
 
     Ok(id.to_string())
@@ -160,22 +328,37 @@ pub fn create_tag_command(
 #[tauri::command]
 pub fn update_tag_command(
     db: tauri::State<Database>,
+    session_manager: tauri::State<SessionManager>,
     tag: UpdateTagPayload,
 ) -> Result<(), String> {
+    let key = session_manager.get_session_key()
+        .map_err(|e| e.to_string())?;
+
     let tag_id = tag.id.parse::<i64>()
         .map_err(|_| "Invalid tag ID".to_string())?;
 
+    let name = tag.name.map(|name| name.trim().to_string());
+    if name.as_deref() == Some("") {
+        return Err(TagError::InvalidInput("Tag name is required".to_string()).to_string());
+    }
+
     // Get existing tag to fill in unchanged fields
     let existing = db.get_tag(tag_id)
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| tag_db_error(e).to_string())?;
 
     // Build update payload with new values or existing ones
     let update_payload = CreateTag {
-        name: tag.name.unwrap_or_else(|| existing.name),
+        name: name.unwrap_or_else(|| existing.name),
         color: tag.color.unwrap_or_else(|| existing.color),
     };
 
+    let tracker = Tracker::capture(&db, vec![Target::Tag(tag_id)])
+        .map_err(|e| e.to_string())?;
+
     db.update_tag(tag_id, update_payload)
+        .map_err(|e| tag_db_error(e).to_string())?;
+
+    tracker.record(&db, &key, MutationKind::UpdateTag)
         .map_err(|e| e.to_string())?;
 
     Ok(())
@@ -187,12 +370,16 @@ pub fn update_tag_command(
 #[tauri::command]
 pub fn delete_tag_command(
     db: tauri::State<Database>,
+    session_manager: tauri::State<SessionManager>,
     id: String,
 ) -> Result<(), String> {
+    let key = session_manager.get_session_key()
+        .map_err(|e| e.to_string())?;
+
     let tag_id = id.parse::<i64>()
         .map_err(|_| "Invalid tag ID".to_string())?;
 
-    db.delete_tag(tag_id)
+    delete_tag(&db, &key, tag_id)
         .map_err(|e| e.to_string())?;
 
     Ok(())
@@ -205,18 +392,28 @@ pub fn delete_tag_command(
 #[tauri::command]
 pub fn add_tag_to_account_command(
     db: tauri::State<Database>,
+    session_manager: tauri::State<SessionManager>,
     account_id: String,
     tag_id: String,
 ) -> Result<(), String> {
+    let key = session_manager.get_session_key()
+        .map_err(|e| e.to_string())?;
+
     let aid = account_id.parse::<i64>()
         .map_err(|_| "Invalid account ID".to_string())?;
 
     let tid = tag_id.parse::<i64>()
         .map_err(|_| "Invalid tag ID".to_string())?;
 
+    let tracker = Tracker::capture(&db, vec![Target::AccountTag { account_id: aid, tag_id: tid }])
+        .map_err(|e| e.to_string())?;
+
     db.add_tag_to_account(aid, tid)
         .map_err(|e| e.to_string())?;
 
+    tracker.record(&db, &key, MutationKind::AddTagToAccount)
+        .map_err(|e| e.to_string())?;
+
     Ok(())
 }
 
@@ -226,18 +423,28 @@ pub fn add_tag_to_account_command(
 #[tauri::command]
 pub fn remove_tag_from_account_command(
     db: tauri::State<Database>,
+    session_manager: tauri::State<SessionManager>,
     account_id: String,
     tag_id: String,
 ) -> Result<(), String> {
+    let key = session_manager.get_session_key()
+        .map_err(|e| e.to_string())?;
+
     let aid = account_id.parse::<i64>()
         .map_err(|_| "Invalid account ID".to_string())?;
 
     let tid = tag_id.parse::<i64>()
         .map_err(|_| "Invalid tag ID".to_string())?;
 
+    let tracker = Tracker::capture(&db, vec![Target::AccountTag { account_id: aid, tag_id: tid }])
+        .map_err(|e| e.to_string())?;
+
     db.remove_tag_from_account(aid, tid)
         .map_err(|e| e.to_string())?;
 
+    tracker.record(&db, &key, MutationKind::RemoveTagFromAccount)
+        .map_err(|e| e.to_string())?;
+
     Ok(())
 }
 
@@ -268,8 +475,12 @@ pub fn get_account_tags_command(
 #[tauri::command]
 pub fn set_account_tags_command(
     db: tauri::State<Database>,
+    session_manager: tauri::State<SessionManager>,
     payload: SetAccountTagsPayload,
 ) -> Result<(), String> {
+    let key = session_manager.get_session_key()
+        .map_err(|e| e.to_string())?;
+
     let account_id = payload.account_id.parse::<i64>()
         .map_err(|_| "Invalid account ID".to_string())?;
 
@@ -281,29 +492,94 @@ pub fn set_account_tags_command(
         tag_ids.push(tid);
     }
 
-    // Get current tags for the account
-    let current_tags = db.get_account_tags(account_id)
+    set_account_tags(&db, &key, account_id, tag_ids)
+        .map_err(|e| e.to_string())
+}
+
+/// Add tags to several accounts
+///
+/// Each account keeps its other tags. In all-or-nothing mode (the default)
+/// one unknown account rolls back the whole batch.
+///
+/// # Returns
+/// A result per account, in request order
+#[tauri::command]
+pub fn batch_add_tags_command(
+    db: tauri::State<Database>,
+    session_manager: tauri::State<SessionManager>,
+    payload: BatchTagsPayload,
+) -> Result<BatchResult, String> {
+    let key = session_manager.get_session_key()
         .map_err(|e| e.to_string())?;
 
-    // Remove tags that are not in the new list
-    for current_tag in &current_tags {
-        if !tag_ids.contains(&current_tag.id) {
-            db.remove_tag_from_account(account_id, current_tag.id)
-                .map_err(|e| e.to_string())?;
-        }
-    }
+    batch_change_account_tags(&db, &key, payload, TagChange::Add)
+        .map_err(|e| e.to_string())
+}
 
-    // Add tags that are in the new list but not currently associated
-    for tag_id in tag_ids {
-        // Check if already associated
-        let is_associated = current_tags.iter().any(|t| t.id == tag_id);
-        if !is_associated {
-            db.add_tag_to_account(account_id, tag_id)
-                .map_err(|e| e.to_string())?;
-        }
-    }
+/// Remove tags from several accounts
+///
+/// Accounts that do not have a tag are left as they are.
+///
+/// # Returns
+/// A result per account, in request order
+#[tauri::command]
+pub fn batch_remove_tags_command(
+    db: tauri::State<Database>,
+    session_manager: tauri::State<SessionManager>,
+    payload: BatchTagsPayload,
+) -> Result<BatchResult, String> {
+    let key = session_manager.get_session_key()
+        .map_err(|e| e.to_string())?;
 
-    Ok(())
+    batch_change_account_tags(&db, &key, payload, TagChange::Remove)
+        .map_err(|e| e.to_string())
+}
+
+/// Replace the tags of several accounts with the given tags
+///
+/// # Returns
+/// A result per account, in request order
+#[tauri::command]
+pub fn batch_replace_tags_command(
+    db: tauri::State<Database>,
+    session_manager: tauri::State<SessionManager>,
+    payload: BatchTagsPayload,
+) -> Result<BatchResult, String> {
+    let key = session_manager.get_session_key()
+        .map_err(|e| e.to_string())?;
+
+    batch_change_account_tags(&db, &key, payload, TagChange::Replace)
+        .map_err(|e| e.to_string())
+}
+
+/// Merge a tag into another
+///
+/// Accounts tagged with the source tag get the target tag instead, and the
+/// source tag is deleted.
+///
+/// # Arguments
+/// * `source_id` - Tag to merge away
+/// * `target_id` - Tag to keep
+///
+/// # Returns
+/// How many accounts moved and how many already had the target tag
+#[tauri::command]
+pub fn merge_tags_command(
+    db: tauri::State<Database>,
+    session_manager: tauri::State<SessionManager>,
+    source_id: String,
+    target_id: String,
+) -> Result<TagMerge, String> {
+    let key = session_manager.get_session_key()
+        .map_err(|e| e.to_string())?;
+
+    let source_id = source_id.parse::<i64>()
+        .map_err(|_| "Invalid tag ID".to_string())?;
+    let target_id = target_id.parse::<i64>()
+        .map_err(|_| "Invalid tag ID".to_string())?;
+
+    merge_tags(&db, &key, source_id, target_id)
+        .map_err(|e| e.to_string())
 }
 
 /// Get the number of accounts that have a specific tag
@@ -386,6 +662,17 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_duplicate_name_is_a_tag_error() {
+        let db = Database::init_in_memory().unwrap();
+        let tag = |name: &str| CreateTag { name: name.to_string(), color: "#10b981".to_string() };
+        db.create_tag(tag("Work")).unwrap();
+
+        let err = tag_db_error(db.create_tag(tag("WORK")).unwrap_err());
+        assert!(matches!(err, TagError::Duplicate(_)));
+        assert_eq!(err.to_string(), "Tag \"Work\" already exists");
+    }
+
     #[test]
     fn test_db_to_frontend_conversion() {
         let db_tag = Tag {
@@ -0,0 +1,506 @@
+//! TOTP code generation for GManager Desktop
+//!
+//! Generates RFC 6238 time-based one-time passwords from the encrypted
+//! `totp_secret` of an account, so the raw secret never has to leave the
+//! Rust backend. Only the current code, its period and the seconds until it
+//! rolls over are returned to the frontend.
+//!
+//! # Supported Secrets
+//!
+//! - Bare base32 secrets (spaces, dashes, lowercase and `=` padding allowed),
+//!   using SHA1, 6 digits and a 30 second period
+//! - `otpauth://totp/...` URIs with the `secret`, `algorithm`, `digits` and
+//!   `period` parameters (SHA1/SHA256/SHA512, 6-8 digits, any period)
+
+use crate::auth::SessionManager;
+use crate::crypto::{decrypt_field, CryptoError};
+use crate::db::{Database, DbError};
+use ring::hmac;
+use serde::Serialize;
+use zeroize::Zeroizing;
+
+// ============================================================================
+// Constants
+// ============================================================================
+
+/// Default number of digits (RFC 6238 / Google Authenticator)
+const DEFAULT_DIGITS: u32 = 6;
+
+/// Default time step in seconds
+const DEFAULT_PERIOD: u64 = 30;
+
+/// Supported number of digits
+const MIN_DIGITS: u32 = 6;
+const MAX_DIGITS: u32 = 8;
+
+/// URI scheme prefix of provisioning URIs
+const OTPAUTH_PREFIX: &str = "otpauth://";
+
+// ============================================================================
+// Error Types
+// ============================================================================
+
+/// TOTP module error type
+#[derive(Debug, thiserror::Error)]
+pub enum TotpError {
+    /// Database error
+    #[error("Database error: {0}")]
+    Database(#[from] DbError),
+
+    /// Cryptographic error (decrypting the stored secret)
+    #[error("Crypto error: {0}")]
+    Crypto(#[from] CryptoError),
+
+    /// Account has no TOTP secret
+    #[error("Account has no TOTP secret")]
+    NoSecret,
+
+    /// Secret is not valid base32
+    #[error("Invalid TOTP secret: {0}")]
+    InvalidSecret(String),
+
+    /// otpauth:// URI is malformed or not a TOTP URI
+    #[error("Invalid otpauth URI: {0}")]
+    InvalidUri(String),
+
+    /// Algorithm is not SHA1, SHA256 or SHA512
+    #[error("Unsupported TOTP algorithm: {0}")]
+    UnsupportedAlgorithm(String),
+
+    /// Digits outside 6-8
+    #[error("Unsupported number of digits: {0} (expected 6-8)")]
+    InvalidDigits(String),
+
+    /// Period is zero or not a number
+    #[error("Invalid TOTP period: {0}")]
+    InvalidPeriod(String),
+}
+
+/// Result type for TOTP operations
+pub type TotpResult<T> = std::result::Result<T, TotpError>;
+
+// ============================================================================
+// Data Types
+// ============================================================================
+
+/// HMAC algorithm used by the TOTP generator
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TotpAlgorithm {
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+impl TotpAlgorithm {
+    fn hmac_algorithm(self) -> hmac::Algorithm {
+        match self {
+            TotpAlgorithm::Sha1 => hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY,
+            TotpAlgorithm::Sha256 => hmac::HMAC_SHA256,
+            TotpAlgorithm::Sha512 => hmac::HMAC_SHA512,
+        }
+    }
+}
+
+/// Parsed TOTP parameters
+pub struct TotpConfig {
+    /// Decoded shared secret (wiped on drop)
+    secret: Zeroizing<Vec<u8>>,
+    pub algorithm: TotpAlgorithm,
+    pub digits: u32,
+    pub period: u64,
+}
+
+/// Current code returned to the frontend
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TotpCode {
+    /// Zero-padded code
+    pub code: String,
+    /// Time step in seconds
+    pub period: u64,
+    /// Seconds until the next code
+    pub remaining_seconds: u64,
+}
+
+// ============================================================================
+// Parsing
+// ============================================================================
+
+impl TotpConfig {
+    /// Build a config from raw secret bytes
+    pub fn new(secret: Vec<u8>, algorithm: TotpAlgorithm, digits: u32, period: u64) -> TotpResult<Self> {
+        if secret.is_empty() {
+            return Err(TotpError::InvalidSecret("secret is empty".to_string()));
+        }
+        if !(MIN_DIGITS..=MAX_DIGITS).contains(&digits) {
+            return Err(TotpError::InvalidDigits(digits.to_string()));
+        }
+        if period == 0 {
+            return Err(TotpError::InvalidPeriod("period must be greater than zero".to_string()));
+        }
+
+        Ok(TotpConfig {
+            secret: Zeroizing::new(secret),
+            algorithm,
+            digits,
+            period,
+        })
+    }
+
+    /// Parse a stored secret: either bare base32 or an `otpauth://` URI
+    pub fn parse(value: &str) -> TotpResult<Self> {
+        let value = value.trim();
+
+        if value.len() >= OTPAUTH_PREFIX.len()
+            && value[..OTPAUTH_PREFIX.len()].eq_ignore_ascii_case(OTPAUTH_PREFIX)
+        {
+            Self::parse_uri(&value[OTPAUTH_PREFIX.len()..])
+        } else {
+            Self::new(decode_base32(value)?, TotpAlgorithm::Sha1, DEFAULT_DIGITS, DEFAULT_PERIOD)
+        }
+    }
+
+    /// Parse the part of an otpauth URI after `otpauth://`
+    fn parse_uri(rest: &str) -> TotpResult<Self> {
+        let (kind, rest) = rest
+            .split_once('/')
+            .ok_or_else(|| TotpError::InvalidUri("missing label".to_string()))?;
+        if !kind.eq_ignore_ascii_case("totp") {
+            return Err(TotpError::InvalidUri(format!("unsupported type '{}'", kind)));
+        }
+
+        let query = rest
+            .split_once('?')
+            .map(|(_, query)| query)
+            .ok_or_else(|| TotpError::InvalidUri("missing parameters".to_string()))?;
+        // Ignore a fragment, if any
+        let query = query.split('#').next().unwrap_or_default();
+
+        let mut secret = None;
+        let mut algorithm = TotpAlgorithm::Sha1;
+        let mut digits = DEFAULT_DIGITS;
+        let mut period = DEFAULT_PERIOD;
+
+        for pair in query.split('&').filter(|p| !p.is_empty()) {
+            let (name, raw_value) = pair.split_once('=').unwrap_or((pair, ""));
+            let value = percent_decode(raw_value)?;
+
+            match name.to_ascii_lowercase().as_str() {
+                "secret" => secret = Some(Zeroizing::new(value)),
+                "algorithm" => {
+                    algorithm = match value.to_ascii_uppercase().as_str() {
+                        "SHA1" => TotpAlgorithm::Sha1,
+                        "SHA256" => TotpAlgorithm::Sha256,
+                        "SHA512" => TotpAlgorithm::Sha512,
+                        _ => return Err(TotpError::UnsupportedAlgorithm(value)),
+                    }
+                }
+                "digits" => {
+                    digits = value.parse().map_err(|_| TotpError::InvalidDigits(value.clone()))?;
+                }
+                "period" => {
+                    period = value.parse().map_err(|_| TotpError::InvalidPeriod(value.clone()))?;
+                }
+                // issuer, image, ... are not needed to generate codes
+                _ => {}
+            }
+        }
+
+        let secret = secret.ok_or_else(|| TotpError::InvalidUri("missing secret".to_string()))?;
+        Self::new(decode_base32(&secret)?, algorithm, digits, period)
+    }
+}
+
+/// Decode RFC 4648 base32, tolerating case, whitespace, dashes and padding
+fn decode_base32(value: &str) -> TotpResult<Vec<u8>> {
+    let mut output = Vec::with_capacity(value.len() * 5 / 8);
+    let mut buffer: u64 = 0;
+    let mut bits = 0u32;
+
+    for c in value.chars() {
+        let digit = match c.to_ascii_uppercase() {
+            c @ 'A'..='Z' => c as u64 - 'A' as u64,
+            c @ '2'..='7' => c as u64 - '2' as u64 + 26,
+            ' ' | '-' | '=' | '\t' => continue,
+            other => {
+                return Err(TotpError::InvalidSecret(format!(
+                    "invalid base32 character '{}'",
+                    other
+                )))
+            }
+        };
+
+        buffer = (buffer << 5) | digit;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            output.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+
+    if output.is_empty() {
+        return Err(TotpError::InvalidSecret("secret is empty".to_string()));
+    }
+    Ok(output)
+}
+
+/// Decode `%XX` escapes (and `+` as space) in a URI query value
+fn percent_decode(value: &str) -> TotpResult<String> {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let hex = bytes
+                    .get(i + 1..i + 3)
+                    .and_then(|h| std::str::from_utf8(h).ok())
+                    .and_then(|h| u8::from_str_radix(h, 16).ok())
+                    .ok_or_else(|| TotpError::InvalidUri("invalid percent-encoding".to_string()))?;
+                decoded.push(hex);
+                i += 3;
+            }
+            b'+' => {
+                decoded.push(b' ');
+                i += 1;
+            }
+            b => {
+                decoded.push(b);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8(decoded).map_err(|_| TotpError::InvalidUri("invalid UTF-8".to_string()))
+}
+
+// ============================================================================
+// Code Generation
+// ============================================================================
+
+/// Generate the code for a Unix timestamp (RFC 6238 / RFC 4226)
+pub fn generate_code(config: &TotpConfig, unix_time: u64) -> String {
+    let counter = unix_time / config.period;
+
+    let key = hmac::Key::new(config.algorithm.hmac_algorithm(), &config.secret);
+    let tag = hmac::sign(&key, &counter.to_be_bytes());
+    let digest = tag.as_ref();
+
+    // Dynamic truncation
+    let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([
+        digest[offset] & 0x7f,
+        digest[offset + 1],
+        digest[offset + 2],
+        digest[offset + 3],
+    ]);
+
+    let code = binary % 10u32.pow(config.digits);
+    format!("{:0width$}", code, width = config.digits as usize)
+}
+
+/// Current code plus the time left in its period
+pub fn current_code(config: &TotpConfig, unix_time: u64) -> TotpCode {
+    TotpCode {
+        code: generate_code(config, unix_time),
+        period: config.period,
+        remaining_seconds: config.period - unix_time % config.period,
+    }
+}
+
+/// Decrypt an account's TOTP secret and generate its current code
+pub fn account_totp_code(
+    db: &Database,
+    key: &[u8; 32],
+    account_id: i64,
+    unix_time: u64,
+) -> TotpResult<TotpCode> {
+    let account = db.get_account(account_id)?;
+
+    let encrypted = account
+        .totp_secret
+        .filter(|s| !s.is_empty())
+        .ok_or(TotpError::NoSecret)?;
+    let secret = Zeroizing::new(decrypt_field(&encrypted, key)?);
+
+    let config = TotpConfig::parse(&secret)?;
+    Ok(current_code(&config, unix_time))
+}
+
+// ============================================================================
+// Tauri Commands
+// ============================================================================
+
+/// Get the current TOTP code for an account
+///
+/// The secret is decrypted and used inside the backend only.
+#[tauri::command]
+pub fn get_totp_code_command(
+    db: tauri::State<Database>,
+    session_manager: tauri::State<SessionManager>,
+    account_id: String,
+) -> Result<TotpCode, String> {
+    let key = session_manager.get_session_key()
+        .map_err(|e| e.to_string())?;
+
+    let account_id = account_id.parse::<i64>()
+        .map_err(|_| "Invalid account ID".to_string())?;
+
+    let now = chrono::Utc::now().timestamp().max(0) as u64;
+
+    account_totp_code(&db, &key, account_id, now)
+        .map_err(|e| e.to_string())
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::{derive_key, encrypt_field};
+    use crate::db::CreateAccount;
+
+    /// RFC 6238 Appendix B seeds
+    const SEED_SHA1: &[u8] = b"12345678901234567890";
+    const SEED_SHA256: &[u8] = b"12345678901234567890123456789012";
+    const SEED_SHA512: &[u8] = b"1234567890123456789012345678901234567890123456789012345678901234";
+
+    /// RFC 6238 Appendix B: (time, SHA1, SHA256, SHA512)
+    const RFC_VECTORS: [(u64, &str, &str, &str); 6] = [
+        (59, "94287082", "46119246", "90693936"),
+        (1111111109, "07081804", "68084774", "25091201"),
+        (1111111111, "14050471", "67062674", "99943326"),
+        (1234567890, "89005924", "91819424", "93441116"),
+        (2000000000, "69279037", "90698825", "38618901"),
+        (20000000000, "65353130", "77737706", "47863826"),
+    ];
+
+    fn config(seed: &[u8], algorithm: TotpAlgorithm, digits: u32) -> TotpConfig {
+        TotpConfig::new(seed.to_vec(), algorithm, digits, 30).unwrap()
+    }
+
+    #[test]
+    fn test_rfc6238_vectors() {
+        let sha1 = config(SEED_SHA1, TotpAlgorithm::Sha1, 8);
+        let sha256 = config(SEED_SHA256, TotpAlgorithm::Sha256, 8);
+        let sha512 = config(SEED_SHA512, TotpAlgorithm::Sha512, 8);
+
+        for (time, expected_sha1, expected_sha256, expected_sha512) in RFC_VECTORS {
+            assert_eq!(generate_code(&sha1, time), expected_sha1, "SHA1 at {}", time);
+            assert_eq!(generate_code(&sha256, time), expected_sha256, "SHA256 at {}", time);
+            assert_eq!(generate_code(&sha512, time), expected_sha512, "SHA512 at {}", time);
+        }
+    }
+
+    #[test]
+    fn test_shorter_codes_are_truncated_and_padded() {
+        let six = config(SEED_SHA1, TotpAlgorithm::Sha1, 6);
+        let seven = config(SEED_SHA1, TotpAlgorithm::Sha1, 7);
+
+        assert_eq!(generate_code(&six, 59), "287082");
+        assert_eq!(generate_code(&seven, 59), "4287082");
+        assert_eq!(generate_code(&six, 1111111109), "081804");
+    }
+
+    #[test]
+    fn test_custom_period_and_remaining_seconds() {
+        let config = TotpConfig::new(SEED_SHA1.to_vec(), TotpAlgorithm::Sha1, 8, 60).unwrap();
+
+        // Time 59 and 0 share the first 60 second step
+        assert_eq!(generate_code(&config, 59), generate_code(&config, 0));
+        assert_ne!(generate_code(&config, 60), generate_code(&config, 59));
+
+        let code = current_code(&config, 125);
+        assert_eq!(code.period, 60);
+        assert_eq!(code.remaining_seconds, 55);
+    }
+
+    #[test]
+    fn test_parse_base32_secret() {
+        // "12345678901234567890" in base32
+        let parsed = TotpConfig::parse("gezd gnbv gy3t qojq-gezd gnbv gy3t qojq").unwrap();
+        assert_eq!(parsed.secret.as_slice(), SEED_SHA1);
+        assert_eq!(parsed.algorithm, TotpAlgorithm::Sha1);
+        assert_eq!(parsed.digits, 6);
+        assert_eq!(parsed.period, 30);
+
+        assert!(matches!(TotpConfig::parse("not base32!"), Err(TotpError::InvalidSecret(_))));
+        assert!(matches!(TotpConfig::parse(""), Err(TotpError::InvalidSecret(_))));
+    }
+
+    #[test]
+    fn test_parse_otpauth_uri() {
+        let parsed = TotpConfig::parse(
+            "otpauth://totp/Example:alice%40google.com?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ\
+             &issuer=Example&algorithm=SHA256&digits=8&period=60",
+        )
+        .unwrap();
+        assert_eq!(parsed.secret.as_slice(), SEED_SHA1);
+        assert_eq!(parsed.algorithm, TotpAlgorithm::Sha256);
+        assert_eq!(parsed.digits, 8);
+        assert_eq!(parsed.period, 60);
+
+        // Defaults apply when parameters are omitted
+        let parsed = TotpConfig::parse("OTPAUTH://TOTP/label?secret=GEZDGNBVGY3TQOJQ%3D%3D").unwrap();
+        assert_eq!(parsed.algorithm, TotpAlgorithm::Sha1);
+        assert_eq!(parsed.digits, 6);
+        assert_eq!(parsed.period, 30);
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_uris() {
+        let cases = [
+            "otpauth://hotp/label?secret=GEZDGNBV&counter=1",
+            "otpauth://totp/label?issuer=Example",
+            "otpauth://totp/label?secret=GEZDGNBV&algorithm=MD5",
+            "otpauth://totp/label?secret=GEZDGNBV&digits=5",
+            "otpauth://totp/label?secret=GEZDGNBV&digits=9",
+            "otpauth://totp/label?secret=GEZDGNBV&period=0",
+        ];
+
+        for uri in cases {
+            assert!(TotpConfig::parse(uri).is_err(), "{}", uri);
+        }
+    }
+
+    #[test]
+    fn test_account_totp_code() {
+        let db = Database::init_in_memory().unwrap();
+        let key = derive_key(b"test-password", b"test-salt-16byte");
+
+        let create = |totp_secret: Option<String>| {
+            db.create_account(CreateAccount {
+                raw_import_id: None,
+                email: encrypt_field("user@example.com", &key).unwrap(),
+                password: encrypt_field("password", &key).unwrap(),
+                recovery_email: None,
+                totp_secret,
+                year: None,
+                notes: None,
+                group_id: None,
+                field_order: None,
+            })
+            .unwrap()
+        };
+
+        let with_secret = create(Some(
+            encrypt_field("GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ", &key).unwrap(),
+        ));
+        let without_secret = create(None);
+
+        let code = account_totp_code(&db, &key, with_secret, 59).unwrap();
+        assert_eq!(code, TotpCode {
+            code: "287082".to_string(),
+            period: 30,
+            remaining_seconds: 1,
+        });
+
+        assert!(matches!(
+            account_totp_code(&db, &key, without_secret, 59),
+            Err(TotpError::NoSecret)
+        ));
+    }
+}
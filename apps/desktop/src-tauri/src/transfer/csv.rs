@@ -0,0 +1,306 @@
+//! Plaintext CSV export
+//!
+//! Writes one row per account with a header row, using the same column
+//! mapping as the CSV importer ([`crate::import::csv`]), so an exported
+//! file can be imported again unchanged. Tags are written as a
+//! `;`-separated list of names and the group as its full path
+//! (`Clients/Work`), which the importer resolves back to the nested group.
+//!
+//! The file contains every selected field in plaintext, so the caller must
+//! set `confirm_plaintext`. Cells that a spreadsheet would evaluate as a
+//! formula get a leading `'` ([`escape_formula`]), which the importer
+//! removes again, so the round trip stays lossless.
+
+use super::{TransferError, TransferResult};
+use crate::accounts::{self, Account};
+use crate::crypto::SecretKey;
+use crate::db::{Account as DbAccount, Database, Group, Pagination};
+use crate::import::csv::{escape_formula, CsvColumn, CsvField, TAG_SEPARATOR};
+use crate::import::{escape_group_name, GROUP_PATH_SEPARATOR};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// ============================================================================
+// Types
+// ============================================================================
+
+/// CSV export options
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CsvExportRequest {
+    /// Columns to write, in order (every field under its default header
+    /// when omitted)
+    pub columns: Option<Vec<CsvColumn>>,
+    /// Only export accounts in this group
+    pub group_id: Option<String>,
+    /// Only export these accounts
+    pub account_ids: Option<Vec<String>>,
+    /// Must be true: the file is not encrypted
+    #[serde(default)]
+    pub confirm_plaintext: bool,
+}
+
+// ============================================================================
+// Export
+// ============================================================================
+
+/// Write the selected accounts as a plaintext CSV file
+///
+/// # Errors
+/// - `ConfirmationRequired` - `confirm_plaintext` is not set
+/// - `InvalidInput` - No columns or an invalid ID
+/// - `Account` - A stored field could not be decrypted
+pub fn export_csv(db: &Database, key: &SecretKey, request: &CsvExportRequest) -> TransferResult<String> {
+    if !request.confirm_plaintext {
+        return Err(TransferError::ConfirmationRequired);
+    }
+
+    let columns = request.columns.clone().unwrap_or_else(CsvColumn::defaults);
+    if columns.is_empty() {
+        return Err(TransferError::InvalidInput("No columns selected".to_string()));
+    }
+
+    let selected = select_accounts(db, request)?;
+    let group_paths = group_paths(&db.get_groups()?);
+
+    let mut writer = ::csv::Writer::from_writer(Vec::new());
+    writer
+        .write_record(columns.iter().map(|c| c.column.as_str()))
+        .map_err(|e| TransferError::InvalidFile(e.to_string()))?;
+
+    let count = selected.len();
+    for db_account in selected {
+        let group = db_account.group_id.and_then(|id| group_paths.get(&id)).cloned();
+        let account = accounts::db_to_frontend_account(db_account, key)?;
+        let row: Vec<String> = columns
+            .iter()
+            .map(|c| escape_formula(&cell(&account, group.as_deref(), c.field)))
+            .collect();
+        writer
+            .write_record(&row)
+            .map_err(|e| TransferError::InvalidFile(e.to_string()))?;
+    }
+
+    let bytes = writer
+        .into_inner()
+        .map_err(|e| TransferError::InvalidFile(e.to_string()))?;
+    let contents = String::from_utf8(bytes).map_err(|e| TransferError::InvalidFile(e.to_string()))?;
+
+    db.log_operation(
+        None,
+        "EXPORT_CSV",
+        Some(&format!("Exported {} accounts as plaintext CSV", count)),
+    )?;
+
+    Ok(contents)
+}
+
+/// Accounts matching the request's group and ID filters
+fn select_accounts(db: &Database, request: &CsvExportRequest) -> TransferResult<Vec<DbAccount>> {
+    let group_id = request.group_id.as_deref().map(parse_id).transpose()?;
+
+    let selected = match &request.account_ids {
+        Some(ids) => ids
+            .iter()
+            .map(|id| Ok(db.get_account(parse_id(id)?)?))
+            .collect::<TransferResult<Vec<_>>>()?,
+        None => {
+            let count = db.get_accounts_count()?;
+            db.get_accounts(Some(Pagination::new(0, count)))?
+        }
+    };
+
+    Ok(selected
+        .into_iter()
+        .filter(|a| group_id.is_none() || a.group_id == group_id)
+        .collect())
+}
+
+/// Full path of every group, with escaped names
+fn group_paths(groups: &[Group]) -> HashMap<i64, String> {
+    let by_id: HashMap<i64, &Group> = groups.iter().map(|g| (g.id, g)).collect();
+
+    groups
+        .iter()
+        .map(|group| {
+            let mut names = vec![escape_group_name(&group.name)];
+            let mut parent_id = group.parent_id;
+            // Bounded by the group count in case of a corrupt cycle
+            while let Some(parent) = parent_id.and_then(|id| by_id.get(&id)) {
+                if names.len() > groups.len() {
+                    break;
+                }
+                names.push(escape_group_name(&parent.name));
+                parent_id = parent.parent_id;
+            }
+            names.reverse();
+            (group.id, names.join(&GROUP_PATH_SEPARATOR.to_string()))
+        })
+        .collect()
+}
+
+/// Value of one field for the CSV row
+fn cell(account: &Account, group: Option<&str>, field: CsvField) -> String {
+    match field {
+        CsvField::Email => account.email.clone(),
+        CsvField::Password => account.password.clone(),
+        CsvField::RecoveryEmail => account.recovery_email.clone().unwrap_or_default(),
+        CsvField::TotpSecret => account.totp_secret.clone().unwrap_or_default(),
+        CsvField::Year => account.year.clone().unwrap_or_default(),
+        CsvField::Notes => account.notes.clone().unwrap_or_default(),
+        CsvField::Group => group.unwrap_or_default().to_string(),
+        CsvField::Tags => account
+            .tags
+            .iter()
+            .map(|t| t.name.as_str())
+            .collect::<Vec<_>>()
+            .join(&TAG_SEPARATOR.to_string()),
+    }
+}
+
+fn parse_id(id: &str) -> TransferResult<i64> {
+    id.parse()
+        .map_err(|_| TransferError::InvalidInput(format!("Invalid ID: {}", id)))
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::accounts::CreateAccountPayload;
+    use crate::crypto::derive_key;
+    use crate::db::{CreateGroup, CreateTag};
+    use crate::import::{self, CsvImportRequest};
+
+    fn test_key() -> SecretKey {
+        derive_key(b"test-password", b"test-salt-16byte")
+    }
+
+    fn create_group(db: &Database, name: &str, parent_id: Option<i64>) -> i64 {
+        db.create_group(CreateGroup {
+            name: name.to_string(),
+            color: "#000000".to_string(),
+            sort_order: 1,
+            parent_id,
+        })
+        .unwrap()
+    }
+
+    fn source_vault(key: &SecretKey) -> Database {
+        let db = Database::init_in_memory().unwrap();
+        let clients = create_group(&db, "Clients", None);
+        let group_id = create_group(&db, "Work/Ops", Some(clients));
+
+        let payload = CreateAccountPayload {
+            raw_import_id: None,
+            email: "a@example.com".to_string(),
+            password: "=pa,ss\"word ".to_string(),
+            recovery_email: None,
+            totp_secret: Some("JBSWY3DPEHPK3PXP".to_string()),
+            year: Some("2020".to_string()),
+            notes: Some("-line one\nline two ".to_string()),
+            group_id: Some(group_id.to_string()),
+            field_order: None,
+        };
+        let id = db.create_account(accounts::frontend_to_db_create(payload, key).unwrap()).unwrap();
+        for name in ["vip", "old"] {
+            let tag_id = db
+                .create_tag(CreateTag {
+                    name: name.to_string(),
+                    color: "#ffffff".to_string(),
+                })
+                .unwrap();
+            db.add_tag_to_account(id, tag_id).unwrap();
+        }
+        db
+    }
+
+    #[test]
+    fn test_requires_confirmation() {
+        let key = test_key();
+        let db = source_vault(&key);
+
+        let result = export_csv(&db, &key, &CsvExportRequest::default());
+        assert!(matches!(result, Err(TransferError::ConfirmationRequired)));
+    }
+
+    #[test]
+    fn test_round_trip_through_import() {
+        let key = test_key();
+        let request = CsvExportRequest {
+            confirm_plaintext: true,
+            ..Default::default()
+        };
+        let contents = export_csv(&source_vault(&key), &key, &request).unwrap();
+        assert!(contents.starts_with("email,password,recovery_email,totp_secret,year,notes,group,tags\n"));
+
+        assert!(contents.contains(",Clients/Work\\/Ops,"));
+        // No cell starts a spreadsheet formula
+        assert!(contents.contains(",\"'=pa,ss\"\"word \",") && contents.contains(",\"'-line one"));
+
+        // A top-level group with the same name is not the nested one
+        let target = Database::init_in_memory().unwrap();
+        let top_level = create_group(&target, "Work/Ops", None);
+        let report = import::import_csv(
+            &target,
+            &key,
+            CsvImportRequest {
+                contents,
+                columns: None,
+                delimiter: None,
+                source_name: Some("export.csv".to_string()),
+                group_id: None,
+                dry_run: false,
+            },
+        )
+        .unwrap();
+        assert_eq!(report.imported_count, 1);
+        assert!(report.errors.is_empty());
+
+        let id: i64 = report.account_ids[0].parse().unwrap();
+        let account = accounts::db_to_frontend_account(target.get_account(id).unwrap(), &key).unwrap();
+        // Formula escaping and surrounding whitespace survive the round trip
+        assert_eq!(account.password, "=pa,ss\"word ");
+        assert_eq!(account.notes.as_deref(), Some("-line one\nline two "));
+        assert_eq!(account.year.as_deref(), Some("2020"));
+
+        let mut tags: Vec<String> = account.tags.into_iter().map(|t| t.name).collect();
+        tags.sort();
+        assert_eq!(tags, vec!["old", "vip"]);
+
+        let group_id: i64 = account.group_id.unwrap().parse().unwrap();
+        assert_ne!(group_id, top_level);
+        let group = target.get_group(group_id).unwrap();
+        assert_eq!(group.name, "Work/Ops");
+        assert_eq!(target.get_group(group.parent_id.unwrap()).unwrap().name, "Clients");
+    }
+
+    #[test]
+    fn test_selected_columns_and_accounts() {
+        let key = test_key();
+        let db = source_vault(&key);
+        let request = CsvExportRequest {
+            columns: Some(vec![
+                CsvColumn { column: "Login".to_string(), field: CsvField::Email },
+                CsvColumn { column: "Labels".to_string(), field: CsvField::Tags },
+            ]),
+            account_ids: Some(vec!["999".to_string()]),
+            confirm_plaintext: true,
+            ..Default::default()
+        };
+        assert!(export_csv(&db, &key, &request).is_err());
+
+        let request = CsvExportRequest {
+            account_ids: None,
+            ..request
+        };
+        let contents = export_csv(&db, &key, &request).unwrap();
+        let mut lines = contents.lines();
+        assert_eq!(lines.next(), Some("Login,Labels"));
+        let row = lines.next().unwrap();
+        assert!(row.starts_with("a@example.com,"));
+        assert!(row.contains("vip") && row.contains(';'));
+    }
+}
@@ -0,0 +1,137 @@
+//! Vault export and import for GManager Desktop
+//!
+//! Moves a whole vault (groups, tags, raw imports, accounts and their tag
+//! links) between installations. The export file is self-describing and
+//! encrypted with its own passphrase; see [`vault`] for the format.
+//!
+//! Accounts can also be exported as a plaintext CSV file for spreadsheets
+//! (see [`csv`]); CSV files are imported through [`crate::import`].
+
+pub mod csv;
+pub mod vault;
+
+use crate::accounts::AccountError;
+use crate::auth::SessionManager;
+use crate::crypto::{CryptoError, KdfParams};
+use crate::db::transfer::TransferMode;
+use crate::db::{Database, DbError};
+use crate::search_index::SearchIndexError;
+use thiserror::Error;
+
+pub use self::csv::CsvExportRequest;
+pub use vault::VaultImportReport;
+
+// ============================================================================
+// Error Types
+// ============================================================================
+
+/// Export/import operation errors
+#[derive(Debug, Error)]
+pub enum TransferError {
+    /// Database error
+    #[error("Database error: {0}")]
+    Database(#[from] DbError),
+
+    /// Cryptographic error
+    #[error("Crypto error: {0}")]
+    Crypto(#[from] CryptoError),
+
+    /// Search index error
+    #[error("Search index error: {0}")]
+    SearchIndex(#[from] SearchIndexError),
+
+    /// Account conversion error
+    #[error("Account error: {0}")]
+    Account(#[from] AccountError),
+
+    /// Invalid passphrase or options
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+
+    /// The file is not a vault export or is damaged
+    #[error("Invalid export file: {0}")]
+    InvalidFile(String),
+
+    /// The file was written by a newer version of the app
+    #[error("Export file version {0} is not supported")]
+    UnsupportedVersion(u32),
+
+    /// The export passphrase does not decrypt the file
+    #[error("Wrong export passphrase")]
+    WrongPassphrase,
+
+    /// Plaintext export was requested without confirming it
+    #[error("Plaintext export must be confirmed")]
+    ConfirmationRequired,
+}
+
+/// Result type for export/import operations
+pub type TransferResult<T> = std::result::Result<T, TransferError>;
+
+// ============================================================================
+// Tauri Commands
+// ============================================================================
+
+/// Export the whole vault as an encrypted file
+///
+/// # Arguments
+/// * `passphrase` - Export passphrase (independent of the master password)
+/// * `kdf_params` - Optional KDF parameters (defaults to Argon2id defaults;
+///   only stronger parameters are accepted)
+///
+/// # Returns
+/// File contents for the frontend to save
+#[tauri::command]
+pub fn export_vault_command(
+    db: tauri::State<Database>,
+    session_manager: tauri::State<SessionManager>,
+    passphrase: String,
+    kdf_params: Option<KdfParams>,
+) -> Result<String, String> {
+    let key = session_manager.get_session_key()
+        .map_err(|e| e.to_string())?;
+
+    vault::export_vault(&db, &key, &passphrase, &kdf_params.unwrap_or_default())
+        .map_err(|e| e.to_string())
+}
+
+/// Import an encrypted vault export
+///
+/// # Arguments
+/// * `contents` - File contents
+/// * `passphrase` - Export passphrase the file was written with
+/// * `mode` - `merge` (default) or `replace`
+#[tauri::command]
+pub fn import_vault_command(
+    db: tauri::State<Database>,
+    session_manager: tauri::State<SessionManager>,
+    contents: String,
+    passphrase: String,
+    mode: Option<TransferMode>,
+) -> Result<VaultImportReport, String> {
+    let key = session_manager.get_session_key()
+        .map_err(|e| e.to_string())?;
+
+    vault::import_vault(&db, &key, &contents, &passphrase, mode.unwrap_or_default())
+        .map_err(|e| e.to_string())
+}
+
+/// Export accounts as a plaintext CSV file
+///
+/// # Arguments
+/// * `request` - Columns, account selection and the plaintext confirmation
+///
+/// # Returns
+/// File contents for the frontend to save
+#[tauri::command]
+pub fn export_csv_command(
+    db: tauri::State<Database>,
+    session_manager: tauri::State<SessionManager>,
+    request: CsvExportRequest,
+) -> Result<String, String> {
+    let key = session_manager.get_session_key()
+        .map_err(|e| e.to_string())?;
+
+    csv::export_csv(&db, &key, &request)
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,615 @@
+//! Encrypted vault export file
+//!
+//! The file is JSON with a plaintext header and an encrypted payload:
+//!
+//! ```json
+//! {
+//!   "format": "gmanager-vault-export",
+//!   "version": 1,
+//!   "created_at": "2024-01-01T00:00:00Z",
+//!   "kdf": { "algorithm": "argon2id", "memory_kib": 65536, "time_cost": 3, "lanes": 4 },
+//!   "salt": "<base64, 16 bytes>",
+//!   "payload": "enc1:<AES-256-GCM ciphertext>"
+//! }
+//! ```
+//!
+//! The payload key is derived from the export passphrase with the header's
+//! KDF parameters, so the file can be opened on any installation. The
+//! decrypted payload is a [`VaultPayload`] with every field in plaintext;
+//! it repeats the format version, which is checked against the header.
+//!
+//! Importing re-encrypts everything under the current session key and
+//! assigns new ids (see [`crate::db::transfer`]). Vault imports are not
+//! recorded in the undo history.
+
+use super::{TransferError, TransferResult};
+use crate::crypto::{
+    decrypt_field, derive_key_with_params, encrypt_field, generate_salt, is_encrypted,
+    CryptoError, KdfParams, SecretKey,
+};
+use crate::db::transfer::{AccountTagLink, TransferMode, VaultSnapshot};
+use crate::db::{Account, Database, Group, RawImport, Tag};
+use crate::search_index;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use zeroize::Zeroizing;
+
+// ============================================================================
+// Constants
+// ============================================================================
+
+/// `format` field identifying a vault export
+pub const VAULT_FILE_FORMAT: &str = "gmanager-vault-export";
+
+/// Current file format version
+pub const VAULT_FILE_VERSION: u32 = 1;
+
+/// Minimum export passphrase length (same as the master password)
+const MIN_PASSPHRASE_LEN: usize = 8;
+
+// ============================================================================
+// File Format
+// ============================================================================
+
+/// Export file as written to disk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VaultFile {
+    pub format: String,
+    pub version: u32,
+    pub created_at: String,
+    pub kdf: KdfParams,
+    /// Base64-encoded KDF salt
+    pub salt: String,
+    /// Encrypted `VaultPayload` JSON
+    pub payload: String,
+}
+
+/// Decrypted file contents
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct VaultPayload {
+    pub version: u32,
+    pub groups: Vec<ExportGroup>,
+    pub tags: Vec<ExportTag>,
+    pub raw_imports: Vec<ExportRawImport>,
+    pub accounts: Vec<ExportAccount>,
+    pub account_tags: Vec<AccountTagLink>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportGroup {
+    pub id: i64,
+    pub name: String,
+    pub color: String,
+    pub sort_order: i32,
+    pub created_at: String,
+    /// Absent in files written before groups could be nested
+    #[serde(default)]
+    pub parent_id: Option<i64>,
+    /// Absent in files written before the Default group was flagged
+    #[serde(default)]
+    pub is_default: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportTag {
+    pub id: i64,
+    pub name: String,
+    pub color: String,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportRawImport {
+    pub id: i64,
+    pub raw_text: String,
+    pub source_type: String,
+    pub source_name: Option<String>,
+    pub imported_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportAccount {
+    pub id: i64,
+    pub raw_import_id: Option<i64>,
+    pub email: String,
+    pub password: String,
+    pub recovery_email: Option<String>,
+    pub totp_secret: Option<String>,
+    pub year: Option<i32>,
+    pub notes: Option<String>,
+    pub group_id: Option<i64>,
+    pub field_order: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// Outcome of a vault import
+#[derive(Debug, Clone, Serialize)]
+pub struct VaultImportReport {
+    pub mode: TransferMode,
+    pub accounts_imported: usize,
+    /// Accounts whose email already exists in the vault (merge only)
+    pub accounts_skipped: usize,
+    pub groups_created: usize,
+    pub groups_matched: usize,
+    pub tags_created: usize,
+    pub tags_matched: usize,
+    pub raw_imports_imported: usize,
+    /// IDs of the created accounts
+    pub account_ids: Vec<String>,
+}
+
+// ============================================================================
+// Export
+// ============================================================================
+
+/// Write the whole vault as an encrypted export file
+///
+/// # Errors
+/// - `InvalidInput` - Passphrase too short or KDF parameters weaker than
+///   the defaults
+/// - `Crypto` - A stored field could not be decrypted
+pub fn export_vault(
+    db: &Database,
+    key: &SecretKey,
+    passphrase: &str,
+    kdf_params: &KdfParams,
+) -> TransferResult<String> {
+    validate_passphrase(passphrase)?;
+    kdf_params.validate()?;
+    if kdf_params.is_weaker_than(&KdfParams::default()) {
+        return Err(TransferError::InvalidInput(
+            "KDF parameters are weaker than the defaults".to_string(),
+        ));
+    }
+
+    let payload = open_snapshot(db.export_snapshot()?, key)?;
+    let json = Zeroizing::new(
+        serde_json::to_string(&payload).map_err(|e| TransferError::InvalidFile(e.to_string()))?,
+    );
+
+    let salt = generate_salt()?;
+    let export_key = derive_key_with_params(passphrase.as_bytes(), &salt, kdf_params)?;
+
+    let file = VaultFile {
+        format: VAULT_FILE_FORMAT.to_string(),
+        version: VAULT_FILE_VERSION,
+        created_at: chrono::Utc::now().to_rfc3339(),
+        kdf: *kdf_params,
+        salt: BASE64.encode(salt),
+        payload: encrypt_field(&json, &export_key)?,
+    };
+
+    db.log_operation(
+        None,
+        "EXPORT_VAULT",
+        Some(&format!("Exported {} accounts", payload.accounts.len())),
+    )?;
+
+    serde_json::to_string_pretty(&file).map_err(|e| TransferError::InvalidFile(e.to_string()))
+}
+
+/// Decrypt a stored snapshot into the plaintext payload
+fn open_snapshot(snapshot: VaultSnapshot, key: &SecretKey) -> TransferResult<VaultPayload> {
+    let open_optional = |value: Option<String>| -> TransferResult<Option<String>> {
+        match value {
+            Some(v) if !v.is_empty() => Ok(Some(decrypt_field(&v, key)?)),
+            _ => Ok(None),
+        }
+    };
+
+    let accounts = snapshot
+        .accounts
+        .into_iter()
+        .map(|a| {
+            Ok(ExportAccount {
+                id: a.id,
+                raw_import_id: a.raw_import_id,
+                email: decrypt_field(&a.email, key)?,
+                password: decrypt_field(&a.password, key)?,
+                recovery_email: open_optional(a.recovery_email)?,
+                totp_secret: open_optional(a.totp_secret)?,
+                year: a.year,
+                notes: open_optional(a.notes)?,
+                group_id: a.group_id,
+                field_order: a.field_order,
+                created_at: a.created_at,
+                updated_at: a.updated_at,
+            })
+        })
+        .collect::<TransferResult<Vec<_>>>()?;
+
+    let raw_imports = snapshot
+        .raw_imports
+        .into_iter()
+        .map(|r| {
+            // Raw imports stored before they were encrypted are plaintext
+            let raw_text = if is_encrypted(&r.raw_text) {
+                decrypt_field(&r.raw_text, key)?
+            } else {
+                r.raw_text
+            };
+            Ok(ExportRawImport {
+                id: r.id,
+                raw_text,
+                source_type: r.source_type,
+                source_name: r.source_name,
+                imported_at: r.imported_at,
+            })
+        })
+        .collect::<TransferResult<Vec<_>>>()?;
+
+    Ok(VaultPayload {
+        version: VAULT_FILE_VERSION,
+        groups: snapshot
+            .groups
+            .into_iter()
+            .map(|g| ExportGroup {
+                id: g.id,
+                name: g.name,
+                color: g.color,
+                sort_order: g.sort_order,
+                created_at: g.created_at,
+                parent_id: g.parent_id,
+                is_default: g.is_default,
+            })
+            .collect(),
+        tags: snapshot
+            .tags
+            .into_iter()
+            .map(|t| ExportTag {
+                id: t.id,
+                name: t.name,
+                color: t.color,
+                created_at: t.created_at,
+            })
+            .collect(),
+        raw_imports,
+        accounts,
+        account_tags: snapshot.account_tags,
+    })
+}
+
+// ============================================================================
+// Import
+// ============================================================================
+
+/// Read an export file and write its contents into the vault
+///
+/// In merge mode, accounts whose email (case-insensitive) already exists
+/// are skipped; groups and tags are matched by name.
+///
+/// # Errors
+/// - `InvalidFile` / `UnsupportedVersion` - Not a readable export file
+/// - `WrongPassphrase` - The passphrase does not decrypt the payload
+pub fn import_vault(
+    db: &Database,
+    key: &SecretKey,
+    contents: &str,
+    passphrase: &str,
+    mode: TransferMode,
+) -> TransferResult<VaultImportReport> {
+    let mut payload = read_vault_file(contents, passphrase)?;
+
+    let mut skipped = 0;
+    if mode == TransferMode::Merge {
+        let existing = existing_emails(db, key)?;
+        let before = payload.accounts.len();
+        payload
+            .accounts
+            .retain(|a| !existing.contains(&a.email.to_lowercase()));
+        skipped = before - payload.accounts.len();
+    }
+
+    let snapshot = seal_payload(&payload, key)?;
+    let ids = db.import_snapshot(&snapshot, mode)?;
+
+    let mut account_ids: Vec<i64> = ids.accounts.values().copied().collect();
+    account_ids.sort_unstable();
+    for id in &account_ids {
+        search_index::index_stored_account(db, key, *id)?;
+    }
+
+    Ok(VaultImportReport {
+        mode,
+        accounts_imported: account_ids.len(),
+        accounts_skipped: skipped,
+        groups_created: ids.groups.len() - ids.groups_matched,
+        groups_matched: ids.groups_matched,
+        tags_created: ids.tags.len() - ids.tags_matched,
+        tags_matched: ids.tags_matched,
+        raw_imports_imported: ids.raw_imports.len(),
+        account_ids: account_ids.iter().map(|id| id.to_string()).collect(),
+    })
+}
+
+/// Check the header and decrypt the payload
+pub fn read_vault_file(contents: &str, passphrase: &str) -> TransferResult<VaultPayload> {
+    let file: VaultFile =
+        serde_json::from_str(contents).map_err(|e| TransferError::InvalidFile(e.to_string()))?;
+
+    if file.format != VAULT_FILE_FORMAT {
+        return Err(TransferError::InvalidFile(format!("Unknown format: {}", file.format)));
+    }
+    if file.version == 0 || file.version > VAULT_FILE_VERSION {
+        return Err(TransferError::UnsupportedVersion(file.version));
+    }
+
+    let salt: [u8; 16] = BASE64
+        .decode(&file.salt)
+        .map_err(CryptoError::from)?
+        .try_into()
+        .map_err(|_| TransferError::InvalidFile("Salt must be 16 bytes".to_string()))?;
+
+    // The parameters come from the file: refuse costs that would hang the
+    // app before spending any time on them
+    file.kdf.validate().map_err(|e| TransferError::InvalidFile(e.to_string()))?;
+
+    let export_key = derive_key_with_params(passphrase.as_bytes(), &salt, &file.kdf)?;
+    let json = Zeroizing::new(decrypt_field(&file.payload, &export_key).map_err(|e| match e {
+        CryptoError::DecryptionFailed(_) => TransferError::WrongPassphrase,
+        other => TransferError::Crypto(other),
+    })?);
+
+    let payload: VaultPayload =
+        serde_json::from_str(&json).map_err(|e| TransferError::InvalidFile(e.to_string()))?;
+    if payload.version != file.version {
+        return Err(TransferError::InvalidFile(
+            "Header and payload versions differ".to_string(),
+        ));
+    }
+
+    Ok(payload)
+}
+
+/// Encrypt a payload under the session key
+fn seal_payload(payload: &VaultPayload, key: &SecretKey) -> TransferResult<VaultSnapshot> {
+    let seal_optional = |value: &Option<String>| -> TransferResult<Option<String>> {
+        match value {
+            Some(v) if !v.is_empty() => Ok(Some(encrypt_field(v, key)?)),
+            _ => Ok(None),
+        }
+    };
+
+    let accounts = payload
+        .accounts
+        .iter()
+        .map(|a| {
+            if a.email.is_empty() || a.password.is_empty() {
+                return Err(TransferError::InvalidFile(format!(
+                    "Account {} has no email or password",
+                    a.id
+                )));
+            }
+            Ok(Account {
+                id: a.id,
+                raw_import_id: a.raw_import_id,
+                email: encrypt_field(&a.email, key)?,
+                password: encrypt_field(&a.password, key)?,
+                recovery_email: seal_optional(&a.recovery_email)?,
+                totp_secret: seal_optional(&a.totp_secret)?,
+                year: a.year,
+                notes: seal_optional(&a.notes)?,
+                group_id: a.group_id,
+                field_order: a.field_order.clone(),
+                created_at: a.created_at.clone(),
+                updated_at: a.updated_at.clone(),
+                tags: Vec::new(),
+            })
+        })
+        .collect::<TransferResult<Vec<_>>>()?;
+
+    let raw_imports = payload
+        .raw_imports
+        .iter()
+        .map(|r| {
+            Ok(RawImport {
+                id: r.id,
+                raw_text: encrypt_field(&r.raw_text, key)?,
+                source_type: r.source_type.clone(),
+                source_name: r.source_name.clone(),
+                imported_at: r.imported_at.clone(),
+            })
+        })
+        .collect::<TransferResult<Vec<_>>>()?;
+
+    Ok(VaultSnapshot {
+        groups: payload
+            .groups
+            .iter()
+            .map(|g| Group {
+                id: g.id,
+                name: g.name.clone(),
+                color: g.color.clone(),
+                sort_order: g.sort_order,
+                created_at: g.created_at.clone(),
+                parent_id: g.parent_id,
+                is_default: g.is_default,
+            })
+            .collect(),
+        tags: payload
+            .tags
+            .iter()
+            .map(|t| Tag {
+                id: t.id,
+                name: t.name.clone(),
+                color: t.color.clone(),
+                created_at: t.created_at.clone(),
+            })
+            .collect(),
+        raw_imports,
+        accounts,
+        account_tags: payload.account_tags.clone(),
+    })
+}
+
+/// Lowercased emails of every account in the vault
+fn existing_emails(db: &Database, key: &SecretKey) -> TransferResult<HashSet<String>> {
+    db.export_snapshot()?
+        .accounts
+        .iter()
+        .map(|a| Ok(decrypt_field(&a.email, key)?.to_lowercase()))
+        .collect()
+}
+
+fn validate_passphrase(passphrase: &str) -> TransferResult<()> {
+    if passphrase.len() < MIN_PASSPHRASE_LEN {
+        return Err(TransferError::InvalidInput(format!(
+            "Export passphrase must be at least {} characters",
+            MIN_PASSPHRASE_LEN
+        )));
+    }
+    Ok(())
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::accounts::{self, CreateAccountPayload};
+    use crate::crypto::derive_key;
+    use crate::db::{CreateGroup, CreateTag, Pagination};
+
+    const PASSPHRASE: &str = "export-passphrase";
+
+    fn test_key() -> SecretKey {
+        derive_key(b"test-password", b"test-salt-16byte")
+    }
+
+    fn other_key() -> SecretKey {
+        derive_key(b"other-password", b"test-salt-16byte")
+    }
+
+    fn create_account(db: &Database, key: &SecretKey, email: &str, group_id: Option<i64>) -> i64 {
+        let payload = CreateAccountPayload {
+            raw_import_id: None,
+            email: email.to_string(),
+            password: "hunter22".to_string(),
+            recovery_email: None,
+            totp_secret: Some("JBSWY3DPEHPK3PXP".to_string()),
+            year: None,
+            notes: Some("note".to_string()),
+            group_id: group_id.map(|id| id.to_string()),
+            field_order: None,
+        };
+        let id = db.create_account(accounts::frontend_to_db_create(payload, key).unwrap()).unwrap();
+        search_index::index_stored_account(db, key, id).unwrap();
+        id
+    }
+
+    fn source_vault(key: &SecretKey) -> Database {
+        let db = Database::init_in_memory().unwrap();
+        let group_id = db
+            .create_group(CreateGroup {
+                name: "Work".to_string(),
+                color: "#000000".to_string(),
+                sort_order: 1,
+                parent_id: None,
+            })
+            .unwrap();
+        let tag_id = db
+            .create_tag(CreateTag {
+                name: "vip".to_string(),
+                color: "#ffffff".to_string(),
+            })
+            .unwrap();
+        let id = create_account(&db, key, "a@example.com", Some(group_id));
+        db.add_tag_to_account(id, tag_id).unwrap();
+        create_account(&db, key, "b@example.com", None);
+        db
+    }
+
+    #[test]
+    fn test_round_trip_between_vaults() {
+        let key = test_key();
+        let file = export_vault(&source_vault(&key), &key, PASSPHRASE, &KdfParams::default()).unwrap();
+        assert!(!file.contains("a@example.com"));
+
+        // The target vault has a different session key
+        let target_key = other_key();
+        let target = Database::init_in_memory().unwrap();
+        let report = import_vault(&target, &target_key, &file, PASSPHRASE, TransferMode::Merge).unwrap();
+
+        assert_eq!(report.accounts_imported, 2);
+        assert_eq!(report.groups_created, 1);
+        assert_eq!(report.groups_matched, 1);
+
+        let accounts = target.get_accounts(Some(Pagination::new(0, 10))).unwrap();
+        let imported = accounts
+            .iter()
+            .find(|a| decrypt_field(&a.email, &target_key).unwrap() == "a@example.com")
+            .unwrap();
+        assert_eq!(decrypt_field(&imported.password, &target_key).unwrap(), "hunter22");
+        assert_eq!(imported.tags[0].name, "vip");
+        assert_eq!(target.get_group(imported.group_id.unwrap()).unwrap().name, "Work");
+        assert!(target.get_unindexed_account_ids().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_merge_skips_existing_emails() {
+        let key = test_key();
+        let source = source_vault(&key);
+        let file = export_vault(&source, &key, PASSPHRASE, &KdfParams::default()).unwrap();
+
+        let report = import_vault(&source, &key, &file, PASSPHRASE, TransferMode::Merge).unwrap();
+
+        assert_eq!(report.accounts_imported, 0);
+        assert_eq!(report.accounts_skipped, 2);
+        assert_eq!(source.get_accounts_count().unwrap(), 2);
+    }
+
+    #[test]
+    fn test_replace_mode() {
+        let key = test_key();
+        let file = export_vault(&source_vault(&key), &key, PASSPHRASE, &KdfParams::default()).unwrap();
+
+        let target = Database::init_in_memory().unwrap();
+        create_account(&target, &key, "old@example.com", None);
+
+        let report = import_vault(&target, &key, &file, PASSPHRASE, TransferMode::Replace).unwrap();
+
+        assert_eq!(report.accounts_imported, 2);
+        assert_eq!(target.get_accounts_count().unwrap(), 2);
+    }
+
+    #[test]
+    fn test_rejects_wrong_passphrase_and_bad_files() {
+        let key = test_key();
+        let file = export_vault(&source_vault(&key), &key, PASSPHRASE, &KdfParams::default()).unwrap();
+
+        assert!(matches!(
+            read_vault_file(&file, "wrong-passphrase"),
+            Err(TransferError::WrongPassphrase)
+        ));
+
+        let mut header: VaultFile = serde_json::from_str(&file).unwrap();
+        header.version = VAULT_FILE_VERSION + 1;
+        assert!(matches!(
+            read_vault_file(&serde_json::to_string(&header).unwrap(), PASSPHRASE),
+            Err(TransferError::UnsupportedVersion(_))
+        ));
+
+        assert!(matches!(read_vault_file("{}", PASSPHRASE), Err(TransferError::InvalidFile(_))));
+
+        // A crafted KDF cost is refused without deriving a key
+        let mut header: VaultFile = serde_json::from_str(&file).unwrap();
+        header.kdf = KdfParams::Pbkdf2 { iterations: u32::MAX };
+        assert!(matches!(
+            read_vault_file(&serde_json::to_string(&header).unwrap(), PASSPHRASE),
+            Err(TransferError::InvalidFile(_))
+        ));
+    }
+
+    #[test]
+    fn test_export_rejects_weak_options() {
+        let key = test_key();
+        let db = source_vault(&key);
+
+        assert!(export_vault(&db, &key, "short", &KdfParams::default()).is_err());
+        assert!(export_vault(&db, &key, PASSPHRASE, &KdfParams::legacy()).is_err());
+    }
+}
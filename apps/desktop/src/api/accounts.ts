@@ -118,15 +118,19 @@ export interface UpdateAccountPayload {
 export interface AccountSearchParams {
   /** Text search query (searches email, recovery_email, notes) */
   query?: string;
+  /** How the query is matched (default: 'contains'; queries under 3 characters match as prefix) */
+  match_mode?: 'contains' | 'prefix' | 'exact';
   /** Filter by group ID */
   group_id?: string;
+  /** Also match accounts in subgroups of `group_id` (default: false) */
+  include_subgroups?: boolean;
   /** Filter by tag ID */
   tag_id?: string;
   /** Filter by year */
   year?: string;
-  /** Pagination offset (default: 0) */
+  /** Pagination offset (default: 0); with a query, counts matches */
   offset?: number;
-  /** Pagination limit (default: 50) */
+  /** Pagination limit (default: 50); only the last page is shorter */
   limit?: number;
 }
 
@@ -146,12 +150,33 @@ export interface AccountStats {
   accounts_per_group: [string, string, number][];
 }
 
+/**
+ * Current TOTP code for an account (the secret stays in the backend)
+ */
+export interface TotpCode {
+  /** Zero-padded one-time code */
+  code: string;
+  /** Code period in seconds */
+  period: number;
+  /** Seconds until the next code */
+  remaining_seconds: number;
+}
+
+/**
+ * How a batch treats items that fail
+ * - all_or_nothing: any failure rolls back the whole batch (default)
+ * - best_effort: failed items are skipped, the rest is committed
+ */
+export type BatchMode = 'all_or_nothing' | 'best_effort';
+
 /**
  * Batch delete request
  */
 export interface BatchDeleteRequest {
   /** Account IDs to delete */
   ids: string[];
+  /** Failure handling (default: all_or_nothing) */
+  mode?: BatchMode;
 }
 
 /**
@@ -162,6 +187,36 @@ export interface BatchUpdateRequest {
   ids: string[];
   /** Updates to apply to all accounts */
   updates: UpdateAccountPayload;
+  /** Failure handling (default: all_or_nothing) */
+  mode?: BatchMode;
+}
+
+/**
+ * Status of one account in a batch
+ */
+export type BatchItemStatus = 'ok' | 'not_found' | 'validation_error' | 'rolled_back';
+
+/**
+ * Per-account batch result
+ */
+export interface BatchItemResult {
+  id: string;
+  status: BatchItemStatus;
+  /** Reason for a not_found or validation_error status */
+  message: string | null;
+}
+
+/**
+ * Batch operation result
+ */
+export interface BatchResult {
+  mode: BatchMode;
+  /** Whether any changes were committed */
+  committed: boolean;
+  succeeded: number;
+  failed: number;
+  /** Results in request order */
+  results: BatchItemResult[];
 }
 
 /**
@@ -420,22 +475,26 @@ export async function searchAccounts(
 /**
  * Delete multiple accounts at once
  *
+ * Runs in a single transaction and reports the outcome for every ID.
+ *
  * @param ids - Array of account IDs to delete
- * @returns The number of accounts successfully deleted
- * @throws AccountApiError if not logged in or database error occurs
+ * @param mode - Failure handling (default: all_or_nothing)
+ * @returns Per-ID results
+ * @throws AccountApiError if database error occurs
  *
  * @example
  * ```ts
- * const deleted = await batchDeleteAccounts(['1', '2', '3']);
- * console.log(`Deleted ${deleted} accounts`);
+ * const result = await batchDeleteAccounts(['1', '2', '3']);
+ * console.log(`Deleted ${result.succeeded} accounts`);
  * ```
  */
 export async function batchDeleteAccounts(
-  ids: string[]
-): Promise<number> {
+  ids: string[],
+  mode?: BatchMode
+): Promise<BatchResult> {
   try {
-    const request: BatchDeleteRequest = { ids };
-    return await invoke<number>('batch_delete_accounts_command', { request });
+    const request: BatchDeleteRequest = { ids, mode };
+    return await invoke<BatchResult>('batch_delete_accounts_command', { request });
   } catch (error) {
     throw new AccountApiError(
       'Failed to batch delete accounts',
@@ -448,9 +507,12 @@ export async function batchDeleteAccounts(
 /**
  * Update multiple accounts with the same changes
  *
+ * Runs in a single transaction and reports the outcome for every ID.
+ *
  * @param ids - Array of account IDs to update
  * @param updates - The updates to apply to all accounts
- * @returns The number of accounts successfully updated
+ * @param mode - Failure handling (default: all_or_nothing)
+ * @returns Per-ID results
  * @throws AccountApiError if not logged in or database error occurs
  *
  * @example
@@ -470,14 +532,16 @@ export async function batchDeleteAccounts(
  */
 export async function batchUpdateAccounts(
   ids: string[],
-  updates: Omit<UpdateAccountPayload, 'id'>
-): Promise<number> {
+  updates: Omit<UpdateAccountPayload, 'id'>,
+  mode?: BatchMode
+): Promise<BatchResult> {
   try {
     const request: BatchUpdateRequest = {
       ids,
       updates: { ...updates, id: '' }, // ID is ignored for batch updates
+      mode,
     };
-    return await invoke<number>('batch_update_accounts_command', { request });
+    return await invoke<BatchResult>('batch_update_accounts_command', { request });
   } catch (error) {
     throw new AccountApiError(
       'Failed to batch update accounts',
@@ -515,6 +579,28 @@ export async function getAccountStats(): Promise<AccountStats> {
   }
 }
 
+/**
+ * Get the current TOTP code for an account
+ *
+ * The TOTP secret is decrypted and used on the Rust side only.
+ *
+ * @param accountId - The account ID
+ * @returns The current code, its period and the seconds remaining
+ * @throws AccountApiError if not logged in, the account has no TOTP secret,
+ *         or the secret is invalid
+ */
+export async function getTotpCode(accountId: string): Promise<TotpCode> {
+  try {
+    return await invoke<TotpCode>('get_totp_code_command', { accountId });
+  } catch (error) {
+    throw new AccountApiError(
+      `Failed to get TOTP code: ${accountId}`,
+      'GET_TOTP_CODE_ERROR',
+      error
+    );
+  }
+}
+
 // ============================================================================
 // Helper Functions
 // ============================================================================
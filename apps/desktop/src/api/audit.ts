@@ -0,0 +1,238 @@
+/**
+ * Audit log API client for GManager Desktop
+ *
+ * Operation log entries are encrypted and hash-chained in the Rust backend.
+ * These functions query and export the log, manage its retention and check
+ * the chain for tampering.
+ *
+ * @module api/audit
+ */
+
+import { invoke } from '@tauri-apps/api/core';
+
+// ============================================================================
+// Type Definitions
+// ============================================================================
+
+/**
+ * Operation log filters
+ */
+export interface LogQuery {
+  account_id?: string;
+  /** Action name, e.g. `CREATE` (case-insensitive) */
+  action?: string;
+  /** Start of the range (RFC 3339 or `YYYY-MM-DD`), inclusive */
+  from?: string;
+  /** End of the range (RFC 3339 or `YYYY-MM-DD`); a plain date includes the whole day */
+  to?: string;
+}
+
+/**
+ * Operation log entry with decrypted details
+ */
+export interface LogEntry {
+  id: string;
+  account_id: string | null;
+  action: string;
+  /** Null when the entry has no details or they cannot be decrypted */
+  details: string | null;
+  /** `YYYY-MM-DD HH:MM:SS`, UTC */
+  created_at: string;
+}
+
+/**
+ * A page of log entries, newest first
+ */
+export interface LogPage {
+  entries: LogEntry[];
+  /** Number of entries matching the filters */
+  total: number;
+  offset: number;
+  limit: number;
+}
+
+/**
+ * Export file format
+ */
+export type LogExportFormat = 'csv' | 'json';
+
+/**
+ * How long log entries are kept
+ */
+export interface LogRetentionPolicy {
+  /** Entries older than this many days are removed at startup (null keeps every entry) */
+  max_age_days: number | null;
+}
+
+/**
+ * Kind of problem found in the audit log
+ *
+ * `unverified` marks entries written while the vault was locked and sealed
+ * at a later unlock; they do not make the report invalid.
+ */
+export type AuditIssueKind = 'broken_link' | 'altered' | 'unsealed' | 'truncated' | 'unverified';
+
+/**
+ * A problem found in the audit log
+ */
+export interface AuditIssue {
+  /** Affected log entry (null for problems with the log as a whole) */
+  log_id: number | null;
+  kind: AuditIssueKind;
+  message: string;
+}
+
+/**
+ * Result of verifying the audit log
+ */
+export interface AuditReport {
+  /** True when no tampering was found (`unverified` issues do not count) */
+  valid: boolean;
+  /** Number of entries checked */
+  checked: number;
+  issues: AuditIssue[];
+}
+
+// ============================================================================
+// Error Handling
+// ============================================================================
+
+/**
+ * Error type for audit log API operations
+ */
+export class AuditApiError extends Error {
+  constructor(
+    message: string,
+    public code?: string,
+    public originalError?: unknown
+  ) {
+    super(message);
+    this.name = 'AuditApiError';
+  }
+}
+
+// ============================================================================
+// API Functions
+// ============================================================================
+
+/**
+ * Get a page of operation log entries, newest first
+ *
+ * @param offset - Number of entries to skip
+ * @param limit - Page size (at most 500)
+ * @param query - Optional filters
+ * @returns Page of entries and the total number of matches
+ * @throws AuditApiError if not logged in or a filter is invalid
+ *
+ * @example
+ * ```ts
+ * const page = await getOperationLogs(0, 50, { action: 'DELETE', from: '2024-01-01' });
+ * ```
+ */
+export async function getOperationLogs(
+  offset: number,
+  limit: number,
+  query?: LogQuery
+): Promise<LogPage> {
+  try {
+    return await invoke<LogPage>('get_operation_logs_command', { query, offset, limit });
+  } catch (error) {
+    throw new AuditApiError(
+      'Failed to get operation logs',
+      'GET_OPERATION_LOGS_ERROR',
+      error
+    );
+  }
+}
+
+/**
+ * Export operation log entries
+ *
+ * The export contains decrypted details, including account emails.
+ *
+ * @param format - `csv` or `json`
+ * @param query - Optional filters
+ * @returns File contents
+ * @throws AuditApiError if not logged in or the export fails
+ */
+export async function exportOperationLogs(
+  format: LogExportFormat,
+  query?: LogQuery
+): Promise<string> {
+  try {
+    return await invoke<string>('export_operation_logs_command', { query, format });
+  } catch (error) {
+    throw new AuditApiError(
+      'Failed to export operation logs',
+      'EXPORT_OPERATION_LOGS_ERROR',
+      error
+    );
+  }
+}
+
+/**
+ * Remove operation log entries older than `days`
+ *
+ * @returns Number of entries removed
+ * @throws AuditApiError if not logged in or database error occurs
+ */
+export async function clearOldLogs(days: number): Promise<number> {
+  try {
+    return await invoke<number>('clear_old_logs_command', { days });
+  } catch (error) {
+    throw new AuditApiError('Failed to clear old logs', 'CLEAR_OLD_LOGS_ERROR', error);
+  }
+}
+
+/**
+ * Get the log retention policy
+ *
+ * @throws AuditApiError if database error occurs
+ */
+export async function getLogRetention(): Promise<LogRetentionPolicy> {
+  try {
+    return await invoke<LogRetentionPolicy>('get_log_retention_command');
+  } catch (error) {
+    throw new AuditApiError(
+      'Failed to get log retention policy',
+      'GET_LOG_RETENTION_ERROR',
+      error
+    );
+  }
+}
+
+/**
+ * Update the log retention policy and apply it right away
+ *
+ * @returns Number of entries removed
+ * @throws AuditApiError if not logged in or the policy is invalid
+ */
+export async function setLogRetention(policy: LogRetentionPolicy): Promise<number> {
+  try {
+    return await invoke<number>('set_log_retention_command', { policy });
+  } catch (error) {
+    throw new AuditApiError(
+      'Failed to set log retention policy',
+      'SET_LOG_RETENTION_ERROR',
+      error
+    );
+  }
+}
+
+/**
+ * Verify that no audit log entry was altered or removed
+ *
+ * @returns Report listing every broken, altered, unsealed or unverified entry
+ * @throws AuditApiError if not logged in or database error occurs
+ */
+export async function verifyAuditLog(): Promise<AuditReport> {
+  try {
+    return await invoke<AuditReport>('verify_audit_log_command');
+  } catch (error) {
+    throw new AuditApiError(
+      'Failed to verify audit log',
+      'VERIFY_AUDIT_LOG_ERROR',
+      error
+    );
+  }
+}
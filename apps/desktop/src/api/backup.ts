@@ -0,0 +1,152 @@
+/**
+ * Backup API client for GManager Desktop
+ *
+ * Backups are verified copies of the encrypted database file. The Rust
+ * backend writes, rotates and restores them and can take them on a
+ * schedule in the background.
+ *
+ * @module api/backup
+ */
+
+import { invoke } from '@tauri-apps/api/core';
+
+// ============================================================================
+// Type Definitions
+// ============================================================================
+
+/**
+ * Backup location, schedule and rotation settings
+ */
+export interface BackupPolicy {
+  /** Write backups in the background */
+  enabled: boolean;
+  /** Absolute backup directory (null for the app data `backups` directory) */
+  directory: string | null;
+  /** Hours between scheduled backups (1 to 168) */
+  interval_hours: number;
+  /** Number of days to keep the newest backup of */
+  keep_daily: number;
+  /** Number of weeks to keep the newest backup of */
+  keep_weekly: number;
+}
+
+/**
+ * A backup file
+ */
+export interface BackupInfo {
+  file_name: string;
+  path: string;
+  /** RFC 3339 time the backup was taken */
+  created_at: string;
+  size_bytes: number;
+}
+
+/**
+ * Outcome of a restore
+ */
+export interface RestoreReport {
+  /** Backup that was restored */
+  restored_from: string;
+  /** Backup of the database as it was before the restore */
+  previous_state: BackupInfo;
+}
+
+// ============================================================================
+// Error Handling
+// ============================================================================
+
+/**
+ * Error type for backup API operations
+ */
+export class BackupApiError extends Error {
+  constructor(
+    message: string,
+    public code?: string,
+    public originalError?: unknown
+  ) {
+    super(message);
+    this.name = 'BackupApiError';
+  }
+}
+
+// ============================================================================
+// API Functions
+// ============================================================================
+
+/**
+ * Write a backup now and rotate old ones
+ *
+ * @returns The new backup
+ * @throws BackupApiError if not logged in or the backup fails verification
+ */
+export async function createBackup(): Promise<BackupInfo> {
+  try {
+    return await invoke<BackupInfo>('create_backup_command');
+  } catch (error) {
+    throw new BackupApiError('Failed to create backup', 'CREATE_BACKUP_ERROR', error);
+  }
+}
+
+/**
+ * List the backups in the configured directory, newest first
+ *
+ * @throws BackupApiError if not logged in or the directory cannot be read
+ */
+export async function listBackups(): Promise<BackupInfo[]> {
+  try {
+    return await invoke<BackupInfo[]>('list_backups_command');
+  } catch (error) {
+    throw new BackupApiError('Failed to list backups', 'LIST_BACKUPS_ERROR', error);
+  }
+}
+
+/**
+ * Restore a backup
+ *
+ * The current database is backed up first. The vault is locked afterwards
+ * and must be unlocked with the restored vault's master password.
+ *
+ * @param path - Backup file to restore
+ * @returns The restored file and the backup of the replaced database
+ * @throws BackupApiError if not logged in or the file is not a valid backup
+ *
+ * @example
+ * ```ts
+ * const [latest] = await listBackups();
+ * await restoreBackup(latest.path);
+ * // Show the unlock screen
+ * ```
+ */
+export async function restoreBackup(path: string): Promise<RestoreReport> {
+  try {
+    return await invoke<RestoreReport>('restore_backup_command', { path });
+  } catch (error) {
+    throw new BackupApiError('Failed to restore backup', 'RESTORE_BACKUP_ERROR', error);
+  }
+}
+
+/**
+ * Get the backup policy
+ */
+export async function getBackupPolicy(): Promise<BackupPolicy> {
+  try {
+    return await invoke<BackupPolicy>('get_backup_policy_command');
+  } catch (error) {
+    throw new BackupApiError('Failed to get backup policy', 'GET_BACKUP_POLICY_ERROR', error);
+  }
+}
+
+/**
+ * Update the backup policy
+ *
+ * The scheduler picks up the new policy at its next check.
+ *
+ * @throws BackupApiError if not logged in or the policy is invalid
+ */
+export async function setBackupPolicy(policy: BackupPolicy): Promise<void> {
+  try {
+    await invoke('set_backup_policy_command', { policy });
+  } catch (error) {
+    throw new BackupApiError('Failed to set backup policy', 'SET_BACKUP_POLICY_ERROR', error);
+  }
+}
@@ -0,0 +1,161 @@
+/**
+ * Diagnostics API client for GManager Desktop
+ *
+ * Checks the database for corruption, broken references and encrypted
+ * fields that no longer decrypt, and can move broken rows into a
+ * quarantine table so the rest of the vault keeps working.
+ *
+ * @module api/diagnostics
+ */
+
+import { invoke } from '@tauri-apps/api/core';
+
+// ============================================================================
+// Type Definitions
+// ============================================================================
+
+/**
+ * Row reported by SQLite's foreign key check
+ */
+export interface ForeignKeyViolation {
+  table: string;
+  /** Null for tables without a rowid */
+  row_id: number | null;
+  /** Table the missing row belongs to */
+  parent: string;
+}
+
+/**
+ * Encrypted field that does not decrypt with the session key
+ */
+export interface UndecryptableField {
+  table: string;
+  row_id: number;
+  column: string;
+  message: string;
+}
+
+/**
+ * Account-tag link whose account or tag no longer exists
+ */
+export interface AccountTagLink {
+  account_id: number;
+  tag_id: number;
+}
+
+/**
+ * Reference to a row that no longer exists
+ */
+export interface DanglingReference {
+  table: string;
+  row_id: number;
+  column: string;
+  missing_id: number;
+}
+
+/**
+ * What a repair changed
+ */
+export interface RepairSummary {
+  /** Rows moved into the quarantine table */
+  quarantined_rows: number;
+  /** Dangling references set to null */
+  references_cleared: number;
+  /** Search index entries of missing accounts removed */
+  search_tokens_removed: number;
+}
+
+/**
+ * Diagnostics result, describing the database before any repair
+ */
+export interface DiagnosticsReport {
+  /** True if no problem was found */
+  healthy: boolean;
+  /** Page-level corruption; cannot be repaired, restore a backup instead */
+  integrity_errors: string[];
+  foreign_key_violations: ForeignKeyViolation[];
+  undecryptable_fields: UndecryptableField[];
+  orphaned_account_tags: AccountTagLink[];
+  dangling_references: DanglingReference[];
+  /** Number of encrypted fields that were decrypted */
+  fields_checked: number;
+  /** Set when broken rows were quarantined */
+  repair: RepairSummary | null;
+}
+
+/**
+ * Row moved out of the vault by a repair
+ */
+export interface QuarantinedRow {
+  id: number;
+  source_table: string;
+  /** Row id in the source table (`account_id:tag_id` for tag links) */
+  source_id: string;
+  /**
+   * JSON object of the row as it was stored (still encrypted), plus
+   * `tag_ids` for accounts and `account_ids` for raw imports
+   */
+  row_data: string;
+  reason: string;
+  quarantined_at: string;
+}
+
+// ============================================================================
+// Error Handling
+// ============================================================================
+
+/**
+ * Error type for diagnostics API operations
+ */
+export class DiagnosticsApiError extends Error {
+  constructor(
+    message: string,
+    public code?: string,
+    public originalError?: unknown
+  ) {
+    super(message);
+    this.name = 'DiagnosticsApiError';
+  }
+}
+
+// ============================================================================
+// API Functions
+// ============================================================================
+
+/**
+ * Check the database and optionally quarantine broken rows
+ *
+ * Operation log entries that do not decrypt are reported but never
+ * removed, since that would break the audit log.
+ *
+ * @param quarantine - Move broken rows into quarantine (default false)
+ * @throws DiagnosticsApiError if not logged in or the checks cannot run
+ *
+ * @example
+ * ```ts
+ * const report = await runDiagnostics();
+ * if (!report.healthy && confirm('Quarantine broken rows?')) {
+ *   await runDiagnostics(true);
+ * }
+ * ```
+ */
+export async function runDiagnostics(quarantine = false): Promise<DiagnosticsReport> {
+  try {
+    return await invoke<DiagnosticsReport>('run_diagnostics_command', { quarantine });
+  } catch (error) {
+    throw new DiagnosticsApiError('Failed to run diagnostics', 'RUN_DIAGNOSTICS_ERROR', error);
+  }
+}
+
+/**
+ * List rows moved into quarantine, newest first
+ *
+ * @throws DiagnosticsApiError if not logged in
+ */
+export async function getQuarantine(): Promise<QuarantinedRow[]> {
+  try {
+    return await invoke<QuarantinedRow[]>('get_quarantine_command');
+  } catch (error) {
+    throw new DiagnosticsApiError('Failed to get quarantined rows', 'GET_QUARANTINE_ERROR', error);
+  }
+}
@@ -27,6 +27,10 @@ export interface ApiGroup {
   sort_order: number;
   /** ISO timestamp when the group was created */
   created_at: string;
+  /** Containing group ID (null for top-level groups) */
+  parent_id: string | null;
+  /** The Default group, which cannot be renamed, moved or deleted */
+  is_default: boolean;
 }
 
 /**
@@ -39,6 +43,8 @@ export interface CreateGroupPayload {
   color?: string;
   /** Display sort order, defaults to 0 */
   sort_order?: number;
+  /** Group to create the new group in (top level if omitted) */
+  parent_id?: string;
 }
 
 /**
@@ -55,6 +61,30 @@ export interface UpdateGroupPayload {
   sort_order?: number;
 }
 
+/**
+ * What happens to a deleted group's accounts
+ *
+ * - `reassign`: move them to `target_id`
+ * - `move_to_default`: move them to the Default group
+ * - `delete_accounts`: delete them too (requires `confirm: true`)
+ */
+export type DeleteGroupStrategy =
+  | { strategy: 'reassign'; target_id: string }
+  | { strategy: 'move_to_default' }
+  | { strategy: 'delete_accounts'; confirm: boolean };
+
+/**
+ * Outcome of a group deletion
+ */
+export interface GroupDeletion {
+  /** Group the accounts were moved to (null if they were deleted) */
+  moved_to: number | null;
+  accounts_moved: number;
+  accounts_deleted: number;
+  /** Subgroups moved up to the deleted group's parent */
+  subgroups_moved: number;
+}
+
 // ============================================================================
 // Error Handling
 // ============================================================================
@@ -188,19 +218,29 @@ export async function updateGroup(group: UpdateGroupPayload): Promise<void> {
 /**
  * Delete a group by ID
  *
- * Note: Accounts associated with this group will have their group_id set to NULL.
+ * Subgroups move up to the deleted group's parent. The Default group and
+ * the last remaining group cannot be deleted.
  *
  * @param id - The group ID to delete
- * @throws GroupApiError if group not found
+ * @param strategy - What to do with the group's accounts (default: move
+ *   them to the Default group)
+ * @returns Where the accounts went
+ * @throws GroupApiError if a group is not found, the group is protected or
+ *   deleting accounts was not confirmed
  *
  * @example
  * ```ts
  * await deleteGroup('1');
+ * await deleteGroup('2', { strategy: 'reassign', target_id: '3' });
+ * await deleteGroup('4', { strategy: 'delete_accounts', confirm: true });
  * ```
  */
-export async function deleteGroup(id: string): Promise<void> {
+export async function deleteGroup(
+  id: string,
+  strategy: DeleteGroupStrategy = { strategy: 'move_to_default' }
+): Promise<GroupDeletion> {
   try {
-    await invoke('delete_group_command', { id });
+    return await invoke<GroupDeletion>('delete_group_command', { id, strategy });
   } catch (error) {
     throw new GroupApiError(
       `Failed to delete group: ${id}`,
@@ -210,22 +250,111 @@ export async function deleteGroup(id: string): Promise<void> {
   }
 }
 
+/**
+ * Move a group (with its subgroups) into another group
+ *
+ * @param id - The group ID to move
+ * @param parentId - The new containing group, or null for the top level
+ * @throws GroupApiError if a group is not found or the move would put the
+ *   group inside itself or one of its subgroups
+ *
+ * @example
+ * ```ts
+ * await moveGroup('3', '1'); // 3 becomes a subgroup of 1
+ * await moveGroup('3', null); // back to the top level
+ * ```
+ */
+export async function moveGroup(id: string, parentId: string | null): Promise<void> {
+  try {
+    await invoke('move_group_command', { id, parentId });
+  } catch (error) {
+    throw new GroupApiError(
+      `Failed to move group: ${id}`,
+      'MOVE_ERROR',
+      error
+    );
+  }
+}
+
+/**
+ * Set the order of groups in one step
+ *
+ * Listed groups are shown in the given order; groups not listed keep their
+ * position. Usually called with all siblings of one parent.
+ *
+ * @param ids - Group IDs in their new order
+ * @throws GroupApiError if a group is not found or listed twice
+ *
+ * @example
+ * ```ts
+ * await reorderGroups(['3', '1', '2']);
+ * ```
+ */
+export async function reorderGroups(ids: string[]): Promise<void> {
+  try {
+    await invoke('reorder_groups_command', { ids });
+  } catch (error) {
+    throw new GroupApiError(
+      'Failed to reorder groups',
+      'REORDER_ERROR',
+      error
+    );
+  }
+}
+
+/**
+ * Move a group to a new position among its siblings (e.g. on drop)
+ *
+ * Usually only the moved group is rewritten, so this is cheaper than
+ * {@link reorderGroups} for a single drag and drop.
+ *
+ * @param id - The group ID to move
+ * @param beforeId - Sibling to place it before, or null to make it the last
+ * @throws GroupApiError if a group is not found or `beforeId` has another
+ *   parent group
+ *
+ * @example
+ * ```ts
+ * await placeGroup('3', '1'); // 3 now comes right before 1
+ * await placeGroup('3', null); // 3 is now the last of its siblings
+ * ```
+ */
+export async function placeGroup(id: string, beforeId: string | null): Promise<void> {
+  try {
+    await invoke('place_group_command', { id, beforeId });
+  } catch (error) {
+    throw new GroupApiError(
+      `Failed to place group: ${id}`,
+      'PLACE_ERROR',
+      error
+    );
+  }
+}
+
 /**
  * Get the number of accounts in a group
  *
  * @param id - The group ID
+ * @param includeSubgroups - Also count accounts in nested groups (default: false)
  * @returns The number of accounts in the group
  * @throws GroupApiError if database error occurs
  *
  * @example
  * ```ts
  * const count = await getGroupAccountsCount('1');
- * console.log(`Accounts in group: ${count}`);
+ * const total = await getGroupAccountsCount('1', true);
+ * console.log(`Accounts in group: ${count} (${total} with subgroups)`);
  * ```
  */
-export async function getGroupAccountsCount(id: string): Promise<number> {
+export async function getGroupAccountsCount(
+  id: string,
+  includeSubgroups = false
+): Promise<number> {
   try {
-    return await invoke<number>('get_group_accounts_count_command', { id });
+    return await invoke<number>('get_group_accounts_count_command', {
+      id,
+      includeSubgroups,
+    });
   } catch (error) {
     throw new GroupApiError(
       `Failed to get account count for group: ${id}`,
@@ -252,6 +381,8 @@ export function apiGroupToGroup(apiGroup: ApiGroup): {
   color: string;
   sort_order: number;
   createdAt: string;
+  parentId: string | null;
+  isDefault: boolean;
 } {
   return {
     id: apiGroup.id,
@@ -259,6 +390,8 @@ export function apiGroupToGroup(apiGroup: ApiGroup): {
     color: apiGroup.color,
     sort_order: apiGroup.sort_order,
     createdAt: apiGroup.created_at,
+    parentId: apiGroup.parent_id,
+    isDefault: apiGroup.is_default,
   };
 }
 
@@ -0,0 +1,133 @@
+/**
+ * Undo/redo API client for GManager Desktop
+ *
+ * Account, group and tag mutations are recorded in an encrypted history in
+ * the Rust backend. These functions replay it.
+ *
+ * @module api/history
+ */
+
+import { invoke } from '@tauri-apps/api/core';
+
+// ============================================================================
+// Type Definitions
+// ============================================================================
+
+/**
+ * Kind of mutation recorded in the history
+ */
+export type MutationKind =
+  | 'CREATE_ACCOUNT'
+  | 'UPDATE_ACCOUNT'
+  | 'DELETE_ACCOUNT'
+  | 'BATCH_DELETE_ACCOUNTS'
+  | 'BATCH_UPDATE_ACCOUNTS'
+  | 'CREATE_GROUP'
+  | 'UPDATE_GROUP'
+  | 'DELETE_GROUP'
+  | 'MOVE_GROUP'
+  | 'REORDER_GROUPS'
+  | 'CREATE_TAG'
+  | 'UPDATE_TAG'
+  | 'DELETE_TAG'
+  | 'ADD_TAG_TO_ACCOUNT'
+  | 'REMOVE_TAG_FROM_ACCOUNT'
+  | 'SET_ACCOUNT_TAGS'
+  | 'BATCH_UPDATE_ACCOUNT_TAGS'
+  | 'MERGE_TAGS';
+
+/**
+ * Result of an undo or redo
+ */
+export interface HistoryStep {
+  /** Mutation that was reverted or re-applied */
+  kind: MutationKind;
+  /** Number of rows that were restored */
+  changed_rows: number;
+}
+
+/**
+ * Current state of the history
+ */
+export interface HistoryStatus {
+  undo_count: number;
+  redo_count: number;
+  /** Mutation the next undo reverts */
+  next_undo: MutationKind | null;
+  /** Mutation the next redo re-applies */
+  next_redo: MutationKind | null;
+}
+
+// ============================================================================
+// Error Handling
+// ============================================================================
+
+/**
+ * Error type for history API operations
+ */
+export class HistoryApiError extends Error {
+  constructor(
+    message: string,
+    public code?: string,
+    public originalError?: unknown
+  ) {
+    super(message);
+    this.name = 'HistoryApiError';
+  }
+}
+
+// ============================================================================
+// API Functions
+// ============================================================================
+
+/**
+ * Undo the most recent mutation
+ *
+ * @returns The undone step, or null if there is nothing to undo
+ * @throws HistoryApiError if not logged in or the undo cannot be applied
+ */
+export async function undo(): Promise<HistoryStep | null> {
+  try {
+    return await invoke<HistoryStep | null>('undo_command');
+  } catch (error) {
+    throw new HistoryApiError('Failed to undo', 'UNDO_ERROR', error);
+  }
+}
+
+/**
+ * Redo the most recently undone mutation
+ *
+ * @returns The redone step, or null if there is nothing to redo
+ * @throws HistoryApiError if not logged in or the redo cannot be applied
+ */
+export async function redo(): Promise<HistoryStep | null> {
+  try {
+    return await invoke<HistoryStep | null>('redo_command');
+  } catch (error) {
+    throw new HistoryApiError('Failed to redo', 'REDO_ERROR', error);
+  }
+}
+
+/**
+ * Get the number of undo/redo steps and the next mutation on each side
+ *
+ * @returns The history status
+ * @throws HistoryApiError if database error occurs
+ *
+ * @example
+ * ```ts
+ * const status = await getHistoryStatus();
+ * undoButton.disabled = status.undo_count === 0;
+ * ```
+ */
+export async function getHistoryStatus(): Promise<HistoryStatus> {
+  try {
+    return await invoke<HistoryStatus>('get_history_status_command');
+  } catch (error) {
+    throw new HistoryApiError(
+      'Failed to get history status',
+      'GET_HISTORY_STATUS_ERROR',
+      error
+    );
+  }
+}
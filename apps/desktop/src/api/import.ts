@@ -0,0 +1,262 @@
+/**
+ * Import API client for GManager Desktop
+ *
+ * This module provides TypeScript functions that call the Tauri Rust backend
+ * for importing accounts. Parsing, encryption and storage all happen in the
+ * backend; previews never contain passwords or TOTP secrets.
+ *
+ * @module api/import
+ */
+
+import { invoke } from '@tauri-apps/api/core';
+
+// ============================================================================
+// Type Definitions
+// ============================================================================
+
+/**
+ * Request for importing a pasted account list
+ */
+export interface TextImportRequest {
+  /** Account list, one account per line */
+  text: string;
+  /** Field delimiter (detected when omitted) */
+  delimiter?: string;
+  /** Label for the source, e.g. a file name */
+  source_name?: string;
+  /** Group for the imported accounts */
+  group_id?: string;
+  /** Only parse and return the preview */
+  dry_run?: boolean;
+}
+
+/**
+ * Account field a CSV column maps to
+ */
+export type CsvField =
+  | 'email'
+  | 'password'
+  | 'recovery_email'
+  | 'totp_secret'
+  | 'year'
+  | 'notes'
+  | 'group'
+  | 'tags';
+
+/**
+ * Mapping of one CSV column to an account field
+ */
+export interface CsvColumn {
+  /** Header of the column (matched ignoring case) */
+  column: string;
+  field: CsvField;
+}
+
+/**
+ * Request for importing a CSV file
+ */
+export interface CsvImportRequest {
+  /** File contents; the first row must be a header */
+  contents: string;
+  /** Column mapping (headers named like the fields are mapped when omitted) */
+  columns?: CsvColumn[];
+  /** Single-character delimiter (default `,`) */
+  delimiter?: string;
+  /** Label for the source, e.g. a file name */
+  source_name?: string;
+  /** Group for rows without a group column value */
+  group_id?: string;
+  /** Only parse and return the preview */
+  dry_run?: boolean;
+}
+
+/**
+ * Export formats of other password managers
+ */
+export type ManagerFormat =
+  | 'bitwarden_json'
+  | 'keepass_xml'
+  | '1password_1pux'
+  | '1password_csv'
+  | 'chrome_csv'
+  | 'firefox_csv';
+
+/**
+ * Request for importing another password manager's export
+ */
+export interface ManagerImportRequest {
+  format: ManagerFormat;
+  /** File contents; base64-encoded for `1password_1pux` archives */
+  contents: string;
+  /** Label for the source, e.g. a file name */
+  source_name?: string;
+  /** Group for items without a folder */
+  group_id?: string;
+  /** Only parse and return the preview */
+  dry_run?: boolean;
+}
+
+/**
+ * A line that could not be imported
+ */
+export interface ImportLineError {
+  /** 1-based line number (item number for JSON sources) */
+  line: number;
+  /** Why the line was rejected */
+  message: string;
+}
+
+/**
+ * Preview of a parsed account (secrets omitted)
+ */
+export interface ImportPreview {
+  /** 1-based line number */
+  line: number;
+  email: string;
+  recovery_email: string | null;
+  year: string | null;
+  notes: string | null;
+  /** Group path from the source, e.g. `Clients/Work` (created on import if missing) */
+  group: string | null;
+  /** Tag names from the source (created on import if missing) */
+  tags: string[];
+  has_password: boolean;
+  has_totp_secret: boolean;
+}
+
+/**
+ * Outcome of an import or dry run
+ */
+export interface ImportReport {
+  dry_run: boolean;
+  /** Raw import the accounts are linked to (null for dry runs) */
+  raw_import_id: string | null;
+  /** Number of records (non-empty lines for text) */
+  total_lines: number;
+  imported_count: number;
+  /** IDs of the created accounts */
+  account_ids: string[];
+  accounts: ImportPreview[];
+  errors: ImportLineError[];
+}
+
+// ============================================================================
+// Error Handling
+// ============================================================================
+
+/**
+ * Error type for import API operations
+ */
+export class ImportApiError extends Error {
+  constructor(
+    message: string,
+    public code?: string,
+    public originalError?: unknown
+  ) {
+    super(message);
+    this.name = 'ImportApiError';
+  }
+}
+
+// ============================================================================
+// API Functions
+// ============================================================================
+
+/**
+ * Import accounts from pasted text
+ *
+ * Lines that cannot be parsed are reported in `errors`; the remaining lines
+ * are imported together.
+ *
+ * @param request - Text and import options
+ * @returns Import report with per-line errors
+ * @throws ImportApiError if not logged in or the import fails
+ *
+ * @example
+ * ```ts
+ * const preview = await importText({ text, dry_run: true });
+ * if (preview.errors.length === 0) {
+ *   await importText({ text });
+ * }
+ * ```
+ */
+export async function importText(request: TextImportRequest): Promise<ImportReport> {
+  try {
+    return await invoke<ImportReport>('import_text_command', { request });
+  } catch (error) {
+    throw new ImportApiError(
+      'Failed to import text',
+      'IMPORT_TEXT_ERROR',
+      error
+    );
+  }
+}
+
+/**
+ * Import accounts from a CSV file
+ *
+ * Rows that cannot be parsed are reported in `errors` with their line
+ * number; the remaining rows are imported together. Group and tag names
+ * that do not exist yet are created.
+ *
+ * @param request - File contents, column mapping and import options
+ * @returns Import report with per-row errors
+ * @throws ImportApiError if not logged in, the header does not match the
+ *   mapping, or the import fails
+ *
+ * @example
+ * ```ts
+ * const report = await importCsv({
+ *   contents,
+ *   columns: [
+ *     { column: 'Login', field: 'email' },
+ *     { column: 'Password', field: 'password' },
+ *     { column: 'Labels', field: 'tags' },
+ *   ],
+ * });
+ * ```
+ */
+export async function importCsv(request: CsvImportRequest): Promise<ImportReport> {
+  try {
+    return await invoke<ImportReport>('import_csv_command', { request });
+  } catch (error) {
+    throw new ImportApiError(
+      'Failed to import CSV',
+      'IMPORT_CSV_ERROR',
+      error
+    );
+  }
+}
+
+/**
+ * Import accounts from another password manager's export file
+ *
+ * Folders become groups, labels become tags and OTP fields become TOTP
+ * secrets. Items that are not logins or lack a username or password are
+ * reported in `errors`.
+ *
+ * @param request - Export format, file contents and import options
+ * @returns Import report with per-item errors
+ * @throws ImportApiError if not logged in, the file cannot be read, or the
+ *   import fails
+ *
+ * @example
+ * ```ts
+ * const report = await importManagerExport({
+ *   format: 'bitwarden_json',
+ *   contents: await file.text(),
+ *   source_name: file.name,
+ * });
+ * ```
+ */
+export async function importManagerExport(request: ManagerImportRequest): Promise<ImportReport> {
+  try {
+    return await invoke<ImportReport>('import_manager_export_command', { request });
+  } catch (error) {
+    throw new ImportApiError(
+      'Failed to import password manager export',
+      'IMPORT_MANAGER_EXPORT_ERROR',
+      error
+    );
+  }
+}
@@ -17,6 +17,7 @@ export {
   batchDeleteAccounts,
   batchUpdateAccounts,
   getAccountStats,
+  getTotpCode,
   apiAccountToAccount,
   accountToUpdatePayload,
   createPaginatedResult,
@@ -27,8 +28,13 @@ export {
   type UpdateAccountPayload,
   type AccountSearchParams,
   type AccountStats,
+  type TotpCode,
   type BatchDeleteRequest,
   type BatchUpdateRequest,
+  type BatchMode,
+  type BatchItemStatus,
+  type BatchItemResult,
+  type BatchResult,
   type PaginatedResult,
   type MutationResult,
   type QueryResult,
@@ -40,12 +46,17 @@ export {
   createGroup,
   updateGroup,
   deleteGroup,
+  moveGroup,
+  reorderGroups,
+  placeGroup,
   getGroupAccountsCount,
   apiGroupToGroup,
   getDefaultGroupColor,
   DEFAULT_GROUP_COLORS,
   type GroupApiError,
   type ApiGroup,
+  type DeleteGroupStrategy,
+  type GroupDeletion,
   type CreateGroupPayload,
   type UpdateGroupPayload,
 } from './groups';
@@ -60,6 +71,10 @@ export {
   removeTagFromAccount,
   getAccountTags,
   setAccountTags,
+  mergeTags,
+  batchAddTags,
+  batchRemoveTags,
+  batchReplaceTags,
   getTagAccountsCount,
   apiTagToTag,
   getDefaultTagColor,
@@ -70,7 +85,88 @@ export {
   type ApiTag,
   type CreateTagPayload,
   type UpdateTagPayload,
+  type BatchTagsRequest,
+  type TagMerge,
 } from './tags';
 
+export {
+  importText,
+  importCsv,
+  importManagerExport,
+  type ImportApiError,
+  type TextImportRequest,
+  type CsvField,
+  type CsvColumn,
+  type CsvImportRequest,
+  type ManagerFormat,
+  type ManagerImportRequest,
+  type ImportLineError,
+  type ImportPreview,
+  type ImportReport,
+} from './import';
+
+export {
+  undo,
+  redo,
+  getHistoryStatus,
+  type HistoryApiError,
+  type MutationKind,
+  type HistoryStep,
+  type HistoryStatus,
+} from './history';
+
+export {
+  getOperationLogs,
+  exportOperationLogs,
+  clearOldLogs,
+  getLogRetention,
+  setLogRetention,
+  verifyAuditLog,
+  type AuditApiError,
+  type LogQuery,
+  type LogEntry,
+  type LogPage,
+  type LogExportFormat,
+  type LogRetentionPolicy,
+  type AuditIssueKind,
+  type AuditIssue,
+  type AuditReport,
+} from './audit';
+
+export {
+  exportVault,
+  importVault,
+  exportCsv,
+  type TransferApiError,
+  type KdfParams,
+  type TransferMode,
+  type VaultImportReport,
+  type CsvExportRequest,
+} from './transfer';
+
+export {
+  createBackup,
+  listBackups,
+  restoreBackup,
+  getBackupPolicy,
+  setBackupPolicy,
+  type BackupApiError,
+  type BackupPolicy,
+  type BackupInfo,
+  type RestoreReport,
+} from './backup';
+
+export {
+  runDiagnostics,
+  getQuarantine,
+  type DiagnosticsApiError,
+  type DiagnosticsReport,
+  type ForeignKeyViolation,
+  type UndecryptableField,
+  type DanglingReference,
+  type RepairSummary,
+  type QuarantinedRow,
+} from './diagnostics';
+
 // Re-export ApiTag from accounts as ApiTagInAccount to avoid naming conflict
 export type { ApiTag as ApiTagInAccount } from './accounts';
@@ -8,6 +8,7 @@
  */
 
 import { invoke } from '@tauri-apps/api/core';
+import type { BatchMode, BatchResult } from './accounts';
 
 // ============================================================================
 // Type Definitions
@@ -59,6 +60,28 @@ export interface AddTagToAccountPayload {
   tag_id: string;
 }
 
+/**
+ * Outcome of merging one tag into another
+ */
+export interface TagMerge {
+  /** Accounts that got the target tag from the source tag */
+  accounts_moved: number;
+  /** Accounts that already had both tags */
+  accounts_already_tagged: number;
+}
+
+/**
+ * Request for changing the tags of several accounts at once
+ */
+export interface BatchTagsRequest {
+  /** Accounts to change */
+  account_ids: string[];
+  /** Tags to add, remove or set */
+  tag_ids: string[];
+  /** Failure handling (default: all_or_nothing) */
+  mode?: BatchMode;
+}
+
 // ============================================================================
 // Error Handling
 // ============================================================================
@@ -137,7 +160,8 @@ export async function getTag(id: string): Promise<ApiTag> {
  *
  * @param tag - The tag data to create
  * @returns The ID of the created tag
- * @throws TagApiError if validation fails or name already exists
+ * @throws TagApiError if validation fails or another tag has the same name
+ *   (ignoring case)
  *
  * @example
  * ```ts
@@ -165,7 +189,8 @@ export async function createTag(tag: CreateTagPayload): Promise<string> {
  * Only include the fields that should be updated.
  *
  * @param tag - The tag updates with ID
- * @throws TagApiError if tag not found or name already exists
+ * @throws TagApiError if tag not found or another tag has the same name
+ *   (ignoring case)
  *
  * @example
  * ```ts
@@ -340,6 +365,112 @@ export async function setAccountTags(
   }
 }
 
+/**
+ * Merge a tag into another
+ *
+ * Accounts tagged with the source tag get the target tag instead, and the
+ * source tag is deleted.
+ *
+ * @param sourceId - The tag to merge away
+ * @param targetId - The tag to keep
+ * @returns How many accounts moved
+ * @throws TagApiError if a tag is not found or both IDs are the same
+ *
+ * @example
+ * ```ts
+ * await mergeTags('7', '2'); // 'wrk' becomes 'work'
+ * ```
+ */
+export async function mergeTags(sourceId: string, targetId: string): Promise<TagMerge> {
+  try {
+    return await invoke<TagMerge>('merge_tags_command', { sourceId, targetId });
+  } catch (error) {
+    throw new TagApiError(
+      `Failed to merge tag ${sourceId} into ${targetId}`,
+      'MERGE_ERROR',
+      error
+    );
+  }
+}
+
+/**
+ * Add tags to several accounts at once
+ *
+ * Runs in a single transaction; each account keeps its other tags.
+ *
+ * @param accountIds - Accounts to tag
+ * @param tagIds - Tags to add
+ * @param mode - Failure handling (default: all_or_nothing)
+ * @returns Per-account results
+ * @throws TagApiError if a tag does not exist or a database error occurs
+ *
+ * @example
+ * ```ts
+ * const result = await batchAddTags(selectedIds, ['tag-1']);
+ * console.log(`Tagged ${result.succeeded} accounts`);
+ * ```
+ */
+export async function batchAddTags(
+  accountIds: string[],
+  tagIds: string[],
+  mode?: BatchMode
+): Promise<BatchResult> {
+  try {
+    const payload: BatchTagsRequest = { account_ids: accountIds, tag_ids: tagIds, mode };
+    return await invoke<BatchResult>('batch_add_tags_command', { payload });
+  } catch (error) {
+    throw new TagApiError('Failed to add tags to accounts', 'BATCH_ADD_TAGS_ERROR', error);
+  }
+}
+
+/**
+ * Remove tags from several accounts at once
+ *
+ * Accounts that do not have a tag are left as they are.
+ *
+ * @param accountIds - Accounts to untag
+ * @param tagIds - Tags to remove
+ * @param mode - Failure handling (default: all_or_nothing)
+ * @returns Per-account results
+ * @throws TagApiError if a tag does not exist or a database error occurs
+ */
+export async function batchRemoveTags(
+  accountIds: string[],
+  tagIds: string[],
+  mode?: BatchMode
+): Promise<BatchResult> {
+  try {
+    const payload: BatchTagsRequest = { account_ids: accountIds, tag_ids: tagIds, mode };
+    return await invoke<BatchResult>('batch_remove_tags_command', { payload });
+  } catch (error) {
+    throw new TagApiError('Failed to remove tags from accounts', 'BATCH_REMOVE_TAGS_ERROR', error);
+  }
+}
+
+/**
+ * Replace the tags of several accounts at once
+ *
+ * Afterwards each account has exactly the given tags.
+ *
+ * @param accountIds - Accounts to change
+ * @param tagIds - Their new tags
+ * @param mode - Failure handling (default: all_or_nothing)
+ * @returns Per-account results
+ * @throws TagApiError if a tag does not exist or a database error occurs
+ */
+export async function batchReplaceTags(
+  accountIds: string[],
+  tagIds: string[],
+  mode?: BatchMode
+): Promise<BatchResult> {
+  try {
+    const payload: BatchTagsRequest = { account_ids: accountIds, tag_ids: tagIds, mode };
+    return await invoke<BatchResult>('batch_replace_tags_command', { payload });
+  } catch (error) {
+    throw new TagApiError('Failed to replace tags of accounts', 'BATCH_REPLACE_TAGS_ERROR', error);
+  }
+}
+
 /**
  * Get the number of accounts that have a specific tag
  *
@@ -0,0 +1,154 @@
+/**
+ * Vault export/import API client for GManager Desktop
+ *
+ * Exports contain the whole vault (groups, tags, raw imports, accounts and
+ * their tag links) encrypted with a separate export passphrase. Encryption
+ * and decryption happen in the Rust backend.
+ *
+ * @module api/transfer
+ */
+
+import { invoke } from '@tauri-apps/api/core';
+import type { CsvColumn } from './import';
+
+// ============================================================================
+// Type Definitions
+// ============================================================================
+
+/**
+ * Key derivation parameters for the export passphrase
+ */
+export type KdfParams =
+  | { algorithm: 'argon2id'; memory_kib: number; time_cost: number; lanes: number }
+  | { algorithm: 'pbkdf2'; iterations: number };
+
+/**
+ * How imported data is combined with the existing vault
+ *
+ * - `merge`: keep existing data; groups and tags are matched by name and
+ *   accounts with an existing email are skipped
+ * - `replace`: delete all groups, tags, raw imports and accounts first
+ */
+export type TransferMode = 'merge' | 'replace';
+
+/**
+ * Outcome of a vault import
+ */
+export interface VaultImportReport {
+  mode: TransferMode;
+  accounts_imported: number;
+  /** Accounts whose email already exists (merge only) */
+  accounts_skipped: number;
+  groups_created: number;
+  groups_matched: number;
+  tags_created: number;
+  tags_matched: number;
+  raw_imports_imported: number;
+  /** IDs of the created accounts */
+  account_ids: string[];
+}
+
+/**
+ * Options for a plaintext CSV export
+ */
+export interface CsvExportRequest {
+  /** Columns to write, in order (all fields under their default headers when omitted) */
+  columns?: CsvColumn[];
+  /** Only export accounts in this group */
+  group_id?: string;
+  /** Only export these accounts */
+  account_ids?: string[];
+  /** Must be true: the file is not encrypted */
+  confirm_plaintext: boolean;
+}
+
+// ============================================================================
+// Error Handling
+// ============================================================================
+
+/**
+ * Error type for export/import API operations
+ */
+export class TransferApiError extends Error {
+  constructor(
+    message: string,
+    public code?: string,
+    public originalError?: unknown
+  ) {
+    super(message);
+    this.name = 'TransferApiError';
+  }
+}
+
+// ============================================================================
+// API Functions
+// ============================================================================
+
+/**
+ * Export the whole vault as an encrypted file
+ *
+ * @param passphrase - Export passphrase (at least 8 characters)
+ * @param kdfParams - Optional KDF parameters (only stronger than the defaults)
+ * @returns File contents to save
+ * @throws TransferApiError if not logged in or the export fails
+ */
+export async function exportVault(
+  passphrase: string,
+  kdfParams?: KdfParams
+): Promise<string> {
+  try {
+    return await invoke<string>('export_vault_command', { passphrase, kdfParams });
+  } catch (error) {
+    throw new TransferApiError('Failed to export vault', 'EXPORT_VAULT_ERROR', error);
+  }
+}
+
+/**
+ * Import an encrypted vault export
+ *
+ * @param contents - File contents
+ * @param passphrase - Export passphrase the file was written with
+ * @param mode - `merge` (default) or `replace`
+ * @returns Import report
+ * @throws TransferApiError if the file or passphrase is invalid
+ *
+ * @example
+ * ```ts
+ * const report = await importVault(contents, passphrase, 'merge');
+ * console.log(`${report.accounts_imported} imported, ${report.accounts_skipped} skipped`);
+ * ```
+ */
+export async function importVault(
+  contents: string,
+  passphrase: string,
+  mode: TransferMode = 'merge'
+): Promise<VaultImportReport> {
+  try {
+    return await invoke<VaultImportReport>('import_vault_command', {
+      contents,
+      passphrase,
+      mode,
+    });
+  } catch (error) {
+    throw new TransferApiError('Failed to import vault', 'IMPORT_VAULT_ERROR', error);
+  }
+}
+
+/**
+ * Export accounts as a plaintext CSV file
+ *
+ * The file contains passwords and TOTP secrets unencrypted, so the caller
+ * must confirm this with `confirm_plaintext`. Cells that would start a
+ * spreadsheet formula get a leading `'`, which the CSV import removes.
+ *
+ * @param request - Columns, account selection and the confirmation
+ * @returns File contents to save
+ * @throws TransferApiError if not logged in, not confirmed, or the export fails
+ */
+export async function exportCsv(request: CsvExportRequest): Promise<string> {
+  try {
+    return await invoke<string>('export_csv_command', { request });
+  } catch (error) {
+    throw new TransferApiError('Failed to export CSV', 'EXPORT_CSV_ERROR', error);
+  }
+}